@@ -135,6 +135,24 @@ pub(crate) struct Args {
     #[arg(long, group = "request_limiting")]
     pub(crate) limit_requests: Option<u32>,
 
+    /// Fails a function execution once it has produced more than the given bytes of output
+    #[arg(long)]
+    pub(crate) limit_output_bytes: Option<u64>,
+
+    /// Reports functions stopped by the cpu time limit in seconds set on cyclone as exceeding it
+    #[arg(long)]
+    pub(crate) limit_cpu_time_secs: Option<u64>,
+
+    /// Reports functions running out of files under the open files limit set on cyclone as
+    /// exceeding it
+    #[arg(long)]
+    pub(crate) limit_open_files: Option<u64>,
+
+    /// Reports functions killed for running out of memory in cyclone's cgroup as exceeding its
+    /// memory limit
+    #[arg(long)]
+    pub(crate) limit_memory_bytes: Option<u64>,
+
     /// Cyclone decryption key file location [example: /run/cyclone/cyclone.key]
     #[arg(long)]
     pub(crate) decryption_key: PathBuf,
@@ -185,12 +203,37 @@ impl TryFrom<Args> for Config {
             builder.enable_resolver(false);
         }
 
+        if args.enable_action_run {
+            builder.enable_action_run(true);
+        } else if args.disable_action_run {
+            builder.enable_action_run(false);
+        }
+
+        if args.enable_reconciliation {
+            builder.enable_reconciliation(true);
+        } else if args.disable_reconciliation {
+            builder.enable_reconciliation(false);
+        }
+
         if args.oneshot {
             builder.limit_requests(1);
         } else if let Some(limit_requests) = args.limit_requests {
             builder.limit_requests(limit_requests);
         }
 
+        if let Some(limit_output_bytes) = args.limit_output_bytes {
+            builder.limit_output_bytes(limit_output_bytes);
+        }
+        if let Some(limit_cpu_time_secs) = args.limit_cpu_time_secs {
+            builder.limit_cpu_time_secs(limit_cpu_time_secs);
+        }
+        if let Some(limit_open_files) = args.limit_open_files {
+            builder.limit_open_files(limit_open_files);
+        }
+        if let Some(limit_memory_bytes) = args.limit_memory_bytes {
+            builder.limit_memory_bytes(limit_memory_bytes);
+        }
+
         builder.build().map_err(Into::into)
     }
 }
@@ -55,6 +55,8 @@ export interface ResultFailure extends Result {
   error: {
     kind: string;
     message: string;
+    // The system error number of a failed system call, e.g. when out of file descriptors
+    errno?: number;
   };
 }
 
@@ -75,6 +77,7 @@ export function failureExecution(
     error: {
       kind: err.name,
       message: err.message,
+      errno: (err as NodeJS.ErrnoException).errno,
     },
   };
 }
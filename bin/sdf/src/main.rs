@@ -114,16 +114,17 @@ async fn async_main() -> Result<()> {
     let (crdt_multiplexer, crdt_multiplexer_client) =
         Multiplexer::new(&nats_conn, CRDT_MULTIPLEXER_SUBJECT).await?;
 
-    let (layer_db, layer_db_graceful_shutdown) = LayerDb::initialize(
+    let (layer_db, layer_db_graceful_shutdown) = LayerDb::initialize_with_config(
         config.layer_cache_sled_path(),
         PgPool::new(config.layer_cache_pg_pool()).await?,
         nats_conn.clone(),
+        config.layer_db().clone(),
         shutdown_token.clone(),
     )
     .await?;
     task_tracker.spawn(layer_db_graceful_shutdown.into_future());
 
-    let services_context = ServicesContext::new(
+    let mut services_context = ServicesContext::new(
         pg_pool,
         nats_conn,
         job_processor,
@@ -134,6 +135,9 @@ async fn async_main() -> Result<()> {
         symmetric_crypto_service,
         layer_db,
     );
+    if config.validate_snapshots() {
+        services_context.set_validate_snapshots();
+    }
 
     if let MigrationMode::Run | MigrationMode::RunAndQuit = config.migration_mode() {
         Server::migrate_database(&services_context).await?;
@@ -148,6 +152,13 @@ async fn async_main() -> Result<()> {
         trace!("migration mode is skip, not running migrations");
     }
 
+    Server::start_layer_db_warm_up(
+        services_context.clone(),
+        config.layer_db().warm_up.clone(),
+        &task_tracker,
+        shutdown_token.clone(),
+    );
+
     let posthog_client = Server::start_posthog(config.posthog()).await?;
 
     task_tracker.close();
@@ -1,11 +1,12 @@
-use std::time::Duration;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use futures::StreamExt;
-use si_data_nats::{NatsClient, Subject, Subscriber};
+use si_data_nats::{Message, NatsClient, Subject, Subscriber};
 use telemetry::{prelude::*, tracing::field};
 use telemetry_nats::propagation;
+use tokio::sync::Mutex;
 
-use crate::{Graph, Id, Request, Response, SubjectGenerator};
+use crate::{Graph, Id, ManagementResponse, Request, Response, SubjectGenerator};
 
 pub mod management;
 
@@ -20,6 +21,8 @@ pub enum ClientError {
     NoListenerAvailable,
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    #[error("unexpected management response: {0:?}")]
+    UnexpectedManagementResponse(ManagementResponse),
 }
 
 #[remain::sorted]
@@ -29,16 +32,83 @@ pub enum State {
     Shutdown,
 }
 
+/// What has been registered with and reported to council, kept so it can all be sent again when council asks
+/// for it.
+#[derive(Debug, Default)]
+struct Progress {
+    // The nodes of the registered dependency graph that have been neither processed nor failed.
+    unprocessed: Graph,
+    // The nodes reported as processed or failed that council has not acknowledged yet.
+    reported: HashMap<Id, Report>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Report {
+    Failed,
+    Processed,
+}
+
 #[derive(Debug, Clone)]
 pub struct PubClient {
     change_set_id: Id,
     pub_channel: Subject,
     reply_channel: Subject,
     nats: NatsClient,
+    progress: Arc<Mutex<Progress>>,
 }
 
 impl PubClient {
     pub async fn register_dependency_graph(&self, dependency_graph: Graph) -> ClientResult<()> {
+        {
+            let mut progress = self.progress.lock().await;
+            for (node_id, dependencies) in &dependency_graph {
+                progress
+                    .unprocessed
+                    .entry(*node_id)
+                    .or_default()
+                    .extend(dependencies.iter().copied());
+            }
+        }
+        self.send_dependency_graph(dependency_graph).await
+    }
+
+    /// Sends every part of the registered dependency graph that has not been processed yet, then reports
+    /// the nodes council has not acknowledged as processed or failed again, so that council has them
+    /// even if they were lost while council was restarting or handing the change set over.
+    pub async fn reregister_dependency_graph(&self) -> ClientResult<()> {
+        let (dependency_graph, reported) = {
+            let progress = self.progress.lock().await;
+            // Reported nodes are done with as far as this job is concerned, so they are neither
+            // registered again nor waited on.
+            let dependency_graph: Graph = progress
+                .unprocessed
+                .iter()
+                .filter(|(node_id, _)| !progress.reported.contains_key(node_id))
+                .map(|(node_id, dependencies)| {
+                    let dependencies = dependencies
+                        .iter()
+                        .filter(|dependency| !progress.reported.contains_key(dependency))
+                        .copied()
+                        .collect();
+                    (*node_id, dependencies)
+                })
+                .collect();
+            (dependency_graph, progress.reported.clone())
+        };
+
+        if !dependency_graph.is_empty() {
+            self.send_dependency_graph(dependency_graph).await?;
+        }
+        for (node_id, report) in reported {
+            match report {
+                Report::Failed => self.failed_processing_value(node_id).await?,
+                Report::Processed => self.processed_value(node_id).await?,
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_dependency_graph(&self, dependency_graph: Graph) -> ClientResult<()> {
         let message = serde_json::to_vec(&Request::ValueDependencyGraph {
             change_set_id: self.change_set_id,
             dependency_graph,
@@ -55,6 +125,11 @@ impl PubClient {
     }
 
     pub async fn processed_value(&self, node_id: Id) -> ClientResult<()> {
+        self.progress
+            .lock()
+            .await
+            .reported
+            .insert(node_id, Report::Processed);
         let message = serde_json::to_vec(&Request::ProcessedValue {
             change_set_id: self.change_set_id,
             node_id,
@@ -71,6 +146,11 @@ impl PubClient {
     }
 
     pub async fn failed_processing_value(&self, node_id: Id) -> ClientResult<()> {
+        self.progress
+            .lock()
+            .await
+            .reported
+            .insert(node_id, Report::Failed);
         let message = serde_json::to_vec(&Request::ValueProcessingFailed {
             change_set_id: self.change_set_id,
             node_id,
@@ -108,7 +188,9 @@ pub struct Client {
     pub_channel: Subject,
     reply_channel: Subject,
     subscriber: Subscriber,
+    management_subscriber: Subscriber,
     nats: NatsClient,
+    progress: Arc<Mutex<Progress>>,
 }
 
 impl Client {
@@ -118,13 +200,16 @@ impl Client {
         id: Id,
         change_set_id: Id,
     ) -> ClientResult<Self> {
-        let (pub_channel, reply_channel) = SubjectGenerator::for_client(subject_prefix, id);
+        let (pub_channel, reply_channel) = SubjectGenerator::for_client(subject_prefix.clone(), id);
+        let management_channel = SubjectGenerator::for_management_client(subject_prefix);
         Ok(Self {
             pub_channel: pub_channel.into(),
             change_set_id,
             subscriber: nats.subscribe(reply_channel.clone()).await?,
+            management_subscriber: nats.subscribe(management_channel).await?,
             reply_channel: reply_channel.into(),
             nats,
+            progress: Arc::default(),
         })
     }
 
@@ -134,6 +219,7 @@ impl Client {
             reply_channel: self.reply_channel.clone(),
             change_set_id: self.change_set_id,
             nats: self.nats.clone(),
+            progress: self.progress.clone(),
         }
     }
 
@@ -151,10 +237,19 @@ impl Client {
         // TODO: handle message.data() empty with Status header as 503:
         // https://github.com/nats-io/nats.go/pull/576
         let msg = loop {
-            let res = tokio::time::timeout(Duration::from_secs(60), self.subscriber.next()).await;
+            let (subscriber, management_subscriber) =
+                (&mut self.subscriber, &mut self.management_subscriber);
+            let res = tokio::time::timeout(Duration::from_secs(60), async move {
+                tokio::select! {
+                    msg = subscriber.next() => Incoming::Response(msg),
+                    Some(msg) = management_subscriber.next() => Incoming::Management(msg),
+                }
+            })
+            .await;
 
             match res {
-                Ok(msg) => break msg,
+                Ok(Incoming::Response(msg)) => break msg,
+                Ok(Incoming::Management(msg)) => self.handle_management_message(msg).await?,
                 Err(_) => {
                     warn!(
                         change_set_id = ?self.change_set_id,
@@ -175,6 +270,7 @@ impl Client {
                 }
                 let response = serde_json::from_slice::<Response>(msg.payload())?;
                 span.record("response", field::debug(&response));
+                self.record_response(&response).await;
                 Ok(Some(response))
             }
             None => {
@@ -186,6 +282,51 @@ impl Client {
         }
     }
 
+    async fn handle_management_message(&self, msg: Message) -> ClientResult<()> {
+        match serde_json::from_slice::<ManagementResponse>(msg.payload()) {
+            Ok(ManagementResponse::Reregister { change_set_ids })
+                if change_set_ids
+                    .as_ref()
+                    .map_or(true, |ids| ids.contains(&self.change_set_id)) =>
+            {
+                info!(
+                    change_set_id = ?self.change_set_id,
+                    reply_channel = ?self.reply_channel,
+                    "council asked for it; registering unprocessed dependency graph and reporting processed values again",
+                );
+                self.clone_into_pub().reregister_dependency_graph().await
+            }
+            // Restarting jobs is up to whoever owns them, through a management client, and other
+            // change sets are not ours to register.
+            Ok(_) => Ok(()),
+            Err(err) => {
+                warn!(error = ?err, "unable to deserialize management message");
+                Ok(())
+            }
+        }
+    }
+
+    async fn record_response(&self, response: &Response) {
+        let node_ids = match response {
+            Response::BeenProcessed { node_id } | Response::Failed { node_id } => {
+                std::slice::from_ref(node_id)
+            }
+            Response::DependencyCycle { node_ids } | Response::Stalled { node_ids } => {
+                node_ids.as_slice()
+            }
+            _ => return,
+        };
+
+        let mut progress = self.progress.lock().await;
+        for node_id in node_ids {
+            progress.unprocessed.remove(node_id);
+            progress.reported.remove(node_id);
+        }
+        for dependencies in progress.unprocessed.values_mut() {
+            dependencies.retain(|dependency| !node_ids.contains(dependency));
+        }
+    }
+
     #[instrument(
         name = "council_client.register_dependency_graph",
         level = "info",
@@ -206,3 +347,8 @@ impl Client {
         self.clone_into_pub().bye().await
     }
 }
+
+enum Incoming {
+    Management(Message),
+    Response(Option<Message>),
+}
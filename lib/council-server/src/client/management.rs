@@ -9,23 +9,75 @@ use telemetry::{prelude::*, tracing::field};
 use telemetry_nats::propagation;
 
 use crate::client::{ClientError, ClientResult};
-use crate::{ManagementResponse, SubjectGenerator};
+use crate::{Id, ManagementResponse, Request, SubjectGenerator};
+
+/// How long to wait for another council instance to answer a graph dump.
+const DUMP_GRAPH_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Debug)]
 pub struct ManagementClient {
     management_channel: Subject,
+    management_request_channel: Subject,
     management_subscriber: Subscriber,
+    nats: NatsClient,
 }
 
 impl ManagementClient {
     pub async fn new(nats: &NatsClient, subject_prefix: Option<String>) -> ClientResult<Self> {
-        let management_channel = SubjectGenerator::for_management_client(subject_prefix);
+        let management_channel = SubjectGenerator::for_management_client(subject_prefix.clone());
+        let management_request_channel = SubjectGenerator::for_management_requests(subject_prefix);
         Ok(Self {
             management_subscriber: nats.subscribe(management_channel.clone()).await?,
             management_channel: management_channel.into(),
+            management_request_channel: management_request_channel.into(),
+            nats: nats.clone(),
         })
     }
 
+    /// Asks council for its graph data for debugging, either for a single change set or for all
+    /// of them. The result is keyed by change set id. Every council instance answers for the change
+    /// sets it owns, so dumping all of them waits until no more instances answer.
+    #[instrument(
+        name = "council_management_client.dump_graph",
+        level = "info",
+        skip(self)
+    )]
+    pub async fn dump_graph(&self, change_set_id: Option<Id>) -> ClientResult<serde_json::Value> {
+        let message = serde_json::to_vec(&Request::DumpGraph { change_set_id })?;
+        let reply_channel = self.nats.new_inbox();
+        let mut replies = self.nats.subscribe(reply_channel.clone()).await?;
+        self.nats
+            .publish_with_reply_and_headers(
+                self.management_request_channel.clone(),
+                reply_channel,
+                propagation::empty_injected_headers(),
+                message.into(),
+            )
+            .await?;
+
+        let mut answered = false;
+        let mut change_sets = serde_json::Map::new();
+        while let Ok(Some(msg)) = tokio::time::timeout(DUMP_GRAPH_TIMEOUT, replies.next()).await {
+            match serde_json::from_slice::<ManagementResponse>(msg.payload())? {
+                ManagementResponse::GraphDump {
+                    change_sets: serde_json::Value::Object(dump),
+                } => change_sets.extend(dump),
+                response => return Err(ClientError::UnexpectedManagementResponse(response)),
+            }
+            answered = true;
+
+            // Only the owner of a change set answers for it.
+            if change_set_id.is_some() {
+                break;
+            }
+        }
+        if !answered {
+            return Err(ClientError::NoListenerAvailable);
+        }
+
+        Ok(serde_json::Value::Object(change_sets))
+    }
+
     // None means subscriber has been unsubscribed or that the connection has been closed
     #[instrument(
         name = "council_management_client.fetch_response",
@@ -72,6 +72,10 @@ pub enum Request {
     Bye {
         change_set_id: Id,
     },
+    /// Asks for the graph data of one change set, or of every change set if none is given.
+    DumpGraph {
+        change_set_id: Option<Id>,
+    },
     ProcessedValue {
         change_set_id: Id,
         node_id: Id,
@@ -87,20 +91,61 @@ pub enum Request {
     },
 }
 
+impl Request {
+    /// The change set the request is about, which decides the council instance that handles it.
+    pub fn change_set_id(&self) -> Option<Id> {
+        match self {
+            Self::Bye { change_set_id }
+            | Self::ProcessedValue { change_set_id, .. }
+            | Self::ValueDependencyGraph { change_set_id, .. }
+            | Self::ValueProcessingFailed { change_set_id, .. } => Some(*change_set_id),
+            Self::DumpGraph { change_set_id } => *change_set_id,
+            Self::Restart => None,
+        }
+    }
+}
+
 #[remain::sorted]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "kind")]
 pub enum Response {
-    BeenProcessed { node_id: Id },
-    Failed { node_id: Id },
-    OkToProcess { node_ids: Vec<Id> },
+    BeenProcessed {
+        node_id: Id,
+    },
+    /// The dependency graph sent was rejected, because these nodes would depend on each other in a
+    /// cycle.
+    DependencyCycle {
+        node_ids: Vec<Id>,
+    },
+    Failed {
+        node_id: Id,
+    },
+    OkToProcess {
+        node_ids: Vec<Id>,
+    },
     Restart,
     Shutdown,
+    /// These nodes were removed from the graph, because the job processing them (or a node they
+    /// depend on) made no progress for too long.
+    Stalled {
+        node_ids: Vec<Id>,
+    },
 }
 
 #[remain::sorted]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "kind")]
 pub enum ManagementResponse {
+    GraphDump {
+        change_sets: serde_json::Value,
+    },
+    /// Council recovered its state after restarting, or took over change sets from another council
+    /// instance, and clients working on the given change sets (or on any, if none are given) should
+    /// send the parts of their dependency graph that have not been processed yet, in case they were
+    /// lost.
+    Reregister {
+        #[serde(default)]
+        change_set_ids: Option<Vec<Id>>,
+    },
     Restart,
 }
@@ -1,29 +1,47 @@
 use futures::StreamExt;
 use graph::ChangeSetGraph;
-use si_data_nats::{NatsClient, Subject, Subscriber};
+use si_data_nats::{async_nats::jetstream, HeaderMap, NatsClient, Subject, Subscriber};
 use std::time::Duration;
 use telemetry::prelude::*;
 use telemetry_nats::propagation;
 use tokio::{signal, sync::watch};
 
 use crate::subject_generator::{ManagementChannel, ManagementReplyChannel};
-use crate::{Graph, Id, Request, Response};
+use crate::{Graph, Id, ManagementResponse, Request, Response};
 use crate::{RequestDiscriminants, SubjectGenerator};
 
 pub use config::Config;
 
+use shard::{Ownership, Shard};
+
 pub mod config;
 mod graph;
+mod shard;
+mod state;
+
+/// Set, to the id of the council instance that sent it, on every message council broadcasts on the management
+/// channel. Council subscribes to the management channel along with every other, and uses it to tell the broadcasts
+/// apart from requests.
+const MANAGEMENT_SENDER_HEADER_KEY: &str = "X-Council-Management-Sender";
+
+/// The longest council goes between checks for stalled nodes, whether or not any messages arrive.
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone)]
 pub struct Server {
     nats: NatsClient,
+    persist_state: bool,
+    instance_id: String,
+    stall_timeout: Duration,
 }
 
 impl Server {
     pub async fn new_with_config(config: config::Config) -> Result<Self> {
         Ok(Self {
             nats: NatsClient::new(config.nats()).await?,
+            persist_state: config.persist_state(),
+            instance_id: config.instance_id().to_owned(),
+            stall_timeout: config.stall_timeout(),
         })
     }
 
@@ -69,19 +87,41 @@ impl Server {
             }
         });
 
-        // Before entering the main loop, tell everyone subscribing to the management channel (i.e. all pinga instances)
-        // to restart their jobs.
-        self.nats
-            .publish_with_reply(
-                management_channel.clone(),
-                management_reply_channel.clone(),
-                serde_json::to_vec(&Response::Restart)?.into(),
-            )
-            .await?;
+        let mut complete_graph = ChangeSetGraph::default();
+        let mut shard = if self.persist_state {
+            let mut shard = Shard::new(&self.nats, self.instance_id.clone()).await?;
+            if let Err(err) = shard.rebalance(&mut complete_graph).await {
+                error!(error = ?err, "unable to take over change sets; will retry");
+            }
+            // Everyone is asked to register again below.
+            shard.take_recovered();
+            Some(shard)
+        } else {
+            None
+        };
+
+        // Before entering the main loop, tell everyone subscribing to the management channel (i.e. all pinga
+        // instances and council clients) what to do about jobs in progress. If our state survives restarts, the jobs
+        // only need to send the dependency data we may have missed while we were away. Otherwise, they have to be
+        // restarted.
+        let management_response = if shard.is_some() {
+            ManagementResponse::Reregister {
+                change_set_ids: None,
+            }
+        } else {
+            ManagementResponse::Restart
+        };
+        self.broadcast(
+            &management_channel,
+            &management_reply_channel,
+            &management_response,
+        )
+        .await?;
         info!(
             %management_channel,
             %management_reply_channel,
-            "published message for all active pinga instances to restart jobs in progress",
+            ?management_response,
+            "published message for all active pinga instances about jobs in progress",
         );
 
         // Begin the main loop. Everything after this point should be infallible.
@@ -91,12 +131,43 @@ impl Server {
             &mut our_shutdown_request_rx,
             &management_channel,
             &management_reply_channel,
+            &mut complete_graph,
+            shard.as_mut(),
         )
         .await;
 
+        // Hand our change sets over to the remaining council instances, rather than having them wait for our
+        // heartbeat to expire.
+        if let Some(shard) = shard.as_mut() {
+            shard.leave(&mut complete_graph).await?;
+        }
+
         Ok(())
     }
 
+    /// Publishes a message to everyone subscribing to the management channel, marked as sent by this instance.
+    async fn broadcast(
+        &self,
+        management_channel: &ManagementChannel,
+        management_reply_channel: &ManagementReplyChannel,
+        management_response: &ManagementResponse,
+    ) -> Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert(MANAGEMENT_SENDER_HEADER_KEY, self.instance_id.as_str());
+        propagation::inject_headers(&mut headers);
+        self.nats
+            .publish_with_reply_and_headers(
+                management_channel.clone(),
+                management_reply_channel.clone(),
+                headers,
+                serde_json::to_vec(management_response)?.into(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn core_loop_infallible_wrapper(
         &self,
         subscriber: &mut Subscriber,
@@ -104,8 +175,9 @@ impl Server {
         our_shutdown_request_rx: &mut watch::Receiver<()>,
         management_channel: &ManagementChannel,
         management_reply_channel: &ManagementReplyChannel,
+        complete_graph: &mut ChangeSetGraph,
+        mut shard: Option<&mut Shard>,
     ) {
-        let mut complete_graph = ChangeSetGraph::default();
         loop {
             match self
                 .core_loop(
@@ -114,7 +186,8 @@ impl Server {
                     our_shutdown_request_rx,
                     management_channel,
                     management_reply_channel,
-                    &mut complete_graph,
+                    complete_graph,
+                    shard.as_deref_mut(),
                 )
                 .await
             {
@@ -126,6 +199,7 @@ impl Server {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn core_loop(
         &self,
         subscriber: &mut Subscriber,
@@ -134,8 +208,49 @@ impl Server {
         management_channel: &ManagementChannel,
         management_reply_channel: &ManagementReplyChannel,
         complete_graph: &mut ChangeSetGraph,
+        mut shard: Option<&mut Shard>,
     ) -> Result<()> {
+        let mut heartbeat = tokio::time::interval(shard::HEARTBEAT_INTERVAL);
+        heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut stall_check = tokio::time::interval(
+            self.stall_timeout
+                .min(STALL_CHECK_INTERVAL)
+                .max(Duration::from_secs(1)),
+        );
+        stall_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut acquire = tokio::time::interval(shard::ACQUIRE_INTERVAL);
+        acquire.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
         loop {
+            if let Some(shard) = shard.as_deref_mut() {
+                let change_set_ids = shard.take_recovered();
+                if !change_set_ids.is_empty() {
+                    info!(
+                        ?change_set_ids,
+                        "asking jobs of change sets taken over to register again"
+                    );
+                    self.broadcast(
+                        management_channel,
+                        management_reply_channel,
+                        &ManagementResponse::Reregister {
+                            change_set_ids: Some(change_set_ids),
+                        },
+                    )
+                    .await?;
+                }
+            }
+
+            for (reply_channel, node_ids) in complete_graph.remove_stalled(self.stall_timeout) {
+                warn!(%reply_channel, ?node_ids, "AttributeValues stalled");
+                self.nats
+                    .publish_with_headers(
+                        reply_channel,
+                        propagation::empty_injected_headers(),
+                        serde_json::to_vec(&Response::Stalled { node_ids })?.into(),
+                    )
+                    .await?;
+            }
+
             for (reply_channel, node_ids) in complete_graph.fetch_all_available() {
                 info!(%reply_channel, ?node_ids, "Ok to process AttributeValue");
                 self.nats
@@ -147,6 +262,14 @@ impl Server {
                     .await?;
             }
 
+            // Snapshot the graph once everything it led to has been published, so that a restarted council picks up
+            // exactly where this one left off.
+            if let Some(shard) = shard.as_deref_mut() {
+                if let Err(err) = shard.save(complete_graph).await {
+                    error!(error = ?err, "unable to save council state");
+                }
+            }
+
             let sleep = tokio::time::sleep(Duration::from_secs(60));
             tokio::pin!(sleep);
             // FIXME: handle timeouts
@@ -160,9 +283,46 @@ impl Server {
                     }
                     continue;
                 }
+                // Stalled nodes are removed at the top of the loop.
+                _ = stall_check.tick() => continue,
+                _ = acquire.tick(), if shard.as_deref().map_or(false, Shard::has_held) => {
+                    if let Some(shard) = shard.as_deref_mut() {
+                        for (reply_channel, request) in shard.acquire_held(complete_graph).await {
+                            if let Err(err) = self
+                                .handle_request(
+                                    complete_graph,
+                                    reply_channel,
+                                    request,
+                                    management_channel,
+                                    management_reply_channel,
+                                    true,
+                                )
+                                .await
+                            {
+                                error!(error = ?err, "unable to handle request held for change set taken over");
+                            }
+                        }
+                    }
+                    continue;
+                }
+                _ = heartbeat.tick(), if shard.is_some() => {
+                    if let Some(shard) = shard.as_deref_mut() {
+                        if let Err(err) = shard.rebalance(complete_graph).await {
+                            error!(error = ?err, "unable to rebalance change sets between council instances");
+                        }
+                    }
+                    continue;
+                }
                 req = subscriber.next() => match req {
                     Some(msg) => {
                         propagation::associate_current_span_from_headers(msg.headers());
+                        if let Some(sender) = msg
+                            .headers()
+                            .and_then(|headers| headers.get(MANAGEMENT_SENDER_HEADER_KEY))
+                        {
+                            trace!(%sender, "ignoring management broadcast from a council instance");
+                            continue;
+                        }
                         match (serde_json::from_slice::<Request>(msg.payload()), msg.reply()) {
                             (Ok(req), Some(reply)) => (reply.to_owned(), req),
                             (Err(err), _) => {
@@ -192,85 +352,158 @@ impl Server {
                 else => unreachable!(),
             };
 
-            // Cache the reply channel in case we are missing dependency data and need to restart.
-            let cached_reply_channel = reply_channel.clone();
+            // Every council instance sees every request, but only the one owning the change set handles it.
+            if let (Some(shard), Some(change_set_id)) =
+                (shard.as_deref_mut(), request.change_set_id())
+            {
+                match shard.ownership(change_set_id) {
+                    Ownership::Owned => {}
+                    Ownership::Pending => {
+                        trace!(%change_set_id, "holding request for change set not yet taken over");
+                        shard.hold(change_set_id, reply_channel, request);
+                        continue;
+                    }
+                    Ownership::Elsewhere => {
+                        if let Request::Bye { .. } = request {
+                            shard.record_departure(change_set_id, reply_channel);
+                        }
+                        trace!(%change_set_id, "ignoring request for change set owned by another council instance");
+                        continue;
+                    }
+                }
+            }
 
-            let (result, discrim) = match request {
-                Request::ValueDependencyGraph {
+            self.handle_request(
+                complete_graph,
+                reply_channel,
+                request,
+                management_channel,
+                management_reply_channel,
+                shard.is_some(),
+            )
+            .await?;
+        }
+    }
+
+    async fn handle_request(
+        &self,
+        complete_graph: &mut ChangeSetGraph,
+        reply_channel: Subject,
+        request: Request,
+        management_channel: &ManagementChannel,
+        management_reply_channel: &ManagementReplyChannel,
+        sharded: bool,
+    ) -> Result<()> {
+        // Cache the reply channel in case we are missing dependency data and need to restart.
+        let cached_reply_channel = reply_channel.clone();
+
+        let (result, discrim) = match request {
+            Request::ValueDependencyGraph {
+                change_set_id,
+                dependency_graph,
+            } => (
+                register_graph_from_job(
+                    complete_graph,
+                    reply_channel,
                     change_set_id,
                     dependency_graph,
-                } => (
-                    register_graph_from_job(
-                        complete_graph,
-                        reply_channel,
-                        change_set_id,
-                        dependency_graph,
-                    )
-                    .await,
-                    RequestDiscriminants::ValueDependencyGraph,
-                ),
-                Request::ProcessedValue {
+                )
+                .await,
+                RequestDiscriminants::ValueDependencyGraph,
+            ),
+            Request::ProcessedValue {
+                change_set_id,
+                node_id,
+            } => (
+                job_processed_a_value(
+                    &self.nats,
+                    complete_graph,
+                    reply_channel,
                     change_set_id,
                     node_id,
-                } => (
-                    job_processed_a_value(
-                        &self.nats,
-                        complete_graph,
-                        reply_channel,
-                        change_set_id,
-                        node_id,
-                    )
-                    .await,
-                    RequestDiscriminants::ProcessedValue,
-                ),
-                Request::Bye { change_set_id } => (
-                    job_is_going_away(complete_graph, reply_channel, change_set_id).await,
-                    RequestDiscriminants::Bye,
-                ),
-                Request::ValueProcessingFailed {
+                )
+                .await,
+                RequestDiscriminants::ProcessedValue,
+            ),
+            Request::DumpGraph { change_set_id } => (
+                dump_graph(&self.nats, complete_graph, reply_channel, change_set_id).await,
+                RequestDiscriminants::DumpGraph,
+            ),
+            Request::Bye { change_set_id } => (
+                job_is_going_away(complete_graph, reply_channel, change_set_id).await,
+                RequestDiscriminants::Bye,
+            ),
+            Request::ValueProcessingFailed {
+                change_set_id,
+                node_id,
+            } => (
+                job_failed_processing_a_value(
+                    &self.nats,
+                    complete_graph,
+                    reply_channel,
                     change_set_id,
                     node_id,
-                } => (
-                    job_failed_processing_a_value(
-                        &self.nats,
-                        complete_graph,
-                        reply_channel,
-                        change_set_id,
-                        node_id,
-                    )
-                    .await,
-                    RequestDiscriminants::ValueProcessingFailed,
-                ),
-                Request::Restart => {
-                    debug!(
-                        %management_channel,
-                        %management_reply_channel,
-                        "found restart request sent to everyone subscribing to management channel: no-op",
-                    );
-                    (Ok(()), RequestDiscriminants::Restart)
-                }
-            };
+                )
+                .await,
+                RequestDiscriminants::ValueProcessingFailed,
+            ),
+            Request::Restart => {
+                debug!(
+                    %management_channel,
+                    %management_reply_channel,
+                    "found restart request sent to everyone subscribing to management channel: no-op",
+                );
+                (Ok(()), RequestDiscriminants::Restart)
+            }
+        };
 
-            match result {
-                Ok(()) => match discrim {
-                    RequestDiscriminants::Restart => {
-                        debug!("no-op successful for restart request")
-                    }
-                    discrim => debug!(?discrim, "processing request successful"),
-                },
-                Err(err) => match err {
-                    Error::DependencyDataMissing => {
-                        self.nats
-                            .publish(
-                                cached_reply_channel,
-                                serde_json::to_vec(&Response::Restart)?.into(),
-                            )
-                            .await?;
-                    }
-                    err => return Err(err),
-                },
+        match result {
+            Ok(()) => match discrim {
+                RequestDiscriminants::Restart => {
+                    debug!("no-op successful for restart request")
+                }
+                discrim => debug!(?discrim, "processing request successful"),
+            },
+            // Jobs report the values they processed again when their change set changes owners, in case the reports
+            // were dropped while it was between them. A value that is gone from the graph, or is not being
+            // processed by the job, has already been reported.
+            Err(
+                Error::DependencyDataMissing
+                | Error::ShouldNotBeProcessingByJob
+                | Error::UnknownNodeId,
+            ) if sharded
+                && matches!(
+                    discrim,
+                    RequestDiscriminants::ProcessedValue
+                        | RequestDiscriminants::ValueProcessingFailed
+                ) =>
+            {
+                debug!(reply_channel = %cached_reply_channel, ?discrim, "ignoring report of a value already handled");
             }
+            Err(err) => match err {
+                Error::DependencyCycle(node_ids) => {
+                    warn!(reply_channel = %cached_reply_channel, ?node_ids, "rejected dependency graph with a cycle");
+                    self.nats
+                        .publish_with_headers(
+                            cached_reply_channel,
+                            propagation::empty_injected_headers(),
+                            serde_json::to_vec(&Response::DependencyCycle { node_ids })?.into(),
+                        )
+                        .await?;
+                }
+                Error::DependencyDataMissing => {
+                    self.nats
+                        .publish(
+                            cached_reply_channel,
+                            serde_json::to_vec(&Response::Restart)?.into(),
+                        )
+                        .await?;
+                }
+                err => return Err(err),
+            },
         }
+
+        Ok(())
     }
 }
 
@@ -299,10 +532,20 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 pub enum Error {
     #[error(transparent)]
     Config(#[from] config::ConfigError),
+    #[error("dependency cycle between attribute values: {}", display_ids(.0))]
+    DependencyCycle(Vec<Id>),
     #[error("missing dependency data")]
     DependencyDataMissing,
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("key value error: {0}")]
+    KeyValue(#[source] si_data_nats::InnerError),
+    #[error("key value create error: {0}")]
+    KeyValueCreate(#[from] jetstream::context::CreateKeyValueError),
+    #[error("key value entry error: {0}")]
+    KeyValueEntry(#[from] jetstream::kv::EntryError),
+    #[error("key value put error: {0}")]
+    KeyValuePut(#[from] jetstream::kv::PutError),
     #[error(transparent)]
     Nats(#[from] si_data_nats::Error),
     #[error("serde json: {0}")]
@@ -315,6 +558,13 @@ pub enum Error {
     UnknownNodeId,
 }
 
+fn display_ids(ids: &[Id]) -> String {
+    ids.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
 pub async fn register_graph_from_job(
     complete_graph: &mut ChangeSetGraph,
     reply_channel: Subject,
@@ -387,3 +637,21 @@ pub async fn job_is_going_away(
 
     Ok(())
 }
+
+pub async fn dump_graph(
+    nats: &NatsClient,
+    complete_graph: &ChangeSetGraph,
+    reply_channel: Subject,
+    change_set_id: Option<Id>,
+) -> Result<(), Error> {
+    debug!(%reply_channel, ?change_set_id, "Dumping graph");
+    let change_sets = complete_graph.dump(change_set_id)?;
+    nats.publish_with_headers(
+        reply_channel,
+        propagation::empty_injected_headers(),
+        serde_json::to_vec(&ManagementResponse::GraphDump { change_sets })?.into(),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
+use std::time::Duration;
+
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use si_data_nats::NatsConfig;
 pub use si_settings::{StandardConfig, StandardConfigFile};
+use ulid::Ulid;
 
 #[remain::sorted]
 #[derive(Debug, thiserror::Error)]
@@ -18,15 +21,41 @@ pub type Result<T, E = ConfigError> = std::result::Result<T, E>;
 pub struct Config {
     #[builder(default = "NatsConfig::default()")]
     nats: NatsConfig,
+
+    #[builder(default = "default_persist_state()")]
+    persist_state: bool,
+
+    #[builder(default = "random_instance_id()")]
+    instance_id: String,
+
+    #[builder(default = "default_stall_timeout_secs()")]
+    stall_timeout_secs: u64,
 }
 
 impl StandardConfig for Config {
     type Builder = ConfigBuilder;
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ConfigFile {
     nats: NatsConfig,
+    #[serde(default = "default_persist_state")]
+    persist_state: bool,
+    #[serde(default = "random_instance_id")]
+    instance_id: String,
+    #[serde(default = "default_stall_timeout_secs")]
+    stall_timeout_secs: u64,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            nats: Default::default(),
+            persist_state: default_persist_state(),
+            instance_id: random_instance_id(),
+            stall_timeout_secs: default_stall_timeout_secs(),
+        }
+    }
 }
 
 impl StandardConfigFile for ConfigFile {
@@ -39,6 +68,9 @@ impl TryFrom<ConfigFile> for Config {
     fn try_from(value: ConfigFile) -> Result<Self> {
         let mut config = Config::builder();
         config.nats(value.nats);
+        config.persist_state(value.persist_state);
+        config.instance_id(value.instance_id);
+        config.stall_timeout_secs(value.stall_timeout_secs);
         config.build().map_err(Into::into)
     }
 }
@@ -54,4 +86,37 @@ impl Config {
     pub fn subject_prefix(&self) -> Option<&str> {
         self.nats.subject_prefix.as_deref()
     }
+
+    /// Whether council snapshots its state to NATS KV and recovers it on boot. This is also what
+    /// lets several council instances share change sets between them. Off by default.
+    ///
+    /// Requires JetStream on the NATS server: council boots only if it can find or create the
+    /// `COUNCIL_STATE` and `COUNCIL_MEMBERS` KV buckets, whose names are prefixed with the subject
+    /// prefix and an underscore if there is one.
+    pub fn persist_state(&self) -> bool {
+        self.persist_state
+    }
+
+    /// Gets a reference to the config's instance id, which identifies this council instance among
+    /// the others sharing change sets with it.
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// How long a job may process a node without any progress before council gives up on it.
+    pub fn stall_timeout(&self) -> Duration {
+        Duration::from_secs(self.stall_timeout_secs)
+    }
+}
+
+fn default_persist_state() -> bool {
+    false
+}
+
+fn default_stall_timeout_secs() -> u64 {
+    15 * 60
+}
+
+fn random_instance_id() -> String {
+    Ulid::new().to_string()
 }
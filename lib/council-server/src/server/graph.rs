@@ -1,15 +1,24 @@
 use node_metadata::NodeMetadata;
+use serde::{Deserialize, Serialize};
 use si_data_nats::Subject;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, SystemTime};
 use telemetry::prelude::*;
 
 use crate::{server::Error, Graph, Id};
 
 mod node_metadata;
 
-#[derive(Default, Debug)]
+/// The graph data of a single change set.
+pub type ChangeSetNodes = HashMap<Id, NodeMetadata>;
+
+#[derive(Default, Debug, Deserialize, Serialize)]
 pub struct ChangeSetGraph {
-    dependency_data: HashMap<Id, HashMap<Id, NodeMetadata>>,
+    dependency_data: HashMap<Id, ChangeSetNodes>,
+    /// The change sets whose graph data has changed since [`take_changed`](Self::take_changed)
+    /// was last called.
+    #[serde(skip)]
+    changed: HashSet<Id>,
 }
 
 impl ChangeSetGraph {
@@ -17,11 +26,36 @@ impl ChangeSetGraph {
         self.dependency_data.is_empty()
     }
 
+    pub fn change_set(&self, change_set_id: Id) -> Option<&ChangeSetNodes> {
+        self.dependency_data.get(&change_set_id)
+    }
+
+    pub fn change_set_ids(&self) -> Vec<Id> {
+        self.dependency_data.keys().copied().collect()
+    }
+
+    pub fn insert_change_set(&mut self, change_set_id: Id, nodes: ChangeSetNodes) {
+        if !nodes.is_empty() {
+            self.dependency_data.insert(change_set_id, nodes);
+        }
+    }
+
+    pub fn remove_change_set(&mut self, change_set_id: Id) -> Option<ChangeSetNodes> {
+        self.dependency_data.remove(&change_set_id)
+    }
+
+    /// Returns the change sets whose graph data has changed since this was last called, including
+    /// those that no longer have any.
+    pub fn take_changed(&mut self) -> HashSet<Id> {
+        std::mem::take(&mut self.changed)
+    }
+
     pub fn fetch_all_available(&mut self) -> HashMap<String, Vec<Id>> {
         let mut result: HashMap<String, Vec<Id>> = HashMap::new();
-        for graph in self.dependency_data.values_mut() {
+        for (change_set_id, graph) in self.dependency_data.iter_mut() {
             for (id, metadata) in graph.iter_mut() {
                 if let Some(reply_channel) = metadata.next_to_process() {
+                    self.changed.insert(*change_set_id);
                     result
                         .entry(reply_channel.to_string())
                         .or_default()
@@ -38,6 +72,13 @@ impl ChangeSetGraph {
         new_dependency_data: Graph,
         change_set_id: Id,
     ) -> Result<(), Error> {
+        // A cycle would leave every node in it waiting on another forever, so refuse the new data
+        // before any of it is merged.
+        if let Some(cycle) = self.find_cycle(change_set_id, &new_dependency_data) {
+            return Err(Error::DependencyCycle(cycle));
+        }
+
+        self.changed.insert(change_set_id);
         let change_set_graph_data = self.dependency_data.entry(change_set_id).or_default();
 
         for (attribute_value_id, dependencies) in new_dependency_data {
@@ -71,6 +112,69 @@ impl ChangeSetGraph {
         Ok(())
     }
 
+    /// Returns the node ids forming a cycle, if merging `new_dependency_data` into the graph for
+    /// `change_set_id` would create one. Each node in the returned cycle depends on the next, and
+    /// the last depends on the first.
+    fn find_cycle(&self, change_set_id: Id, new_dependency_data: &Graph) -> Option<Vec<Id>> {
+        let mut depends_on: HashMap<Id, HashSet<Id>> = HashMap::new();
+        if let Some(graph) = self.dependency_data.get(&change_set_id) {
+            for (id, metadata) in graph {
+                depends_on
+                    .entry(*id)
+                    .or_default()
+                    .extend(metadata.depends_on_node_ids_iter());
+            }
+        }
+        for (id, dependencies) in new_dependency_data {
+            depends_on.entry(*id).or_default().extend(dependencies);
+        }
+
+        let dependencies_of = |id: &Id| -> std::vec::IntoIter<Id> {
+            depends_on
+                .get(id)
+                .map(|dependencies| dependencies.iter().copied().collect::<Vec<_>>())
+                .unwrap_or_default()
+                .into_iter()
+        };
+
+        let mut visited = HashSet::new();
+        for start in depends_on.keys() {
+            if visited.contains(start) {
+                continue;
+            }
+
+            // Depth first search, keeping the path from `start` to the current node so a cycle
+            // can be reported when the search comes back around to a node on it.
+            let mut path = vec![*start];
+            let mut on_path = HashSet::from([*start]);
+            let mut pending = vec![dependencies_of(start)];
+            while let Some(dependencies) = pending.last_mut() {
+                match dependencies.next() {
+                    Some(dependency) if on_path.contains(&dependency) => {
+                        let cycle_start = path.iter().position(|id| *id == dependency)?;
+                        return Some(path.split_off(cycle_start));
+                    }
+                    Some(dependency) => {
+                        if visited.insert(dependency) {
+                            path.push(dependency);
+                            on_path.insert(dependency);
+                            pending.push(dependencies_of(&dependency));
+                        }
+                    }
+                    None => {
+                        pending.pop();
+                        if let Some(id) = path.pop() {
+                            on_path.remove(&id);
+                        }
+                    }
+                }
+            }
+            visited.insert(*start);
+        }
+
+        None
+    }
+
     pub fn mark_node_as_processed(
         &mut self,
         reply_channel: &Subject,
@@ -88,6 +192,14 @@ impl ChangeSetGraph {
             } else {
                 return Err(Error::UnknownNodeId);
             };
+        self.changed.insert(change_set_id);
+
+        // The job is still working through what it was given, so nothing else it is processing has
+        // stalled yet.
+        let now = SystemTime::now();
+        for node_metadata in change_set_graph_data.values_mut() {
+            node_metadata.record_processing_progress(reply_channel, now);
+        }
 
         if ok_to_remove_node {
             change_set_graph_data.remove(&node_id);
@@ -106,6 +218,7 @@ impl ChangeSetGraph {
 
     pub fn remove_channel(&mut self, change_set_id: Id, reply_channel: &Subject) {
         if let Some(graph) = self.dependency_data.get_mut(&change_set_id) {
+            self.changed.insert(change_set_id);
             let mut to_remove = Vec::new();
             for (id, metadata) in graph.iter_mut() {
                 metadata.remove_channel(reply_channel);
@@ -122,6 +235,67 @@ impl ChangeSetGraph {
         }
     }
 
+    /// Remove every node a job has been processing for longer than `stall_timeout` without any
+    /// progress, along with the nodes depending on it. Returns the removed node ids, grouped by the
+    /// reply channels of the jobs that wanted or were processing them.
+    pub fn remove_stalled(&mut self, stall_timeout: Duration) -> HashMap<String, Vec<Id>> {
+        let now = SystemTime::now();
+        let mut stalled = Vec::new();
+        for (change_set_id, graph) in &self.dependency_data {
+            for (node_id, metadata) in graph {
+                if metadata.is_processing_stalled(now, stall_timeout) {
+                    if let Some(reply_channel) = metadata.processing_reply_channel() {
+                        stalled.push((*change_set_id, *node_id, reply_channel.clone()));
+                    }
+                }
+            }
+        }
+
+        let mut result: HashMap<String, Vec<Id>> = HashMap::new();
+        for (change_set_id, node_id, reply_channel) in stalled {
+            // An earlier stalled node may have taken this one out with it already.
+            if !self
+                .dependency_data
+                .get(&change_set_id)
+                .map_or(false, |graph| graph.contains_key(&node_id))
+            {
+                continue;
+            }
+
+            warn!(%change_set_id, %node_id, %reply_channel, ?stall_timeout, "AttributeValue processing stalled");
+            match self.remove_node_and_dependents(reply_channel, change_set_id, node_id) {
+                Ok(notifications) => {
+                    for (reply_channel, node_id) in notifications {
+                        let node_ids = result.entry(reply_channel.to_string()).or_default();
+                        if !node_ids.contains(&node_id) {
+                            node_ids.push(node_id);
+                        }
+                    }
+                }
+                Err(err) => {
+                    error!(error = ?err, %change_set_id, %node_id, "unable to remove stalled node");
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Returns the graph data for `change_set_id`, or for every change set, keyed by change set id.
+    pub fn dump(&self, change_set_id: Option<Id>) -> Result<serde_json::Value, Error> {
+        let dump = match change_set_id {
+            Some(change_set_id) => serde_json::to_value(
+                self.dependency_data
+                    .get_key_value(&change_set_id)
+                    .into_iter()
+                    .collect::<HashMap<_, _>>(),
+            )?,
+            None => serde_json::to_value(&self.dependency_data)?,
+        };
+
+        Ok(dump)
+    }
+
     /// Return all `wanted_by_reply_channels` for `node_id` and remove the node
     /// from the graph. Also, remove the sub-graph starting at `node_id`,
     /// returning all `wanted_by_reply_channels` (with the associated `node_id`)
@@ -137,6 +311,7 @@ impl ChangeSetGraph {
             .dependency_data
             .get_mut(&change_set_id)
             .ok_or(Error::DependencyDataMissing)?;
+        self.changed.insert(change_set_id);
 
         let mut node_ids_to_fail = VecDeque::new();
         node_ids_to_fail.push_back(node_id);
@@ -174,3 +349,203 @@ impl ChangeSetGraph {
         Ok(failure_notifications)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: &str = "council.job.reply";
+
+    fn channel() -> Subject {
+        Subject::from(CHANNEL)
+    }
+
+    /// Whether each node in the cycle depends on the next, and the last on the first.
+    fn is_cycle_of(cycle: &[Id], depends_on: &Graph) -> bool {
+        !cycle.is_empty()
+            && cycle.iter().enumerate().all(|(index, id)| {
+                let next = cycle[(index + 1) % cycle.len()];
+                depends_on
+                    .get(id)
+                    .map_or(false, |dependencies| dependencies.contains(&next))
+            })
+    }
+
+    #[test]
+    fn finds_cycle_in_new_dependency_data() {
+        let graph = ChangeSetGraph::default();
+        let (a, b, c) = (Id::default(), Id::default(), Id::default());
+        let new_dependency_data = Graph::from([(a, vec![b]), (b, vec![c]), (c, vec![a])]);
+
+        let cycle = graph
+            .find_cycle(Id::default(), &new_dependency_data)
+            .expect("should find a cycle");
+
+        assert_eq!(3, cycle.len());
+        assert!(is_cycle_of(&cycle, &new_dependency_data));
+    }
+
+    #[test]
+    fn finds_cycle_through_merged_dependency_data() {
+        let mut graph = ChangeSetGraph::default();
+        let change_set_id = Id::default();
+        let (a, b) = (Id::default(), Id::default());
+        graph
+            .merge_dependency_graph(channel(), Graph::from([(a, vec![b])]), change_set_id)
+            .expect("should merge");
+
+        let result =
+            graph.merge_dependency_graph(channel(), Graph::from([(b, vec![a])]), change_set_id);
+
+        let combined = Graph::from([(a, vec![b]), (b, vec![a])]);
+        assert!(
+            matches!(&result, Err(Error::DependencyCycle(cycle)) if is_cycle_of(cycle, &combined)),
+            "expected a dependency cycle, got {result:?}"
+        );
+        // Nothing from the rejected data was merged.
+        let nodes = graph.change_set(change_set_id).expect("should have nodes");
+        assert!(!nodes[&b].depends_on(a));
+    }
+
+    #[test]
+    fn finds_no_cycle_in_shared_dependencies_or_across_change_sets() {
+        let mut graph = ChangeSetGraph::default();
+        let (a, b, c, d) = (Id::default(), Id::default(), Id::default(), Id::default());
+        let other_change_set_id = Id::default();
+        graph
+            .merge_dependency_graph(channel(), Graph::from([(a, vec![b])]), other_change_set_id)
+            .expect("should merge");
+
+        // A diamond, and the reverse of a dependency in another change set.
+        let new_dependency_data =
+            Graph::from([(a, vec![c, d]), (b, vec![a]), (c, vec![d]), (d, vec![])]);
+
+        assert_eq!(None, graph.find_cycle(Id::default(), &new_dependency_data));
+    }
+
+    #[test]
+    fn finds_self_dependency() {
+        let graph = ChangeSetGraph::default();
+        let a = Id::default();
+
+        assert_eq!(
+            Some(vec![a]),
+            graph.find_cycle(Id::default(), &Graph::from([(a, vec![a])]))
+        );
+    }
+
+    #[test]
+    fn removes_stalled_nodes_and_their_dependents() {
+        let mut graph = ChangeSetGraph::default();
+        let change_set_id = Id::default();
+        let (a, b, c) = (Id::default(), Id::default(), Id::default());
+        // `a` waits on `b`, which is processed first. `c` waits on nothing stalled.
+        graph
+            .merge_dependency_graph(
+                channel(),
+                Graph::from([(a, vec![b]), (c, vec![])]),
+                change_set_id,
+            )
+            .expect("should merge");
+        let available = graph.fetch_all_available();
+        assert_eq!(2, available[CHANNEL].len());
+
+        assert!(graph.remove_stalled(Duration::from_secs(60)).is_empty());
+
+        std::thread::sleep(Duration::from_millis(10));
+        let mut stalled = graph.remove_stalled(Duration::from_millis(1));
+
+        // Both `b` and `c` have stalled, and `a` goes with `b`.
+        let mut node_ids = stalled.remove(CHANNEL).expect("should have stalled nodes");
+        node_ids.sort_by_key(|id| id.to_string());
+        let mut expected = vec![a, b, c];
+        expected.sort_by_key(|id| id.to_string());
+        assert_eq!(expected, node_ids);
+        assert!(stalled.is_empty());
+        assert!(graph.is_empty());
+    }
+
+    #[test]
+    fn tracks_the_change_sets_that_changed() {
+        let mut graph = ChangeSetGraph::default();
+        let (first, second) = (Id::default(), Id::default());
+        let (a, b) = (Id::default(), Id::default());
+        graph
+            .merge_dependency_graph(channel(), Graph::from([(a, vec![])]), first)
+            .expect("should merge");
+        graph
+            .merge_dependency_graph(channel(), Graph::from([(b, vec![])]), second)
+            .expect("should merge");
+        assert_eq!(HashSet::from([first, second]), graph.take_changed());
+        assert!(graph.take_changed().is_empty());
+
+        graph.fetch_all_available();
+        assert_eq!(HashSet::from([first, second]), graph.take_changed());
+
+        // Nothing more to hand out, so nothing changes.
+        graph.fetch_all_available();
+        assert!(graph.take_changed().is_empty());
+
+        graph
+            .mark_node_as_processed(&channel(), first, a)
+            .expect("should mark as processed");
+        assert_eq!(HashSet::from([first]), graph.take_changed());
+        assert!(graph.change_set(first).is_none());
+    }
+
+    #[test]
+    fn does_not_remove_nodes_that_are_waiting() {
+        let mut graph = ChangeSetGraph::default();
+        let change_set_id = Id::default();
+        let (a, b) = (Id::default(), Id::default());
+        graph
+            .merge_dependency_graph(channel(), Graph::from([(a, vec![b])]), change_set_id)
+            .expect("should merge");
+
+        // Nothing has been handed out to process, so nothing can stall.
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(graph.remove_stalled(Duration::from_millis(1)).is_empty());
+        assert_eq!(
+            2,
+            graph
+                .change_set(change_set_id)
+                .expect("should have nodes")
+                .len()
+        );
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let mut graph = ChangeSetGraph::default();
+        let change_set_id = Id::default();
+        let (a, b) = (Id::default(), Id::default());
+        graph
+            .merge_dependency_graph(channel(), Graph::from([(a, vec![b])]), change_set_id)
+            .expect("should merge");
+        // Have `b` be processing, so that every field of its metadata is set.
+        graph.fetch_all_available();
+
+        let serialized = serde_json::to_vec(&graph).expect("should serialize");
+        let mut deserialized: ChangeSetGraph =
+            serde_json::from_slice(&serialized).expect("should deserialize");
+
+        assert_eq!(
+            graph.dump(None).expect("should dump"),
+            deserialized.dump(None).expect("should dump")
+        );
+        let metadata = &deserialized
+            .change_set(change_set_id)
+            .expect("should have nodes")[&b];
+        assert_eq!(Some(&channel()), metadata.processing_reply_channel());
+
+        // The deserialized graph carries on where the original left off.
+        let processed = deserialized
+            .mark_node_as_processed(&channel(), change_set_id, b)
+            .expect("should mark as processed");
+        assert_eq!(HashSet::from([CHANNEL.to_string()]), processed);
+        assert_eq!(
+            HashMap::from([(CHANNEL.to_string(), vec![a])]),
+            deserialized.fetch_all_available()
+        );
+    }
+}
@@ -1,21 +1,30 @@
 use std::{
-    collections::{vec_deque::Iter, HashSet, VecDeque},
-    time::Instant,
+    collections::{hash_set, vec_deque::Iter, HashSet, VecDeque},
+    time::{Duration, SystemTime},
 };
 
+use serde::{Deserialize, Serialize};
 use si_data_nats::Subject;
 
 use crate::{server::Error, Id};
 
-#[derive(Debug)]
+// Timestamps are wall clock times rather than `Instant`s so that they still mean something when
+// the metadata is recovered from a snapshot by another council process.
+#[derive(Debug, Deserialize, Serialize)]
 pub struct NodeMetadata {
     // This should really be an ordered set, to remove duplicates, but we'll deal with
     // that later.
+    #[serde(with = "reply_channels")]
     wanted_by_reply_channels: VecDeque<Subject>,
+    #[serde(with = "reply_channel")]
     processing_reply_channel: Option<Subject>,
     depends_on_node_ids: HashSet<Id>,
-    processing_started_at: Option<Instant>,
-    last_updated_at: Instant,
+    processing_started_at: Option<SystemTime>,
+    /// When the job processing this node last showed any sign of working on it. Unlike
+    /// `last_updated_at`, this is not touched by other jobs merging their graphs into this node.
+    #[serde(default)]
+    processing_progress_at: Option<SystemTime>,
+    last_updated_at: SystemTime,
 }
 
 impl Default for NodeMetadata {
@@ -25,7 +34,8 @@ impl Default for NodeMetadata {
             processing_reply_channel: Option::default(),
             depends_on_node_ids: HashSet::default(),
             processing_started_at: Option::default(),
-            last_updated_at: Instant::now(),
+            processing_progress_at: Option::default(),
+            last_updated_at: SystemTime::now(),
         }
     }
 }
@@ -34,7 +44,7 @@ impl NodeMetadata {
     pub fn add_wanted_by_reply_channel(&mut self, reply_channel: &Subject) {
         self.wanted_by_reply_channels
             .push_back(reply_channel.to_owned());
-        self.last_updated_at = Instant::now();
+        self.last_updated_at = SystemTime::now();
     }
 
     pub fn dependencies_satisfied(&self) -> bool {
@@ -45,6 +55,10 @@ impl NodeMetadata {
         self.depends_on_node_ids.contains(&node_id)
     }
 
+    pub fn depends_on_node_ids_iter(&self) -> hash_set::Iter<'_, Id> {
+        self.depends_on_node_ids.iter()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.wanted_by_reply_channels.is_empty() && self.processing_reply_channel.is_none()
     }
@@ -62,6 +76,30 @@ impl NodeMetadata {
         false
     }
 
+    /// Whether the job processing this node has made no progress for more than `stall_timeout`.
+    pub fn is_processing_stalled(&self, now: SystemTime, stall_timeout: Duration) -> bool {
+        if self.processing_reply_channel.is_none() {
+            return false;
+        }
+
+        // Snapshots taken before progress was tracked only know when processing started.
+        match self.processing_progress_at.or(self.processing_started_at) {
+            Some(processing_progress_at) => now
+                .duration_since(processing_progress_at)
+                .map(|idle| idle > stall_timeout)
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+
+    /// Records that the job with the given reply channel has made progress, which counts towards
+    /// this node if that job is the one processing it.
+    pub fn record_processing_progress(&mut self, reply_channel: &Subject, now: SystemTime) {
+        if self.processing_reply_channel.as_ref() == Some(reply_channel) {
+            self.processing_progress_at = Some(now);
+        }
+    }
+
     pub fn mark_as_processed(
         &mut self,
         reply_channel: &Subject,
@@ -91,7 +129,7 @@ impl NodeMetadata {
     }
 
     pub fn merge_metadata(&mut self, reply_channel: Subject, dependencies: &Vec<Id>) {
-        self.last_updated_at = Instant::now();
+        self.last_updated_at = SystemTime::now();
 
         if !self.wanted_by_reply_channels.contains(&reply_channel) {
             self.wanted_by_reply_channels.push_back(reply_channel);
@@ -101,14 +139,15 @@ impl NodeMetadata {
 
     pub fn next_to_process(&mut self) -> Option<Subject> {
         if self.depends_on_node_ids.is_empty() && self.processing_reply_channel.is_none() {
-            self.last_updated_at = Instant::now();
+            self.last_updated_at = SystemTime::now();
 
             self.processing_reply_channel = self.wanted_by_reply_channels.pop_front();
             if self.processing_reply_channel.is_some() {
-                self.processing_started_at = Some(Instant::now());
+                self.processing_started_at = Some(SystemTime::now());
             } else {
                 self.processing_started_at = None;
             }
+            self.processing_progress_at = self.processing_started_at;
             return self.processing_reply_channel.clone();
         }
         None
@@ -119,7 +158,7 @@ impl NodeMetadata {
     }
 
     pub fn remove_channel(&mut self, reply_channel: &Subject) {
-        self.last_updated_at = Instant::now();
+        self.last_updated_at = SystemTime::now();
 
         self.wanted_by_reply_channels
             .retain(|el| el != reply_channel);
@@ -131,7 +170,7 @@ impl NodeMetadata {
 
     pub fn remove_dependency(&mut self, node_id: Id) {
         if self.depends_on_node_ids.remove(&node_id) {
-            self.last_updated_at = Instant::now();
+            self.last_updated_at = SystemTime::now();
         };
     }
 
@@ -143,3 +182,143 @@ impl NodeMetadata {
         self.wanted_by_reply_channels.iter()
     }
 }
+
+mod reply_channels {
+    use std::collections::VecDeque;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use si_data_nats::Subject;
+
+    pub fn serialize<S>(
+        reply_channels: &VecDeque<Subject>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(reply_channels.iter().map(|s| s.to_string()))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<VecDeque<Subject>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let reply_channels = Vec::<String>::deserialize(deserializer)?;
+        Ok(reply_channels.into_iter().map(Subject::from).collect())
+    }
+}
+
+mod reply_channel {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use si_data_nats::Subject;
+
+    pub fn serialize<S>(reply_channel: &Option<Subject>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match reply_channel {
+            Some(reply_channel) => serializer.serialize_some(&reply_channel.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Subject>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let reply_channel = Option::<String>::deserialize(deserializer)?;
+        Ok(reply_channel.map(Subject::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_serde() {
+        let mut metadata = NodeMetadata::default();
+        metadata.merge_metadata(Subject::from("council.first.reply"), &vec![Id::default()]);
+        metadata.add_wanted_by_reply_channel(&Subject::from("council.second.reply"));
+        metadata.depends_on_node_ids.clear();
+        metadata.next_to_process();
+
+        let serialized = serde_json::to_value(&metadata).expect("should serialize");
+        let deserialized: NodeMetadata =
+            serde_json::from_value(serialized.clone()).expect("should deserialize");
+
+        assert_eq!(
+            serialized,
+            serde_json::to_value(&deserialized).expect("should serialize")
+        );
+        assert_eq!(
+            Some(&Subject::from("council.first.reply")),
+            deserialized.processing_reply_channel()
+        );
+        assert_eq!(
+            vec!["council.second.reply".to_string()],
+            deserialized
+                .wanted_by_reply_channels_iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            metadata.processing_started_at,
+            deserialized.processing_started_at
+        );
+    }
+
+    #[test]
+    fn stalls_despite_other_jobs_merging_into_the_node() {
+        let processing = Subject::from("council.first.reply");
+        let mut metadata = NodeMetadata::default();
+        metadata.merge_metadata(processing.clone(), &vec![]);
+        metadata.next_to_process();
+        let started_at = metadata
+            .processing_started_at
+            .expect("should be processing");
+
+        // Another job wanting the node, and the processing job registering again, are not progress.
+        metadata.merge_metadata(Subject::from("council.second.reply"), &vec![]);
+        metadata.merge_metadata(processing.clone(), &vec![]);
+        let later = started_at + Duration::from_secs(120);
+        assert!(metadata.is_processing_stalled(later, Duration::from_secs(60)));
+
+        // Progress from another job does not count either, but progress from this one does.
+        metadata.record_processing_progress(
+            &Subject::from("council.second.reply"),
+            started_at + Duration::from_secs(90),
+        );
+        assert!(metadata.is_processing_stalled(later, Duration::from_secs(60)));
+        metadata.record_processing_progress(&processing, started_at + Duration::from_secs(90));
+        assert!(!metadata.is_processing_stalled(later, Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn falls_back_to_when_processing_started_for_old_snapshots() {
+        let mut metadata = NodeMetadata::default();
+        metadata.merge_metadata(Subject::from("council.first.reply"), &vec![]);
+        metadata.next_to_process();
+        let started_at = metadata
+            .processing_started_at
+            .expect("should be processing");
+
+        let mut serialized = serde_json::to_value(&metadata).expect("should serialize");
+        serialized
+            .as_object_mut()
+            .expect("should be an object")
+            .remove("processing_progress_at");
+        let deserialized: NodeMetadata =
+            serde_json::from_value(serialized).expect("should deserialize");
+
+        assert_eq!(None, deserialized.processing_progress_at);
+        assert!(!deserialized.is_processing_stalled(
+            started_at + Duration::from_secs(30),
+            Duration::from_secs(60)
+        ));
+        assert!(deserialized.is_processing_stalled(
+            started_at + Duration::from_secs(90),
+            Duration::from_secs(60)
+        ));
+    }
+}
@@ -0,0 +1,480 @@
+//! This module contains [`Shard`], which splits change sets between council instances.
+//!
+//! Every instance heartbeats into a NATS KV bucket whose entries expire, so the live instances are the keys of that
+//! bucket. Change sets are assigned to live instances by consistent hashing of their ids, so an instance joining or
+//! leaving only moves the change sets it gains or loses. Every instance receives every request, and ignores those
+//! for change sets it does not own.
+//!
+//! An instance owns a change set once it has taken over its graph data from the [`StateStore`]. It can only do
+//! that once the previous owner has released it, or has stopped heartbeating. Whether an instance owns a change set
+//! is known without going to the [`StateStore`], so handling a request never waits on it: requests for a change set
+//! assigned to this instance but not yet taken over are held, and handled once a timer has taken it over. Requests
+//! that arrived before an instance knew it was assigned a change set are lost, so the new owner asks the jobs
+//! working on it to register their dependency graphs again, and to report the values they processed since. Jobs that
+//! went away in the meantime cannot be asked, so every instance remembers them for a while, and the new owner forgets
+//! about them once it takes over.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use si_data_nats::{async_nats::jetstream, NatsClient, Subject};
+use telemetry::prelude::*;
+
+use super::graph::ChangeSetGraph;
+use super::state::{self, StateStore};
+use super::{Error, Result};
+use crate::{Id, Request};
+
+const MEMBERS_BUCKET_NAME: &str = "COUNCIL_MEMBERS";
+const VIRTUAL_NODES_PER_MEMBER: u64 = 64;
+
+/// How often an instance heartbeats and rebalances change sets.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long an instance is considered live after its last heartbeat.
+const MEMBER_TTL: Duration = Duration::from_secs(15);
+/// How long jobs going away from change sets owned by another instance are remembered. A change set is between
+/// owners for at most the time it takes its owner's heartbeat to expire and the next one to rebalance.
+const DEPARTURE_TTL: Duration = Duration::from_secs(30);
+/// How often an instance tries to take over the change sets it holds requests for.
+pub const ACQUIRE_INTERVAL: Duration = Duration::from_millis(500);
+/// How many requests are held for a single change set before the oldest are dropped.
+const MAX_HELD_REQUESTS: usize = 10_000;
+
+/// Whether this instance handles the requests for a change set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ownership {
+    /// Another instance is assigned the change set.
+    Elsewhere,
+    /// This instance owns the change set.
+    Owned,
+    /// This instance is assigned the change set, but has not taken it over yet.
+    Pending,
+}
+
+#[derive(Debug)]
+pub struct Shard {
+    instance_id: String,
+    members: jetstream::kv::Store,
+    state_store: StateStore,
+    ring: HashRing,
+    live_members: HashSet<String>,
+    owned: HashSet<Id>,
+    recovered: Vec<Id>,
+    departed: HashMap<Id, Vec<(Subject, Instant)>>,
+    held: HashMap<Id, VecDeque<(Subject, Request)>>,
+    unsaved: HashSet<Id>,
+}
+
+impl Shard {
+    pub async fn new(nats: &NatsClient, instance_id: impl Into<String>) -> Result<Self> {
+        let instance_id = instance_id.into();
+        let members = state::get_or_create_bucket(
+            nats,
+            MEMBERS_BUCKET_NAME,
+            jetstream::kv::Config {
+                description: "Live council instances".to_owned(),
+                history: 1,
+                max_age: MEMBER_TTL,
+                ..Default::default()
+            },
+        )
+        .await?;
+        let state_store = StateStore::new(nats, instance_id.clone()).await?;
+
+        let mut shard = Self {
+            instance_id,
+            members,
+            state_store,
+            ring: HashRing::default(),
+            live_members: HashSet::new(),
+            owned: HashSet::new(),
+            recovered: Vec::new(),
+            departed: HashMap::new(),
+            held: HashMap::new(),
+            unsaved: HashSet::new(),
+        };
+        shard.heartbeat().await?;
+
+        Ok(shard)
+    }
+
+    /// Heartbeats, then hands over the change sets this instance is no longer assigned and takes over the ones it
+    /// newly is.
+    pub async fn rebalance(&mut self, complete_graph: &mut ChangeSetGraph) -> Result<()> {
+        self.heartbeat().await?;
+        self.departed.retain(|_, departures| {
+            departures.retain(|(_, departed_at)| departed_at.elapsed() < DEPARTURE_TTL);
+            !departures.is_empty()
+        });
+
+        let released: Vec<Id> = self
+            .owned
+            .iter()
+            .copied()
+            .filter(|change_set_id| !self.is_assigned(*change_set_id))
+            .collect();
+        for change_set_id in released {
+            let nodes = complete_graph.remove_change_set(change_set_id);
+            self.state_store
+                .release(change_set_id, nodes.as_ref())
+                .await?;
+            self.owned.remove(&change_set_id);
+            info!(%change_set_id, owner = ?self.ring.owner(change_set_id), "handed over change set");
+        }
+
+        for change_set_id in self.state_store.change_set_ids().await? {
+            if !self.owned.contains(&change_set_id) && self.is_assigned(change_set_id) {
+                self.try_acquire(change_set_id, complete_graph).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether this instance handles the requests for the change set, as of the last heartbeat.
+    pub fn ownership(&self, change_set_id: Id) -> Ownership {
+        if self.owned.contains(&change_set_id) {
+            Ownership::Owned
+        } else if self.is_assigned(change_set_id) {
+            Ownership::Pending
+        } else {
+            Ownership::Elsewhere
+        }
+    }
+
+    /// Holds a request for a change set this instance is assigned but has not taken over yet, to be handled once it
+    /// has.
+    pub fn hold(&mut self, change_set_id: Id, reply_channel: Subject, request: Request) {
+        let held = self.held.entry(change_set_id).or_default();
+        if held.len() >= MAX_HELD_REQUESTS {
+            warn!(%change_set_id, "too many requests held for change set; dropping the oldest");
+            held.pop_front();
+        }
+        held.push_back((reply_channel, request));
+    }
+
+    /// Whether any requests are held for change sets not yet taken over.
+    pub fn has_held(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    /// Tries to take over the change sets requests are held for. Returns the held requests for those taken over, to
+    /// be handled in the order they arrived.
+    pub async fn acquire_held(
+        &mut self,
+        complete_graph: &mut ChangeSetGraph,
+    ) -> Vec<(Subject, Request)> {
+        let mut ready = Vec::new();
+        for change_set_id in self.held.keys().copied().collect::<Vec<_>>() {
+            let acquired = match self.ownership(change_set_id) {
+                Ownership::Owned => true,
+                Ownership::Pending => match self.try_acquire(change_set_id, complete_graph).await {
+                    Ok(acquired) => acquired,
+                    Err(err) => {
+                        error!(error = ?err, %change_set_id, "unable to take over change set");
+                        false
+                    }
+                },
+                Ownership::Elsewhere => {
+                    debug!(%change_set_id, "dropping requests held for change set assigned to another council instance");
+                    for (reply_channel, request) in
+                        self.held.remove(&change_set_id).unwrap_or_default()
+                    {
+                        if let Request::Bye { .. } = request {
+                            self.record_departure(change_set_id, reply_channel);
+                        }
+                    }
+                    continue;
+                }
+            };
+            if acquired {
+                ready.extend(self.held.remove(&change_set_id).unwrap_or_default());
+            }
+        }
+
+        ready
+    }
+
+    /// Saves the graph data of the change sets this instance owns that have changed since they were last saved.
+    pub async fn save(&mut self, complete_graph: &mut ChangeSetGraph) -> Result<()> {
+        self.unsaved.extend(complete_graph.take_changed());
+        self.unsaved
+            .retain(|change_set_id| self.owned.contains(change_set_id));
+        if self.unsaved.is_empty() {
+            return Ok(());
+        }
+
+        self.state_store.save(complete_graph, &self.unsaved).await?;
+        self.unsaved.clear();
+
+        Ok(())
+    }
+
+    /// Returns the change sets taken over with graph data since this was last called. The jobs working on them may
+    /// have sent requests that were dropped while the change sets were between owners.
+    pub fn take_recovered(&mut self) -> Vec<Id> {
+        std::mem::take(&mut self.recovered)
+    }
+
+    /// Remembers a job going away from a change set owned by another instance, in case that instance is gone and
+    /// this one takes the change set over without the job's departure.
+    pub fn record_departure(&mut self, change_set_id: Id, reply_channel: Subject) {
+        self.departed
+            .entry(change_set_id)
+            .or_default()
+            .push((reply_channel, Instant::now()));
+    }
+
+    /// Hands over every change set this instance owns and stops heartbeating, so that other instances can take
+    /// them over right away.
+    pub async fn leave(&mut self, complete_graph: &mut ChangeSetGraph) -> Result<()> {
+        for change_set_id in std::mem::take(&mut self.owned) {
+            let nodes = complete_graph.remove_change_set(change_set_id);
+            self.state_store
+                .release(change_set_id, nodes.as_ref())
+                .await?;
+        }
+
+        self.members
+            .delete(&self.instance_id)
+            .await
+            .map_err(|err| Error::KeyValue(err.into()))
+    }
+
+    async fn heartbeat(&mut self) -> Result<()> {
+        self.members
+            .put(&self.instance_id, Vec::new().into())
+            .await?;
+
+        let mut keys = self
+            .members
+            .keys()
+            .await
+            .map_err(|err| Error::KeyValue(err.into()))?;
+        let mut live_members = HashSet::new();
+        while let Some(key) = keys.next().await {
+            live_members.insert(key.map_err(|err| Error::KeyValue(err.into()))?);
+        }
+        // Our own heartbeat may not be visible yet.
+        live_members.insert(self.instance_id.clone());
+
+        if live_members != self.live_members {
+            info!(?live_members, "council members changed");
+            self.ring = HashRing::new(&live_members);
+            self.live_members = live_members;
+        }
+
+        Ok(())
+    }
+
+    fn is_assigned(&self, change_set_id: Id) -> bool {
+        self.ring.owner(change_set_id) == Some(self.instance_id.as_str())
+    }
+
+    async fn try_acquire(
+        &mut self,
+        change_set_id: Id,
+        complete_graph: &mut ChangeSetGraph,
+    ) -> Result<bool> {
+        match self.state_store.load(change_set_id).await? {
+            None => {
+                if !self.state_store.claim(change_set_id).await? {
+                    return Ok(false);
+                }
+            }
+            Some((record, revision)) => {
+                if let Some(owner) = &record.owner {
+                    if *owner != self.instance_id && self.live_members.contains(owner) {
+                        debug!(%change_set_id, %owner, "waiting for change set to be handed over");
+                        return Ok(false);
+                    }
+                }
+                if !self
+                    .state_store
+                    .take_over(change_set_id, &record.nodes, revision)
+                    .await?
+                {
+                    return Ok(false);
+                }
+
+                info!(%change_set_id, previous_owner = ?record.owner, "took over change set");
+                complete_graph.insert_change_set(change_set_id, record.nodes);
+                self.recovered.push(change_set_id);
+            }
+        }
+        self.owned.insert(change_set_id);
+
+        // Removing a job that was already removed before the change set was saved does nothing.
+        for (reply_channel, _) in self.departed.remove(&change_set_id).unwrap_or_default() {
+            complete_graph.remove_channel(change_set_id, &reply_channel);
+        }
+
+        Ok(true)
+    }
+}
+
+/// A consistent hash ring of council instances.
+#[derive(Debug, Default)]
+struct HashRing {
+    ring: BTreeMap<u64, String>,
+}
+
+impl HashRing {
+    fn new(members: &HashSet<String>) -> Self {
+        let mut ring = BTreeMap::new();
+        for member in members {
+            for virtual_node in 0..VIRTUAL_NODES_PER_MEMBER {
+                ring.insert(
+                    hash(format!("{member}-{virtual_node}").as_bytes()),
+                    member.clone(),
+                );
+            }
+        }
+
+        Self { ring }
+    }
+
+    fn owner(&self, change_set_id: Id) -> Option<&str> {
+        let hash = hash(change_set_id.to_string().as_bytes());
+        self.ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, member)| member.as_str())
+    }
+}
+
+// Every instance has to place change sets and members on the ring identically, whatever version of Rust it was
+// built with, so this is FNV-1a with a final mix to spread out similar inputs (ulids share their prefixes).
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGE_SET_COUNT: usize = 3000;
+
+    fn members(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn owners(ring: &HashRing, change_set_ids: &[Id]) -> Vec<String> {
+        change_set_ids
+            .iter()
+            .map(|change_set_id| {
+                ring.owner(*change_set_id)
+                    .expect("should have an owner")
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    fn change_set_ids() -> Vec<Id> {
+        (0..CHANGE_SET_COUNT).map(|_| Id::default()).collect()
+    }
+
+    #[test]
+    fn has_no_owner_without_members() {
+        assert_eq!(None, HashRing::default().owner(Id::default()));
+        assert_eq!(None, HashRing::new(&HashSet::new()).owner(Id::default()));
+    }
+
+    #[test]
+    fn spreads_change_sets_between_members() {
+        let ring = HashRing::new(&members(&["council-0", "council-1", "council-2"]));
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for owner in owners(&ring, &change_set_ids()) {
+            *counts.entry(owner).or_default() += 1;
+        }
+
+        // Each member has a third of the change sets, give or take half of that.
+        assert_eq!(3, counts.len());
+        for (member, count) in counts {
+            assert!(
+                (CHANGE_SET_COUNT / 6..=CHANGE_SET_COUNT / 2).contains(&count),
+                "{member} owns {count} of {CHANGE_SET_COUNT} change sets"
+            );
+        }
+    }
+
+    #[test]
+    fn assigns_change_sets_the_same_way_whatever_the_order_of_members() {
+        let change_set_ids = change_set_ids();
+        let mut names = vec!["council-0", "council-1", "council-2"];
+        let ring = HashRing::new(&members(&names));
+        names.reverse();
+
+        assert_eq!(
+            owners(&ring, &change_set_ids),
+            owners(&HashRing::new(&members(&names)), &change_set_ids)
+        );
+    }
+
+    #[test]
+    fn only_moves_change_sets_to_a_joining_member() {
+        let change_set_ids = change_set_ids();
+        let before = owners(
+            &HashRing::new(&members(&["council-0", "council-1", "council-2"])),
+            &change_set_ids,
+        );
+        let after = owners(
+            &HashRing::new(&members(&[
+                "council-0",
+                "council-1",
+                "council-2",
+                "council-3",
+            ])),
+            &change_set_ids,
+        );
+
+        let mut moved = 0;
+        for (before, after) in before.iter().zip(after.iter()) {
+            if before != after {
+                assert_eq!("council-3", after);
+                moved += 1;
+            }
+        }
+        assert!(moved > 0);
+    }
+
+    #[test]
+    fn only_moves_change_sets_of_a_leaving_member() {
+        let change_set_ids = change_set_ids();
+        let before = owners(
+            &HashRing::new(&members(&["council-0", "council-1", "council-2"])),
+            &change_set_ids,
+        );
+        let after = owners(
+            &HashRing::new(&members(&["council-0", "council-1"])),
+            &change_set_ids,
+        );
+
+        for (before, after) in before.iter().zip(after.iter()) {
+            if before != "council-2" {
+                assert_eq!(before, after);
+            }
+        }
+    }
+
+    // Every instance has to agree on where things go on the ring, so the hash must never change.
+    #[test]
+    fn hashes_stably() {
+        assert_eq!(0xefd01f60ba992926, hash(b""));
+        assert_eq!(0x6d70f37fd5e0d3bb, hash(b"council-0"));
+        assert_eq!(0xb462e97f6ba89a50, hash(b"01HXYZ0000000000000000000A"));
+    }
+}
@@ -0,0 +1,413 @@
+//! This module contains [`StateStore`], which snapshots the graph data of each change set to a NATS KV bucket so
+//! that queued and in-flight work survives a council restart, and can be handed from one council instance to
+//! another.
+
+use std::collections::{HashMap, HashSet};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use si_data_nats::{async_nats::jetstream, NatsClient};
+use telemetry::prelude::*;
+
+use super::graph::{ChangeSetGraph, ChangeSetNodes};
+use super::{Error, Result};
+use crate::Id;
+
+const STATE_BUCKET_NAME: &str = "COUNCIL_STATE";
+const CHANGE_SET_KEY_PREFIX: &str = "change-sets.";
+
+/// The graph data of a change set, along with the council instance that owns it. A change set without an owner
+/// has been released by its last owner and can be taken over by any instance.
+#[derive(Debug, Deserialize)]
+pub struct ChangeSetRecord {
+    pub owner: Option<String>,
+    pub nodes: ChangeSetNodes,
+}
+
+#[derive(Serialize)]
+struct ChangeSetRecordRef<'a> {
+    owner: Option<&'a str>,
+    nodes: &'a ChangeSetNodes,
+}
+
+#[derive(Debug)]
+pub struct StateStore {
+    store: jetstream::kv::Store,
+    instance_id: String,
+    last_saved: HashMap<Id, Vec<u8>>,
+}
+
+impl StateStore {
+    /// Finds or creates the bucket that council state is kept in.
+    pub async fn new(nats: &NatsClient, instance_id: impl Into<String>) -> Result<Self> {
+        let store = get_or_create_bucket(
+            nats,
+            STATE_BUCKET_NAME,
+            jetstream::kv::Config {
+                description: "Council dependency graph snapshots".to_owned(),
+                history: 1,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(Self {
+            store,
+            instance_id: instance_id.into(),
+            last_saved: HashMap::new(),
+        })
+    }
+
+    /// Lists the change sets that have graph data saved.
+    pub async fn change_set_ids(&self) -> Result<Vec<Id>> {
+        let mut keys = self
+            .store
+            .keys()
+            .await
+            .map_err(|err| Error::KeyValue(err.into()))?;
+
+        let mut change_set_ids = Vec::new();
+        while let Some(key) = keys.next().await {
+            let key = key.map_err(|err| Error::KeyValue(err.into()))?;
+            if let Some(change_set_id) = key.strip_prefix(CHANGE_SET_KEY_PREFIX) {
+                match Id::from_string(change_set_id) {
+                    Ok(change_set_id) => change_set_ids.push(change_set_id),
+                    Err(err) => {
+                        warn!(error = ?err, %key, "ignoring council state with invalid key")
+                    }
+                }
+            }
+        }
+
+        Ok(change_set_ids)
+    }
+
+    /// Loads the saved graph data of a change set, if there is any, along with its revision.
+    pub async fn load(&mut self, change_set_id: Id) -> Result<Option<(ChangeSetRecord, u64)>> {
+        let entry = match self.store.entry(change_set_key(change_set_id)).await? {
+            Some(entry) if entry.operation == jetstream::kv::Operation::Put => entry,
+            _ => return Ok(None),
+        };
+
+        Ok(Some((
+            serde_json::from_slice(&entry.value)?,
+            entry.revision,
+        )))
+    }
+
+    /// Claims a change set that has no graph data saved for this instance. Returns `false` if another instance
+    /// saved some first.
+    pub async fn claim(&mut self, change_set_id: Id) -> Result<bool> {
+        // Graph data that has been deleted leaves a marker behind, which is the revision to write after.
+        let revision = match self.store.entry(change_set_key(change_set_id)).await? {
+            Some(entry) if entry.operation == jetstream::kv::Operation::Put => return Ok(false),
+            Some(entry) => entry.revision,
+            None => 0,
+        };
+        let bytes = serde_json::to_vec(&ChangeSetRecordRef {
+            owner: Some(&self.instance_id),
+            nodes: &ChangeSetNodes::new(),
+        })?;
+
+        self.write_at_revision(change_set_id, bytes, revision).await
+    }
+
+    /// Records this instance as the owner of a change set, as long as its graph data is still at the given
+    /// revision. Returns `false` if another instance changed it first.
+    pub async fn take_over(
+        &mut self,
+        change_set_id: Id,
+        nodes: &ChangeSetNodes,
+        revision: u64,
+    ) -> Result<bool> {
+        let bytes = serde_json::to_vec(&ChangeSetRecordRef {
+            owner: Some(&self.instance_id),
+            nodes,
+        })?;
+
+        self.write_at_revision(change_set_id, bytes, revision).await
+    }
+
+    /// Saves the graph data of the given change sets, which this instance owns, skipping those unchanged since they
+    /// were last saved, and forgets change sets that have no graph data left.
+    pub async fn save(
+        &mut self,
+        graph: &ChangeSetGraph,
+        change_set_ids: &HashSet<Id>,
+    ) -> Result<()> {
+        for change_set_id in change_set_ids {
+            match graph.change_set(*change_set_id) {
+                Some(nodes) => {
+                    let bytes = serde_json::to_vec(&ChangeSetRecordRef {
+                        owner: Some(&self.instance_id),
+                        nodes,
+                    })?;
+                    if self.last_saved.get(change_set_id) == Some(&bytes) {
+                        continue;
+                    }
+
+                    self.store
+                        .put(change_set_key(*change_set_id), bytes.clone().into())
+                        .await?;
+                    trace!(%change_set_id, bytes = bytes.len(), "saved council state");
+                    self.last_saved.insert(*change_set_id, bytes);
+                }
+                None => {
+                    if self.last_saved.remove(change_set_id).is_some() {
+                        self.delete(*change_set_id).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Saves the graph data of a change set without an owner, so that another instance can take it over.
+    pub async fn release(
+        &mut self,
+        change_set_id: Id,
+        nodes: Option<&ChangeSetNodes>,
+    ) -> Result<()> {
+        self.last_saved.remove(&change_set_id);
+        match nodes {
+            Some(nodes) => {
+                let bytes = serde_json::to_vec(&ChangeSetRecordRef { owner: None, nodes })?;
+                self.store
+                    .put(change_set_key(change_set_id), bytes.into())
+                    .await?;
+            }
+            None => self.delete(change_set_id).await?,
+        }
+
+        Ok(())
+    }
+
+    async fn write_at_revision(
+        &mut self,
+        change_set_id: Id,
+        bytes: Vec<u8>,
+        revision: u64,
+    ) -> Result<bool> {
+        let key = change_set_key(change_set_id);
+        match self
+            .store
+            .update(&key, bytes.clone().into(), revision)
+            .await
+        {
+            Ok(_) => {
+                self.last_saved.insert(change_set_id, bytes);
+                Ok(true)
+            }
+            Err(err) => {
+                // A write at the wrong revision is not told apart from other failures, so look at whether the
+                // graph data has moved on since.
+                let current_revision = self
+                    .store
+                    .entry(key)
+                    .await?
+                    .map_or(0, |entry| entry.revision);
+                if current_revision == revision {
+                    Err(Error::KeyValue(err.into()))
+                } else {
+                    Ok(false)
+                }
+            }
+        }
+    }
+
+    async fn delete(&self, change_set_id: Id) -> Result<()> {
+        self.store
+            .delete(change_set_key(change_set_id))
+            .await
+            .map_err(|err| Error::KeyValue(err.into()))
+    }
+}
+
+/// Finds or creates a KV bucket, named after the subject prefix if there is one.
+pub async fn get_or_create_bucket(
+    nats: &NatsClient,
+    name: &str,
+    config: jetstream::kv::Config,
+) -> Result<jetstream::kv::Store> {
+    let context = jetstream::new(nats.as_inner().clone());
+    let bucket = bucket_name(nats.metadata().subject_prefix(), name);
+
+    let store = match context.get_key_value(&bucket).await {
+        Ok(store) => store,
+        Err(_) => {
+            context
+                .create_key_value(jetstream::kv::Config { bucket, ..config })
+                .await?
+        }
+    };
+
+    Ok(store)
+}
+
+fn change_set_key(change_set_id: Id) -> String {
+    format!("{CHANGE_SET_KEY_PREFIX}{change_set_id}")
+}
+
+fn bucket_name(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}_{name}"),
+        None => name.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use si_data_nats::{NatsConfig, Subject};
+
+    use super::*;
+
+    const ENV_VAR_NATS_URL: &str = "SI_TEST_NATS_URL";
+
+    // Every test gets its own subject prefix, and so its own bucket.
+    async fn state_stores() -> (StateStore, StateStore) {
+        let mut nats_config = NatsConfig {
+            subject_prefix: Some(ulid::Ulid::new().to_string()),
+            ..Default::default()
+        };
+        #[allow(clippy::disallowed_methods)] // Environment variables are used exclusively in test
+        if let Ok(value) = std::env::var(ENV_VAR_NATS_URL) {
+            nats_config.url = value;
+        }
+        let nats = NatsClient::new(&nats_config)
+            .await
+            .expect("failed to connect to nats");
+
+        (
+            StateStore::new(&nats, "first")
+                .await
+                .expect("should create state store"),
+            StateStore::new(&nats, "second")
+                .await
+                .expect("should create state store"),
+        )
+    }
+
+    fn nodes(change_set_id: Id) -> ChangeSetNodes {
+        let mut graph = ChangeSetGraph::default();
+        graph
+            .merge_dependency_graph(
+                Subject::from("council.job.reply"),
+                HashMap::from([(Id::default(), vec![Id::default()])]),
+                change_set_id,
+            )
+            .expect("should merge");
+        graph
+            .remove_change_set(change_set_id)
+            .expect("should have nodes")
+    }
+
+    async fn owner(state_store: &mut StateStore, change_set_id: Id) -> Option<String> {
+        let (record, _) = state_store
+            .load(change_set_id)
+            .await
+            .expect("should load")
+            .expect("should have a record");
+        record.owner
+    }
+
+    #[tokio::test]
+    async fn claims_a_change_set_once() {
+        let (mut first, mut second) = state_stores().await;
+        let change_set_id = Id::default();
+
+        assert!(first.claim(change_set_id).await.expect("should claim"));
+        assert!(!second.claim(change_set_id).await.expect("should claim"));
+
+        assert_eq!(
+            Some("first".to_owned()),
+            owner(&mut second, change_set_id).await
+        );
+    }
+
+    #[tokio::test]
+    async fn claims_a_change_set_whose_graph_data_was_deleted() {
+        let (mut first, mut second) = state_stores().await;
+        let change_set_id = Id::default();
+        assert!(first.claim(change_set_id).await.expect("should claim"));
+
+        first
+            .release(change_set_id, None)
+            .await
+            .expect("should release");
+
+        assert!(second.claim(change_set_id).await.expect("should claim"));
+        assert_eq!(
+            Some("second".to_owned()),
+            owner(&mut first, change_set_id).await
+        );
+    }
+
+    #[tokio::test]
+    async fn only_one_instance_takes_over_a_released_change_set() {
+        let (mut first, mut second) = state_stores().await;
+        let change_set_id = Id::default();
+        let nodes = nodes(change_set_id);
+        assert!(first.claim(change_set_id).await.expect("should claim"));
+        first
+            .release(change_set_id, Some(&nodes))
+            .await
+            .expect("should release");
+
+        // Both see the released change set at the same revision.
+        let (first_record, first_revision) = first
+            .load(change_set_id)
+            .await
+            .expect("should load")
+            .expect("should have a record");
+        let (second_record, second_revision) = second
+            .load(change_set_id)
+            .await
+            .expect("should load")
+            .expect("should have a record");
+        assert_eq!(None, first_record.owner);
+        assert_eq!(first_revision, second_revision);
+
+        assert!(second
+            .take_over(change_set_id, &second_record.nodes, second_revision)
+            .await
+            .expect("should take over"));
+        assert!(!first
+            .take_over(change_set_id, &first_record.nodes, first_revision)
+            .await
+            .expect("should take over"));
+
+        assert_eq!(
+            Some("second".to_owned()),
+            owner(&mut first, change_set_id).await
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_take_over_a_change_set_saved_since_it_was_loaded() {
+        let (mut first, mut second) = state_stores().await;
+        let change_set_id = Id::default();
+        assert!(first.claim(change_set_id).await.expect("should claim"));
+        let (record, revision) = second
+            .load(change_set_id)
+            .await
+            .expect("should load")
+            .expect("should have a record");
+
+        let mut graph = ChangeSetGraph::default();
+        graph.insert_change_set(change_set_id, nodes(change_set_id));
+        first
+            .save(&graph, &HashSet::from([change_set_id]))
+            .await
+            .expect("should save");
+
+        assert!(!second
+            .take_over(change_set_id, &record.nodes, revision)
+            .await
+            .expect("should take over"));
+        assert_eq!(
+            Some("first".to_owned()),
+            owner(&mut second, change_set_id).await
+        );
+    }
+}
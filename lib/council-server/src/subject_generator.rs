@@ -7,6 +7,7 @@ pub(crate) type ReplyChannel = String;
 pub(crate) type AllChannels = String;
 pub(crate) type ManagementChannel = String;
 pub(crate) type ManagementReplyChannel = String;
+pub(crate) type ManagementRequestChannel = String;
 
 pub(crate) struct SubjectGenerator;
 
@@ -24,6 +25,11 @@ impl SubjectGenerator {
         Self::management_subject(subject_prefix)
     }
 
+    pub fn for_management_requests(subject_prefix: Option<String>) -> ManagementRequestChannel {
+        let base_subject = Self::base_subject(subject_prefix);
+        format!("{base_subject}.management-requests")
+    }
+
     pub fn for_server(
         subject_prefix: Option<String>,
     ) -> (AllChannels, ManagementChannel, ManagementReplyChannel) {
@@ -10,10 +10,11 @@ use std::{
 
 use async_trait::async_trait;
 use cyclone_core::{
-    ActionRunRequest, ActionRunResultSuccess, LivenessStatus, LivenessStatusParseError,
-    ReadinessStatus, ReadinessStatusParseError, ReconciliationRequest, ReconciliationResultSuccess,
-    ResolverFunctionRequest, ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
-    SchemaVariantDefinitionResultSuccess, ValidationRequest, ValidationResultSuccess,
+    ActionRunRequest, ActionRunResultSuccess, ExecutionEnvelope, FunctionRequest, LivenessStatus,
+    LivenessStatusParseError, ReadinessStatus, ReadinessStatusParseError, ReconciliationRequest,
+    ReconciliationResultSuccess, ResolverFunctionRequest, ResolverFunctionResultSuccess,
+    SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess, ValidationRequest,
+    ValidationResultSuccess,
 };
 use http::{
     request::Builder,
@@ -171,6 +172,16 @@ where
         Execution<Strm, SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess>,
         ClientError,
     >;
+
+    /// Executes a function of any kind through the generic execution endpoint, which takes the
+    /// request wrapped in a kind-tagged [`ExecutionEnvelope`].
+    async fn execute_function<Request>(
+        &mut self,
+        request: Request,
+    ) -> result::Result<Execution<Strm, ExecutionEnvelope<Request>, Request::Success>, ClientError>
+    where
+        Request: FunctionRequest + Send + 'static,
+        Request::Success: Send;
 }
 
 impl Client<(), (), ()> {
@@ -335,6 +346,18 @@ where
             request,
         ))
     }
+
+    async fn execute_function<Request>(
+        &mut self,
+        request: Request,
+    ) -> result::Result<Execution<Strm, ExecutionEnvelope<Request>, Request::Success>, ClientError>
+    where
+        Request: FunctionRequest + Send + 'static,
+        Request::Success: Send,
+    {
+        let stream = self.websocket_stream("/execute").await?;
+        Ok(execution::execute(stream, ExecutionEnvelope::new(request)))
+    }
 }
 
 impl<Conn, Strm, Sock> Client<Conn, Strm, Sock>
@@ -837,6 +860,7 @@ mod tests {
 
         let req = ResolverFunctionRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "doit".to_string(),
             component: ResolverFunctionComponent {
                 data: ComponentView {
@@ -939,6 +963,7 @@ mod tests {
 
         let req = ResolverFunctionRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "doit".to_string(),
             component: ResolverFunctionComponent {
                 data: ComponentView {
@@ -1035,6 +1060,7 @@ mod tests {
     {
         let req = ValidationRequest {
             execution_id: "1337".to_string(),
+            timeout_secs: None,
             handler: "validate".to_string(),
             value: "a string is a sequence of bytes".into(),
             code_base64: base64_encode(
@@ -1128,6 +1154,71 @@ mod tests {
         execute_validation(client).await
     }
 
+    async fn execute_function<C, Strm>(mut client: C)
+    where
+        Strm: AsyncRead + AsyncWrite + Connection + Unpin + Send + 'static,
+        C: CycloneClient<Strm>,
+    {
+        let req = ValidationRequest {
+            execution_id: "1337".to_string(),
+            timeout_secs: None,
+            handler: "validate".to_string(),
+            value: "a string is a sequence of bytes".into(),
+            code_base64: base64_encode(
+                r"function validate(value) {
+                    return { valid: value === 'a string is a sequence of bytes' };
+                }",
+            ),
+            before: vec![],
+        };
+        let mut progress = client
+            .execute_function(req)
+            .await
+            .expect("failed to establish websocket stream")
+            .start()
+            .await
+            .expect("failed to start protocol");
+
+        loop {
+            match progress.next().await {
+                None => break,
+                Some(Ok(ProgressMessage::Heartbeat)) => continue,
+                Some(unexpected) => panic!("output stream should be done: {unexpected:?}"),
+            };
+        }
+        let result = progress.finish().await.expect("failed to return result");
+        match result {
+            FunctionResult::Success(success) => {
+                assert!(success.valid);
+            }
+            FunctionResult::Failure(failure) => {
+                panic!("result should be success; failure={failure:?}")
+            }
+        }
+    }
+
+    #[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+    #[test(tokio::test)]
+    async fn http_execute_function() {
+        let (_, key) = gen_keys();
+        let mut builder = Config::builder();
+        let client = http_client_for_running_server(builder.enable_validation(true), key).await;
+
+        execute_function(client).await
+    }
+
+    #[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+    #[test(tokio::test)]
+    async fn uds_execute_function() {
+        let (_, key) = gen_keys();
+        let tmp_socket = rand_uds();
+        let mut builder = Config::builder();
+        let client =
+            uds_client_for_running_server(builder.enable_validation(true), &tmp_socket, key).await;
+
+        execute_function(client).await
+    }
+
     #[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
     #[test(tokio::test)]
     async fn http_execute_action_run() {
@@ -1137,6 +1228,7 @@ mod tests {
 
         let req = ActionRunRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "workit".to_string(),
             args: Default::default(),
             code_base64: base64_encode(
@@ -1224,6 +1316,7 @@ mod tests {
 
         let req = ActionRunRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "workit".to_string(),
             args: Default::default(),
             code_base64: base64_encode(
@@ -1311,6 +1404,7 @@ mod tests {
 
         let req = ReconciliationRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "workit".to_string(),
             args: Default::default(),
             code_base64: base64_encode(
@@ -1388,6 +1482,7 @@ mod tests {
 
         let req = ReconciliationRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "workit".to_string(),
             args: Default::default(),
             code_base64: base64_encode(
@@ -1469,6 +1564,7 @@ mod tests {
 
         let req = SchemaVariantDefinitionRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "createAsset".to_string(),
             code_base64: base64_encode(
                 r#"function createAsset() {
@@ -1548,6 +1644,7 @@ mod tests {
 
         let req = SchemaVariantDefinitionRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "createAsset".to_string(),
             code_base64: base64_encode(
                 r#"function createAsset() {
@@ -6,9 +6,9 @@ mod watch;
 pub use client::{Client, ClientConfig, ClientError, CycloneClient, HttpClient, UdsClient};
 pub use cyclone_core::{
     ActionRunRequest, ActionRunResultSuccess, CycloneEncryptionKey, CycloneEncryptionKeyError,
-    LivenessStatus, LivenessStatusParseError, ReadinessStatus, ReadinessStatusParseError,
-    ReconciliationRequest, ReconciliationResultSuccess, ResolverFunctionRequest,
-    ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
+    ExecutionEnvelope, FunctionRequest, LivenessStatus, LivenessStatusParseError, ReadinessStatus,
+    ReadinessStatusParseError, ReconciliationRequest, ReconciliationResultSuccess,
+    ResolverFunctionRequest, ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
     SchemaVariantDefinitionResultSuccess,
 };
 pub use execution::{Execution, ExecutionError};
@@ -1,16 +1,38 @@
-use crate::BeforeFunction;
+use crate::{BeforeFunction, FunctionRequest};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActionRunRequest {
     pub execution_id: String,
+    /// See [`FunctionRequest::timeout`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
     pub handler: String,
     pub code_base64: String,
     pub args: serde_json::Value,
     pub before: Vec<BeforeFunction>,
 }
 
+impl FunctionRequest for ActionRunRequest {
+    const KIND: &'static str = "actionRun";
+
+    type Success = ActionRunResultSuccess;
+
+    fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    fn handler(&self) -> &str {
+        &self.handler
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+}
+
 #[remain::sorted]
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
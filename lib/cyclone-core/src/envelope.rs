@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// The version of the [`ExecutionEnvelope`] format produced by this crate.
+pub const EXECUTION_ENVELOPE_VERSION: u32 = 1;
+
+/// A versioned, kind-tagged request to execute a function.
+///
+/// The envelope lets a single endpoint accept requests for every kind of function. The request is
+/// left as raw JSON until it reaches whatever was registered to handle its
+/// [`kind`](Self::kind), which then turns it into a typed request with
+/// [`into_request`](Self::into_request).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionEnvelope<R = Value> {
+    pub version: u32,
+    pub kind: String,
+    pub request: R,
+}
+
+impl<R> ExecutionEnvelope<R>
+where
+    R: FunctionRequest,
+{
+    /// Wraps a request in an envelope of the current version, tagged with the request's kind.
+    pub fn new(request: R) -> Self {
+        Self {
+            version: EXECUTION_ENVELOPE_VERSION,
+            kind: R::KIND.to_string(),
+            request,
+        }
+    }
+}
+
+impl ExecutionEnvelope {
+    /// Deserializes the enveloped request as a request of the given kind.
+    pub fn into_request<R>(self) -> Result<R, serde_json::Error>
+    where
+        R: FunctionRequest,
+    {
+        serde_json::from_value(self.request)
+    }
+}
+
+/// A request to execute a function of one kind.
+///
+/// Adding a new kind of function is a matter of implementing this trait for its request type and
+/// registering a handler for the kind wherever such requests are executed.
+pub trait FunctionRequest: Serialize + DeserializeOwned {
+    /// The tag identifying requests of this kind in an [`ExecutionEnvelope`].
+    const KIND: &'static str;
+
+    /// The result of a successful execution of the function.
+    type Success: Serialize + DeserializeOwned;
+
+    fn execution_id(&self) -> &str;
+
+    fn handler(&self) -> &str;
+
+    /// Returns how long the function may run before it is killed, if it is limited.
+    ///
+    /// Every request type takes this from an optional `timeoutSecs` field, in seconds. Requests
+    /// without one, including those serialized before the field existed, run for as long as they
+    /// take.
+    fn timeout(&self) -> Option<Duration>;
+}
@@ -16,6 +16,7 @@ mod before;
 mod canonical_command;
 mod component_view;
 mod crypto;
+mod envelope;
 mod liveness;
 pub mod process;
 mod progress;
@@ -39,6 +40,7 @@ pub use crypto::{
     decrypt_value_tree, encrypt_value_tree, CycloneSensitiveStrings, CycloneValueDecryptError,
     CycloneValueEncryptError,
 };
+pub use envelope::{ExecutionEnvelope, FunctionRequest, EXECUTION_ENVELOPE_VERSION};
 pub use liveness::{LivenessStatus, LivenessStatusParseError};
 pub use progress::{
     FunctionResult, FunctionResultFailure, FunctionResultFailureError, Message, OutputStream,
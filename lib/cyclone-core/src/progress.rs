@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// A line of output, streamed from an executing function.
@@ -110,6 +112,37 @@ pub struct FunctionResultFailureError {
     pub message: String,
 }
 
+impl FunctionResultFailureError {
+    /// The kind of failure of a function that was cancelled before it finished.
+    pub const KIND_CANCELLED: &'static str = "cancelled";
+    /// The kind of failure of a function that exceeded one of its resource limits (memory, cpu
+    /// time, output size or open files) and was stopped.
+    pub const KIND_RESOURCE_LIMIT: &'static str = "resourceLimit";
+    /// The kind of failure of a function that ran for longer than its timeout and was killed.
+    pub const KIND_TIMEOUT: &'static str = "timeout";
+
+    pub fn cancelled() -> Self {
+        Self {
+            kind: Self::KIND_CANCELLED.to_string(),
+            message: "function execution was cancelled".to_string(),
+        }
+    }
+
+    pub fn resource_limit(message: impl Into<String>) -> Self {
+        Self {
+            kind: Self::KIND_RESOURCE_LIMIT.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn timeout(timeout: Duration) -> Self {
+        Self {
+            kind: Self::KIND_TIMEOUT.to_string(),
+            message: format!("function execution timed out after {}s", timeout.as_secs()),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Fail {
     pub message: String,
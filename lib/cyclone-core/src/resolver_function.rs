@@ -1,13 +1,18 @@
+use std::time::Duration;
+
 use crate::before::BeforeFunction;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::ComponentView;
+use crate::{ComponentView, FunctionRequest};
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResolverFunctionRequest {
     pub execution_id: String,
+    /// See [`FunctionRequest::timeout`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
     pub handler: String,
     pub component: ResolverFunctionComponent,
     pub response_type: ResolverFunctionResponseType,
@@ -15,6 +20,24 @@ pub struct ResolverFunctionRequest {
     pub before: Vec<BeforeFunction>,
 }
 
+impl FunctionRequest for ResolverFunctionRequest {
+    const KIND: &'static str = "resolverFunction";
+
+    type Success = ResolverFunctionResultSuccess;
+
+    fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    fn handler(&self) -> &str {
+        &self.handler
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ResolverFunctionComponent {
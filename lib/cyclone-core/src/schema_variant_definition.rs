@@ -1,13 +1,38 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use crate::FunctionRequest;
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SchemaVariantDefinitionRequest {
     pub execution_id: String,
+    /// See [`FunctionRequest::timeout`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
     pub handler: String,
     pub code_base64: String,
 }
 
+impl FunctionRequest for SchemaVariantDefinitionRequest {
+    const KIND: &'static str = "schemaVariantDefinition";
+
+    type Success = SchemaVariantDefinitionResultSuccess;
+
+    fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    fn handler(&self) -> &str {
+        &self.handler
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+}
+
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SchemaVariantDefinitionResultSuccess {
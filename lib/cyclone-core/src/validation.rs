@@ -1,16 +1,38 @@
-use crate::BeforeFunction;
+use crate::{BeforeFunction, FunctionRequest};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidationRequest {
     pub execution_id: String,
+    /// See [`FunctionRequest::timeout`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
     pub handler: String,
     pub value: serde_json::Value,
     pub code_base64: String,
     pub before: Vec<BeforeFunction>,
 }
 
+impl FunctionRequest for ValidationRequest {
+    const KIND: &'static str = "validation";
+
+    type Success = ValidationResultSuccess;
+
+    fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    fn handler(&self) -> &str {
+        &self.handler
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+}
+
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidationResultSuccess {
@@ -54,6 +54,18 @@ pub struct Config {
 
     #[builder(setter(into), default)]
     limit_requests: Option<u32>,
+
+    #[builder(setter(into), default)]
+    limit_output_bytes: Option<u64>,
+
+    #[builder(setter(into), default)]
+    limit_cpu_time_secs: Option<u64>,
+
+    #[builder(setter(into), default)]
+    limit_open_files: Option<u64>,
+
+    #[builder(setter(into), default)]
+    limit_memory_bytes: Option<u64>,
 }
 
 impl Config {
@@ -122,6 +134,30 @@ impl Config {
     pub fn limit_requests(&self) -> Option<u32> {
         self.limit_requests
     }
+
+    /// Gets the config's limit on the bytes of output a single function execution may produce.
+    #[must_use]
+    pub fn limit_output_bytes(&self) -> Option<u64> {
+        self.limit_output_bytes
+    }
+
+    /// Gets the config's limit on the cpu time in seconds functions run under.
+    #[must_use]
+    pub fn limit_cpu_time_secs(&self) -> Option<u64> {
+        self.limit_cpu_time_secs
+    }
+
+    /// Gets the config's limit on the number of files functions may have open.
+    #[must_use]
+    pub fn limit_open_files(&self) -> Option<u64> {
+        self.limit_open_files
+    }
+
+    /// Gets the config's limit on the bytes of memory functions may use.
+    #[must_use]
+    pub fn limit_memory_bytes(&self) -> Option<u64> {
+        self.limit_memory_bytes
+    }
 }
 
 impl ConfigBuilder {
@@ -1,7 +1,8 @@
 use std::{
     fmt, io,
     marker::{PhantomData, Unpin},
-    path::PathBuf,
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
     process::Stdio,
     string::FromUtf8Error,
     sync::Arc,
@@ -13,17 +14,19 @@ use bytes_lines_codec::BytesLinesCodec;
 use cyclone_core::{
     process::{self, ShutdownError},
     CycloneDecryptionKey, CycloneDecryptionKeyError, CycloneSensitiveStrings,
-    CycloneValueDecryptError, FunctionResult, FunctionResultFailure, FunctionResultFailureError,
-    Message, OutputStream,
+    CycloneValueDecryptError, FunctionRequest, FunctionResult, FunctionResultFailure,
+    FunctionResultFailureError, Message, OutputStream,
 };
 use futures::{SinkExt, StreamExt, TryStreamExt};
+use nix::errno::Errno;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use telemetry::prelude::*;
 use thiserror::Error;
 use tokio::{
+    fs,
     process::{Child, ChildStderr, ChildStdin, ChildStdout, Command},
-    time,
+    time::{self, Instant},
 };
 use tokio_serde::{formats::SymmetricalJson, Deserializer, Framed, SymmetricallyFramed};
 use tokio_util::codec::{Decoder, FramedRead, FramedWrite};
@@ -32,14 +35,29 @@ use crate::{request::DecryptRequest, WebSocketMessage};
 
 const TX_TIMEOUT_SECS: Duration = Duration::from_secs(5);
 
+/// The resource limits functions run under.
+///
+/// Only the output limit is enforced by Cyclone. The others are set on Cyclone by whatever spawned
+/// it and inherited by each function, so they only serve to tell when a function was stopped for
+/// exceeding one of them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExecutionLimits {
+    pub output_bytes: Option<u64>,
+    pub cpu_time_secs: Option<u64>,
+    pub open_files: Option<u64>,
+    pub memory_bytes: Option<u64>,
+}
+
 pub fn new<Request, LangServerSuccess, Success>(
     lang_server_path: impl Into<PathBuf>,
+    limits: ExecutionLimits,
     lang_server_debugging: bool,
     key: Arc<CycloneDecryptionKey>,
     command: String,
 ) -> Execution<Request, LangServerSuccess, Success> {
     Execution {
         lang_server_path: lang_server_path.into(),
+        limits,
         lang_server_debugging,
         key,
         command,
@@ -62,6 +80,8 @@ pub enum ExecutionError {
     ChildShutdown(#[from] ShutdownError),
     #[error("failed to spawn child process; program={0}")]
     ChildSpawn(#[source] io::Error, PathBuf),
+    #[error("client closed the websocket before the function finished")]
+    ClientClosed,
     #[error("failed to decrypt request")]
     CycloneValueDecrypt(#[from] CycloneValueDecryptError),
     #[error("failed to decode string as utf8")]
@@ -91,6 +111,7 @@ type Result<T> = std::result::Result<T, ExecutionError>;
 #[derive(Debug)]
 pub struct Execution<Request, LangServerSuccess, Success> {
     lang_server_path: PathBuf,
+    limits: ExecutionLimits,
     lang_server_debugging: bool,
     key: Arc<CycloneDecryptionKey>,
     command: String,
@@ -101,22 +122,21 @@ pub struct Execution<Request, LangServerSuccess, Success> {
 
 impl<Request, LangServerSuccess, Success> Execution<Request, LangServerSuccess, Success>
 where
-    Request: DecryptRequest + Serialize + DeserializeOwned + Unpin + core::fmt::Debug,
+    Request: DecryptRequest + FunctionRequest + Unpin + fmt::Debug,
     LangServerSuccess: DeserializeOwned,
     Success: Serialize,
 {
+    /// Starts executing a request that was read from the websocket with [`start_request`].
     pub async fn start(
         self,
-        ws: &mut WebSocket,
+        mut request: Request,
     ) -> Result<ExecutionStarted<LangServerSuccess, Success>> {
-        // Send start is the initial communication before we read the request.
-        Self::ws_send_start(ws).await?;
         let mut sensitive_strings = CycloneSensitiveStrings::default();
-        // Read the request message from the web socket
-        let mut request = Self::read_request(ws).await?;
         // Decrypt the relevant contents of the request and track any resulting sensitive strings
         // to be redacted
         request.decrypt(&mut sensitive_strings, &self.key)?;
+        let execution_id = request.execution_id().to_owned();
+        let timeout = request.timeout();
 
         // Spawn lang server as a child process with handles on all i/o descriptors
         let mut command = Command::new(&self.lang_server_path);
@@ -128,10 +148,17 @@ where
         if self.lang_server_debugging {
             command.env("SI_LANG_JS_LOG", "*");
         }
+        // Only needed to tell an OOM kill apart from any other, so it is only read when there is a
+        // memory limit to exceed
+        let oom_kills = match self.limits.memory_bytes {
+            Some(_) => oom_kills().await,
+            None => None,
+        };
         debug!(cmd = ?command, "spawning child process");
         let mut child = command
             .spawn()
             .map_err(|err| ExecutionError::ChildSpawn(err, self.lang_server_path.clone()))?;
+        let deadline = timeout.map(|timeout| (Instant::now() + timeout, timeout));
 
         let stdin = child.stdin.take().ok_or(ExecutionError::ChildIO("stdin"))?;
         Self::child_send_function_request(stdin, request).await?;
@@ -157,35 +184,15 @@ where
             child,
             stdout,
             stderr,
+            execution_id,
+            deadline,
+            limits: self.limits,
+            oom_kills,
             sensitive_strings: Arc::new(sensitive_strings),
             success_marker: self.success_marker,
         })
     }
 
-    async fn read_request(ws: &mut WebSocket) -> Result<Request> {
-        let request = match ws.next().await {
-            Some(Ok(WebSocketMessage::Text(json_str))) => {
-                serde_json::from_str(&json_str).map_err(ExecutionError::JSONDeserialize)?
-            }
-            Some(Ok(unexpected)) => return Err(ExecutionError::UnexpectedMessageType(unexpected)),
-            Some(Err(err)) => return Err(ExecutionError::WSRecvIO(err)),
-            None => return Err(ExecutionError::WSRecvClosed),
-        };
-        Ok(request)
-    }
-
-    async fn ws_send_start(ws: &mut WebSocket) -> Result<()> {
-        let msg = Message::<Success>::Start
-            .serialize_to_string()
-            .map_err(ExecutionError::JSONSerialize)?;
-
-        time::timeout(TX_TIMEOUT_SECS, ws.send(WebSocketMessage::Text(msg)))
-            .await
-            .map_err(ExecutionError::SendTimeout)?
-            .map_err(ExecutionError::WSSendIO)?;
-        Ok(())
-    }
-
     async fn child_send_function_request(stdin: ChildStdin, request: Request) -> Result<()> {
         let value = serde_json::to_value(&request).map_err(ExecutionError::JSONSerialize)?;
 
@@ -204,6 +211,69 @@ where
     }
 }
 
+/// Sends the start message and reads the request that follows it, which is an
+/// [`ExecutionEnvelope`](cyclone_core::ExecutionEnvelope) unless the endpoint only executes functions of one kind.
+pub async fn start_request<T: DeserializeOwned>(ws: &mut WebSocket) -> Result<T> {
+    // Send start is the initial communication before we read the request.
+    ws_send_start(ws).await?;
+    read_message(ws).await
+}
+
+async fn read_message<T: DeserializeOwned>(ws: &mut WebSocket) -> Result<T> {
+    let message = match ws.next().await {
+        Some(Ok(WebSocketMessage::Text(json_str))) => {
+            serde_json::from_str(&json_str).map_err(ExecutionError::JSONDeserialize)?
+        }
+        Some(Ok(unexpected)) => return Err(ExecutionError::UnexpectedMessageType(unexpected)),
+        Some(Err(err)) => return Err(ExecutionError::WSRecvIO(err)),
+        None => return Err(ExecutionError::WSRecvClosed),
+    };
+    Ok(message)
+}
+
+/// Returns the number of processes the OOM killer stopped in Cyclone's own cgroup (v2), which its
+/// functions are spawned into, or `None` if that isn't known.
+///
+/// A Cyclone instance runs one function at a time, so an OOM kill seen while a function ran is
+/// that function's.
+async fn oom_kills() -> Option<u64> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup").await.ok()?;
+    let events = fs::read_to_string(memory_events_path(&cgroups)?)
+        .await
+        .ok()?;
+    parse_oom_kills(&events)
+}
+
+// The unified (v2) hierarchy is the one entry with a hierarchy id of 0 and no controllers
+fn memory_events_path(cgroups: &str) -> Option<PathBuf> {
+    let cgroup = cgroups.lines().find_map(|line| line.strip_prefix("0::"))?;
+    Some(
+        Path::new("/sys/fs/cgroup")
+            .join(cgroup.trim_start_matches('/'))
+            .join("memory.events"),
+    )
+}
+
+fn parse_oom_kills(events: &str) -> Option<u64> {
+    events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+async fn ws_send_start(ws: &mut WebSocket) -> Result<()> {
+    // The start message carries no result, so any result type serializes it the same
+    let msg = Message::<()>::Start
+        .serialize_to_string()
+        .map_err(ExecutionError::JSONSerialize)?;
+
+    time::timeout(TX_TIMEOUT_SECS, ws.send(WebSocketMessage::Text(msg)))
+        .await
+        .map_err(ExecutionError::SendTimeout)?
+        .map_err(ExecutionError::WSSendIO)?;
+    Ok(())
+}
+
 type SiFramedRead = FramedRead<ChildStdout, BytesLinesCodec>;
 type SiFramed<S> = Framed<SiFramedRead, S, S, SymmetricalJson<S>>;
 type SiMessage<S> = LangServerMessage<S>;
@@ -215,6 +285,11 @@ pub struct ExecutionStarted<LangServerSuccess, Success> {
     child: Child,
     stdout: SiFramed<SiMessage<LangServerSuccess>>,
     stderr: FramedRead<ChildStderr, BytesLinesCodec>,
+    execution_id: String,
+    deadline: Option<(Instant, Duration)>,
+    limits: ExecutionLimits,
+    // The OOM kills in Cyclone's cgroup before the function was spawned
+    oom_kills: Option<u64>,
     sensitive_strings: Arc<CycloneSensitiveStrings>,
     success_marker: PhantomData<Success>,
 }
@@ -249,37 +324,136 @@ where
     SymmetricalJson<SiMessage<LangServerSuccess>>: Deserializer<SiMessage<LangServerSuccess>>,
     SiDecoderError: From<SiJsonError<LangServerSuccess>>,
 {
-    pub async fn process(self, ws: &mut WebSocket) -> Result<ExecutionClosing<Success>> {
+    pub async fn process(mut self, ws: &mut WebSocket) -> Result<ExecutionClosing<Success>> {
         tokio::spawn(handle_stderr(self.stderr, self.sensitive_strings.clone()));
 
-        let mut stream = self
-            .stdout
-            .map(|ls_result| match ls_result {
-                Ok(ls_msg) => match ls_msg {
-                    LangServerMessage::Output(mut output) => {
-                        Self::filter_output(&mut output, &self.sensitive_strings)?;
-                        Ok(Message::OutputStream(output.into()))
+        let open_files_limited = self.limits.open_files.is_some();
+        let mut stream = self.stdout.map(|ls_result| match ls_result {
+            Ok(ls_msg) => match ls_msg {
+                LangServerMessage::Output(mut output) => {
+                    Self::filter_output(&mut output, &self.sensitive_strings)?;
+                    Ok(Message::<Success>::OutputStream(output.into()))
+                }
+                LangServerMessage::Result(mut result) => {
+                    Self::filter_result(&mut result, &self.sensitive_strings)?;
+                    let result: FunctionResult<Success> = match result {
+                        // Running out of file descriptors surfaces as an ordinary error in the
+                        // function, so it is its error number that tells us a limit was hit
+                        LangServerResult::Failure(failure)
+                            if open_files_limited && failure.error.is_out_of_open_files() =>
+                        {
+                            FunctionResult::Failure(FunctionResultFailure {
+                                execution_id: failure.execution_id,
+                                error: FunctionResultFailureError::resource_limit(
+                                    "function exceeded its open files limit",
+                                ),
+                                timestamp: crate::timestamp(),
+                            })
+                        }
+                        result => result.into(),
+                    };
+                    Ok(Message::Result(result))
+                }
+            },
+            Err(err) => Err(ExecutionError::ChildRecvIO(err)),
+        });
+
+        let deadline = self.deadline;
+        let timed_out = async move {
+            match deadline {
+                Some((deadline, _)) => time::sleep_until(deadline).await,
+                None => futures::future::pending().await,
+            }
+        };
+        tokio::pin!(timed_out);
+
+        let mut output_bytes: u64 = 0;
+        let mut result_sent = false;
+
+        loop {
+            tokio::select! {
+                msg = stream.try_next() => match msg? {
+                    Some(msg) => {
+                        let json_str = msg
+                            .serialize_to_string()
+                            .map_err(ExecutionError::JSONSerialize)?;
+
+                        output_bytes = output_bytes.saturating_add(json_str.len() as u64);
+                        if let Some(limit) = self.limits.output_bytes {
+                            if output_bytes > limit {
+                                warn!(
+                                    execution_id = %self.execution_id,
+                                    limit,
+                                    "function execution exceeded its output limit, killing child process",
+                                );
+                                Self::kill_child(&mut self.child).await;
+                                Self::ws_send_failure(
+                                    ws,
+                                    &self.execution_id,
+                                    FunctionResultFailureError::resource_limit(format!(
+                                        "function output exceeded its limit of {limit} bytes"
+                                    )),
+                                )
+                                .await?;
+                                break;
+                            }
+                        }
+
+                        result_sent |= matches!(msg, Message::Result(_));
+                        ws.send(WebSocketMessage::Text(json_str))
+                            .await
+                            .map_err(ExecutionError::WSSendIO)?;
                     }
-                    LangServerMessage::Result(mut result) => {
-                        Self::filter_result(&mut result, &self.sensitive_strings)?;
-                        Ok(Message::Result(result.into()))
+                    None => {
+                        if !result_sent {
+                            if let Some(error) = Self::resource_limit_failure(
+                                &mut self.child,
+                                &self.limits,
+                                self.oom_kills,
+                            )
+                            .await
+                            {
+                                warn!(
+                                    execution_id = %self.execution_id,
+                                    message = %error.message,
+                                    "function execution exceeded a resource limit",
+                                );
+                                Self::ws_send_failure(ws, &self.execution_id, error).await?;
+                            }
+                        }
+                        break;
                     }
                 },
-                Err(err) => Err(ExecutionError::ChildRecvIO(err)),
-            })
-            .map(|msg_result: Result<_>| match msg_result {
-                Ok(msg) => match msg
-                    .serialize_to_string()
-                    .map_err(ExecutionError::JSONSerialize)
-                {
-                    Ok(json_str) => Ok(WebSocketMessage::Text(json_str)),
-                    Err(err) => Err(err),
+                _ = &mut timed_out => {
+                    let timeout = deadline.map(|(_, timeout)| timeout).unwrap_or_default();
+                    warn!(
+                        execution_id = %self.execution_id,
+                        ?timeout,
+                        "function execution timed out, killing child process",
+                    );
+                    Self::kill_child(&mut self.child).await;
+                    Self::ws_send_failure(
+                        ws,
+                        &self.execution_id,
+                        FunctionResultFailureError::timeout(timeout),
+                    )
+                    .await?;
+                    break;
+                }
+                incoming = ws.next() => match incoming {
+                    // The client sends nothing after its request, so it has gone away and there
+                    // is no one left to run the function for
+                    Some(Ok(WebSocketMessage::Close(_))) | Some(Err(_)) | None => {
+                        debug!(
+                            execution_id = %self.execution_id,
+                            "client closed websocket, killing child process",
+                        );
+                        Self::kill_child(&mut self.child).await;
+                        return Err(ExecutionError::ClientClosed);
+                    }
+                    Some(Ok(_)) => {}
                 },
-                Err(err) => Err(err),
-            });
-
-        while let Some(msg) = stream.try_next().await? {
-            ws.send(msg).await.map_err(ExecutionError::WSSendIO)?;
+            }
         }
 
         Ok(ExecutionClosing {
@@ -288,6 +462,65 @@ where
         })
     }
 
+    /// Determines whether the child was stopped for exceeding one of the resource limits it runs
+    /// under, which shows as the child being signaled before it sent a result.
+    async fn resource_limit_failure(
+        child: &mut Child,
+        limits: &ExecutionLimits,
+        oom_kills_before: Option<u64>,
+    ) -> Option<FunctionResultFailureError> {
+        let status = match time::timeout(TX_TIMEOUT_SECS, child.wait()).await {
+            Ok(Ok(status)) => status,
+            Ok(Err(_)) | Err(_) => return None,
+        };
+
+        match process::Signal::try_from(status.signal()?).ok()? {
+            process::Signal::SIGXCPU if limits.cpu_time_secs.is_some() => Some(
+                FunctionResultFailureError::resource_limit("function exceeded its cpu time limit"),
+            ),
+            // Anything may send a SIGKILL, so it is only put down to the memory limit when the
+            // OOM killer is known to have been at work in the meantime
+            process::Signal::SIGKILL if limits.memory_bytes.is_some() => {
+                match (oom_kills_before, oom_kills().await) {
+                    (Some(before), Some(after)) if after > before => {
+                        Some(FunctionResultFailureError::resource_limit(
+                            "function exceeded its memory limit",
+                        ))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    async fn kill_child(child: &mut Child) {
+        if let Err(err) = process::child_shutdown(child, Some(process::Signal::SIGKILL), None).await
+        {
+            warn!(error = ?err, "failed to kill child process");
+        }
+    }
+
+    async fn ws_send_failure(
+        ws: &mut WebSocket,
+        execution_id: &str,
+        error: FunctionResultFailureError,
+    ) -> Result<()> {
+        let msg = Message::<Success>::Result(FunctionResult::Failure(FunctionResultFailure {
+            execution_id: execution_id.to_owned(),
+            error,
+            timestamp: crate::timestamp(),
+        }))
+        .serialize_to_string()
+        .map_err(ExecutionError::JSONSerialize)?;
+
+        time::timeout(TX_TIMEOUT_SECS, ws.send(WebSocketMessage::Text(msg)))
+            .await
+            .map_err(ExecutionError::SendTimeout)?
+            .map_err(ExecutionError::WSSendIO)?;
+        Ok(())
+    }
+
     fn filter_output(
         output: &mut LangServerOutput,
         sensitive_strings: &CycloneSensitiveStrings,
@@ -465,4 +698,16 @@ pub struct LangServerFailure {
 struct LangServerFailureError {
     kind: String,
     message: String,
+    // Node reports the error number of a failed system call negated, as libuv does
+    #[serde(default)]
+    errno: Option<i32>,
+}
+
+impl LangServerFailureError {
+    /// Returns whether the function failed because it ran out of file descriptors.
+    fn is_out_of_open_files(&self) -> bool {
+        self.errno
+            .map(|errno| Errno::from_i32(errno.saturating_abs()) == Errno::EMFILE)
+            .unwrap_or(false)
+    }
 }
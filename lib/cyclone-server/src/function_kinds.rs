@@ -0,0 +1,69 @@
+use std::{collections::HashMap, fmt, marker::Unpin};
+
+use axum::extract::ws::WebSocket;
+use cyclone_core::{ExecutionEnvelope, FunctionRequest};
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    handlers::{self, ExecutionContext},
+    request::DecryptRequest,
+};
+
+type KindHandler = Box<
+    dyn Fn(WebSocket, ExecutionEnvelope, ExecutionContext) -> BoxFuture<'static, ()> + Send + Sync,
+>;
+
+/// The kinds of functions Cyclone executes, keyed by the kind their requests are tagged with in an
+/// [`ExecutionEnvelope`].
+#[derive(Default)]
+pub struct FunctionKinds {
+    handlers: HashMap<&'static str, KindHandler>,
+    endpoints: Vec<(&'static str, &'static str)>,
+}
+
+impl FunctionKinds {
+    /// Registers a kind of function, which is executed by running the lang server with the given
+    /// command.
+    ///
+    /// Every kind is executed through the generic execution endpoint. A kind that predates it
+    /// also has an endpoint of its own at the given path, which takes its requests without an
+    /// envelope.
+    pub fn register<Request, LangServerSuccess>(
+        &mut self,
+        lang_server_command: &'static str,
+        endpoint: Option<&'static str>,
+    ) -> &mut Self
+    where
+        Request: DecryptRequest + FunctionRequest + Unpin + fmt::Debug + Send + 'static,
+        Request::Success: Unpin + fmt::Debug + Send,
+        LangServerSuccess: Serialize
+            + DeserializeOwned
+            + Unpin
+            + fmt::Debug
+            + Into<Request::Success>
+            + Send
+            + 'static,
+    {
+        self.handlers.insert(
+            Request::KIND,
+            Box::new(move |socket, envelope, context| {
+                let execute = handlers::handle_envelope_request::<Request, LangServerSuccess>;
+                Box::pin(execute(socket, envelope, context, lang_server_command))
+            }),
+        );
+        if let Some(path) = endpoint {
+            self.endpoints.push((path, Request::KIND));
+        }
+        self
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&KindHandler> {
+        self.handlers.get(kind)
+    }
+
+    /// Returns the path and kind of every registered kind's own endpoint.
+    pub fn endpoints(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.endpoints.iter().copied()
+    }
+}
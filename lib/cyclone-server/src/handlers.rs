@@ -13,10 +13,8 @@ use axum::{
     response::IntoResponse,
 };
 use cyclone_core::{
-    ActionRunRequest, ActionRunResultSuccess, LivenessStatus, Message, ReadinessStatus,
-    ReconciliationRequest, ReconciliationResultSuccess, ResolverFunctionRequest,
-    ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
-    SchemaVariantDefinitionResultSuccess, ValidationRequest, ValidationResultSuccess,
+    ExecutionEnvelope, FunctionRequest, LivenessStatus, Message, ReadinessStatus,
+    EXECUTION_ENVELOPE_VERSION,
 };
 use hyper::StatusCode;
 use serde::{de::DeserializeOwned, Serialize};
@@ -25,12 +23,9 @@ use telemetry_http::ParentSpan;
 
 use super::extract::LimitRequestGuard;
 use crate::{
-    execution::{self, Execution},
+    execution::{self, Execution, ExecutionError, ExecutionLimits},
+    function_kinds::FunctionKinds,
     request::DecryptRequest,
-    result::{
-        LangServerActionRunResultSuccess, LangServerReconciliationResultSuccess,
-        LangServerResolverFunctionResultSuccess, LangServerValidationResultSuccess,
-    },
     state::{DecryptionKey, LangServerPath, TelemetryLevel, WatchKeepalive},
     watch,
 };
@@ -98,154 +93,210 @@ pub async fn ws_execute_ping(
     })
 }
 
-#[allow(clippy::unused_async)]
-pub async fn ws_execute_resolver(
+/// The kind of function executed by an endpoint of its own, whose requests come without an
+/// [`ExecutionEnvelope`].
+#[derive(Clone, Copy, Debug)]
+pub struct EndpointKind(pub &'static str);
+
+#[allow(clippy::too_many_arguments)]
+pub async fn ws_execute_kind(
     wsu: WebSocketUpgrade,
     State(lang_server_path): State<LangServerPath>,
+    State(limits): State<ExecutionLimits>,
     State(key): State<DecryptionKey>,
     State(telemetry_level): State<TelemetryLevel>,
     limit_request_guard: LimitRequestGuard,
     Extension(request_span): Extension<ParentSpan>,
+    Extension(function_kinds): Extension<Arc<FunctionKinds>>,
+    Extension(EndpointKind(kind)): Extension<EndpointKind>,
 ) -> impl IntoResponse {
-    let lang_server_path = lang_server_path.as_path().to_path_buf();
-    let telemetry_level = telemetry_level.is_debug_or_lower().await;
-    wsu.on_upgrade(move |socket| {
-        let request: PhantomData<ResolverFunctionRequest> = PhantomData;
-        let lang_server_success: PhantomData<LangServerResolverFunctionResultSuccess> = PhantomData;
-        let success: PhantomData<ResolverFunctionResultSuccess> = PhantomData;
-        handle_socket(
-            socket,
-            lang_server_path,
-            telemetry_level,
-            key.into(),
-            limit_request_guard,
-            "resolverfunction".to_owned(),
-            request,
-            lang_server_success,
-            success,
-            request_span.into_inner(),
-        )
-    })
+    let context = ExecutionContext::new(
+        lang_server_path,
+        limits,
+        key,
+        telemetry_level,
+        limit_request_guard,
+        request_span,
+    )
+    .await;
+    wsu.on_upgrade(move |socket| handle_kind_request(socket, function_kinds, kind, context))
 }
 
-#[allow(clippy::unused_async)]
-pub async fn ws_execute_validation(
+#[allow(clippy::too_many_arguments)]
+pub async fn ws_execute(
     wsu: WebSocketUpgrade,
     State(lang_server_path): State<LangServerPath>,
+    State(limits): State<ExecutionLimits>,
     State(key): State<DecryptionKey>,
     State(telemetry_level): State<TelemetryLevel>,
     limit_request_guard: LimitRequestGuard,
     Extension(request_span): Extension<ParentSpan>,
+    Extension(function_kinds): Extension<Arc<FunctionKinds>>,
 ) -> impl IntoResponse {
-    let lang_server_path = lang_server_path.as_path().to_path_buf();
-    let telemetry_level = telemetry_level.is_debug_or_lower().await;
-    wsu.on_upgrade(move |socket| {
-        let request: PhantomData<ValidationRequest> = PhantomData;
-        let lang_server_success: PhantomData<LangServerValidationResultSuccess> = PhantomData;
-        let success: PhantomData<ValidationResultSuccess> = PhantomData;
-        handle_socket(
-            socket,
-            lang_server_path,
-            telemetry_level,
-            key.into(),
-            limit_request_guard,
-            "validation".to_owned(),
-            request,
-            lang_server_success,
-            success,
-            request_span.into_inner(),
-        )
-    })
+    let context = ExecutionContext::new(
+        lang_server_path,
+        limits,
+        key,
+        telemetry_level,
+        limit_request_guard,
+        request_span,
+    )
+    .await;
+    wsu.on_upgrade(move |socket| handle_envelope(socket, function_kinds, context))
 }
 
-#[allow(clippy::unused_async)]
-pub async fn ws_execute_action_run(
-    wsu: WebSocketUpgrade,
-    State(lang_server_path): State<LangServerPath>,
-    State(key): State<DecryptionKey>,
-    State(telemetry_level): State<TelemetryLevel>,
+/// Everything the execution of a function needs, other than its request.
+pub struct ExecutionContext {
+    lang_server_path: PathBuf,
+    limits: ExecutionLimits,
+    lang_server_debugging: bool,
+    key: Arc<cyclone_core::CycloneDecryptionKey>,
     limit_request_guard: LimitRequestGuard,
-    Extension(request_span): Extension<ParentSpan>,
-) -> impl IntoResponse {
-    let lang_server_path = lang_server_path.as_path().to_path_buf();
-    let telemetry_level = telemetry_level.is_debug_or_lower().await;
-    wsu.on_upgrade(move |socket| {
-        let request: PhantomData<ActionRunRequest> = PhantomData;
-        let lang_server_success: PhantomData<LangServerActionRunResultSuccess> = PhantomData;
-        let success: PhantomData<ActionRunResultSuccess> = PhantomData;
-        handle_socket(
-            socket,
-            lang_server_path,
-            telemetry_level,
-            key.into(),
-            limit_request_guard,
-            "actionRun".to_owned(),
-            request,
-            lang_server_success,
-            success,
-            request_span.into_inner(),
-        )
-    })
+    request_span: Span,
 }
 
-#[allow(clippy::unused_async)]
-pub async fn ws_execute_reconciliation(
-    wsu: WebSocketUpgrade,
-    State(lang_server_path): State<LangServerPath>,
-    State(key): State<DecryptionKey>,
-    State(telemetry_level): State<TelemetryLevel>,
-    limit_request_guard: LimitRequestGuard,
-    Extension(request_span): Extension<ParentSpan>,
-) -> impl IntoResponse {
-    let lang_server_path = lang_server_path.as_path().to_path_buf();
-    let telemetry_level = telemetry_level.is_debug_or_lower().await;
-    wsu.on_upgrade(move |socket| {
-        let request: PhantomData<ReconciliationRequest> = PhantomData;
-        let lang_server_success: PhantomData<LangServerReconciliationResultSuccess> = PhantomData;
-        let success: PhantomData<ReconciliationResultSuccess> = PhantomData;
-        handle_socket(
-            socket,
-            lang_server_path,
-            telemetry_level,
-            key.into(),
+impl ExecutionContext {
+    async fn new(
+        lang_server_path: LangServerPath,
+        limits: ExecutionLimits,
+        key: DecryptionKey,
+        telemetry_level: TelemetryLevel,
+        limit_request_guard: LimitRequestGuard,
+        request_span: ParentSpan,
+    ) -> Self {
+        Self {
+            lang_server_path: lang_server_path.as_path().to_path_buf(),
+            limits,
+            lang_server_debugging: telemetry_level.is_debug_or_lower().await,
+            key: key.into(),
             limit_request_guard,
-            "reconciliation".to_owned(),
-            request,
-            lang_server_success,
-            success,
-            request_span.into_inner(),
-        )
-    })
+            request_span: request_span.into_inner(),
+        }
+    }
 }
 
-#[allow(clippy::unused_async)]
-pub async fn ws_execute_schema_variant_definition(
-    wsu: WebSocketUpgrade,
-    State(lang_server_path): State<LangServerPath>,
-    State(key): State<DecryptionKey>,
-    State(telemetry_level): State<TelemetryLevel>,
-    limit_request_guard: LimitRequestGuard,
-    Extension(request_span): Extension<ParentSpan>,
-) -> impl IntoResponse {
-    let lang_server_path = lang_server_path.as_path().to_path_buf();
-    let telemetry_level = telemetry_level.is_debug_or_lower().await;
-    wsu.on_upgrade(move |socket| {
-        let request: PhantomData<SchemaVariantDefinitionRequest> = PhantomData;
-        let lang_server_success: PhantomData<SchemaVariantDefinitionResultSuccess> = PhantomData;
-        let success: PhantomData<SchemaVariantDefinitionResultSuccess> = PhantomData;
-        handle_socket(
+async fn handle_kind_request(
+    mut socket: WebSocket,
+    function_kinds: Arc<FunctionKinds>,
+    kind: &'static str,
+    context: ExecutionContext,
+) {
+    let request = match execution::start_request(&mut socket).await {
+        Ok(request) => request,
+        Err(err) => {
+            warn!(error = ?err, "failed to start protocol");
+            context.request_span.record_err(&err);
+            fail_envelope(socket, "failed to start protocol").await;
+            return;
+        }
+    };
+
+    // The endpoint's kind stands in for the envelope its requests come without
+    let envelope = ExecutionEnvelope {
+        version: EXECUTION_ENVELOPE_VERSION,
+        kind: kind.to_owned(),
+        request,
+    };
+    dispatch_envelope(socket, &function_kinds, envelope, context).await;
+}
+
+async fn handle_envelope(
+    mut socket: WebSocket,
+    function_kinds: Arc<FunctionKinds>,
+    context: ExecutionContext,
+) {
+    let envelope: ExecutionEnvelope = match execution::start_request(&mut socket).await {
+        Ok(envelope) => envelope,
+        Err(err) => {
+            warn!(error = ?err, "failed to start protocol");
+            context.request_span.record_err(&err);
+            fail_envelope(socket, "failed to start protocol").await;
+            return;
+        }
+    };
+
+    if envelope.version != EXECUTION_ENVELOPE_VERSION {
+        warn!(
+            version = envelope.version,
+            "unsupported execution envelope version"
+        );
+        fail_envelope(
             socket,
-            lang_server_path,
-            telemetry_level,
-            key.into(),
-            limit_request_guard,
-            "schemaVariantDefinition".to_owned(),
-            request,
-            lang_server_success,
-            success,
-            request_span.into_inner(),
+            format!(
+                "unsupported execution envelope version: {}",
+                envelope.version
+            ),
         )
-    })
+        .await;
+        return;
+    }
+
+    dispatch_envelope(socket, &function_kinds, envelope, context).await;
+}
+
+async fn dispatch_envelope(
+    socket: WebSocket,
+    function_kinds: &FunctionKinds,
+    envelope: ExecutionEnvelope,
+    context: ExecutionContext,
+) {
+    match function_kinds.get(&envelope.kind) {
+        Some(handler) => handler(socket, envelope, context).await,
+        None => {
+            warn!(kind = %envelope.kind, "unsupported function kind");
+            fail_envelope(
+                socket,
+                format!("unsupported function kind: {}", envelope.kind),
+            )
+            .await;
+        }
+    }
+}
+
+/// Executes the request of an [`ExecutionEnvelope`] as a request of the given kind.
+pub(crate) async fn handle_envelope_request<Request, LangServerSuccess>(
+    socket: WebSocket,
+    envelope: ExecutionEnvelope,
+    context: ExecutionContext,
+    sub_command: &'static str,
+) where
+    Request: DecryptRequest + FunctionRequest + Unpin + fmt::Debug,
+    Request::Success: Unpin + fmt::Debug,
+    LangServerSuccess: Serialize + DeserializeOwned + Unpin + fmt::Debug + Into<Request::Success>,
+{
+    let request = match envelope.into_request::<Request>() {
+        Ok(request) => request,
+        Err(err) => {
+            warn!(error = ?err, kind = Request::KIND, "failed to deserialize enveloped request");
+            context.request_span.record_err(&err);
+            fail_envelope(socket, format!("failed to deserialize request: {err}")).await;
+            return;
+        }
+    };
+
+    let lang_server_success: PhantomData<LangServerSuccess> = PhantomData;
+    let success: PhantomData<Request::Success> = PhantomData;
+    handle_socket(
+        socket,
+        context.lang_server_path,
+        context.limits,
+        context.lang_server_debugging,
+        context.key,
+        context.limit_request_guard,
+        sub_command.to_owned(),
+        request,
+        lang_server_success,
+        success,
+        context.request_span,
+    )
+    .await;
+}
+
+async fn fail_envelope(socket: WebSocket, message: impl Into<String>) {
+    // A failure carries no result, so any result type serializes it the same
+    if let Err(err) = fail_to_process(socket, message, PhantomData::<()>).await {
+        warn!(error = ?err, "failed to fail execute function");
+    }
 }
 
 #[instrument(
@@ -259,23 +310,29 @@ pub async fn ws_execute_schema_variant_definition(
 async fn handle_socket<Request, LangServerSuccess, Success>(
     mut socket: WebSocket,
     lang_server_path: PathBuf,
+    limits: ExecutionLimits,
     lang_server_debugging: bool,
     key: Arc<cyclone_core::CycloneDecryptionKey>,
     _limit_request_guard: LimitRequestGuard,
     sub_command: String,
-    _request_marker: PhantomData<Request>,
+    request: Request,
     _lang_server_success_marker: PhantomData<LangServerSuccess>,
     success_marker: PhantomData<Success>,
     request_span: Span,
 ) where
-    Request: DecryptRequest + Serialize + DeserializeOwned + Unpin + fmt::Debug,
+    Request: DecryptRequest + FunctionRequest + Unpin + fmt::Debug,
     Success: Serialize + Unpin + fmt::Debug,
     LangServerSuccess: Serialize + DeserializeOwned + Unpin + fmt::Debug + Into<Success>,
 {
     let proto = {
-        let execution: Execution<Request, LangServerSuccess, Success> =
-            execution::new(lang_server_path, lang_server_debugging, key, sub_command);
-        match execution.start(&mut socket).await {
+        let execution: Execution<Request, LangServerSuccess, Success> = execution::new(
+            lang_server_path,
+            limits,
+            lang_server_debugging,
+            key,
+            sub_command,
+        );
+        match execution.start(request).await {
             Ok(started) => started,
             Err(err) => {
                 warn!(error = ?err, "failed to start protocol");
@@ -295,6 +352,12 @@ async fn handle_socket<Request, LangServerSuccess, Success>(
     };
     let proto = match proto.process(&mut socket).await {
         Ok(processed) => processed,
+        // The client has gone away, so there is no one to report the failure to
+        Err(err @ ExecutionError::ClientClosed) => {
+            debug!(error = ?err, "client closed protocol before it finished");
+            request_span.record_err(&err);
+            return;
+        }
         Err(err) => {
             warn!(error = ?err, "failed to process protocol");
             request_span.record_err(&err);
@@ -1,6 +1,7 @@
 mod config;
 mod execution;
 mod extract;
+mod function_kinds;
 mod handlers;
 mod request;
 mod result;
@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
 use axum::{routing::get, Extension, Router};
+use cyclone_core::{
+    ActionRunRequest, ReconciliationRequest, ResolverFunctionRequest,
+    SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess, ValidationRequest,
+};
 use telemetry::prelude::*;
 use telemetry_http::{HttpMakeSpan, HttpOnResponse};
 use tokio::sync::mpsc;
@@ -8,7 +12,12 @@ use tower_http::{compression::CompressionLayer, trace::TraceLayer};
 
 use crate::{
     extract::RequestLimiter,
-    handlers,
+    function_kinds::FunctionKinds,
+    handlers::{self, EndpointKind},
+    result::{
+        LangServerActionRunResultSuccess, LangServerReconciliationResultSuccess,
+        LangServerResolverFunctionResultSuccess, LangServerValidationResultSuccess,
+    },
     state::{AppState, WatchKeepalive},
     tower::WebSocketTraceLayer,
     watch, Config, ShutdownSource,
@@ -74,40 +83,59 @@ pub fn routes(
 
 fn execute_routes(config: &Config, shutdown_tx: mpsc::Sender<ShutdownSource>) -> Router<AppState> {
     let mut router = Router::new();
+    let mut function_kinds = FunctionKinds::default();
 
     if config.enable_ping() {
         debug!("enabling ping endpoint");
         router = router.merge(Router::new().route("/ping", get(handlers::ws_execute_ping)));
     }
     if config.enable_resolver() {
-        debug!("enabling resolver endpoint");
-        router = router.merge(Router::new().route("/resolver", get(handlers::ws_execute_resolver)));
+        function_kinds
+            .register::<ResolverFunctionRequest, LangServerResolverFunctionResultSuccess>(
+                "resolverfunction",
+                Some("/resolver"),
+            );
     }
     if config.enable_validation() {
-        debug!("enabling validation endpoint");
-        router =
-            router.merge(Router::new().route("/validation", get(handlers::ws_execute_validation)));
+        function_kinds.register::<ValidationRequest, LangServerValidationResultSuccess>(
+            "validation",
+            Some("/validation"),
+        );
     }
     if config.enable_action_run() {
-        debug!("enabling command run endpoint");
-        router =
-            router.merge(Router::new().route("/command", get(handlers::ws_execute_action_run)));
+        function_kinds.register::<ActionRunRequest, LangServerActionRunResultSuccess>(
+            "actionRun",
+            Some("/command"),
+        );
     }
     if config.enable_reconciliation() {
-        debug!("enabling reconciliation endpoint");
-        router = router.merge(
-            Router::new().route("/reconciliation", get(handlers::ws_execute_reconciliation)),
+        function_kinds.register::<ReconciliationRequest, LangServerReconciliationResultSuccess>(
+            "reconciliation",
+            Some("/reconciliation"),
         );
     }
     if config.enable_schema_variant_definition() {
-        debug!("enabling schema variant definition endpoint");
-        router = router.merge(Router::new().route(
-            "/schema_variant_definition",
-            get(handlers::ws_execute_schema_variant_definition),
-        ));
+        function_kinds
+            .register::<SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess>(
+                "schemaVariantDefinition",
+                Some("/schema_variant_definition"),
+            );
+    }
+
+    for (path, kind) in function_kinds.endpoints() {
+        debug!(path, kind, "enabling function kind endpoint");
+        router = router.merge(
+            Router::new()
+                .route(path, get(handlers::ws_execute_kind))
+                .layer(Extension(EndpointKind(kind))),
+        );
     }
+    debug!("enabling generic execution endpoint");
+    router = router.route("/", get(handlers::ws_execute));
 
     let limit_requests = Arc::new(config.limit_requests().map(|i| i.into()));
 
-    router.layer(Extension(RequestLimiter::new(limit_requests, shutdown_tx)))
+    router
+        .layer(Extension(Arc::new(function_kinds)))
+        .layer(Extension(RequestLimiter::new(limit_requests, shutdown_tx)))
 }
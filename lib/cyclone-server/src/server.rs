@@ -18,8 +18,8 @@ use tokio::{
 };
 
 use crate::{
-    routes::routes, state::AppState, Config, IncomingStream, UdsIncomingStream,
-    UdsIncomingStreamError,
+    execution::ExecutionLimits, routes::routes, state::AppState, Config, IncomingStream,
+    UdsIncomingStream, UdsIncomingStreamError,
 };
 
 #[cfg(target_os = "linux")]
@@ -230,7 +230,17 @@ fn build_service(
 ) -> Result<(IntoMakeService<Router>, oneshot::Receiver<()>)> {
     let (shutdown_tx, shutdown_rx) = mpsc::channel(4);
 
-    let state = AppState::new(config.lang_server_path(), decryption_key, telemetry_level);
+    let state = AppState::new(
+        config.lang_server_path(),
+        ExecutionLimits {
+            output_bytes: config.limit_output_bytes(),
+            cpu_time_secs: config.limit_cpu_time_secs(),
+            open_files: config.limit_open_files(),
+            memory_bytes: config.limit_memory_bytes(),
+        },
+        decryption_key,
+        telemetry_level,
+    );
 
     let routes = routes(config, state, shutdown_tx);
 
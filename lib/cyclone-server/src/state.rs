@@ -8,9 +8,12 @@ use std::{
 use axum::extract::FromRef;
 use tokio::sync::mpsc;
 
+use crate::execution::ExecutionLimits;
+
 #[derive(Clone, FromRef)]
 pub struct AppState {
     lang_server_path: LangServerPath,
+    limits: ExecutionLimits,
     decryption_key: DecryptionKey,
     telemetry_level: TelemetryLevel,
 }
@@ -18,11 +21,13 @@ pub struct AppState {
 impl AppState {
     pub fn new(
         lang_server_path: impl Into<PathBuf>,
+        limits: ExecutionLimits,
         decryption_key: cyclone_core::CycloneDecryptionKey,
         telemetry_level: Box<dyn telemetry::TelemetryLevel>,
     ) -> Self {
         Self {
             lang_server_path: LangServerPath(Arc::new(lang_server_path.into())),
+            limits,
             decryption_key: DecryptionKey(Arc::new(decryption_key)),
             telemetry_level: TelemetryLevel(Arc::new(telemetry_level)),
         }
@@ -1,4 +1,5 @@
 use color_eyre::Result;
+use dal::change_set::pointer_history::ChangeSetPointerOperation;
 use dal::change_set::{ChangeSet, ChangeSetId};
 use dal::{DalContext, UserClaim};
 use jwt_simple::algorithms::RSAKeyPairLike;
@@ -147,18 +148,24 @@ pub async fn create_change_set_and_update_ctx(
         .await
         .expect("could not perform find change set")
         .expect("no change set found");
+    let base_workspace_snapshot_address = base_change_set
+        .workspace_snapshot_address
+        .expect("no workspace snapshot set on base change set");
     let mut change_set = ChangeSet::new(ctx, generate_fake_name(), Some(base_change_set_id))
         .await
         .expect("could not create change set");
     change_set
         .update_pointer(
             ctx,
-            base_change_set
-                .workspace_snapshot_address
-                .expect("no workspace snapshot set on base change set"),
+            base_workspace_snapshot_address,
+            ChangeSetPointerOperation::Fork,
         )
         .await
         .expect("could not update pointer");
+    change_set
+        .update_base_pointer(ctx, base_workspace_snapshot_address)
+        .await
+        .expect("could not update base pointer");
     ctx.update_visibility_and_snapshot_to_visibility(change_set.id)
         .await
         .expect("could not update visibility and snapshot");
@@ -21,6 +21,7 @@ use si_crypto::{
 };
 use si_data_nats::{NatsClient, NatsConfig};
 use si_data_pg::{PgPool, PgPoolConfig};
+use si_layer_cache::{durable::DurableBackendConfig, LayerDbConfig};
 use si_std::ResultExt;
 use telemetry::prelude::*;
 use tokio::{fs::File, io::AsyncReadExt, sync::Mutex};
@@ -55,6 +56,7 @@ const ENV_VAR_LAYER_CACHE_PG_DBNAME: &str = "SI_TEST_LAYER_CACHE_PG_DBNAME";
 const ENV_VAR_PG_USER: &str = "SI_TEST_PG_USER";
 const ENV_VAR_PG_PORT: &str = "SI_TEST_PG_PORT";
 const ENV_VAR_KEEP_OLD_DBS: &str = "SI_TEST_KEEP_OLD_DBS";
+const ENV_VAR_LAYER_DB_SNAPSHOT_DIRECTORY: &str = "SI_TEST_LAYER_DB_SNAPSHOT_DIRECTORY";
 const SI_AWS_EC2_PKG: &str = "si-aws-ec2-2023-09-26.sipkg";
 const SI_DOCKER_IMAGE_PKG: &str = "si-docker-image-2023-09-13.sipkg";
 const SI_COREOS_PKG: &str = "si-coreos-2023-09-13.sipkg";
@@ -110,6 +112,8 @@ pub struct Config {
     #[allow(dead_code)]
     #[builder(default = "si_layer_cache::default_pg_pool_config()")]
     layer_cache_pg_pool: PgPoolConfig,
+    #[builder(default = "LayerDbConfig::default()")]
+    layer_db: LayerDbConfig,
 }
 
 impl Config {
@@ -155,6 +159,11 @@ impl Config {
         config.layer_cache_pg_pool.certificate_path =
             Some(config.postgres_key_path.clone().try_into()?);
 
+        if let Ok(value) = env::var(ENV_VAR_LAYER_DB_SNAPSHOT_DIRECTORY) {
+            config.layer_db.workspace_snapshot_durable_backend =
+                DurableBackendConfig::LocalDirectory { path: value.into() };
+        }
+
         if let Ok(value) = env::var(ENV_VAR_MODULE_INDEX_URL) {
             config.module_index_url = value;
         }
@@ -286,13 +295,30 @@ impl TestContext {
         &self,
         token: CancellationToken,
         tracker: TaskTracker,
+    ) -> ServicesContext {
+        self.create_services_context_with_layer_db_config(
+            token,
+            tracker,
+            self.config.layer_db.clone(),
+        )
+        .await
+    }
+
+    /// Creates a new [`ServicesContext`] whose layer db is configured differently from the
+    /// global configuration.
+    pub async fn create_services_context_with_layer_db_config(
+        &self,
+        token: CancellationToken,
+        tracker: TaskTracker,
+        layer_db_config: LayerDbConfig,
     ) -> ServicesContext {
         let veritech = veritech_client::Client::new(self.nats_conn.clone());
 
-        let (layer_db, layer_db_graceful_shutdown) = DalLayerDb::initialize(
+        let (layer_db, layer_db_graceful_shutdown) = DalLayerDb::initialize_with_config(
             self.layer_db_sled_path.clone(),
             self.layer_db_pg_pool.clone(),
             self.nats_conn.clone(),
+            layer_db_config,
             token,
         )
         .await
@@ -538,6 +564,8 @@ pub fn rebaser_server(services_context: &ServicesContext) -> Result<rebaser_serv
         services_context.job_processor(),
         services_context.symmetric_crypto_service().clone(),
         services_context.layer_db().clone(),
+        Default::default(),
+        services_context.validate_snapshots(),
     )
     .wrap_err("failed to create Rebaser server")?;
 
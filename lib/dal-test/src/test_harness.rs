@@ -14,6 +14,20 @@ pub async fn commit_and_update_snapshot(ctx: &mut DalContext) {
         .expect("unable to update snapshot to visibility");
 }
 
+/// Asserts that the workspace snapshot of the [`DalContext`] passes
+/// [`validation`](dal::WorkspaceSnapshotGraph::validate).
+pub async fn assert_snapshot_is_valid(ctx: &DalContext) {
+    let report = ctx
+        .workspace_snapshot()
+        .expect("unable to get workspace snapshot")
+        .validate()
+        .await;
+    assert!(
+        report.is_valid(),
+        "workspace snapshot is invalid: {report:?}"
+    );
+}
+
 pub fn generate_fake_name() -> String {
     Generator::with_naming(Name::Numbered).next().unwrap()
 }
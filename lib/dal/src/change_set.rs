@@ -12,8 +12,10 @@ use thiserror::Error;
 use ulid::{Generator, Ulid};
 
 use crate::action::ActionBag;
+use crate::change_set::pointer_history::{ChangeSetPointerHistoryEntry, ChangeSetPointerOperation};
 use crate::context::RebaseRequest;
 use crate::job::definition::{ActionRunnerItem, ActionsJob};
+use crate::workspace_snapshot::conflict::ConflictChoice;
 use crate::workspace_snapshot::vector_clock::VectorClockId;
 use crate::{
     id, Action, ActionBatch, ActionBatchError, ActionError, ActionId, ActionPrototypeId,
@@ -23,13 +25,17 @@ use crate::{
 };
 
 pub mod event;
+pub mod pointer_history;
 pub mod status;
+pub mod undo;
 pub mod view;
 
 /// The primary error type for this module.
 #[remain::sorted]
 #[derive(Debug, Error)]
 pub enum ChangeSetError {
+    #[error("conflicts found when applying change set {0} to its base change set")]
+    ConflictsFound(ChangeSetId),
     #[error("could not find default change set: {0}")]
     DefaultChangeSetNotFound(ChangeSetId),
     #[error("default change set {0} has no workspace snapshot pointer")]
@@ -119,6 +125,9 @@ pub struct ChangeSet {
     pub status: ChangeSetStatus,
     pub base_change_set_id: Option<ChangeSetId>,
     pub workspace_snapshot_address: Option<WorkspaceSnapshotAddress>,
+    /// The address of the snapshot this change set was forked from, if any. This is the common
+    /// ancestor used when merging content that was modified both here and in the base change set.
+    pub base_workspace_snapshot_address: Option<WorkspaceSnapshotAddress>,
     pub workspace_id: Option<WorkspacePk>,
 
     #[serde(skip)]
@@ -139,6 +148,7 @@ impl TryFrom<PgRow> for ChangeSet {
             status,
             base_change_set_id: value.try_get("base_change_set_id")?,
             workspace_snapshot_address: value.try_get("workspace_snapshot_address")?,
+            base_workspace_snapshot_address: value.try_get("base_workspace_snapshot_address")?,
             workspace_id: value.try_get("workspace_id")?,
             generator: Arc::new(Mutex::new(Default::default())),
         })
@@ -157,6 +167,7 @@ impl ChangeSet {
             generator: Arc::new(Mutex::new(generator)),
             base_change_set_id: None,
             workspace_snapshot_address: None,
+            base_workspace_snapshot_address: None,
             workspace_id: None,
             name: "".to_string(),
             status: ChangeSetStatus::Open,
@@ -167,6 +178,7 @@ impl ChangeSet {
         let mut new_local = Self::new_local()?;
         new_local.base_change_set_id = self.base_change_set_id;
         new_local.workspace_snapshot_address = self.workspace_snapshot_address;
+        new_local.base_workspace_snapshot_address = self.base_workspace_snapshot_address;
         new_local.workspace_id = self.workspace_id;
         new_local.name = self.name.to_owned();
         new_local.status = self.status.to_owned();
@@ -217,19 +229,25 @@ impl ChangeSet {
                 workspace.default_change_set_id(),
             ))?;
 
+        let base_workspace_snapshot_address = base_change_set.workspace_snapshot_address.ok_or(
+            ChangeSetError::DefaultChangeSetNoWorkspaceSnapshotPointer(
+                workspace.default_change_set_id(),
+            ),
+        )?;
+
         let mut change_set =
             ChangeSet::new(ctx, name, Some(workspace.default_change_set_id())).await?;
 
         change_set
             .update_pointer(
                 ctx,
-                base_change_set.workspace_snapshot_address.ok_or(
-                    ChangeSetError::DefaultChangeSetNoWorkspaceSnapshotPointer(
-                        workspace.default_change_set_id(),
-                    ),
-                )?,
+                base_workspace_snapshot_address,
+                ChangeSetPointerOperation::Fork,
             )
             .await?;
+        change_set
+            .update_base_pointer(ctx, base_workspace_snapshot_address)
+            .await?;
 
         Ok(change_set)
     }
@@ -266,11 +284,15 @@ impl ChangeSet {
         Ok(())
     }
 
+    /// Points the [`ChangeSet`] at a new [`WorkspaceSnapshotAddress`], recording the move (and
+    /// the [`ChangeSetPointerOperation`] that caused it) in the change set's pointer history.
     pub async fn update_pointer(
         &mut self,
         ctx: &DalContext,
         workspace_snapshot_address: WorkspaceSnapshotAddress,
+        operation: ChangeSetPointerOperation,
     ) -> ChangeSetResult<()> {
+        ctx.ensure_writable()?;
         ctx.txns()
             .await?
             .pg()
@@ -279,12 +301,34 @@ impl ChangeSet {
                 &[&self.id, &workspace_snapshot_address],
             )
             .await?;
+        ChangeSetPointerHistoryEntry::append(ctx, self.id, workspace_snapshot_address, operation)
+            .await?;
 
         self.workspace_snapshot_address = Some(workspace_snapshot_address);
 
         Ok(())
     }
 
+    pub async fn update_base_pointer(
+        &mut self,
+        ctx: &DalContext,
+        base_workspace_snapshot_address: WorkspaceSnapshotAddress,
+    ) -> ChangeSetResult<()> {
+        ctx.ensure_writable()?;
+        ctx.txns()
+            .await?
+            .pg()
+            .query_none(
+                "UPDATE change_set_pointers SET base_workspace_snapshot_address = $2 WHERE id = $1",
+                &[&self.id, &base_workspace_snapshot_address],
+            )
+            .await?;
+
+        self.base_workspace_snapshot_address = Some(base_workspace_snapshot_address);
+
+        Ok(())
+    }
+
     pub async fn update_status(
         &mut self,
         ctx: &DalContext,
@@ -353,6 +397,22 @@ impl ChangeSet {
     pub async fn apply_to_base_change_set(
         ctx: &mut DalContext,
         allow_system_init_history_actor: bool,
+    ) -> ChangeSetApplyResult<ChangeSet> {
+        Self::apply_to_base_change_set_with_conflict_choices(
+            ctx,
+            allow_system_init_history_actor,
+            vec![],
+        )
+        .await
+    }
+
+    /// Like [`Self::apply_to_base_change_set()`], but with resolutions chosen for specific
+    /// conflicts between the current [`ChangeSet`] and its base. Typically used to re-submit an
+    /// apply that failed with [`ChangeSetError::ConflictsFound`].
+    pub async fn apply_to_base_change_set_with_conflict_choices(
+        ctx: &mut DalContext,
+        allow_system_init_history_actor: bool,
+        conflict_choices: Vec<ConflictChoice>,
     ) -> ChangeSetApplyResult<ChangeSet> {
         // Gather actions to run, which should only be populated if we are applying to head.
         let (actions_to_run, prototype_by_action_id) = Self::list_actions_to_run(ctx).await?;
@@ -364,7 +424,7 @@ impl ChangeSet {
         ctx.update_visibility_and_snapshot_to_visibility_no_editing_change_set(ctx.change_set_id())
             .await?;
         change_set_to_be_applied
-            .apply_to_base_change_set_inner(ctx)
+            .apply_to_base_change_set_inner(ctx, conflict_choices)
             .await?;
         ctx.blocking_commit().await?;
         let change_set_that_was_applied = change_set_to_be_applied;
@@ -461,8 +521,13 @@ impl ChangeSet {
     /// of the [`ChangeSet`] accordingly.
     ///
     /// This function neither changes the visibility nor the snapshot after performing the
-    /// aforementioned actions.
-    async fn apply_to_base_change_set_inner(&mut self, ctx: &DalContext) -> ChangeSetResult<()> {
+    /// aforementioned actions. If the rebase finds conflicts that could not be resolved, the
+    /// [`ChangeSet`] is left open and [`ChangeSetError::ConflictsFound`] is returned.
+    async fn apply_to_base_change_set_inner(
+        &mut self,
+        ctx: &DalContext,
+        conflict_choices: Vec<ConflictChoice>,
+    ) -> ChangeSetResult<()> {
         let to_rebase_change_set_id = self
             .base_change_set_id
             .ok_or(ChangeSetError::NoBaseChangeSet(self.id))?;
@@ -473,8 +538,13 @@ impl ChangeSet {
             onto_workspace_snapshot_address,
             onto_vector_clock_id: self.vector_clock_id(),
             to_rebase_change_set_id,
+            conflict_choices,
+            actor: ctx.events_actor(),
+            pointer_operation: Some(ChangeSetPointerOperation::Apply),
         };
-        ctx.do_rebase_request(rebase_request).await?;
+        if ctx.do_rebase_request(rebase_request).await?.is_some() {
+            return Err(ChangeSetError::ConflictsFound(self.id));
+        }
 
         self.update_status(ctx, ChangeSetStatus::Applied).await?;
 
@@ -0,0 +1,156 @@
+//! An append-only log of every [`WorkspaceSnapshotAddress`] a [`ChangeSet`](crate::ChangeSet)
+//! has pointed to, along with who moved the pointer, when and why.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use si_data_pg::PgRow;
+use si_events::WorkspaceSnapshotAddress;
+use si_layer_cache::activities::rebase::RebasePointerOperation;
+use strum::{Display, EnumString};
+use telemetry::prelude::*;
+
+use crate::change_set::{ChangeSetError, ChangeSetId, ChangeSetResult};
+use crate::DalContext;
+
+/// The operation that moved a [`ChangeSet`](crate::ChangeSet) pointer.
+#[remain::sorted]
+#[derive(Deserialize, Serialize, Debug, Display, EnumString, PartialEq, Eq, Copy, Clone)]
+pub enum ChangeSetPointerOperation {
+    /// Another change set was applied to this one.
+    Apply,
+    /// Edits made in this change set were committed.
+    Commit,
+    /// The change set was created (e.g. along with its workspace).
+    Create,
+    /// The change set was forked from its base change set.
+    Fork,
+    /// The change set was rebased by a requester that did not say why.
+    Rebase,
+    /// A change undone by its author was made again.
+    Redo,
+    /// The most recent change made by an author was undone.
+    Undo,
+}
+
+impl From<ChangeSetPointerOperation> for RebasePointerOperation {
+    fn from(value: ChangeSetPointerOperation) -> Self {
+        match value {
+            ChangeSetPointerOperation::Apply => Self::Apply,
+            ChangeSetPointerOperation::Commit => Self::Commit,
+            ChangeSetPointerOperation::Create => Self::Create,
+            ChangeSetPointerOperation::Fork => Self::Fork,
+            ChangeSetPointerOperation::Rebase => Self::Rebase,
+            ChangeSetPointerOperation::Redo => Self::Redo,
+            ChangeSetPointerOperation::Undo => Self::Undo,
+        }
+    }
+}
+
+impl From<RebasePointerOperation> for ChangeSetPointerOperation {
+    fn from(value: RebasePointerOperation) -> Self {
+        match value {
+            RebasePointerOperation::Apply => Self::Apply,
+            RebasePointerOperation::Commit => Self::Commit,
+            RebasePointerOperation::Create => Self::Create,
+            RebasePointerOperation::Fork => Self::Fork,
+            RebasePointerOperation::Rebase => Self::Rebase,
+            RebasePointerOperation::Redo => Self::Redo,
+            RebasePointerOperation::Undo => Self::Undo,
+        }
+    }
+}
+
+/// A single entry in the pointer history of a [`ChangeSet`](crate::ChangeSet).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChangeSetPointerHistoryEntry {
+    pub created_at: DateTime<Utc>,
+    pub change_set_id: ChangeSetId,
+    pub workspace_snapshot_address: WorkspaceSnapshotAddress,
+    pub actor: si_events::Actor,
+    pub operation: ChangeSetPointerOperation,
+}
+
+impl TryFrom<PgRow> for ChangeSetPointerHistoryEntry {
+    type Error = ChangeSetError;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        let actor: serde_json::Value = value.try_get("actor")?;
+        let operation_string: String = value.try_get("operation")?;
+        Ok(Self {
+            created_at: value.try_get("created_at")?,
+            change_set_id: value.try_get("change_set_id")?,
+            workspace_snapshot_address: value.try_get("workspace_snapshot_address")?,
+            actor: serde_json::from_value(actor)?,
+            operation: ChangeSetPointerOperation::try_from(operation_string.as_str())?,
+        })
+    }
+}
+
+impl ChangeSetPointerHistoryEntry {
+    /// Records that the given [`ChangeSet`](crate::ChangeSet) now points at the given
+    /// [`WorkspaceSnapshotAddress`]. Entries are never modified or removed.
+    pub(crate) async fn append(
+        ctx: &DalContext,
+        change_set_id: ChangeSetId,
+        workspace_snapshot_address: WorkspaceSnapshotAddress,
+        operation: ChangeSetPointerOperation,
+    ) -> ChangeSetResult<Self> {
+        let actor = serde_json::to_value(ctx.events_actor())?;
+        let row = ctx
+            .txns()
+            .await?
+            .pg()
+            .query_one(
+                "INSERT INTO change_set_pointer_history (change_set_id, workspace_snapshot_address, actor, operation) VALUES ($1, $2, $3, $4) RETURNING *",
+                &[&change_set_id, &workspace_snapshot_address, &actor, &operation.to_string()],
+            )
+            .await?;
+
+        Self::try_from(row)
+    }
+
+    /// Lists the pointer history of the given [`ChangeSet`](crate::ChangeSet), oldest first.
+    #[instrument(skip_all)]
+    pub async fn list(ctx: &DalContext, change_set_id: ChangeSetId) -> ChangeSetResult<Vec<Self>> {
+        let rows = ctx
+            .txns()
+            .await?
+            .pg()
+            .query(
+                "SELECT * FROM change_set_pointer_history WHERE change_set_id = $1 ORDER BY created_at, id",
+                &[&change_set_id],
+            )
+            .await?;
+
+        let mut result = Vec::with_capacity(rows.len());
+        for row in rows {
+            result.push(Self::try_from(row)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Finds the entry that was current for the given [`ChangeSet`](crate::ChangeSet) at the
+    /// given time, if the change set had a pointer by then.
+    #[instrument(skip_all)]
+    pub async fn find_as_of(
+        ctx: &DalContext,
+        change_set_id: ChangeSetId,
+        as_of: DateTime<Utc>,
+    ) -> ChangeSetResult<Option<Self>> {
+        let row = ctx
+            .txns()
+            .await?
+            .pg()
+            .query_opt(
+                "SELECT * FROM change_set_pointer_history WHERE change_set_id = $1 AND created_at <= $2 ORDER BY created_at DESC, id DESC LIMIT 1",
+                &[&change_set_id, &as_of],
+            )
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::try_from(row)?)),
+            None => Ok(None),
+        }
+    }
+}
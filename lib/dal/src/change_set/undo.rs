@@ -0,0 +1,255 @@
+//! Undo and redo of the changes each author makes in a [`ChangeSet`], derived from the change
+//! set's [pointer history](crate::change_set::pointer_history).
+//!
+//! Undoing a change does not move the change set back to an earlier snapshot. Instead, the
+//! [`Updates`](crate::workspace_snapshot::update::Update) that revert the change are performed on
+//! the current snapshot, so that changes made by others in the meantime are kept.
+
+use serde::{Deserialize, Serialize};
+use si_events::WorkspaceSnapshotAddress;
+use telemetry::prelude::*;
+use thiserror::Error;
+
+use crate::change_set::pointer_history::{ChangeSetPointerHistoryEntry, ChangeSetPointerOperation};
+use crate::change_set::{ChangeSet, ChangeSetError, ChangeSetId};
+use crate::workspace_snapshot::WorkspaceSnapshotError;
+use crate::{DalContext, TransactionsError, WorkspaceSnapshot};
+
+#[remain::sorted]
+#[derive(Debug, Error)]
+pub enum ChangeSetUndoError {
+    #[error("change set error: {0}")]
+    ChangeSet(#[from] ChangeSetError),
+    #[error("conflicts found when performing {0} in change set {1}")]
+    ConflictsFound(ChangeSetPointerOperation, ChangeSetId),
+    #[error("transactions error: {0}")]
+    Transactions(#[from] TransactionsError),
+    #[error("workspace snapshot error: {0}")]
+    WorkspaceSnapshot(#[from] WorkspaceSnapshotError),
+}
+
+pub type ChangeSetUndoResult<T> = Result<T, ChangeSetUndoError>;
+
+/// A change made by a single commit: the snapshots the change set pointed to before and after it.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoStep {
+    pub before: WorkspaceSnapshotAddress,
+    pub after: WorkspaceSnapshotAddress,
+}
+
+/// The changes an author can undo and redo in a [`ChangeSet`], most recent last.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct UndoStacks {
+    pub undo: Vec<UndoStep>,
+    pub redo: Vec<UndoStep>,
+}
+
+impl UndoStacks {
+    /// Replays the pointer history of a [`ChangeSet`] (oldest first) to find what the given actor
+    /// can undo and redo. Making a new change clears what can be redone.
+    pub fn from_history(
+        history: &[ChangeSetPointerHistoryEntry],
+        actor: &si_events::Actor,
+    ) -> Self {
+        let mut stacks = Self::default();
+        let mut previous_address: Option<WorkspaceSnapshotAddress> = None;
+
+        for entry in history {
+            if &entry.actor == actor {
+                match entry.operation {
+                    ChangeSetPointerOperation::Commit => {
+                        if let Some(before) = previous_address {
+                            stacks.undo.push(UndoStep {
+                                before,
+                                after: entry.workspace_snapshot_address,
+                            });
+                            stacks.redo.clear();
+                        }
+                    }
+                    ChangeSetPointerOperation::Redo => {
+                        if let Some(step) = stacks.redo.pop() {
+                            stacks.undo.push(step);
+                        }
+                    }
+                    ChangeSetPointerOperation::Undo => {
+                        if let Some(step) = stacks.undo.pop() {
+                            stacks.redo.push(step);
+                        }
+                    }
+                    ChangeSetPointerOperation::Apply
+                    | ChangeSetPointerOperation::Create
+                    | ChangeSetPointerOperation::Fork
+                    | ChangeSetPointerOperation::Rebase => {}
+                }
+            }
+            previous_address = Some(entry.workspace_snapshot_address);
+        }
+
+        stacks
+    }
+
+    /// Finds what the given actor can undo and redo in the given [`ChangeSet`].
+    pub async fn find(
+        ctx: &DalContext,
+        change_set_id: ChangeSetId,
+        actor: &si_events::Actor,
+    ) -> ChangeSetUndoResult<Self> {
+        let history = ChangeSetPointerHistoryEntry::list(ctx, change_set_id).await?;
+        Ok(Self::from_history(&history, actor))
+    }
+}
+
+impl ChangeSet {
+    /// Undoes the most recent change made by the [`DalContext`]'s actor in its change set, if
+    /// there is one. Returns whether anything was undone.
+    #[instrument(level = "info", skip_all)]
+    pub async fn undo(ctx: &DalContext) -> ChangeSetUndoResult<bool> {
+        let stacks = UndoStacks::find(ctx, ctx.change_set_id(), &ctx.events_actor()).await?;
+        match stacks.undo.last() {
+            Some(step) => {
+                revert(
+                    ctx,
+                    step.before,
+                    step.after,
+                    ChangeSetPointerOperation::Undo,
+                )
+                .await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Redoes the change most recently undone by the [`DalContext`]'s actor in its change set, if
+    /// there is one. Returns whether anything was redone.
+    #[instrument(level = "info", skip_all)]
+    pub async fn redo(ctx: &DalContext) -> ChangeSetUndoResult<bool> {
+        let stacks = UndoStacks::find(ctx, ctx.change_set_id(), &ctx.events_actor()).await?;
+        match stacks.redo.last() {
+            Some(step) => {
+                revert(
+                    ctx,
+                    step.after,
+                    step.before,
+                    ChangeSetPointerOperation::Redo,
+                )
+                .await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Reverts the changes made between the `before` and `after` snapshots in the [`DalContext`]'s
+/// snapshot and rebases its change set onto the result, recording the given operation.
+async fn revert(
+    ctx: &DalContext,
+    before: WorkspaceSnapshotAddress,
+    after: WorkspaceSnapshotAddress,
+    operation: ChangeSetPointerOperation,
+) -> ChangeSetUndoResult<()> {
+    let before = WorkspaceSnapshot::find(ctx, before).await?;
+    let after = WorkspaceSnapshot::find(ctx, after).await?;
+
+    let workspace_snapshot = ctx.workspace_snapshot()?;
+    let updates = workspace_snapshot
+        .detect_reverting_updates(&before, &after)
+        .await?;
+    workspace_snapshot
+        .perform_updates(ctx.change_set()?, &before, &updates)
+        .await?;
+
+    let workspace_snapshot_address = ctx
+        .write_snapshot()
+        .await?
+        .ok_or(WorkspaceSnapshotError::WorkspaceSnapshotNotFetched)?;
+    let mut rebase_request = ctx.get_rebase_request(workspace_snapshot_address)?;
+    rebase_request.pointer_operation = Some(operation);
+    if ctx.do_rebase_request(rebase_request).await?.is_some() {
+        return Err(ChangeSetUndoError::ConflictsFound(
+            operation,
+            ctx.change_set_id(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn entry(
+        address: &[u8],
+        actor: &si_events::Actor,
+        operation: ChangeSetPointerOperation,
+    ) -> ChangeSetPointerHistoryEntry {
+        ChangeSetPointerHistoryEntry {
+            created_at: Utc::now(),
+            change_set_id: ChangeSetId::generate(),
+            workspace_snapshot_address: WorkspaceSnapshotAddress::new(address),
+            actor: actor.clone(),
+            operation,
+        }
+    }
+
+    #[test]
+    fn stacks_follow_each_actors_history() {
+        let system = si_events::Actor::System;
+        let user = si_events::Actor::User(si_events::UserPk::new());
+        let step = |before: &[u8], after: &[u8]| UndoStep {
+            before: WorkspaceSnapshotAddress::new(before),
+            after: WorkspaceSnapshotAddress::new(after),
+        };
+
+        let history = vec![
+            entry(b"fork", &system, ChangeSetPointerOperation::Fork),
+            entry(b"a", &user, ChangeSetPointerOperation::Commit),
+            entry(b"b", &system, ChangeSetPointerOperation::Commit),
+            entry(b"c", &user, ChangeSetPointerOperation::Commit),
+            entry(b"undo c", &user, ChangeSetPointerOperation::Undo),
+        ];
+        assert_eq!(
+            UndoStacks {
+                undo: vec![step(b"fork", b"a")],
+                redo: vec![step(b"b", b"c")],
+            },
+            UndoStacks::from_history(&history, &user)
+        );
+        assert_eq!(
+            UndoStacks {
+                undo: vec![step(b"a", b"b")],
+                redo: vec![],
+            },
+            UndoStacks::from_history(&history, &system)
+        );
+
+        let mut history = history;
+        history.push(entry(b"redo c", &user, ChangeSetPointerOperation::Redo));
+        assert_eq!(
+            UndoStacks {
+                undo: vec![step(b"fork", b"a"), step(b"b", b"c")],
+                redo: vec![],
+            },
+            UndoStacks::from_history(&history, &user)
+        );
+
+        // A new change cannot be followed by a redo of an older one.
+        history.push(entry(
+            b"undo c again",
+            &user,
+            ChangeSetPointerOperation::Undo,
+        ));
+        history.push(entry(b"d", &user, ChangeSetPointerOperation::Commit));
+        assert_eq!(
+            UndoStacks {
+                undo: vec![step(b"fork", b"a"), step(b"undo c again", b"d")],
+                redo: vec![],
+            },
+            UndoStacks::from_history(&history, &user)
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{collections::HashMap, collections::HashSet, mem, path::PathBuf, sync::Arc};
 
+use chrono::{DateTime, Utc};
 use futures::Future;
 use serde::{Deserialize, Serialize};
 use si_crypto::SymmetricCryptoService;
@@ -20,10 +21,16 @@ use veritech_client::{Client as VeritechClient, CycloneEncryptionKey};
 
 use crate::layer_db_types::ContentTypes;
 use crate::workspace_snapshot::{
-    conflict::Conflict, graph::WorkspaceSnapshotGraph, update::Update, vector_clock::VectorClockId,
+    conflict::{ConflictChoice, StableConflict},
+    graph::WorkspaceSnapshotGraph,
+    update::StableUpdate,
+    vector_clock::VectorClockId,
 };
 use crate::{
-    change_set::{ChangeSet, ChangeSetId},
+    change_set::{
+        pointer_history::{ChangeSetPointerHistoryEntry, ChangeSetPointerOperation},
+        ChangeSet, ChangeSetError, ChangeSetId,
+    },
     job::{
         definition::ActionsJob,
         processor::{JobQueueProcessor, JobQueueProcessorError},
@@ -62,6 +69,9 @@ pub struct ServicesContext {
     symmetric_crypto_service: SymmetricCryptoService,
     /// The layer db (moka-rs, sled and postgres)
     layer_db: DalLayerDb,
+    /// Determines if every [`DalContextBuilder`] made from this validates workspace snapshot
+    /// graphs before they are written.
+    validate_snapshots: bool,
 }
 
 impl ServicesContext {
@@ -88,18 +98,31 @@ impl ServicesContext {
             module_index_url,
             symmetric_crypto_service,
             layer_db,
+            validate_snapshots: false,
         }
     }
 
     /// Consumes and returns [`DalContextBuilder`].
     pub fn into_builder(self, blocking: bool) -> DalContextBuilder {
         DalContextBuilder {
+            validate_snapshots: self.validate_snapshots,
             services_context: self,
             blocking,
             no_dependent_values: false,
         }
     }
 
+    /// Validate workspace snapshot graphs before they are written, in every [`DalContext`] built
+    /// from this.
+    pub fn set_validate_snapshots(&mut self) {
+        self.validate_snapshots = true;
+    }
+
+    /// Whether workspace snapshot graphs are validated before they are written.
+    pub fn validate_snapshots(&self) -> bool {
+        self.validate_snapshots
+    }
+
     /// Gets a reference to the Postgres pool.
     pub fn pg_pool(&self) -> &PgPool {
         &self.pg_pool
@@ -288,10 +311,15 @@ pub struct DalContext {
     /// Determines if we should not enqueue dependent value update jobs for attribute updates in
     /// this context. Useful for builtin migrations, since we don't care about attribute values propagation then.
     no_dependent_values: bool,
+    /// Determines if the workspace snapshot graph should be validated before it is written, and
+    /// the write refused if it is invalid. Useful for tests and debugging.
+    validate_snapshots: bool,
     /// The workspace snapshot for this context
     workspace_snapshot: Option<Arc<WorkspaceSnapshot>>,
     /// The change set for this context
     change_set: Option<ChangeSet>,
+    /// Set when the workspace snapshot is a historical one, which must never be committed.
+    read_only: bool,
 }
 
 impl DalContext {
@@ -299,6 +327,7 @@ impl DalContext {
     /// `DalContext`.
     pub fn builder(services_context: ServicesContext, blocking: bool) -> DalContextBuilder {
         DalContextBuilder {
+            validate_snapshots: services_context.validate_snapshots,
             services_context,
             blocking,
             no_dependent_values: false,
@@ -328,10 +357,59 @@ impl DalContext {
 
         self.set_change_set(change_set)?;
         self.set_workspace_snapshot(workspace_snapshot);
+        self.read_only = false;
+
+        Ok(())
+    }
+
+    /// Updates this context to read from the snapshot its change set pointed to at the given time,
+    /// according to the change set's pointer history. The context becomes read-only.
+    pub async fn update_snapshot_to_change_set_history_as_of(
+        &mut self,
+        as_of: DateTime<Utc>,
+    ) -> Result<(), TransactionsError> {
+        let entry = ChangeSetPointerHistoryEntry::find_as_of(self, self.change_set_id(), as_of)
+            .await
+            .map_err(Box::new)?
+            .ok_or(TransactionsError::NoChangeSetPointerHistory(
+                self.change_set_id(),
+                as_of,
+            ))?;
+
+        self.update_snapshot_to_address_read_only(entry.workspace_snapshot_address)
+            .await
+    }
+
+    /// Updates this context to read from the snapshot at the given address. The context becomes
+    /// read-only, so any attempt to commit it will fail.
+    pub async fn update_snapshot_to_address_read_only(
+        &mut self,
+        workspace_snapshot_address: WorkspaceSnapshotAddress,
+    ) -> Result<(), TransactionsError> {
+        let workspace_snapshot = WorkspaceSnapshot::find(self, workspace_snapshot_address)
+            .await
+            .map_err(Box::new)?;
+
+        self.set_workspace_snapshot(workspace_snapshot);
+        self.read_only = true;
 
         Ok(())
     }
 
+    /// Whether this context reads from a historical snapshot and cannot be committed.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fails if this context is read-only. Checked wherever this context would commit or move a
+    /// change set pointer.
+    pub fn ensure_writable(&self) -> Result<(), TransactionsError> {
+        if self.read_only {
+            return Err(TransactionsError::ReadOnly(self.change_set_id()));
+        }
+        Ok(())
+    }
+
     pub async fn update_snapshot_to_visibility_no_editing_change_set(
         &mut self,
     ) -> Result<(), TransactionsError> {
@@ -346,6 +424,7 @@ impl DalContext {
 
         self.change_set = Some(change_set);
         self.set_workspace_snapshot(workspace_snapshot);
+        self.read_only = false;
 
         Ok(())
     }
@@ -353,6 +432,8 @@ impl DalContext {
     pub async fn write_snapshot(
         &self,
     ) -> Result<Option<WorkspaceSnapshotAddress>, TransactionsError> {
+        self.ensure_writable()?;
+
         if let Some(snapshot) = &self.workspace_snapshot {
             let vector_clock_id = self.change_set()?.vector_clock_id();
 
@@ -364,7 +445,7 @@ impl DalContext {
         }
     }
 
-    fn get_rebase_request(
+    pub(crate) fn get_rebase_request(
         &self,
         onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
     ) -> Result<RebaseRequest, TransactionsError> {
@@ -375,6 +456,9 @@ impl DalContext {
             // of the current change set
             to_rebase_change_set_id: self.change_set_id(),
             onto_vector_clock_id: vector_clock_id,
+            conflict_choices: vec![],
+            actor: self.events_actor(),
+            pointer_operation: Some(ChangeSetPointerOperation::Commit),
         })
     }
 
@@ -382,6 +466,7 @@ impl DalContext {
         &self,
         rebase_request: RebaseRequest,
     ) -> Result<Option<Conflicts>, TransactionsError> {
+        self.ensure_writable()?;
         rebase(&self.tenancy, &self.layer_db(), rebase_request).await
     }
 
@@ -389,6 +474,7 @@ impl DalContext {
         &self,
         rebase_request: Option<RebaseRequest>,
     ) -> Result<Option<Conflicts>, TransactionsError> {
+        self.ensure_writable()?;
         let conflicts = if self.blocking {
             self.blocking_commit_internal(rebase_request).await?
         } else {
@@ -409,6 +495,7 @@ impl DalContext {
         &self,
         rebase_request: Option<RebaseRequest>,
     ) -> Result<Option<Conflicts>, TransactionsError> {
+        self.ensure_writable()?;
         let mut guard = self.conns_state.lock().await;
 
         let (new_guard, conflicts) = guard
@@ -425,6 +512,7 @@ impl DalContext {
             services_context: self.services_context.clone(),
             blocking: self.blocking,
             no_dependent_values: self.no_dependent_values,
+            validate_snapshots: self.validate_snapshots,
         }
     }
 
@@ -461,8 +549,14 @@ impl DalContext {
         }
     }
 
+    /// The layer db for this context. If the context is read-only, every write through it is
+    /// refused.
     pub fn layer_db(&self) -> DalLayerDb {
-        self.services_context().layer_db().clone()
+        if self.read_only {
+            self.services_context().layer_db().read_only()
+        } else {
+            self.services_context().layer_db().clone()
+        }
     }
 
     /// Fetch the change set for the current change set visibility
@@ -505,6 +599,10 @@ impl DalContext {
         self.no_dependent_values
     }
 
+    pub fn validate_snapshots(&self) -> bool {
+        self.validate_snapshots
+    }
+
     pub fn services_context(&self) -> ServicesContext {
         self.services_context.clone()
     }
@@ -885,6 +983,8 @@ pub struct DalContextBuilder {
     /// Determines if we should not enqueue dependent value update jobs for attribute value
     /// changes.
     no_dependent_values: bool,
+    /// Determines if workspace snapshot graphs should be validated before they are written.
+    validate_snapshots: bool,
 }
 
 impl DalContextBuilder {
@@ -900,8 +1000,10 @@ impl DalContextBuilder {
             visibility: Visibility::new_head(),
             history_actor: HistoryActor::SystemInit,
             no_dependent_values: self.no_dependent_values,
+            validate_snapshots: self.validate_snapshots,
             workspace_snapshot: None,
             change_set: None,
+            read_only: false,
         })
     }
 
@@ -920,8 +1022,10 @@ impl DalContextBuilder {
             history_actor: access_builder.history_actor,
             visibility: Visibility::new_head(),
             no_dependent_values: self.no_dependent_values,
+            validate_snapshots: self.validate_snapshots,
             workspace_snapshot: None,
             change_set: None,
+            read_only: false,
         };
 
         // TODO(nick): there's a chicken and egg problem here. We want a dal context to get the
@@ -950,8 +1054,10 @@ impl DalContextBuilder {
             visibility: request_context.visibility,
             history_actor: request_context.history_actor,
             no_dependent_values: self.no_dependent_values,
+            validate_snapshots: self.validate_snapshots,
             workspace_snapshot: None,
             change_set: None,
+            read_only: false,
         };
 
         ctx.update_snapshot_to_visibility().await?;
@@ -987,6 +1093,10 @@ impl DalContextBuilder {
     pub fn set_no_dependent_values(&mut self) {
         self.no_dependent_values = true;
     }
+
+    pub fn set_validate_snapshots(&mut self) {
+        self.validate_snapshots = true;
+    }
 }
 
 #[remain::sorted]
@@ -1008,10 +1118,18 @@ pub enum TransactionsError {
     Nats(#[from] NatsError),
     #[error("no base change set for change set: {0}")]
     NoBaseChangeSet(ChangeSetId),
+    #[error("no pointer history for change set {0} as of {1}")]
+    NoChangeSetPointerHistory(ChangeSetId, DateTime<Utc>),
     #[error(transparent)]
     Pg(#[from] PgError),
     #[error(transparent)]
     PgPool(#[from] PgPoolError),
+    #[error(transparent)]
+    PointerHistory(#[from] Box<ChangeSetError>),
+    #[error("cannot write a historical snapshot for change set {0} (the context is read-only)")]
+    ReadOnly(ChangeSetId),
+    #[error(transparent)]
+    ReadOnlySnapshot(#[from] Box<WorkspaceSnapshotError>),
     #[error("rebase of snapshot {0} change set id {1} failed {2}")]
     RebaseFailed(WorkspaceSnapshotAddress, ChangeSetId, String),
     #[error(transparent)]
@@ -1099,12 +1217,30 @@ pub struct RebaseRequest {
     pub to_rebase_change_set_id: ChangeSetId,
     pub onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
     pub onto_vector_clock_id: VectorClockId,
+    /// Resolutions chosen for specific conflicts, which take precedence over the rebaser's
+    /// conflict resolution policy.
+    pub conflict_choices: Vec<ConflictChoice>,
+    /// Who requested the rebase.
+    pub actor: si_events::Actor,
+    /// The operation to record in the change set's pointer history. If unset, the rebaser records
+    /// a [`Rebase`](ChangeSetPointerOperation::Rebase).
+    pub pointer_operation: Option<ChangeSetPointerOperation>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Conflicts {
-    conflicts_found: Vec<Conflict>,
-    updates_found_and_skipped: Vec<Update>,
+    conflicts_found: Vec<StableConflict>,
+    updates_found_and_skipped: Vec<StableUpdate>,
+}
+
+impl Conflicts {
+    pub fn conflicts_found(&self) -> &[StableConflict] {
+        &self.conflicts_found
+    }
+
+    pub fn updates_found_and_skipped(&self) -> &[StableUpdate] {
+        &self.updates_found_and_skipped
+    }
 }
 
 // TODO(nick): we need to determine the long term vision for tenancy-scoped subjects. We're leaking the tenancy into
@@ -1122,9 +1258,17 @@ async fn rebase(
             tenancy.workspace_pk().unwrap_or(WorkspacePk::NONE).into(),
             rebase_request.to_rebase_change_set_id.into(),
         ),
-        si_events::Actor::System,
+        rebase_request.actor.clone(),
     );
 
+    let conflict_choices = rebase_request
+        .conflict_choices
+        .iter()
+        .copied()
+        .map(Into::into)
+        .collect();
+    let pointer_operation = rebase_request.pointer_operation.map(Into::into);
+
     info!("requesting rebase: {:?}", start.elapsed());
     let rebase_finished_activity = layer_db
         .activity()
@@ -1133,6 +1277,8 @@ async fn rebase(
             rebase_request.to_rebase_change_set_id.into(),
             rebase_request.onto_workspace_snapshot_address,
             rebase_request.onto_vector_clock_id.into(),
+            conflict_choices,
+            pointer_operation,
             metadata,
         )
         .await?;
@@ -36,6 +36,7 @@ impl FuncDispatch for FuncBackendJsAction {
             // Once we start tracking the state of these executions, then this id will be useful,
             // but for now it's passed along and back, and is opaue
             execution_id: "ayrtonsennajscommand".to_string(),
+            timeout_secs: None,
             handler: handler.into(),
             code_base64: code_base64.into(),
             args: serde_json::to_value(args)
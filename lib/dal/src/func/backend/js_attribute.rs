@@ -36,6 +36,7 @@ impl FuncDispatch for FuncBackendJsAttribute {
             // Once we start tracking the state of these executions, then this id will be useful,
             // but for now it's passed along and back, and is opaque
             execution_id: "tomcruise".to_string(),
+            timeout_secs: None,
             handler: handler.into(),
             component: args.component,
             response_type: args.response_type,
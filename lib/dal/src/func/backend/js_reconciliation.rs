@@ -49,6 +49,7 @@ impl FuncDispatch for FuncBackendJsReconciliation {
             // Once we start tracking the state of these executions, then this id will be useful,
             // but for now it's passed along and back, and is opaue
             execution_id: "freeronaldinhogauchojsreconciliation".to_string(),
+            timeout_secs: None,
             handler: handler.into(),
             code_base64: code_base64.into(),
             args: serde_json::to_value(args)
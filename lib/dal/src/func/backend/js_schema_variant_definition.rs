@@ -25,6 +25,7 @@ impl FuncDispatch for FuncBackendJsSchemaVariantDefinition {
     ) -> Box<Self> {
         let request = SchemaVariantDefinitionRequest {
             execution_id: "villanelle".to_string(),
+            timeout_secs: None,
             handler: handler.into(),
             code_base64: code_base64.to_owned(),
         };
@@ -49,6 +49,15 @@ impl From<UserPk> for HistoryActor {
     }
 }
 
+impl From<si_events::Actor> for HistoryActor {
+    fn from(value: si_events::Actor) -> Self {
+        match value {
+            si_events::Actor::System => HistoryActor::SystemInit,
+            si_events::Actor::User(user_pk) => HistoryActor::User(user_pk.into()),
+        }
+    }
+}
+
 pk!(HistoryEventPk);
 
 /// HistoryEvents are the audit trail for things in SI. They track
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use si_events::{CasValue, ContentHash, EncryptedSecretKey};
+use si_layer_cache::SchemaVersioned;
 use strum::EnumDiscriminants;
 
 use crate::{
@@ -40,6 +41,8 @@ pub enum ContentTypes {
     OutputSocket(OutputSocketContent),
 }
 
+impl SchemaVersioned for ContentTypes {}
+
 macro_rules! impl_into_content_types {
     (
         $(#[$($attrss:tt)*])*
@@ -0,0 +1,3 @@
+-- the snapshot a change set was forked from, used as the common ancestor when merging its content
+ALTER TABLE change_set_pointers
+    ADD COLUMN base_workspace_snapshot_address text;
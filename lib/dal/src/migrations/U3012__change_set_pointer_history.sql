@@ -0,0 +1,13 @@
+-- an append-only log of every workspace snapshot address a change set has pointed to
+CREATE TABLE change_set_pointer_history
+(
+    id                         ident primary key        NOT NULL DEFAULT ident_create_v1(),
+    created_at                 timestamp with time zone NOT NULL DEFAULT CLOCK_TIMESTAMP(),
+    change_set_id              ident                    NOT NULL,
+    workspace_snapshot_address text                     NOT NULL,
+    actor                      jsonb                    NOT NULL,
+    operation                  text                     NOT NULL
+);
+
+CREATE INDEX change_set_pointer_history_change_set_id_created_at
+    ON change_set_pointer_history (change_set_id, created_at);
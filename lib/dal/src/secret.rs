@@ -32,7 +32,7 @@ use si_data_pg::PgError;
 use si_events::ContentHash;
 use si_events::EncryptedSecretKey;
 use si_hash::Hash;
-use si_layer_cache::LayerDbError;
+use si_layer_cache::{LayerDbError, SchemaVersioned};
 use sodiumoxide::crypto::box_::{PublicKey, SecretKey};
 use sodiumoxide::crypto::sealedbox;
 use std::collections::HashMap;
@@ -449,6 +449,8 @@ pub struct EncryptedSecret {
     crypted: Vec<u8>,
 }
 
+impl SchemaVersioned for EncryptedSecret {}
+
 impl fmt::Debug for EncryptedSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EncryptedSecret")
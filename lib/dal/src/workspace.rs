@@ -5,6 +5,7 @@ use si_data_pg::{PgError, PgRow};
 use telemetry::prelude::*;
 use thiserror::Error;
 
+use crate::change_set::pointer_history::ChangeSetPointerOperation;
 use crate::change_set::{ChangeSet, ChangeSetError, ChangeSetId};
 use crate::workspace_snapshot::WorkspaceSnapshotError;
 use crate::{
@@ -116,7 +117,11 @@ impl Workspace {
         let mut change_set = ChangeSet::new(ctx, DEFAULT_CHANGE_SET_NAME, None).await?;
         let workspace_snapshot = WorkspaceSnapshot::initial(ctx, &change_set).await?;
         change_set
-            .update_pointer(ctx, workspace_snapshot.id().await)
+            .update_pointer(
+                ctx,
+                workspace_snapshot.id().await,
+                ChangeSetPointerOperation::Create,
+            )
             .await?;
         let change_set_id = change_set.id;
 
@@ -206,7 +211,11 @@ impl Workspace {
         let workspace_snapshot =
             WorkspaceSnapshot::find_for_change_set(ctx, builtin.default_change_set_id).await?;
         change_set
-            .update_pointer(ctx, workspace_snapshot.id().await)
+            .update_pointer(
+                ctx,
+                workspace_snapshot.id().await,
+                ChangeSetPointerOperation::Create,
+            )
             .await?;
         let change_set_id = change_set.id;
 
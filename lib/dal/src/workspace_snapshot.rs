@@ -23,14 +23,19 @@
 
 pub mod conflict;
 pub mod content_address;
+pub mod diff;
 pub mod edge_weight;
+pub mod garbage_collection;
 pub mod graph;
+pub mod json_merge;
 pub mod lamport_clock;
 pub mod node_weight;
 pub mod update;
 pub mod vector_clock;
 
+use chrono::Utc;
 use si_layer_cache::persister::PersistStatus;
+use si_layer_cache::WarmUpConfig;
 use si_pkg::KeyOrIndex;
 use std::sync::Arc;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -44,17 +49,22 @@ use thiserror::Error;
 use ulid::Ulid;
 
 use crate::change_set::{ChangeSet, ChangeSetError, ChangeSetId};
-use crate::workspace_snapshot::conflict::Conflict;
+use crate::workspace_snapshot::conflict::{
+    Conflict, ConflictChoice, ConflictResolutionPolicy, ConflictResolutions, StableConflict,
+};
+use crate::workspace_snapshot::content_address::ContentAddress;
 use crate::workspace_snapshot::edge_weight::{
     EdgeWeight, EdgeWeightError, EdgeWeightKind, EdgeWeightKindDiscriminants,
 };
+use crate::workspace_snapshot::graph::ValidationReport;
+use crate::workspace_snapshot::json_merge::{three_way_merge, JsonMergeOutcome};
 use crate::workspace_snapshot::node_weight::category_node_weight::CategoryNodeKind;
 use crate::workspace_snapshot::node_weight::NodeWeight;
-use crate::workspace_snapshot::update::Update;
+use crate::workspace_snapshot::update::{StableUpdate, Update};
 use crate::workspace_snapshot::vector_clock::VectorClockId;
 use crate::{
     workspace_snapshot::{graph::WorkspaceSnapshotGraphError, node_weight::NodeWeightError},
-    DalContext, TransactionsError, WorkspaceSnapshotGraph,
+    ChangeSetStatus, DalContext, TransactionsError, WorkspaceSnapshotGraph,
 };
 
 use self::node_weight::{NodeWeightDiscriminants, OrderingNodeWeight};
@@ -68,6 +78,8 @@ pub enum WorkspaceSnapshotError {
     ChangeSetMissingWorkspaceSnapshotAddress(ChangeSetId),
     #[error("edge weight error: {0}")]
     EdgeWeight(#[from] EdgeWeightError),
+    #[error("workspace snapshot graph is invalid: {0:?}")]
+    InvalidGraph(ValidationReport),
     #[error("layer db error: {0}")]
     LayerDb(#[from] si_layer_cache::LayerDbError),
     #[error("missing content from store for id: {0}")]
@@ -102,6 +114,49 @@ pub enum WorkspaceSnapshotError {
 
 pub type WorkspaceSnapshotResult<T> = Result<T, WorkspaceSnapshotError>;
 
+/// A [`Conflict::NodeContent`] conflict between [`AttributeValue`](crate::AttributeValue) nodes
+/// settled by a three-way merge of their content, found by
+/// [`WorkspaceSnapshot::preview_attribute_value_merges()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeValueMerge {
+    onto: NodeIndex,
+    to_rebase: NodeIndex,
+    value: MergedContent,
+    unprocessed_value: MergedContent,
+    materialized_view: MergedContent,
+}
+
+/// The merged content of one slot of an [`AttributeValueMerge`].
+#[derive(Debug, Clone, PartialEq)]
+enum MergedContent {
+    /// Content one of the sides already has.
+    Address(Option<ContentAddress>),
+    /// Content combining both sides, which has yet to be written to the content store.
+    Value(serde_json::Value),
+}
+
+impl MergedContent {
+    async fn write(self, ctx: &DalContext) -> WorkspaceSnapshotResult<Option<ContentAddress>> {
+        Ok(match self {
+            Self::Address(address) => address,
+            Self::Value(value) => {
+                let cas_value: si_events::CasValue = value.into();
+                let (hash, _) = ctx
+                    .layer_db()
+                    .cas()
+                    .write(
+                        Arc::new(cas_value.into()),
+                        None,
+                        ctx.events_tenancy(),
+                        ctx.events_actor(),
+                    )
+                    .await?;
+                Some(ContentAddress::JsonValue(hash))
+            }
+        })
+    }
+}
+
 /// The workspace graph. The concurrency types used here to give us interior
 /// mutability in the tokio run time are *not* sufficient to prevent data races
 /// when operating on the same graph on different threads, since our graph
@@ -246,19 +301,30 @@ impl WorkspaceSnapshot {
         ctx: &DalContext,
         vector_clock_id: VectorClockId,
     ) -> WorkspaceSnapshotResult<WorkspaceSnapshotAddress> {
+        // The snapshot we started from, which the new one is stored as a delta against.
+        let base_address = self.id().await;
+
         // Pull out the working copy and clean it up.
         let new_address = {
             let mut working_copy = self.working_copy_mut().await;
             working_copy.cleanup();
 
+            if ctx.validate_snapshots() {
+                let report = working_copy.validate();
+                if !report.is_valid() {
+                    return Err(WorkspaceSnapshotError::InvalidGraph(report));
+                }
+            }
+
             // Mark everything left as seen.
             working_copy.mark_graph_seen(vector_clock_id)?;
 
             let (new_address, status_reader) = ctx
                 .layer_db()
                 .workspace_snapshot()
-                .write(
+                .write_delta(
                     Arc::new(working_copy.clone()),
+                    base_address,
                     None,
                     ctx.events_tenancy(),
                     ctx.events_actor(),
@@ -409,6 +475,247 @@ impl WorkspaceSnapshot {
         )?)
     }
 
+    /// Calls [`WorkspaceSnapshotGraph::validate()`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn validate(&self) -> ValidationReport {
+        self.working_copy().await.validate()
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::detect_reverting_updates()`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn detect_reverting_updates(
+        &self,
+        before: &WorkspaceSnapshot,
+        after: &WorkspaceSnapshot,
+    ) -> WorkspaceSnapshotResult<Vec<Update>> {
+        Ok(self.working_copy().await.detect_reverting_updates(
+            &*before.working_copy().await,
+            &*after.working_copy().await,
+        )?)
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::conflict_node_id()`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn conflict_node_id(
+        &self,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        conflict: &Conflict,
+    ) -> WorkspaceSnapshotResult<Ulid> {
+        Ok(self
+            .working_copy()
+            .await
+            .conflict_node_id(&*onto_workspace_snapshot.working_copy().await, conflict)?)
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::stable_conflict()`] for each [`Conflict`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn stable_conflicts(
+        &self,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        conflicts: &[Conflict],
+    ) -> WorkspaceSnapshotResult<Vec<StableConflict>> {
+        let working_copy = self.working_copy().await;
+        let onto_working_copy = onto_workspace_snapshot.working_copy().await;
+        Ok(conflicts
+            .iter()
+            .map(|conflict| working_copy.stable_conflict(&onto_working_copy, conflict))
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::conflict_from_stable()`] for each [`StableConflict`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn conflicts_from_stable(
+        &self,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        stable_conflicts: &[StableConflict],
+    ) -> WorkspaceSnapshotResult<Vec<Conflict>> {
+        let working_copy = self.working_copy().await;
+        let onto_working_copy = onto_workspace_snapshot.working_copy().await;
+        Ok(stable_conflicts
+            .iter()
+            .map(|stable_conflict| {
+                working_copy.conflict_from_stable(&onto_working_copy, stable_conflict)
+            })
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::stable_update()`] for each [`Update`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn stable_updates(
+        &self,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        updates: &[Update],
+    ) -> WorkspaceSnapshotResult<Vec<StableUpdate>> {
+        let working_copy = self.working_copy().await;
+        let onto_working_copy = onto_workspace_snapshot.working_copy().await;
+        Ok(updates
+            .iter()
+            .map(|update| working_copy.stable_update(&onto_working_copy, update))
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::update_from_stable()`] for each [`StableUpdate`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn updates_from_stable(
+        &self,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        stable_updates: &[StableUpdate],
+    ) -> WorkspaceSnapshotResult<Vec<Update>> {
+        let working_copy = self.working_copy().await;
+        let onto_working_copy = onto_workspace_snapshot.working_copy().await;
+        Ok(stable_updates
+            .iter()
+            .map(|stable_update| working_copy.update_from_stable(&onto_working_copy, stable_update))
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Calls [`WorkspaceSnapshotGraph::resolve_conflicts()`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn resolve_conflicts(
+        &self,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        conflicts: &[Conflict],
+        policy: &ConflictResolutionPolicy,
+        choices: &[ConflictChoice],
+    ) -> WorkspaceSnapshotResult<ConflictResolutions> {
+        Ok(self.working_copy().await.resolve_conflicts(
+            &*onto_workspace_snapshot.working_copy().await,
+            conflicts,
+            policy,
+            choices,
+        )?)
+    }
+
+    /// Finds the [`Conflict::NodeContent`] conflicts between
+    /// [`AttributeValue`](crate::AttributeValue) nodes that a structural [`three_way_merge`] of
+    /// their content can settle, using the content found in `ancestor_workspace_snapshot` as the
+    /// common ancestor. The merges are computed in memory: nothing is written to the content store
+    /// and this snapshot is left untouched until they are applied with
+    /// [`Self::apply_attribute_value_merges()`].
+    ///
+    /// Returns the merges along with the conflicts that could not be settled this way.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn preview_attribute_value_merges(
+        &self,
+        ctx: &DalContext,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        ancestor_workspace_snapshot: &WorkspaceSnapshot,
+        conflicts: Vec<Conflict>,
+    ) -> WorkspaceSnapshotResult<(Vec<AttributeValueMerge>, Vec<Conflict>)> {
+        let mut merges = Vec::new();
+        let mut remaining_conflicts = Vec::new();
+
+        for conflict in conflicts {
+            let Conflict::NodeContent { onto, to_rebase } = conflict else {
+                remaining_conflicts.push(conflict);
+                continue;
+            };
+
+            let (
+                NodeWeight::AttributeValue(onto_weight),
+                NodeWeight::AttributeValue(to_rebase_weight),
+            ) = (
+                onto_workspace_snapshot.get_node_weight(onto).await?,
+                self.get_node_weight(to_rebase).await?,
+            )
+            else {
+                remaining_conflicts.push(conflict);
+                continue;
+            };
+
+            let ancestor_weight = match ancestor_workspace_snapshot
+                .get_node_weight_by_id(to_rebase_weight.id())
+                .await
+            {
+                Ok(NodeWeight::AttributeValue(ancestor_weight)) => Some(ancestor_weight),
+                Ok(_) => None,
+                Err(WorkspaceSnapshotError::WorkspaceSnapshotGraph(
+                    WorkspaceSnapshotGraphError::NodeWithIdNotFound(_),
+                )) => None,
+                Err(err) => return Err(err),
+            };
+            let ancestor_weight = ancestor_weight.as_ref();
+
+            let value = merge_json_value_address(
+                ctx,
+                to_rebase_weight.id(),
+                ancestor_weight.and_then(|weight| weight.value()),
+                to_rebase_weight.value(),
+                onto_weight.value(),
+            )
+            .await?;
+            let unprocessed_value = merge_json_value_address(
+                ctx,
+                to_rebase_weight.id(),
+                ancestor_weight.and_then(|weight| weight.unprocessed_value()),
+                to_rebase_weight.unprocessed_value(),
+                onto_weight.unprocessed_value(),
+            )
+            .await?;
+            let materialized_view = merge_json_value_address(
+                ctx,
+                to_rebase_weight.id(),
+                ancestor_weight.and_then(|weight| weight.materialized_view()),
+                to_rebase_weight.materialized_view(),
+                onto_weight.materialized_view(),
+            )
+            .await?;
+
+            match (value, unprocessed_value, materialized_view) {
+                (Some(value), Some(unprocessed_value), Some(materialized_view)) => {
+                    merges.push(AttributeValueMerge {
+                        onto,
+                        to_rebase,
+                        value,
+                        unprocessed_value,
+                        materialized_view,
+                    })
+                }
+                _ => remaining_conflicts.push(conflict),
+            }
+        }
+
+        Ok((merges, remaining_conflicts))
+    }
+
+    /// Applies merges found by [`Self::preview_attribute_value_merges()`] to this snapshot,
+    /// writing any merged content to the content store. Should only be called once the rebase the
+    /// merges are part of is known to succeed.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn apply_attribute_value_merges(
+        &self,
+        ctx: &DalContext,
+        to_rebase_change_set: &ChangeSet,
+        onto_workspace_snapshot: &WorkspaceSnapshot,
+        merges: Vec<AttributeValueMerge>,
+    ) -> WorkspaceSnapshotResult<()> {
+        for merge in merges {
+            // Applying earlier merges replaces the ancestors of the merged nodes, so we need to
+            // make sure we are looking at the latest version of this one.
+            let to_rebase = self.get_latest_node_index(merge.to_rebase).await?;
+            let mut merged_weight = self
+                .get_node_weight(to_rebase)
+                .await?
+                .get_attribute_value_node_weight()?;
+            merged_weight.set_value(merge.value.write(ctx).await?);
+            merged_weight.set_unprocessed_value(merge.unprocessed_value.write(ctx).await?);
+            merged_weight.set_materialized_view(merge.materialized_view.write(ctx).await?);
+
+            // Merging the clocks records that the onto side's changes have been incorporated, so
+            // the same content will not be reported as conflicting again.
+            let mut merged_weight = NodeWeight::AttributeValue(merged_weight);
+            merged_weight.merge_clocks(
+                to_rebase_change_set,
+                &onto_workspace_snapshot.get_node_weight(merge.onto).await?,
+            )?;
+
+            self.add_node(merged_weight).await?;
+            self.replace_references(to_rebase).await?;
+        }
+
+        Ok(())
+    }
+
     // NOTE(nick): this should only be used by the rebaser.
     #[instrument(level = "debug", skip_all)]
     pub async fn edge_endpoints(
@@ -536,8 +843,12 @@ impl WorkspaceSnapshot {
     ) -> WorkspaceSnapshotResult<Self> {
         let start = tokio::time::Instant::now();
 
-        let snapshot = ctx
-            .layer_db()
+        let layer_db = ctx.layer_db();
+        let already_in_memory = layer_db
+            .workspace_snapshot()
+            .cache
+            .contains(&workspace_snapshot_addr.to_string());
+        let snapshot = layer_db
             .workspace_snapshot()
             .read(&workspace_snapshot_addr)
             .await?
@@ -547,6 +858,12 @@ impl WorkspaceSnapshot {
 
         info!("snapshot fetch took: {:?}", start.elapsed());
 
+        // The first time a snapshot is read, so is most of its content soon after. Reading it all
+        // in the background now (if configured to) saves reading it one piece at a time later.
+        if !already_in_memory {
+            layer_db.spawn_prefetch(workspace_snapshot_addr);
+        }
+
         Ok(Self {
             address: Arc::new(RwLock::new(workspace_snapshot_addr)),
             read_only_graph: snapshot,
@@ -554,6 +871,56 @@ impl WorkspaceSnapshot {
         })
     }
 
+    /// Prefetches the snapshots of the change sets most recently written to, as configured, so
+    /// that the first requests after a service starts do not have to read them. Returns how many
+    /// snapshots were prefetched.
+    #[instrument(name = "workspace_snapshot.warm_up", level = "info", skip_all)]
+    pub async fn warm_up(
+        ctx: &DalContext,
+        config: &WarmUpConfig,
+    ) -> WorkspaceSnapshotResult<usize> {
+        if !config.enabled || config.max_change_sets == 0 {
+            return Ok(0);
+        }
+
+        let active_since = Utc::now() - chrono::Duration::seconds(config.active_within_secs as i64);
+        let open_status = ChangeSetStatus::Open.to_string();
+        let rows = ctx
+            .txns()
+            .await?
+            .pg()
+            .query(
+                "SELECT c.workspace_snapshot_address, max(h.created_at) AS last_written_at
+                FROM change_set_pointers c
+                JOIN change_set_pointer_history h ON h.change_set_id = c.id
+                WHERE c.status = $1 AND c.workspace_snapshot_address IS NOT NULL AND h.created_at >= $2
+                GROUP BY c.id, c.workspace_snapshot_address
+                ORDER BY last_written_at DESC
+                LIMIT $3",
+                &[&open_status, &active_since, &i64::from(config.max_change_sets)],
+            )
+            .await?;
+
+        let layer_db = ctx.layer_db();
+        let mut prefetched = 0;
+        for row in rows {
+            let address: WorkspaceSnapshotAddress = row.try_get("workspace_snapshot_address")?;
+            if let Some(report) = layer_db.prefetch(&address).await? {
+                debug!(
+                    %address,
+                    content_hashes = report.content_hashes,
+                    loaded = report.loaded,
+                    "prefetched snapshot"
+                );
+                prefetched += 1;
+            }
+        }
+
+        info!(prefetched, "layer db warm-up finished");
+
+        Ok(prefetched)
+    }
+
     pub async fn find_for_change_set(
         ctx: &DalContext,
         change_set_id: ChangeSetId,
@@ -966,3 +1333,62 @@ impl WorkspaceSnapshot {
         Ok(None)
     }
 }
+
+/// Three-way merge the JSON content behind the given addresses, writing the merged value to the
+/// content store when it differs from both sides. Returns `None` if the content conflicts.
+async fn merge_json_value_address(
+    ctx: &DalContext,
+    attribute_value_id: Ulid,
+    base: Option<ContentAddress>,
+    ours: Option<ContentAddress>,
+    theirs: Option<ContentAddress>,
+) -> WorkspaceSnapshotResult<Option<MergedContent>> {
+    // Most slots will have only been changed on one side (if at all), which we can tell without
+    // fetching any content.
+    if ours == theirs || base == theirs {
+        return Ok(Some(MergedContent::Address(ours)));
+    }
+    if base == ours {
+        return Ok(Some(MergedContent::Address(theirs)));
+    }
+
+    let base_value = fetch_json_value(ctx, attribute_value_id, base).await?;
+    let ours_value = fetch_json_value(ctx, attribute_value_id, ours).await?;
+    let theirs_value = fetch_json_value(ctx, attribute_value_id, theirs).await?;
+
+    match three_way_merge(
+        base_value.as_ref(),
+        ours_value.as_ref(),
+        theirs_value.as_ref(),
+    ) {
+        JsonMergeOutcome::Merged(None) => Ok(Some(MergedContent::Address(None))),
+        JsonMergeOutcome::Merged(Some(merged)) => Ok(Some(MergedContent::Value(merged))),
+        JsonMergeOutcome::Conflicted(pointers) => {
+            debug!(
+                "attribute value {} has conflicting content at {:?}",
+                attribute_value_id, pointers
+            );
+            Ok(None)
+        }
+    }
+}
+
+async fn fetch_json_value(
+    ctx: &DalContext,
+    attribute_value_id: Ulid,
+    address: Option<ContentAddress>,
+) -> WorkspaceSnapshotResult<Option<serde_json::Value>> {
+    Ok(match address {
+        Some(address) => Some(
+            ctx.layer_db()
+                .cas()
+                .try_read_as::<si_events::CasValue>(&address.content_hash())
+                .await?
+                .ok_or(WorkspaceSnapshotError::MissingContentFromStore(
+                    attribute_value_id,
+                ))?
+                .into(),
+        ),
+        None => None,
+    })
+}
@@ -1,11 +1,20 @@
 use petgraph::stable_graph::NodeIndex;
 use serde::Deserialize;
 use serde::Serialize;
+use si_layer_cache::activities::rebase::{
+    RebaseConflictChoice, RebaseConflictKind, RebaseConflictStrategy,
+};
+use strum::EnumDiscriminants;
+use ulid::Ulid;
+
+use crate::workspace_snapshot::node_weight::NodeInformation;
+use crate::workspace_snapshot::update::Update;
 
 /// Describe the type of conflict between the given locations in a
 /// workspace graph.
 #[remain::sorted]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize, EnumDiscriminants)]
+#[strum_discriminants(derive(Hash, Serialize, Deserialize))]
 pub enum Conflict {
     ChildOrder {
         onto: NodeIndex,
@@ -22,6 +31,28 @@ pub enum Conflict {
     },
 }
 
+/// A [`Conflict`] with each [`NodeIndex`] replaced by the [`NodeInformation`] of the node it
+/// referred to, so that it remains meaningful once the graphs it was detected between are gone.
+/// See [`WorkspaceSnapshotGraph::stable_conflict()`](crate::WorkspaceSnapshotGraph::stable_conflict)
+/// and [`WorkspaceSnapshotGraph::conflict_from_stable()`](crate::WorkspaceSnapshotGraph::conflict_from_stable).
+#[remain::sorted]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StableConflict {
+    ChildOrder {
+        onto: NodeInformation,
+        to_rebase: NodeInformation,
+    },
+    ModifyRemovedItem(NodeInformation),
+    NodeContent {
+        onto: NodeInformation,
+        to_rebase: NodeInformation,
+    },
+    RemoveModifiedItem {
+        container: NodeInformation,
+        removed_item: NodeInformation,
+    },
+}
+
 /// The [`NodeIndex`] of the location in the graph where a conflict occurs.
 #[derive(Debug, Copy, Clone)]
 pub struct ConflictLocation {
@@ -30,3 +61,142 @@ pub struct ConflictLocation {
     /// The location of the conflict in the graph that is attempting to be merged into "base".
     pub to_rebase: NodeIndex,
 }
+
+/// How a [`Conflict`] should be handled when performing a rebase.
+#[remain::sorted]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolutionStrategy {
+    /// Leave the [`Conflict`] unresolved, which prevents the rebase from being performed.
+    #[default]
+    Fail,
+    /// Merge the child orderings of both sides, keeping the "to rebase" ordering and inserting
+    /// items only known to "onto" after their nearest preceding sibling. Only applicable to
+    /// [`Conflict::ChildOrder`].
+    Interleave,
+    /// Use what exists in the "onto" graph. For [`Conflict::RemoveModifiedItem`], this re-adds the
+    /// removed item to its container.
+    OntoWins,
+    /// Keep what exists in the "to rebase" graph. For [`Conflict::ModifyRemovedItem`], this keeps
+    /// the modified item in its container.
+    ToRebaseWins,
+}
+
+/// The [`ConflictResolutionStrategy`] to use for each kind of [`Conflict`]. The default policy
+/// resolves nothing.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConflictResolutionPolicy {
+    pub child_order: ConflictResolutionStrategy,
+    pub modify_removed_item: ConflictResolutionStrategy,
+    pub node_content: ConflictResolutionStrategy,
+    pub remove_modified_item: ConflictResolutionStrategy,
+}
+
+impl ConflictResolutionPolicy {
+    /// Returns the [`ConflictResolutionStrategy`] configured for the kind of the given
+    /// [`Conflict`].
+    pub fn strategy_for(&self, conflict: &Conflict) -> ConflictResolutionStrategy {
+        match conflict {
+            Conflict::ChildOrder { .. } => self.child_order,
+            Conflict::ModifyRemovedItem(_) => self.modify_removed_item,
+            Conflict::NodeContent { .. } => self.node_content,
+            Conflict::RemoveModifiedItem { .. } => self.remove_modified_item,
+        }
+    }
+}
+
+/// A [`ConflictResolutionStrategy`] chosen for one specific [`Conflict`], taking precedence over
+/// the [`ConflictResolutionPolicy`] for it. Since a [`NodeIndex`] is only meaningful for the graph
+/// it came from, the conflict is identified by its kind and the id of the node it is about (see
+/// [`WorkspaceSnapshotGraph::conflict_node_id()`](crate::WorkspaceSnapshotGraph::conflict_node_id)).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictChoice {
+    pub kind: ConflictDiscriminants,
+    pub node_id: Ulid,
+    pub strategy: ConflictResolutionStrategy,
+}
+
+impl From<ConflictChoice> for RebaseConflictChoice {
+    fn from(value: ConflictChoice) -> Self {
+        Self {
+            kind: value.kind.into(),
+            node_id: value.node_id,
+            strategy: value.strategy.into(),
+        }
+    }
+}
+
+impl From<RebaseConflictChoice> for ConflictChoice {
+    fn from(value: RebaseConflictChoice) -> Self {
+        Self {
+            kind: value.kind.into(),
+            node_id: value.node_id,
+            strategy: value.strategy.into(),
+        }
+    }
+}
+
+impl From<ConflictDiscriminants> for RebaseConflictKind {
+    fn from(value: ConflictDiscriminants) -> Self {
+        match value {
+            ConflictDiscriminants::ChildOrder => Self::ChildOrder,
+            ConflictDiscriminants::ModifyRemovedItem => Self::ModifyRemovedItem,
+            ConflictDiscriminants::NodeContent => Self::NodeContent,
+            ConflictDiscriminants::RemoveModifiedItem => Self::RemoveModifiedItem,
+        }
+    }
+}
+
+impl From<RebaseConflictKind> for ConflictDiscriminants {
+    fn from(value: RebaseConflictKind) -> Self {
+        match value {
+            RebaseConflictKind::ChildOrder => Self::ChildOrder,
+            RebaseConflictKind::ModifyRemovedItem => Self::ModifyRemovedItem,
+            RebaseConflictKind::NodeContent => Self::NodeContent,
+            RebaseConflictKind::RemoveModifiedItem => Self::RemoveModifiedItem,
+        }
+    }
+}
+
+impl From<ConflictResolutionStrategy> for RebaseConflictStrategy {
+    fn from(value: ConflictResolutionStrategy) -> Self {
+        match value {
+            ConflictResolutionStrategy::Fail => Self::Fail,
+            ConflictResolutionStrategy::Interleave => Self::Interleave,
+            ConflictResolutionStrategy::OntoWins => Self::OntoWins,
+            ConflictResolutionStrategy::ToRebaseWins => Self::ToRebaseWins,
+        }
+    }
+}
+
+impl From<RebaseConflictStrategy> for ConflictResolutionStrategy {
+    fn from(value: RebaseConflictStrategy) -> Self {
+        match value {
+            RebaseConflictStrategy::Fail => Self::Fail,
+            RebaseConflictStrategy::Interleave => Self::Interleave,
+            RebaseConflictStrategy::OntoWins => Self::OntoWins,
+            RebaseConflictStrategy::ToRebaseWins => Self::ToRebaseWins,
+        }
+    }
+}
+
+/// A [`Conflict`] (or [`StableConflict`]) that was resolved, along with how it was resolved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResolvedConflict<C = Conflict> {
+    pub conflict: C,
+    pub strategy: ConflictResolutionStrategy,
+}
+
+/// The outcome of attempting to resolve [`Conflicts`](Conflict) with a
+/// [`ConflictResolutionPolicy`] and any [`ConflictChoices`](ConflictChoice).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConflictResolutions {
+    /// The conflicts that were resolved.
+    pub resolved: Vec<ResolvedConflict>,
+    /// The conflicts that could not be resolved.
+    pub unresolved: Vec<Conflict>,
+    /// The updates needed to carry out the resolutions.
+    pub updates: Vec<Update>,
+}
@@ -0,0 +1,327 @@
+//! Domain-level differences between two [`WorkspaceSnapshots`](WorkspaceSnapshot).
+//!
+//! Unlike [`WorkspaceSnapshot::detect_conflicts_and_updates()`], which is tailored to rebasing,
+//! [`WorkspaceSnapshot::diff()`] needs no vector clocks and describes changes in terms a person
+//! reviewing a change set would recognize.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use si_events::ContentHash;
+use thiserror::Error;
+use ulid::Ulid;
+
+use crate::attribute::prototype::argument::AttributePrototypeArgumentId;
+use crate::attribute::value::AttributeValueError;
+use crate::change_set::{ChangeSet, ChangeSetId};
+use crate::workspace_snapshot::content_address::ContentAddress;
+use crate::workspace_snapshot::graph::WorkspaceSnapshotGraph;
+use crate::workspace_snapshot::node_weight::attribute_prototype_argument_node_weight::ArgumentTargets;
+use crate::workspace_snapshot::node_weight::NodeWeight;
+use crate::workspace_snapshot::WorkspaceSnapshotError;
+use crate::{
+    AttributeValue, AttributeValueId, Component, ComponentError, ComponentId, DalContext, FuncId,
+    WorkspaceSnapshot,
+};
+
+/// The path of the attribute value holding a component's name.
+const COMPONENT_NAME_PATH: &str = "root/si/name";
+
+#[remain::sorted]
+#[derive(Debug, Error)]
+pub enum SnapshotDiffError {
+    #[error("attribute value error: {0}")]
+    AttributeValue(#[from] AttributeValueError),
+    #[error("component error: {0}")]
+    Component(#[from] ComponentError),
+    #[error("change set {0} has no base change set")]
+    NoBaseChangeSet(ChangeSetId),
+    #[error("workspace snapshot error: {0}")]
+    WorkspaceSnapshot(#[from] WorkspaceSnapshotError),
+}
+
+pub type SnapshotDiffResult<T> = Result<T, SnapshotDiffError>;
+
+/// A single difference found by [`WorkspaceSnapshot::diff()`].
+#[remain::sorted]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum SnapshotChange {
+    #[serde(rename_all = "camelCase")]
+    AttributeValueChanged {
+        attribute_value_id: AttributeValueId,
+        component_id: ComponentId,
+        path: Option<String>,
+        old_value: Option<serde_json::Value>,
+        new_value: Option<serde_json::Value>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ComponentAdded {
+        component_id: ComponentId,
+        name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ComponentRemoved {
+        component_id: ComponentId,
+        name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ComponentRenamed {
+        component_id: ComponentId,
+        old_name: String,
+        new_name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ConnectionAdded {
+        attribute_prototype_argument_id: AttributePrototypeArgumentId,
+        source_component_id: ComponentId,
+        destination_component_id: ComponentId,
+    },
+    #[serde(rename_all = "camelCase")]
+    ConnectionRemoved {
+        attribute_prototype_argument_id: AttributePrototypeArgumentId,
+        source_component_id: ComponentId,
+        destination_component_id: ComponentId,
+    },
+    #[serde(rename_all = "camelCase")]
+    FuncAdded { func_id: FuncId, name: String },
+    #[serde(rename_all = "camelCase")]
+    FuncChanged { func_id: FuncId, name: String },
+    #[serde(rename_all = "camelCase")]
+    FuncRemoved { func_id: FuncId, name: String },
+}
+
+/// The nodes of a graph that [`WorkspaceSnapshot::diff()`] is interested in, by id.
+#[derive(Default)]
+struct DiffableNodes {
+    components: BTreeSet<Ulid>,
+    attribute_values: BTreeMap<Ulid, Option<ContentAddress>>,
+    connections: BTreeMap<Ulid, ArgumentTargets>,
+    funcs: BTreeMap<Ulid, (String, ContentHash)>,
+}
+
+impl DiffableNodes {
+    fn collect(graph: &WorkspaceSnapshotGraph) -> Self {
+        let mut nodes = Self::default();
+        for (node_weight, node_index) in graph.nodes() {
+            // Skip anything that has been replaced, but not yet cleaned up.
+            if graph.get_node_index_by_id(node_weight.id()).ok() != Some(node_index) {
+                continue;
+            }
+
+            match node_weight {
+                NodeWeight::AttributePrototypeArgument(weight) => {
+                    if let Some(targets) = weight.targets() {
+                        nodes.connections.insert(weight.id(), targets);
+                    }
+                }
+                NodeWeight::AttributeValue(weight) => {
+                    nodes.attribute_values.insert(weight.id(), weight.value());
+                }
+                NodeWeight::Component(weight) => {
+                    nodes.components.insert(weight.id());
+                }
+                NodeWeight::Func(weight) => {
+                    nodes
+                        .funcs
+                        .insert(weight.id(), (weight.name().to_owned(), weight.node_hash()));
+                }
+                NodeWeight::Category(_)
+                | NodeWeight::Content(_)
+                | NodeWeight::FuncArgument(_)
+                | NodeWeight::Ordering(_)
+                | NodeWeight::Prop(_) => {}
+            }
+        }
+
+        nodes
+    }
+}
+
+impl WorkspaceSnapshot {
+    /// Describes what the [`ChangeSet`] changes compared to the snapshot it was forked from. Unlike
+    /// diffing against what its base change set points to now, anything applied to the base
+    /// change set since forking is not mistaken for something the change set undoes.
+    pub async fn diff_change_set(
+        ctx: &DalContext,
+        change_set: &ChangeSet,
+    ) -> SnapshotDiffResult<Vec<SnapshotChange>> {
+        let fork_point = match change_set.base_workspace_snapshot_address {
+            Some(fork_point_address) => WorkspaceSnapshot::find(ctx, fork_point_address).await?,
+            // Change sets forked before their fork point was recorded can only be compared with
+            // what their base change set points to now.
+            None => {
+                let base_change_set_id = change_set
+                    .base_change_set_id
+                    .ok_or(SnapshotDiffError::NoBaseChangeSet(change_set.id))?;
+                WorkspaceSnapshot::find_for_change_set(ctx, base_change_set_id).await?
+            }
+        };
+        let change_set_snapshot =
+            WorkspaceSnapshot::find_for_change_set(ctx, change_set.id).await?;
+
+        fork_point.diff(ctx, &change_set_snapshot).await
+    }
+
+    /// Describes what changed going from [`self`](WorkspaceSnapshot) to `other`. The
+    /// [`DalContext`] is only used for its services; neither snapshot needs to be its snapshot.
+    pub async fn diff(
+        &self,
+        ctx: &DalContext,
+        other: &WorkspaceSnapshot,
+    ) -> SnapshotDiffResult<Vec<SnapshotChange>> {
+        let before = DiffableNodes::collect(&*self.working_copy().await);
+        let after = DiffableNodes::collect(&*other.working_copy().await);
+
+        let mut before_ctx = ctx.clone();
+        before_ctx.set_workspace_snapshot(self.clone());
+        let mut after_ctx = ctx.clone();
+        after_ctx.set_workspace_snapshot(other.clone());
+
+        let mut changes = Vec::new();
+
+        // Components coming or going take everything below them with them, so nothing else about
+        // them is described.
+        for id in &after.components {
+            if !before.components.contains(id) {
+                let component_id: ComponentId = (*id).into();
+                changes.push(SnapshotChange::ComponentAdded {
+                    component_id,
+                    name: component_name(&after_ctx, component_id).await?,
+                });
+            }
+        }
+        for id in &before.components {
+            if !after.components.contains(id) {
+                let component_id: ComponentId = (*id).into();
+                changes.push(SnapshotChange::ComponentRemoved {
+                    component_id,
+                    name: component_name(&before_ctx, component_id).await?,
+                });
+            }
+        }
+
+        for (id, after_value) in &after.attribute_values {
+            let before_value = match before.attribute_values.get(id) {
+                Some(before_value) if before_value == after_value => continue,
+                Some(before_value) => *before_value,
+                None => None,
+            };
+            let attribute_value_id: AttributeValueId = (*id).into();
+            let component_id = AttributeValue::component_id(&after_ctx, attribute_value_id).await?;
+            if !before.components.contains(&Ulid::from(component_id)) {
+                continue;
+            }
+
+            let old_value = match before_value {
+                Some(_) => {
+                    AttributeValue::get_by_id(&before_ctx, attribute_value_id)
+                        .await?
+                        .value(&before_ctx)
+                        .await?
+                }
+                None => None,
+            };
+            let new_value = AttributeValue::get_by_id(&after_ctx, attribute_value_id)
+                .await?
+                .value(&after_ctx)
+                .await?;
+            if old_value == new_value {
+                continue;
+            }
+
+            let path = AttributeValue::get_path_for_id(&after_ctx, attribute_value_id).await?;
+            changes.push(match (path.as_deref(), &old_value, &new_value) {
+                (Some(COMPONENT_NAME_PATH), Some(old_name), Some(new_name)) => {
+                    SnapshotChange::ComponentRenamed {
+                        component_id,
+                        old_name: old_name.as_str().unwrap_or_default().to_owned(),
+                        new_name: new_name.as_str().unwrap_or_default().to_owned(),
+                    }
+                }
+                _ => SnapshotChange::AttributeValueChanged {
+                    attribute_value_id,
+                    component_id,
+                    path,
+                    old_value,
+                    new_value,
+                },
+            });
+        }
+        for id in before.attribute_values.keys() {
+            if after.attribute_values.contains_key(id) {
+                continue;
+            }
+            let attribute_value_id: AttributeValueId = (*id).into();
+            let component_id =
+                AttributeValue::component_id(&before_ctx, attribute_value_id).await?;
+            if !after.components.contains(&Ulid::from(component_id)) {
+                continue;
+            }
+
+            let old_value = AttributeValue::get_by_id(&before_ctx, attribute_value_id)
+                .await?
+                .value(&before_ctx)
+                .await?;
+            changes.push(SnapshotChange::AttributeValueChanged {
+                attribute_value_id,
+                component_id,
+                path: AttributeValue::get_path_for_id(&before_ctx, attribute_value_id).await?,
+                old_value,
+                new_value: None,
+            });
+        }
+
+        for (id, targets) in &after.connections {
+            if !before.connections.contains_key(id) {
+                changes.push(SnapshotChange::ConnectionAdded {
+                    attribute_prototype_argument_id: (*id).into(),
+                    source_component_id: targets.source_component_id,
+                    destination_component_id: targets.destination_component_id,
+                });
+            }
+        }
+        for (id, targets) in &before.connections {
+            if !after.connections.contains_key(id) {
+                changes.push(SnapshotChange::ConnectionRemoved {
+                    attribute_prototype_argument_id: (*id).into(),
+                    source_component_id: targets.source_component_id,
+                    destination_component_id: targets.destination_component_id,
+                });
+            }
+        }
+
+        for (id, (name, node_hash)) in &after.funcs {
+            match before.funcs.get(id) {
+                None => changes.push(SnapshotChange::FuncAdded {
+                    func_id: (*id).into(),
+                    name: name.to_owned(),
+                }),
+                Some((_, before_node_hash)) if before_node_hash != node_hash => {
+                    changes.push(SnapshotChange::FuncChanged {
+                        func_id: (*id).into(),
+                        name: name.to_owned(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, (name, _)) in &before.funcs {
+            if !after.funcs.contains_key(id) {
+                changes.push(SnapshotChange::FuncRemoved {
+                    func_id: (*id).into(),
+                    name: name.to_owned(),
+                });
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+async fn component_name(ctx: &DalContext, component_id: ComponentId) -> SnapshotDiffResult<String> {
+    Ok(Component::get_by_id(ctx, component_id)
+        .await?
+        .name(ctx)
+        .await?)
+}
@@ -0,0 +1,353 @@
+//! Mark-and-sweep garbage collection of the snapshots and content stored in the layer db.
+//!
+//! Everything reachable from a change set that is still in use is marked: the snapshots its
+//! pointers refer to, every snapshot written within the grace period, the snapshots those are stored
+//! as deltas against, and the content that the nodes of those snapshots refer to. Everything else that was written before the grace period is
+//! swept from every layer. Anything left only in this instance's disk cache that is not reachable
+//! is swept from it too.
+//!
+//! What is found and removed is recorded on the `workspace_snapshot.collect_garbage` span and its
+//! per-cache `workspace_snapshot.collect_garbage.sweep` child spans.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use si_data_pg::PgError;
+use si_events::WorkspaceSnapshotAddress;
+use si_layer_cache::db::workspace_snapshot::SnapshotContent;
+use si_layer_cache::db::{cas, workspace_snapshot};
+use si_layer_cache::layer_cache::LayerCache;
+use si_layer_cache::LayerDbError;
+use telemetry::prelude::*;
+use thiserror::Error;
+
+use crate::{ChangeSetStatus, DalContext, TransactionsError, WorkspaceSnapshot};
+
+/// How many keys are removed from the layer db at a time.
+const SWEEP_BATCH_SIZE: usize = 1000;
+
+/// Change sets in these states are never read again, so nothing is kept alive on their behalf.
+const COLLECTABLE_CHANGE_SET_STATUSES: [ChangeSetStatus; 3] = [
+    ChangeSetStatus::Abandoned,
+    ChangeSetStatus::Applied,
+    ChangeSetStatus::Closed,
+];
+
+#[remain::sorted]
+#[derive(Debug, Error)]
+pub enum GarbageCollectionError {
+    #[error("layer db error: {0}")]
+    LayerDb(#[from] LayerDbError),
+    #[error("pg error: {0}")]
+    Pg(#[from] PgError),
+    #[error("transactions error: {0}")]
+    Transactions(#[from] TransactionsError),
+}
+
+pub type GarbageCollectionResult<T> = Result<T, GarbageCollectionError>;
+
+/// How [`WorkspaceSnapshot::collect_garbage()`] decides what to remove.
+#[derive(Debug, Clone, Copy)]
+pub struct GarbageCollectionOptions {
+    /// Count what would be removed without removing anything.
+    pub dry_run: bool,
+    /// Nothing written more recently than this is removed, so that snapshots and content written
+    /// for a commit that has not yet moved a change set pointer survive.
+    pub grace_period: Duration,
+    /// How far back the pointer history of change sets in use is kept alive. Undoing a change
+    /// older than this, or reading a change set as of before it, may no longer be possible.
+    pub history_retention: Duration,
+}
+
+impl Default for GarbageCollectionOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            grace_period: Duration::hours(1),
+            history_retention: Duration::days(7),
+        }
+    }
+}
+
+/// What was found to be unreachable in a single layer db cache, and how much of it was removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepReport {
+    pub cache_name: String,
+    /// Keys written before the grace period.
+    pub examined: usize,
+    pub unreachable: usize,
+    /// Always zero for a dry run.
+    pub removed: u64,
+    /// Unreachable keys found in this instance's disk cache but not in the durable layer, which
+    /// are removed from the disk cache unless this is a dry run.
+    pub disk_orphans: u64,
+}
+
+/// The outcome of [`WorkspaceSnapshot::collect_garbage()`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GarbageCollectionReport {
+    pub dry_run: bool,
+    pub live_snapshots: usize,
+    pub live_content_hashes: usize,
+    /// Snapshots referred to by a change set in use, or written within the grace period, that
+    /// could not be read.
+    pub missing_snapshots: usize,
+    pub snapshots: SweepReport,
+    pub snapshot_deltas: SweepReport,
+    pub cas: SweepReport,
+}
+
+impl WorkspaceSnapshot {
+    /// Removes every snapshot and piece of content from the layer db that can no longer be
+    /// reached from a change set in use, across all workspaces.
+    ///
+    /// Only this instance's memory and disk caches are cleared; other instances keep their copies
+    /// until they are evicted.
+    #[instrument(
+        name = "workspace_snapshot.collect_garbage",
+        level = "info",
+        skip_all,
+        fields(
+            dry_run = options.dry_run,
+            layer_db_gc.live_snapshots = Empty,
+            layer_db_gc.live_content_hashes = Empty,
+            layer_db_gc.missing_snapshots = Empty,
+            layer_db_gc.unreachable = Empty,
+            layer_db_gc.removed = Empty,
+            layer_db_gc.disk_orphans = Empty,
+        )
+    )]
+    pub async fn collect_garbage(
+        ctx: &DalContext,
+        options: GarbageCollectionOptions,
+    ) -> GarbageCollectionResult<GarbageCollectionReport> {
+        let started_at = Utc::now();
+        let created_before = started_at - options.grace_period;
+
+        let mut roots = live_snapshot_roots(ctx, started_at - options.history_retention).await?;
+
+        let snapshot_db = ctx.layer_db().workspace_snapshot();
+
+        // A snapshot written within the grace period may be about to become the target of a
+        // pointer, so it and the content it refers to are kept as if it already were.
+        for key in snapshot_db
+            .cache
+            .durable()
+            .keys_created_since(created_before)
+            .await?
+            .into_iter()
+            .chain(
+                snapshot_db
+                    .delta_cache
+                    .durable()
+                    .keys_created_since(created_before)
+                    .await?,
+            )
+        {
+            match WorkspaceSnapshotAddress::from_str(&key) {
+                Ok(address) => {
+                    roots.insert(address);
+                }
+                Err(err) => warn!(key, error = %err, "recent snapshot key is not an address"),
+            }
+        }
+
+        let mut live_snapshots: HashSet<WorkspaceSnapshotAddress> = HashSet::new();
+        let mut live_content_hashes = HashSet::new();
+        let mut missing_snapshots = 0;
+        for root in roots {
+            match snapshot_db.read(&root).await? {
+                Some(graph) => live_content_hashes.extend(graph.content_hashes()),
+                None => {
+                    warn!(address = %root, "live snapshot not found");
+                    missing_snapshots += 1;
+                }
+            }
+
+            // Keep the chain of deltas the snapshot is stored at the end of, up to the first
+            // snapshot stored in full.
+            let mut current = root;
+            while live_snapshots.insert(current) {
+                let key = current.to_string();
+                if snapshot_db.cache.durable().contains_key(&key).await? {
+                    break;
+                }
+                match snapshot_db.delta_cache.get(key.into()).await? {
+                    Some(record) => current = record.base,
+                    None => break,
+                }
+            }
+        }
+
+        let live_snapshot_keys: HashSet<String> =
+            live_snapshots.iter().map(ToString::to_string).collect();
+        let live_content_keys: HashSet<String> = live_content_hashes
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let report = GarbageCollectionReport {
+            dry_run: options.dry_run,
+            live_snapshots: live_snapshot_keys.len(),
+            live_content_hashes: live_content_keys.len(),
+            missing_snapshots,
+            snapshots: sweep(
+                workspace_snapshot::CACHE_NAME,
+                &snapshot_db.cache,
+                &live_snapshot_keys,
+                created_before,
+                options.dry_run,
+            )
+            .await?,
+            snapshot_deltas: sweep(
+                workspace_snapshot::DELTAS_CACHE_NAME,
+                &snapshot_db.delta_cache,
+                &live_snapshot_keys,
+                created_before,
+                options.dry_run,
+            )
+            .await?,
+            cas: sweep(
+                cas::CACHE_NAME,
+                &ctx.layer_db().cas().cache,
+                &live_content_keys,
+                created_before,
+                options.dry_run,
+            )
+            .await?,
+        };
+
+        let sweeps = [&report.snapshots, &report.snapshot_deltas, &report.cas];
+        let span = Span::current();
+        span.record("layer_db_gc.live_snapshots", report.live_snapshots);
+        span.record(
+            "layer_db_gc.live_content_hashes",
+            report.live_content_hashes,
+        );
+        span.record("layer_db_gc.missing_snapshots", report.missing_snapshots);
+        span.record(
+            "layer_db_gc.unreachable",
+            sweeps.iter().map(|sweep| sweep.unreachable).sum::<usize>(),
+        );
+        span.record(
+            "layer_db_gc.removed",
+            sweeps.iter().map(|sweep| sweep.removed).sum::<u64>(),
+        );
+        span.record(
+            "layer_db_gc.disk_orphans",
+            sweeps.iter().map(|sweep| sweep.disk_orphans).sum::<u64>(),
+        );
+
+        info!(
+            layer_db_gc.dry_run = report.dry_run,
+            layer_db_gc.elapsed_ms = (Utc::now() - started_at).num_milliseconds(),
+            "layer db garbage collection finished"
+        );
+
+        Ok(report)
+    }
+}
+
+/// The snapshots that change sets in use point to, have as their base, or pointed to since
+/// `history_since`.
+async fn live_snapshot_roots(
+    ctx: &DalContext,
+    history_since: DateTime<Utc>,
+) -> GarbageCollectionResult<HashSet<WorkspaceSnapshotAddress>> {
+    let collectable_statuses: Vec<String> = COLLECTABLE_CHANGE_SET_STATUSES
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let txns = ctx.txns().await?;
+
+    let mut roots = HashSet::new();
+    for row in txns
+        .pg()
+        .query(
+            "SELECT workspace_snapshot_address, base_workspace_snapshot_address FROM change_set_pointers WHERE status <> ALL($1)",
+            &[&collectable_statuses],
+        )
+        .await?
+    {
+        let address: Option<WorkspaceSnapshotAddress> = row.try_get("workspace_snapshot_address")?;
+        let base_address: Option<WorkspaceSnapshotAddress> =
+            row.try_get("base_workspace_snapshot_address")?;
+        roots.extend(address);
+        roots.extend(base_address);
+    }
+    for row in txns
+        .pg()
+        .query(
+            "SELECT DISTINCT h.workspace_snapshot_address FROM change_set_pointer_history h JOIN change_set_pointers c ON c.id = h.change_set_id WHERE c.status <> ALL($1) AND h.created_at >= $2",
+            &[&collectable_statuses, &history_since],
+        )
+        .await?
+    {
+        roots.insert(row.try_get("workspace_snapshot_address")?);
+    }
+
+    Ok(roots)
+}
+
+/// Removes (or, for a dry run, counts) every key in the cache's durable layer that was written
+/// before `created_before` and is not live, and then every key left in this instance's disk cache
+/// that is in neither.
+#[instrument(
+    name = "workspace_snapshot.collect_garbage.sweep",
+    level = "info",
+    skip_all,
+    fields(
+        layer_db_gc.cache_name = cache_name,
+        layer_db_gc.examined = Empty,
+        layer_db_gc.unreachable = Empty,
+        layer_db_gc.removed = Empty,
+        layer_db_gc.disk_orphans = Empty,
+    )
+)]
+async fn sweep<V>(
+    cache_name: &str,
+    cache: &LayerCache<V>,
+    live_keys: &HashSet<String>,
+    created_before: DateTime<Utc>,
+    dry_run: bool,
+) -> GarbageCollectionResult<SweepReport>
+where
+    V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    let keys = cache.durable().keys_created_before(created_before).await?;
+    let unreachable: Vec<Arc<str>> = keys
+        .iter()
+        .filter(|key| !live_keys.contains(*key))
+        .map(|key| key.as_str().into())
+        .collect();
+
+    let mut removed = 0;
+    if !dry_run {
+        for batch in unreachable.chunks(SWEEP_BATCH_SIZE) {
+            removed += cache
+                .remove_many_created_before(batch, created_before)
+                .await?;
+        }
+    }
+
+    let disk_orphans = cache.remove_disk_orphans(live_keys, dry_run).await?;
+
+    let span = Span::current();
+    span.record("layer_db_gc.examined", keys.len());
+    span.record("layer_db_gc.unreachable", unreachable.len());
+    span.record("layer_db_gc.removed", removed);
+    span.record("layer_db_gc.disk_orphans", disk_orphans);
+
+    Ok(SweepReport {
+        cache_name: cache_name.to_string(),
+        examined: keys.len(),
+        unreachable: unreachable.len(),
+        removed,
+        disk_orphans,
+    })
+}
@@ -10,6 +10,8 @@ pub use petgraph::Direction;
 use petgraph::{algo, prelude::*, visit::DfsEvent};
 use serde::{Deserialize, Serialize};
 use si_events::ContentHash;
+use si_layer_cache::db::workspace_snapshot::SnapshotContent;
+use si_layer_cache::SchemaVersioned;
 use thiserror::Error;
 use ulid::Ulid;
 
@@ -21,14 +23,22 @@ use crate::workspace_snapshot::node_weight::category_node_weight::CategoryNodeKi
 use crate::workspace_snapshot::node_weight::{CategoryNodeWeight, NodeWeightDiscriminants};
 use crate::workspace_snapshot::vector_clock::VectorClockId;
 use crate::workspace_snapshot::{
-    conflict::Conflict,
+    conflict::{
+        Conflict, ConflictChoice, ConflictDiscriminants, ConflictResolutionPolicy,
+        ConflictResolutionStrategy, ConflictResolutions, ResolvedConflict, StableConflict,
+    },
     content_address::ContentAddress,
     edge_weight::{EdgeWeight, EdgeWeightError, EdgeWeightKind, EdgeWeightKindDiscriminants},
-    node_weight::{NodeWeight, NodeWeightError, OrderingNodeWeight},
-    update::Update,
+    node_weight::{NodeInformation, NodeWeight, NodeWeightError, OrderingNodeWeight},
+    update::{StableUpdate, Update},
 };
 
+mod delta;
 mod tests;
+mod validation;
+
+pub use delta::WorkspaceSnapshotGraphDelta;
+pub use validation::{ValidationIssue, ValidationReport};
 
 pub type LineageId = Ulid;
 
@@ -54,6 +64,8 @@ pub enum WorkspaceSnapshotGraphError {
     EdgeWeight(#[from] EdgeWeightError),
     #[error("EdgeWeight not found")]
     EdgeWeightNotFound,
+    #[error("no node equivalent to {0:?} found")]
+    EquivalentNodeNotFound(NodeInformation),
     #[error("Problem during graph traversal: {0:?}")]
     GraphTraversal(petgraph::visit::DfsEvent<NodeIndex>),
     #[error("Incompatible node types")]
@@ -68,6 +80,8 @@ pub enum WorkspaceSnapshotGraphError {
     NodeWithIdNotFound(Ulid),
     #[error("No Prop found for NodeIndex {0:?}")]
     NoPropFound(NodeIndex),
+    #[error("postcard error: {0}")]
+    Postcard(#[from] postcard::Error),
     #[error("NodeIndex has too many Ordering children: {0:?}")]
     TooManyOrderingForNode(NodeIndex),
     #[error("NodeIndex has too many Prop children: {0:?}")]
@@ -88,6 +102,20 @@ pub struct WorkspaceSnapshotGraph {
     root_index: NodeIndex,
 }
 
+/// Changing the serialized form of the graph means bumping the schema version here and reading
+/// snapshots written at the older versions in `deserialize_schema_version`.
+impl SchemaVersioned for WorkspaceSnapshotGraph {}
+
+impl SnapshotContent for WorkspaceSnapshotGraph {
+    fn content_hashes(&self) -> Vec<ContentHash> {
+        self.nodes()
+            .flat_map(|(node_weight, _)| node_weight.content_addresses())
+            .filter(|content_address| !matches!(content_address, ContentAddress::Root))
+            .map(|content_address| content_address.content_hash())
+            .collect()
+    }
+}
+
 impl std::fmt::Debug for WorkspaceSnapshotGraph {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("WorkspaceSnapshotGraph")
@@ -866,6 +894,508 @@ impl WorkspaceSnapshotGraph {
         }
     }
 
+    /// Returns the id of the node a [`Conflict`] found by
+    /// [`Self::detect_conflicts_and_updates()`] is about, where [`self`](WorkspaceSnapshotGraph)
+    /// is the "to rebase" graph. Unlike the [`NodeIndex`] values in the conflict, the id is
+    /// meaningful outside of the graphs the conflict was detected between.
+    pub fn conflict_node_id(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        conflict: &Conflict,
+    ) -> WorkspaceSnapshotGraphResult<Ulid> {
+        Ok(match *conflict {
+            Conflict::ChildOrder { to_rebase, .. }
+            | Conflict::NodeContent { to_rebase, .. }
+            | Conflict::ModifyRemovedItem(to_rebase) => self.get_node_weight(to_rebase)?.id(),
+            Conflict::RemoveModifiedItem { removed_item, .. } => {
+                onto.get_node_weight(removed_item)?.id()
+            }
+        })
+    }
+
+    /// Returns the [`NodeInformation`] for the node at the given [`NodeIndex`].
+    pub fn node_information(
+        &self,
+        node_index: NodeIndex,
+    ) -> WorkspaceSnapshotGraphResult<NodeInformation> {
+        Ok(self.get_node_weight(node_index)?.into())
+    }
+
+    /// Returns the [`NodeIndex`] of the latest node with the id and lineage id of the given
+    /// [`NodeInformation`].
+    pub fn node_index_for_information(
+        &self,
+        node_information: &NodeInformation,
+    ) -> WorkspaceSnapshotGraphResult<NodeIndex> {
+        self.find_equivalent_node(node_information.id, node_information.lineage_id)?
+            .ok_or(WorkspaceSnapshotGraphError::EquivalentNodeNotFound(
+                *node_information,
+            ))
+    }
+
+    /// Converts a [`Conflict`] found by [`Self::detect_conflicts_and_updates()`] into a
+    /// [`StableConflict`], where [`self`](WorkspaceSnapshotGraph) is the "to rebase" graph.
+    pub fn stable_conflict(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        conflict: &Conflict,
+    ) -> WorkspaceSnapshotGraphResult<StableConflict> {
+        Ok(match *conflict {
+            Conflict::ChildOrder {
+                onto: onto_index,
+                to_rebase,
+            } => StableConflict::ChildOrder {
+                onto: onto.node_information(onto_index)?,
+                to_rebase: self.node_information(to_rebase)?,
+            },
+            Conflict::ModifyRemovedItem(to_rebase) => {
+                StableConflict::ModifyRemovedItem(self.node_information(to_rebase)?)
+            }
+            Conflict::NodeContent {
+                onto: onto_index,
+                to_rebase,
+            } => StableConflict::NodeContent {
+                onto: onto.node_information(onto_index)?,
+                to_rebase: self.node_information(to_rebase)?,
+            },
+            Conflict::RemoveModifiedItem {
+                container,
+                removed_item,
+            } => StableConflict::RemoveModifiedItem {
+                container: self.node_information(container)?,
+                removed_item: onto.node_information(removed_item)?,
+            },
+        })
+    }
+
+    /// Converts a [`StableConflict`] back into a [`Conflict`] between
+    /// [`self`](WorkspaceSnapshotGraph) (the "to rebase" graph) and `onto`, pointing at the
+    /// latest version of each node.
+    pub fn conflict_from_stable(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        stable_conflict: &StableConflict,
+    ) -> WorkspaceSnapshotGraphResult<Conflict> {
+        Ok(match stable_conflict {
+            StableConflict::ChildOrder {
+                onto: onto_information,
+                to_rebase,
+            } => Conflict::ChildOrder {
+                onto: onto.node_index_for_information(onto_information)?,
+                to_rebase: self.node_index_for_information(to_rebase)?,
+            },
+            StableConflict::ModifyRemovedItem(to_rebase) => {
+                Conflict::ModifyRemovedItem(self.node_index_for_information(to_rebase)?)
+            }
+            StableConflict::NodeContent {
+                onto: onto_information,
+                to_rebase,
+            } => Conflict::NodeContent {
+                onto: onto.node_index_for_information(onto_information)?,
+                to_rebase: self.node_index_for_information(to_rebase)?,
+            },
+            StableConflict::RemoveModifiedItem {
+                container,
+                removed_item,
+            } => Conflict::RemoveModifiedItem {
+                container: self.node_index_for_information(container)?,
+                removed_item: onto.node_index_for_information(removed_item)?,
+            },
+        })
+    }
+
+    /// Converts an [`Update`] found by [`Self::detect_conflicts_and_updates()`] (or produced by
+    /// [`Self::resolve_conflicts()`]) into a [`StableUpdate`], where
+    /// [`self`](WorkspaceSnapshotGraph) is the "to rebase" graph.
+    pub fn stable_update(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        update: &Update,
+    ) -> WorkspaceSnapshotGraphResult<StableUpdate> {
+        Ok(match update {
+            Update::NewEdge {
+                source,
+                destination,
+                edge_weight,
+            } => StableUpdate::NewEdge {
+                source: self.node_information(*source)?,
+                destination: onto.node_information(*destination)?,
+                edge_weight: edge_weight.clone(),
+            },
+            Update::RemoveEdge {
+                source,
+                destination,
+                edge_kind,
+            } => StableUpdate::RemoveEdge {
+                source: self.node_information(*source)?,
+                destination: self.node_information(*destination)?,
+                edge_kind: *edge_kind,
+            },
+            Update::ReplaceOrder { ordering, order } => StableUpdate::ReplaceOrder {
+                ordering: self.node_information(*ordering)?,
+                order: order.clone(),
+            },
+            Update::ReplaceSubgraph {
+                onto: onto_index,
+                to_rebase,
+            } => StableUpdate::ReplaceSubgraph {
+                onto: onto.node_information(*onto_index)?,
+                to_rebase: self.node_information(*to_rebase)?,
+            },
+            Update::RevertNode {
+                onto: onto_index,
+                to_rebase,
+            } => StableUpdate::RevertNode {
+                onto: onto.node_information(*onto_index)?,
+                to_rebase: self.node_information(*to_rebase)?,
+            },
+        })
+    }
+
+    /// Converts a [`StableUpdate`] back into an [`Update`] that can be performed on
+    /// [`self`](WorkspaceSnapshotGraph) (the "to rebase" graph) using `onto`.
+    pub fn update_from_stable(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        stable_update: &StableUpdate,
+    ) -> WorkspaceSnapshotGraphResult<Update> {
+        Ok(match stable_update {
+            StableUpdate::NewEdge {
+                source,
+                destination,
+                edge_weight,
+            } => Update::NewEdge {
+                source: self.node_index_for_information(source)?,
+                destination: onto.node_index_for_information(destination)?,
+                edge_weight: edge_weight.clone(),
+            },
+            StableUpdate::RemoveEdge {
+                source,
+                destination,
+                edge_kind,
+            } => Update::RemoveEdge {
+                source: self.node_index_for_information(source)?,
+                destination: self.node_index_for_information(destination)?,
+                edge_kind: *edge_kind,
+            },
+            StableUpdate::ReplaceOrder { ordering, order } => Update::ReplaceOrder {
+                ordering: self.node_index_for_information(ordering)?,
+                order: order.clone(),
+            },
+            StableUpdate::ReplaceSubgraph {
+                onto: onto_information,
+                to_rebase,
+            } => Update::ReplaceSubgraph {
+                onto: onto.node_index_for_information(onto_information)?,
+                to_rebase: self.node_index_for_information(to_rebase)?,
+            },
+            StableUpdate::RevertNode {
+                onto: onto_information,
+                to_rebase,
+            } => Update::RevertNode {
+                onto: onto.node_index_for_information(onto_information)?,
+                to_rebase: self.node_index_for_information(to_rebase)?,
+            },
+        })
+    }
+
+    /// Finds the [`Updates`](Update) that revert the changes made between `before` and `after`,
+    /// to be performed on [`self`](WorkspaceSnapshotGraph) (the "to rebase" graph) using `before`
+    /// as the "onto" graph. Anything in [`self`](WorkspaceSnapshotGraph) that has changed again
+    /// since `after` is left alone, so that only those changes are reverted.
+    pub fn detect_reverting_updates(
+        &self,
+        before: &WorkspaceSnapshotGraph,
+        after: &WorkspaceSnapshotGraph,
+    ) -> WorkspaceSnapshotGraphResult<Vec<Update>> {
+        let before_edges = before.edges_by_endpoint_ids()?;
+        let after_edges = after.edges_by_endpoint_ids()?;
+        let mut updates = Vec::new();
+
+        // Edges added since "before" are removed and edges removed since "before" are added back.
+        // Only edges whose source exists on both sides need to be considered: everything below a
+        // node that was created or deleted comes and goes along with the edge to that node.
+        for (key @ (source_id, destination_id, kind), _) in after_edges.iter() {
+            if before_edges.contains_key(key) || before.get_node_index_by_id(*source_id).is_err() {
+                continue;
+            }
+            if let Some((source, destination)) =
+                self.edge_endpoints_by_ids(*source_id, *destination_id, kind)?
+            {
+                updates.push(Update::RemoveEdge {
+                    source,
+                    destination,
+                    edge_kind: kind.into(),
+                });
+            }
+        }
+        for (key @ (source_id, destination_id, kind), (edge_weight, before_destination)) in
+            before_edges.iter()
+        {
+            if after_edges.contains_key(key) || after.get_node_index_by_id(*source_id).is_err() {
+                continue;
+            }
+            if let Ok(source) = self.get_node_index_by_id(*source_id) {
+                if self
+                    .edge_endpoints_by_ids(*source_id, *destination_id, kind)?
+                    .is_none()
+                {
+                    updates.push(Update::NewEdge {
+                        source,
+                        destination: *before_destination,
+                        edge_weight: edge_weight.clone(),
+                    });
+                }
+            }
+        }
+
+        // Nodes changed since "before" are changed back.
+        for (after_node_weight, _) in after.nodes() {
+            let before_index = match before.get_node_index_by_id(after_node_weight.id()) {
+                Ok(before_index) => before_index,
+                Err(_) => continue,
+            };
+            let before_node_weight = before.get_node_weight(before_index)?;
+            if before_node_weight.node_hash() == after_node_weight.node_hash() {
+                continue;
+            }
+            let current_index = match self.get_node_index_by_id(after_node_weight.id()) {
+                Ok(current_index) => current_index,
+                Err(_) => continue,
+            };
+            let current_node_weight = self.get_node_weight(current_index)?;
+            if current_node_weight.node_hash() != after_node_weight.node_hash() {
+                continue;
+            }
+
+            updates.push(match (before_node_weight, current_node_weight) {
+                (NodeWeight::Ordering(before_ordering), NodeWeight::Ordering(current_ordering)) => {
+                    Update::ReplaceOrder {
+                        ordering: current_index,
+                        order: interleave_orders(before_ordering.order(), current_ordering.order()),
+                    }
+                }
+                _ => Update::RevertNode {
+                    onto: before_index,
+                    to_rebase: current_index,
+                },
+            });
+        }
+
+        Ok(updates)
+    }
+
+    /// Every edge in the graph, keyed by the ids of its endpoints and its kind, along with the
+    /// index of its destination.
+    fn edges_by_endpoint_ids(
+        &self,
+    ) -> WorkspaceSnapshotGraphResult<HashMap<(Ulid, Ulid, EdgeWeightKind), (EdgeWeight, NodeIndex)>>
+    {
+        let mut edges = HashMap::new();
+        for (edge_weight, source, destination) in self.edges() {
+            edges.insert(
+                (
+                    self.get_node_weight(source)?.id(),
+                    self.get_node_weight(destination)?.id(),
+                    edge_weight.kind().clone(),
+                ),
+                (edge_weight.clone(), destination),
+            );
+        }
+
+        Ok(edges)
+    }
+
+    /// Finds the endpoints of the edge of the given kind between the latest nodes with the given
+    /// ids, if there is one.
+    fn edge_endpoints_by_ids(
+        &self,
+        source_id: Ulid,
+        destination_id: Ulid,
+        kind: &EdgeWeightKind,
+    ) -> WorkspaceSnapshotGraphResult<Option<(NodeIndex, NodeIndex)>> {
+        let source = match self.get_node_index_by_id(source_id) {
+            Ok(source) => source,
+            Err(_) => return Ok(None),
+        };
+        for edge_ref in self.graph.edges_directed(source, Outgoing) {
+            if edge_ref.weight().kind() == kind
+                && self.get_node_weight(edge_ref.target())?.id() == destination_id
+            {
+                return Ok(Some((source, edge_ref.target())));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Attempt to resolve [`Conflicts`](Conflict) found by
+    /// [`Self::detect_conflicts_and_updates()`] using the given [`ConflictResolutionPolicy`], where
+    /// [`self`](WorkspaceSnapshotGraph) is the "to rebase" graph. A matching [`ConflictChoice`]
+    /// takes precedence over the policy. The [`Updates`](Update) in the result carry out the
+    /// resolutions and must be performed after the detected updates.
+    pub fn resolve_conflicts(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        conflicts: &[Conflict],
+        policy: &ConflictResolutionPolicy,
+        choices: &[ConflictChoice],
+    ) -> WorkspaceSnapshotGraphResult<ConflictResolutions> {
+        let mut resolutions = ConflictResolutions::default();
+
+        for conflict in conflicts {
+            let choice = if choices.is_empty() {
+                None
+            } else {
+                let kind = ConflictDiscriminants::from(conflict);
+                let node_id = self.conflict_node_id(onto, conflict)?;
+                choices
+                    .iter()
+                    .find(|choice| choice.kind == kind && choice.node_id == node_id)
+            };
+            let strategy = match choice {
+                Some(choice) => choice.strategy,
+                None => policy.strategy_for(conflict),
+            };
+            match self.resolve_conflict(onto, conflict, strategy)? {
+                Some(updates) => {
+                    resolutions.updates.extend(updates);
+                    resolutions.resolved.push(ResolvedConflict {
+                        conflict: *conflict,
+                        strategy,
+                    });
+                }
+                None => resolutions.unresolved.push(*conflict),
+            }
+        }
+
+        Ok(resolutions)
+    }
+
+    /// Returns the [`Updates`](Update) needed to resolve a single [`Conflict`] with the given
+    /// [`ConflictResolutionStrategy`], or [`None`] if the strategy cannot resolve it.
+    fn resolve_conflict(
+        &self,
+        onto: &WorkspaceSnapshotGraph,
+        conflict: &Conflict,
+        strategy: ConflictResolutionStrategy,
+    ) -> WorkspaceSnapshotGraphResult<Option<Vec<Update>>> {
+        let updates = match (*conflict, strategy) {
+            (_, ConflictResolutionStrategy::Fail) => return Ok(None),
+            // The "to rebase" graph is what we are updating, so keeping its side of the conflict
+            // requires no updates.
+            (_, ConflictResolutionStrategy::ToRebaseWins) => vec![],
+            (
+                Conflict::ChildOrder {
+                    onto: onto_index,
+                    to_rebase,
+                }
+                | Conflict::NodeContent {
+                    onto: onto_index,
+                    to_rebase,
+                },
+                ConflictResolutionStrategy::OntoWins,
+            ) => vec![Update::ReplaceSubgraph {
+                onto: onto_index,
+                to_rebase,
+            }],
+            (
+                Conflict::ChildOrder {
+                    onto: onto_index,
+                    to_rebase,
+                },
+                ConflictResolutionStrategy::Interleave,
+            ) => {
+                let onto_ordering = onto
+                    .get_node_weight(onto_index)?
+                    .get_ordering_node_weight()?;
+                let to_rebase_ordering = self
+                    .get_node_weight(to_rebase)?
+                    .get_ordering_node_weight()?;
+
+                vec![Update::ReplaceOrder {
+                    ordering: to_rebase,
+                    order: interleave_orders(to_rebase_ordering.order(), onto_ordering.order()),
+                }]
+            }
+            (Conflict::ModifyRemovedItem(item), ConflictResolutionStrategy::OntoWins) => {
+                // Remove the item from every container that no longer contains it in "onto".
+                // Removing the edge from the container also takes care of its ordering, so the
+                // ordinal edges are skipped.
+                let item_lineage_id = self.get_node_weight(item)?.lineage_id();
+                let mut updates = Vec::new();
+                for edge_ref in self.graph.edges_directed(item, Incoming) {
+                    let edge_kind = edge_ref.weight().kind();
+                    if edge_kind == &EdgeWeightKind::Ordinal {
+                        continue;
+                    }
+
+                    let source_lineage_id = self.get_node_weight(edge_ref.source())?.lineage_id();
+                    let mut onto_has_edge = false;
+                    for onto_source in onto.get_node_index_by_lineage(source_lineage_id) {
+                        for onto_edge_ref in onto.graph.edges_directed(onto_source, Outgoing) {
+                            if onto_edge_ref.weight().kind() == edge_kind
+                                && onto.get_node_weight(onto_edge_ref.target())?.lineage_id()
+                                    == item_lineage_id
+                            {
+                                onto_has_edge = true;
+                            }
+                        }
+                    }
+
+                    if !onto_has_edge {
+                        updates.push(Update::RemoveEdge {
+                            source: edge_ref.source(),
+                            destination: item,
+                            edge_kind: edge_kind.into(),
+                        });
+                    }
+                }
+
+                if updates.is_empty() {
+                    return Ok(None);
+                }
+                updates
+            }
+            (
+                Conflict::RemoveModifiedItem {
+                    container,
+                    removed_item,
+                },
+                ConflictResolutionStrategy::OntoWins,
+            ) => {
+                // Re-add the item using the edge that "onto" has from the equivalent container.
+                let container_lineage_id = self.get_node_weight(container)?.lineage_id();
+                let mut onto_edge_weight = None;
+                for onto_edge_ref in onto.graph.edges_directed(removed_item, Incoming) {
+                    if onto.get_node_weight(onto_edge_ref.source())?.lineage_id()
+                        == container_lineage_id
+                    {
+                        onto_edge_weight = Some(onto_edge_ref.weight().clone());
+                        break;
+                    }
+                }
+
+                match onto_edge_weight {
+                    Some(edge_weight) => vec![Update::NewEdge {
+                        source: container,
+                        destination: removed_item,
+                        edge_weight,
+                    }],
+                    None => return Ok(None),
+                }
+            }
+            (
+                Conflict::ModifyRemovedItem(_)
+                | Conflict::NodeContent { .. }
+                | Conflict::RemoveModifiedItem { .. },
+                ConflictResolutionStrategy::Interleave,
+            ) => return Ok(None),
+        };
+
+        Ok(Some(updates))
+    }
+
     #[allow(dead_code)]
     pub fn dot(&self) {
         // NOTE(nick): copy the output and execute this on macOS. It will create a file in the
@@ -1768,6 +2298,25 @@ impl WorkspaceSnapshotGraph {
                         *edge_kind,
                     )?;
                 }
+                Update::ReplaceOrder { ordering, order } => {
+                    let ordering_index = self.get_latest_node_idx(*ordering)?;
+                    let mut children = HashSet::new();
+                    for edge_ref in self.graph.edges_directed(ordering_index, Outgoing) {
+                        if edge_ref.weight().kind() == &EdgeWeightKind::Ordinal {
+                            children.insert(self.get_node_weight(edge_ref.target())?.id());
+                        }
+                    }
+                    let order = order
+                        .iter()
+                        .copied()
+                        .filter(|id| children.contains(id))
+                        .collect();
+
+                    let new_ordering_index = self.copy_node_by_index(ordering_index)?;
+                    self.get_node_weight_mut(new_ordering_index)?
+                        .set_order(to_rebase_change_set, order)?;
+                    self.replace_references(ordering_index)?;
+                }
                 Update::ReplaceSubgraph {
                     onto: onto_subgraph_root,
                     to_rebase: to_rebase_subgraph_root,
@@ -1776,6 +2325,22 @@ impl WorkspaceSnapshotGraph {
                     self.find_in_self_or_create_using_onto(*onto_subgraph_root, onto)?;
                     self.replace_references(updated_to_rebase)?;
                 }
+                Update::RevertNode {
+                    onto: onto_index,
+                    to_rebase,
+                } => {
+                    let updated_to_rebase = self.get_latest_node_idx(*to_rebase)?;
+                    // Carry over everything the "to rebase" node has seen so that the reverted
+                    // weight is the newest write of the node.
+                    let mut reverted_node_weight = onto.get_node_weight(*onto_index)?.clone();
+                    reverted_node_weight.merge_clocks(
+                        to_rebase_change_set,
+                        self.get_node_weight(updated_to_rebase)?,
+                    )?;
+                    reverted_node_weight.increment_vector_clock(to_rebase_change_set)?;
+                    self.add_node(reverted_node_weight)?;
+                    self.replace_references(updated_to_rebase)?;
+                }
             }
         }
         Ok(())
@@ -1860,6 +2425,28 @@ impl WorkspaceSnapshotGraph {
     }
 }
 
+/// Merge two orderings of the same container's children. The "to rebase" ordering is kept as-is,
+/// and each item that only exists in the "onto" ordering is inserted after the nearest item that
+/// precedes it in "onto" (or at the front, if nothing precedes it).
+fn interleave_orders(to_rebase_order: &[Ulid], onto_order: &[Ulid]) -> Vec<Ulid> {
+    let mut merged = to_rebase_order.to_vec();
+    let mut previous: Option<Ulid> = None;
+    for id in onto_order {
+        if !merged.contains(id) {
+            let position = previous
+                .and_then(|previous_id| {
+                    merged
+                        .iter()
+                        .position(|merged_id| *merged_id == previous_id)
+                })
+                .map_or(0, |previous_position| previous_position + 1);
+            merged.insert(position, *id);
+        }
+        previous = Some(*id);
+    }
+    merged
+}
+
 fn ordering_node_indexes_for_node_index(
     snapshot: &WorkspaceSnapshotGraph,
     node_index: NodeIndex,
@@ -0,0 +1,183 @@
+//! Storing a [`WorkspaceSnapshotGraph`] as the difference between it and the graph it was derived
+//! from, so that committing a small change does not mean storing the whole graph again.
+//!
+//! Deltas refer to nodes by id rather than by [`NodeIndex`], so a graph reconstructed from a delta
+//! is equivalent to the original, but its nodes may not have the same indices.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::prelude::*;
+use serde::{Deserialize, Serialize};
+use si_layer_cache::db::workspace_snapshot::SnapshotDelta;
+use ulid::Ulid;
+
+use crate::workspace_snapshot::edge_weight::EdgeWeight;
+use crate::workspace_snapshot::graph::{
+    WorkspaceSnapshotGraph, WorkspaceSnapshotGraphError, WorkspaceSnapshotGraphResult,
+};
+use crate::workspace_snapshot::node_weight::NodeWeight;
+
+/// The nodes and edges that differ between two [`WorkspaceSnapshotGraphs`](WorkspaceSnapshotGraph).
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct WorkspaceSnapshotGraphDelta {
+    root_id: Ulid,
+    removed_node_ids: Vec<Ulid>,
+    /// Nodes that were added, or whose weights changed.
+    node_weights: Vec<NodeWeight>,
+    /// The complete set of outgoing edges, as (target id, weight), for every node whose outgoing
+    /// edges were added, removed or changed, in the order they were added to the graph.
+    outgoing_edges: Vec<(Ulid, Vec<(Ulid, EdgeWeight)>)>,
+}
+
+impl WorkspaceSnapshotGraph {
+    /// The outgoing edges of a node as (target id, weight), in the order they were added.
+    fn outgoing_edges_by_target_id(
+        &self,
+        node_index: NodeIndex,
+    ) -> WorkspaceSnapshotGraphResult<Vec<(Ulid, EdgeWeight)>> {
+        let mut edges = Vec::new();
+        // Petgraph walks the edges of a node from the most recently added.
+        for edge_ref in self.graph.edges_directed(node_index, Outgoing) {
+            edges.push((
+                self.get_node_weight(edge_ref.target())?.id(),
+                edge_ref.weight().clone(),
+            ));
+        }
+        edges.reverse();
+
+        Ok(edges)
+    }
+
+    /// Describes how to get from `base` to `self`.
+    pub fn delta_from(
+        &self,
+        base: &WorkspaceSnapshotGraph,
+    ) -> WorkspaceSnapshotGraphResult<WorkspaceSnapshotGraphDelta> {
+        let base_node_indices: HashMap<Ulid, NodeIndex> = base
+            .graph
+            .node_indices()
+            .filter_map(|node_index| {
+                base.graph
+                    .node_weight(node_index)
+                    .map(|node_weight| (node_weight.id(), node_index))
+            })
+            .collect();
+
+        let mut delta = WorkspaceSnapshotGraphDelta {
+            root_id: self.get_node_weight(self.root_index)?.id(),
+            ..Default::default()
+        };
+
+        let mut node_ids = HashSet::new();
+        for node_index in self.graph.node_indices() {
+            let node_weight = self.get_node_weight(node_index)?;
+            let node_id = node_weight.id();
+            node_ids.insert(node_id);
+
+            let base_node_index = base_node_indices.get(&node_id).copied();
+            let unchanged = match base_node_index {
+                Some(base_node_index) => {
+                    postcard::to_stdvec(node_weight)?
+                        == postcard::to_stdvec(base.get_node_weight(base_node_index)?)?
+                }
+                None => false,
+            };
+            if !unchanged {
+                delta.node_weights.push(node_weight.clone());
+            }
+
+            let outgoing_edges = self.outgoing_edges_by_target_id(node_index)?;
+            let base_outgoing_edges = match base_node_index {
+                Some(base_node_index) => base.outgoing_edges_by_target_id(base_node_index)?,
+                None => Vec::new(),
+            };
+            if outgoing_edges != base_outgoing_edges {
+                delta.outgoing_edges.push((node_id, outgoing_edges));
+            }
+        }
+
+        delta.removed_node_ids = base_node_indices
+            .into_keys()
+            .filter(|node_id| !node_ids.contains(node_id))
+            .collect();
+
+        Ok(delta)
+    }
+
+    /// Reconstructs a graph from the graph a [`WorkspaceSnapshotGraphDelta`] was made against.
+    pub fn apply_delta(
+        base: &WorkspaceSnapshotGraph,
+        delta: WorkspaceSnapshotGraphDelta,
+    ) -> WorkspaceSnapshotGraphResult<WorkspaceSnapshotGraph> {
+        let mut graph = base.clone();
+
+        for node_id in delta.removed_node_ids {
+            if let Some(node_index) = graph.node_index_by_id.remove(&node_id) {
+                graph.graph.remove_node(node_index);
+            }
+        }
+
+        for node_weight in delta.node_weights {
+            let node_id = node_weight.id();
+            match graph.node_index_by_id.get(&node_id).copied() {
+                // Replacing the weight in place keeps the edges of the node.
+                Some(node_index) => {
+                    *graph.get_node_weight_mut(node_index)? = node_weight;
+                }
+                None => {
+                    let node_index = graph.graph.add_node(node_weight);
+                    graph.node_index_by_id.insert(node_id, node_index);
+                }
+            }
+        }
+
+        for (source_id, edges) in delta.outgoing_edges {
+            let source_index = graph.get_node_index_by_id(source_id)?;
+            let stale_edges: Vec<EdgeIndex> = graph
+                .graph
+                .edges_directed(source_index, Outgoing)
+                .map(|edge_ref| edge_ref.id())
+                .collect();
+            for edge_index in stale_edges {
+                graph.graph.remove_edge(edge_index);
+            }
+            for (target_id, edge_weight) in edges {
+                let target_index = graph.get_node_index_by_id(target_id)?;
+                graph
+                    .graph
+                    .add_edge(source_index, target_index, edge_weight);
+            }
+        }
+
+        graph.root_index = graph.get_node_index_by_id(delta.root_id)?;
+
+        graph.node_indices_by_lineage_id.clear();
+        for node_index in graph.graph.node_indices() {
+            let lineage_id = graph
+                .graph
+                .node_weight(node_index)
+                .ok_or(WorkspaceSnapshotGraphError::NodeWeightNotFound)?
+                .lineage_id();
+            graph
+                .node_indices_by_lineage_id
+                .entry(lineage_id)
+                .or_default()
+                .insert(node_index);
+        }
+
+        Ok(graph)
+    }
+}
+
+impl SnapshotDelta for WorkspaceSnapshotGraph {
+    type Delta = WorkspaceSnapshotGraphDelta;
+    type Error = WorkspaceSnapshotGraphError;
+
+    fn delta_from(&self, base: &Self) -> Result<Self::Delta, Self::Error> {
+        WorkspaceSnapshotGraph::delta_from(self, base)
+    }
+
+    fn apply_delta(base: &Self, delta: Self::Delta) -> Result<Self, Self::Error> {
+        WorkspaceSnapshotGraph::apply_delta(base, delta)
+    }
+}
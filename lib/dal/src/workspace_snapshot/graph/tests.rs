@@ -10,14 +10,19 @@ mod test {
     use si_events::ContentHash;
     use std::collections::HashMap;
     use std::collections::HashSet;
+    use ulid::Ulid;
 
     use crate::change_set::ChangeSet;
-    use crate::workspace_snapshot::conflict::Conflict;
+    use crate::workspace_snapshot::conflict::{
+        Conflict, ConflictChoice, ConflictDiscriminants, ConflictResolutionPolicy,
+        ConflictResolutionStrategy, ResolvedConflict, StableConflict,
+    };
     use crate::workspace_snapshot::content_address::ContentAddress;
     use crate::workspace_snapshot::edge_weight::{
         EdgeWeight, EdgeWeightKind, EdgeWeightKindDiscriminants,
     };
-    use crate::workspace_snapshot::node_weight::NodeWeight;
+    use crate::workspace_snapshot::graph::{interleave_orders, ValidationIssue, ValidationReport};
+    use crate::workspace_snapshot::node_weight::{NodeWeight, NodeWeightDiscriminants};
     use crate::workspace_snapshot::update::Update;
     use crate::WorkspaceSnapshotGraph;
     use crate::{ComponentId, FuncId, PropId, PropKind, SchemaId, SchemaVariantId};
@@ -1380,6 +1385,634 @@ mod test {
         assert_eq!(Vec::<Update>::new(), updates);
     }
 
+    #[test]
+    fn resolve_conflicts_node_content_with_onto_wins() {
+        let initial_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let base_change_set = &initial_change_set;
+        let mut base_graph = WorkspaceSnapshotGraph::new(base_change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let component_id = base_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_index = base_graph
+            .add_node(
+                NodeWeight::new_content(
+                    base_change_set,
+                    component_id,
+                    ContentAddress::Component(ContentHash::from("Component A")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component A");
+        base_graph
+            .add_edge(
+                base_graph.root_index,
+                EdgeWeight::new(base_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_index,
+            )
+            .expect("Unable to add root -> component edge");
+        base_graph.cleanup();
+
+        let new_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let new_change_set = &new_change_set;
+        let mut new_graph = base_graph.clone();
+
+        new_graph
+            .update_content(
+                new_change_set,
+                component_id,
+                ContentHash::from("Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        new_graph.cleanup();
+
+        base_graph
+            .update_content(
+                base_change_set,
+                component_id,
+                ContentHash::from("Base Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        base_graph.cleanup();
+
+        let (conflicts, updates) = new_graph
+            .detect_conflicts_and_updates(
+                new_change_set.vector_clock_id(),
+                &base_graph,
+                base_change_set.vector_clock_id(),
+            )
+            .expect("Unable to detect conflicts and updates");
+        assert_eq!(1, conflicts.len());
+        assert_eq!(Vec::<Update>::new(), updates);
+
+        let unresolved = new_graph
+            .resolve_conflicts(
+                &base_graph,
+                &conflicts,
+                &ConflictResolutionPolicy::default(),
+                &[],
+            )
+            .expect("Unable to resolve conflicts");
+        assert_eq!(conflicts, unresolved.unresolved);
+        assert!(unresolved.resolved.is_empty());
+
+        let choices = [ConflictChoice {
+            kind: ConflictDiscriminants::NodeContent,
+            node_id: component_id,
+            strategy: ConflictResolutionStrategy::ToRebaseWins,
+        }];
+        let chosen = new_graph
+            .resolve_conflicts(
+                &base_graph,
+                &conflicts,
+                &ConflictResolutionPolicy::default(),
+                &choices,
+            )
+            .expect("Unable to resolve conflicts");
+        assert!(chosen.unresolved.is_empty());
+        assert_eq!(
+            vec![ResolvedConflict {
+                conflict: conflicts[0],
+                strategy: ConflictResolutionStrategy::ToRebaseWins,
+            }],
+            chosen.resolved
+        );
+        assert!(chosen.updates.is_empty());
+
+        let policy = ConflictResolutionPolicy {
+            node_content: ConflictResolutionStrategy::OntoWins,
+            ..Default::default()
+        };
+        let resolutions = new_graph
+            .resolve_conflicts(&base_graph, &conflicts, &policy, &[])
+            .expect("Unable to resolve conflicts");
+        assert!(resolutions.unresolved.is_empty());
+        assert_eq!(
+            vec![ResolvedConflict {
+                conflict: conflicts[0],
+                strategy: ConflictResolutionStrategy::OntoWins,
+            }],
+            resolutions.resolved
+        );
+        assert_eq!(
+            vec![Update::ReplaceSubgraph {
+                onto: base_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get component NodeIndex"),
+                to_rebase: new_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get component NodeIndex"),
+            }],
+            resolutions.updates
+        );
+
+        new_graph
+            .perform_updates(new_change_set, &base_graph, &resolutions.updates)
+            .expect("Unable to perform updates");
+        new_graph.cleanup();
+
+        assert_eq!(
+            ContentHash::from("Base Updated Component A"),
+            new_graph
+                .get_node_weight(
+                    new_graph
+                        .get_node_index_by_id(component_id)
+                        .expect("Unable to get component NodeIndex")
+                )
+                .expect("Unable to get component NodeWeight")
+                .content_hash()
+        );
+    }
+
+    #[test]
+    fn stable_conflicts_and_updates_round_trip() {
+        let initial_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let base_change_set = &initial_change_set;
+        let mut base_graph = WorkspaceSnapshotGraph::new(base_change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let component_id = base_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_index = base_graph
+            .add_node(
+                NodeWeight::new_content(
+                    base_change_set,
+                    component_id,
+                    ContentAddress::Component(ContentHash::from("Component A")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component A");
+        base_graph
+            .add_edge(
+                base_graph.root_index,
+                EdgeWeight::new(base_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_index,
+            )
+            .expect("Unable to add root -> component edge");
+        base_graph.cleanup();
+
+        let new_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let new_change_set = &new_change_set;
+        let mut new_graph = base_graph.clone();
+
+        new_graph
+            .update_content(
+                new_change_set,
+                component_id,
+                ContentHash::from("Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        new_graph.cleanup();
+
+        base_graph
+            .update_content(
+                base_change_set,
+                component_id,
+                ContentHash::from("Base Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        base_graph.cleanup();
+
+        let (conflicts, _) = new_graph
+            .detect_conflicts_and_updates(
+                new_change_set.vector_clock_id(),
+                &base_graph,
+                base_change_set.vector_clock_id(),
+            )
+            .expect("Unable to detect conflicts and updates");
+        assert_eq!(1, conflicts.len());
+
+        let stable_conflict = new_graph
+            .stable_conflict(&base_graph, &conflicts[0])
+            .expect("Unable to create stable conflict");
+        match stable_conflict {
+            StableConflict::NodeContent { onto, to_rebase } => {
+                assert_eq!(component_id, onto.id);
+                assert_eq!(component_id, to_rebase.id);
+                assert_eq!(NodeWeightDiscriminants::Content, to_rebase.node_weight_kind);
+            }
+            other => panic!("Unexpected stable conflict: {other:?}"),
+        }
+        let serialized =
+            serde_json::to_string(&stable_conflict).expect("Unable to serialize stable conflict");
+        let deserialized: StableConflict =
+            serde_json::from_str(&serialized).expect("Unable to deserialize stable conflict");
+        assert_eq!(
+            conflicts[0],
+            new_graph
+                .conflict_from_stable(&base_graph, &deserialized)
+                .expect("Unable to translate stable conflict")
+        );
+
+        let update = Update::ReplaceSubgraph {
+            onto: base_graph
+                .get_node_index_by_id(component_id)
+                .expect("Unable to get component NodeIndex"),
+            to_rebase: new_graph
+                .get_node_index_by_id(component_id)
+                .expect("Unable to get component NodeIndex"),
+        };
+        let stable_update = new_graph
+            .stable_update(&base_graph, &update)
+            .expect("Unable to create stable update");
+        assert_eq!(
+            update,
+            new_graph
+                .update_from_stable(&base_graph, &stable_update)
+                .expect("Unable to translate stable update")
+        );
+    }
+
+    #[test]
+    fn detect_reverting_updates_keeps_concurrent_changes() {
+        let initial_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let base_change_set = &initial_change_set;
+        let mut before_graph = WorkspaceSnapshotGraph::new(base_change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let component_a_id = base_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_a_index = before_graph
+            .add_node(
+                NodeWeight::new_content(
+                    base_change_set,
+                    component_a_id,
+                    ContentAddress::Component(ContentHash::from("Component A")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component A");
+        before_graph
+            .add_edge(
+                before_graph.root_index,
+                EdgeWeight::new(base_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_a_index,
+            )
+            .expect("Unable to add root -> component A edge");
+        before_graph.cleanup();
+
+        // The change to revert: Component A is updated and Component B is created.
+        let mut after_graph = before_graph.clone();
+        after_graph
+            .update_content(
+                base_change_set,
+                component_a_id,
+                ContentHash::from("Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        let component_b_id = base_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_b_index = after_graph
+            .add_node(
+                NodeWeight::new_content(
+                    base_change_set,
+                    component_b_id,
+                    ContentAddress::Component(ContentHash::from("Component B")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component B");
+        after_graph
+            .add_edge(
+                after_graph.root_index,
+                EdgeWeight::new(base_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_b_index,
+            )
+            .expect("Unable to add root -> component B edge");
+        after_graph.cleanup();
+
+        // Someone else creates Component C afterwards.
+        let mut current_graph = after_graph.clone();
+        let component_c_id = base_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_c_index = current_graph
+            .add_node(
+                NodeWeight::new_content(
+                    base_change_set,
+                    component_c_id,
+                    ContentAddress::Component(ContentHash::from("Component C")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component C");
+        current_graph
+            .add_edge(
+                current_graph.root_index,
+                EdgeWeight::new(base_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_c_index,
+            )
+            .expect("Unable to add root -> component C edge");
+        current_graph.cleanup();
+
+        let undo_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let undo_change_set = &undo_change_set;
+        let undo_updates = current_graph
+            .detect_reverting_updates(&before_graph, &after_graph)
+            .expect("Unable to detect reverting updates");
+        assert_eq!(2, undo_updates.len());
+        current_graph
+            .perform_updates(undo_change_set, &before_graph, &undo_updates)
+            .expect("Unable to perform updates");
+        current_graph.cleanup();
+
+        let component_content_hash = |graph: &WorkspaceSnapshotGraph, id: Ulid| {
+            graph
+                .get_node_weight(
+                    graph
+                        .get_node_index_by_id(id)
+                        .expect("Unable to get component NodeIndex"),
+                )
+                .expect("Unable to get component NodeWeight")
+                .content_hash()
+        };
+        assert_eq!(
+            ContentHash::from("Component A"),
+            component_content_hash(&current_graph, component_a_id)
+        );
+        assert!(current_graph.get_node_index_by_id(component_b_id).is_err());
+        assert_eq!(
+            ContentHash::from("Component C"),
+            component_content_hash(&current_graph, component_c_id)
+        );
+
+        // Redoing is reverting the undo.
+        let redo_updates = current_graph
+            .detect_reverting_updates(&after_graph, &before_graph)
+            .expect("Unable to detect reverting updates");
+        assert_eq!(2, redo_updates.len());
+        current_graph
+            .perform_updates(undo_change_set, &after_graph, &redo_updates)
+            .expect("Unable to perform updates");
+        current_graph.cleanup();
+
+        assert_eq!(
+            ContentHash::from("Updated Component A"),
+            component_content_hash(&current_graph, component_a_id)
+        );
+        assert_eq!(
+            ContentHash::from("Component B"),
+            component_content_hash(&current_graph, component_b_id)
+        );
+        assert_eq!(
+            ContentHash::from("Component C"),
+            component_content_hash(&current_graph, component_c_id)
+        );
+    }
+
+    #[test]
+    fn resolve_conflicts_modify_removed_item_with_onto_wins() {
+        let initial_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let base_change_set = &initial_change_set;
+        let mut base_graph = WorkspaceSnapshotGraph::new(base_change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let component_id = base_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_index = base_graph
+            .add_node(
+                NodeWeight::new_content(
+                    base_change_set,
+                    component_id,
+                    ContentAddress::Component(ContentHash::from("Component A")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component A");
+        base_graph
+            .add_edge(
+                base_graph.root_index,
+                EdgeWeight::new(base_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_index,
+            )
+            .expect("Unable to add root -> component edge");
+        base_graph.cleanup();
+
+        let new_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let new_change_set = &new_change_set;
+        let mut new_graph = base_graph.clone();
+
+        base_graph
+            .remove_edge(
+                base_change_set,
+                base_graph.root_index,
+                base_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get NodeIndex"),
+                EdgeWeightKindDiscriminants::Use,
+            )
+            .expect("Unable to remove Component A");
+        base_graph.cleanup();
+
+        new_graph
+            .update_content(
+                new_change_set,
+                component_id,
+                ContentHash::from("Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        new_graph.cleanup();
+
+        let (conflicts, updates) = new_graph
+            .detect_conflicts_and_updates(
+                new_change_set.vector_clock_id(),
+                &base_graph,
+                base_change_set.vector_clock_id(),
+            )
+            .expect("Unable to detect conflicts and updates");
+        assert_eq!(
+            vec![Conflict::ModifyRemovedItem(
+                new_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get NodeIndex")
+            )],
+            conflicts
+        );
+        assert_eq!(Vec::<Update>::new(), updates);
+
+        let policy = ConflictResolutionPolicy {
+            modify_removed_item: ConflictResolutionStrategy::OntoWins,
+            ..Default::default()
+        };
+        let resolutions = new_graph
+            .resolve_conflicts(&base_graph, &conflicts, &policy, &[])
+            .expect("Unable to resolve conflicts");
+        assert!(resolutions.unresolved.is_empty());
+        assert_eq!(
+            vec![ResolvedConflict {
+                conflict: conflicts[0],
+                strategy: ConflictResolutionStrategy::OntoWins,
+            }],
+            resolutions.resolved
+        );
+        assert_eq!(
+            vec![Update::RemoveEdge {
+                source: new_graph.root_index,
+                destination: new_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get NodeIndex"),
+                edge_kind: EdgeWeightKindDiscriminants::Use,
+            }],
+            resolutions.updates
+        );
+
+        new_graph
+            .perform_updates(new_change_set, &base_graph, &resolutions.updates)
+            .expect("Unable to perform updates");
+        new_graph.cleanup();
+
+        // The item is removed along with the modification made to it.
+        assert!(new_graph.get_node_index_by_id(component_id).is_err());
+    }
+
+    #[test]
+    fn resolve_conflicts_remove_modified_item_with_onto_wins() {
+        let initial_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let initial_change_set = &initial_change_set;
+        let mut initial_graph = WorkspaceSnapshotGraph::new(initial_change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let component_id = initial_change_set
+            .generate_ulid()
+            .expect("Unable to generate Ulid");
+        let component_index = initial_graph
+            .add_node(
+                NodeWeight::new_content(
+                    initial_change_set,
+                    component_id,
+                    ContentAddress::Component(ContentHash::from("Component A")),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add Component A");
+        initial_graph
+            .add_edge(
+                initial_graph.root_index,
+                EdgeWeight::new(initial_change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                component_index,
+            )
+            .expect("Unable to add root -> component edge");
+        initial_graph.cleanup();
+
+        // The "to rebase" change set has seen everything "onto" had before "onto" modified the
+        // component, and then removes the component.
+        let onto_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let onto_change_set = &onto_change_set;
+        let new_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let new_change_set = &new_change_set;
+        let mut new_graph = initial_graph.clone();
+        new_graph
+            .mark_graph_seen(onto_change_set.vector_clock_id())
+            .expect("Unable to mark graph seen");
+
+        let mut onto_graph = initial_graph.clone();
+        onto_graph
+            .update_content(
+                onto_change_set,
+                component_id,
+                ContentHash::from("Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        onto_graph.cleanup();
+
+        new_graph
+            .remove_edge(
+                new_change_set,
+                new_graph.root_index,
+                new_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get NodeIndex"),
+                EdgeWeightKindDiscriminants::Use,
+            )
+            .expect("Unable to remove Component A");
+        new_graph.cleanup();
+
+        let (conflicts, updates) = new_graph
+            .detect_conflicts_and_updates(
+                new_change_set.vector_clock_id(),
+                &onto_graph,
+                onto_change_set.vector_clock_id(),
+            )
+            .expect("Unable to detect conflicts and updates");
+        assert_eq!(
+            vec![Conflict::RemoveModifiedItem {
+                container: new_graph.root_index,
+                removed_item: onto_graph
+                    .get_node_index_by_id(component_id)
+                    .expect("Unable to get NodeIndex"),
+            }],
+            conflicts
+        );
+        assert_eq!(Vec::<Update>::new(), updates);
+
+        let policy = ConflictResolutionPolicy {
+            remove_modified_item: ConflictResolutionStrategy::OntoWins,
+            ..Default::default()
+        };
+        let resolutions = new_graph
+            .resolve_conflicts(&onto_graph, &conflicts, &policy, &[])
+            .expect("Unable to resolve conflicts");
+        assert!(resolutions.unresolved.is_empty());
+        assert_eq!(
+            vec![ResolvedConflict {
+                conflict: conflicts[0],
+                strategy: ConflictResolutionStrategy::OntoWins,
+            }],
+            resolutions.resolved
+        );
+
+        new_graph
+            .perform_updates(new_change_set, &onto_graph, &resolutions.updates)
+            .expect("Unable to perform updates");
+        new_graph.cleanup();
+
+        // The removed item is back, as modified by "onto".
+        assert_eq!(
+            ContentHash::from("Updated Component A"),
+            new_graph
+                .get_node_weight(
+                    new_graph
+                        .get_node_index_by_id(component_id)
+                        .expect("Unable to get component NodeIndex")
+                )
+                .expect("Unable to get component NodeWeight")
+                .content_hash()
+        );
+    }
+
+    #[test]
+    fn interleave_child_orders() {
+        let change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let ids: Vec<Ulid> = (0..5)
+            .map(|_| change_set.generate_ulid().expect("Unable to generate Ulid"))
+            .collect();
+
+        let to_rebase_order = vec![ids[1], ids[0], ids[3], ids[2]];
+        let onto_order = vec![ids[4], ids[0], ids[1], ids[2], ids[3]];
+
+        assert_eq!(
+            vec![ids[4], ids[1], ids[0], ids[3], ids[2]],
+            interleave_orders(&to_rebase_order, &onto_order)
+        );
+        assert_eq!(
+            to_rebase_order,
+            interleave_orders(&to_rebase_order, &to_rebase_order)
+        );
+    }
+
     #[test]
     fn detect_conflicts_and_updates_add_unordered_child_to_ordered_container() {
         let base_change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
@@ -5920,4 +6553,190 @@ mod test {
                 .expect("Unable to generate attribute value view"),
         );
     }
+
+    #[test]
+    fn apply_delta_reconstructs_graph() {
+        let change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let change_set = &change_set;
+        let mut base_graph = WorkspaceSnapshotGraph::new(change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let add_component = |graph: &mut WorkspaceSnapshotGraph, name: &str| {
+            let component_id = change_set.generate_ulid().expect("Unable to generate Ulid");
+            let component_index = graph
+                .add_node(
+                    NodeWeight::new_content(
+                        change_set,
+                        component_id,
+                        ContentAddress::Component(ContentHash::from(name)),
+                    )
+                    .expect("Unable to create NodeWeight"),
+                )
+                .expect("Unable to add component");
+            graph
+                .add_edge(
+                    graph.root_index,
+                    EdgeWeight::new(change_set, EdgeWeightKind::new_use())
+                        .expect("Unable to create EdgeWeight"),
+                    component_index,
+                )
+                .expect("Unable to add root -> component edge");
+            component_id
+        };
+        let component_a_id = add_component(&mut base_graph, "Component A");
+        let component_b_id = add_component(&mut base_graph, "Component B");
+        base_graph.cleanup();
+
+        // Update one component, remove another and add a third.
+        let mut graph = base_graph.clone();
+        graph
+            .update_content(
+                change_set,
+                component_a_id,
+                ContentHash::from("Updated Component A"),
+            )
+            .expect("Unable to update Component A");
+        let component_b_index = graph
+            .get_node_index_by_id(component_b_id)
+            .expect("Unable to get NodeIndex");
+        graph
+            .remove_edge(
+                change_set,
+                graph.root_index,
+                component_b_index,
+                EdgeWeightKindDiscriminants::Use,
+            )
+            .expect("Unable to remove root -> component B edge");
+        add_component(&mut graph, "Component C");
+        graph.cleanup();
+
+        let delta = graph
+            .delta_from(&base_graph)
+            .expect("Unable to compute delta");
+        let reconstructed_graph =
+            WorkspaceSnapshotGraph::apply_delta(&base_graph, delta).expect("Unable to apply delta");
+
+        let describe = |graph: &WorkspaceSnapshotGraph| {
+            let nodes: HashMap<Ulid, Vec<u8>> = graph
+                .nodes()
+                .map(|(node_weight, _)| {
+                    (
+                        node_weight.id(),
+                        postcard::to_stdvec(node_weight).expect("Unable to serialize NodeWeight"),
+                    )
+                })
+                .collect();
+            let mut edges: Vec<(Ulid, Ulid, Vec<u8>)> = graph
+                .edges()
+                .map(|(edge_weight, source, target)| {
+                    (
+                        graph
+                            .node_index_to_id(source)
+                            .expect("Unable to get source id"),
+                        graph
+                            .node_index_to_id(target)
+                            .expect("Unable to get target id"),
+                        postcard::to_stdvec(edge_weight).expect("Unable to serialize EdgeWeight"),
+                    )
+                })
+                .collect();
+            edges.sort();
+            let root_id = graph
+                .node_index_to_id(graph.root())
+                .expect("Unable to get root id");
+            (root_id, nodes, edges)
+        };
+        assert_eq!(describe(&graph), describe(&reconstructed_graph));
+        assert!(reconstructed_graph
+            .get_node_index_by_id(component_b_id)
+            .is_err());
+    }
+
+    #[test]
+    fn validate_reports_ordinal_edge_missing() {
+        let change_set = ChangeSet::new_local().expect("Unable to create ChangeSet");
+        let change_set = &change_set;
+        let mut graph = WorkspaceSnapshotGraph::new(change_set)
+            .expect("Unable to create WorkspaceSnapshotGraph");
+
+        let container_id = change_set.generate_ulid().expect("Unable to generate Ulid");
+        let container_index = graph
+            .add_ordered_node(
+                change_set,
+                NodeWeight::new_content(
+                    change_set,
+                    container_id,
+                    ContentAddress::Prop(ContentHash::new(container_id.to_string().as_bytes())),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add container prop");
+        graph
+            .add_edge(
+                graph.root_index,
+                EdgeWeight::new(change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                container_index,
+            )
+            .expect("Unable to add root -> container prop edge");
+
+        let element_id = change_set.generate_ulid().expect("Unable to generate Ulid");
+        let element_index = graph
+            .add_node(
+                NodeWeight::new_content(
+                    change_set,
+                    element_id,
+                    ContentAddress::Prop(ContentHash::new(element_id.to_string().as_bytes())),
+                )
+                .expect("Unable to create NodeWeight"),
+            )
+            .expect("Unable to add element prop");
+        graph
+            .add_ordered_edge(
+                change_set,
+                graph
+                    .get_node_index_by_id(container_id)
+                    .expect("Unable to get NodeIndex"),
+                EdgeWeight::new(change_set, EdgeWeightKind::new_use())
+                    .expect("Unable to create EdgeWeight"),
+                element_index,
+            )
+            .expect("Unable to add container prop -> element prop edge");
+        graph.cleanup();
+
+        assert_eq!(ValidationReport::default(), graph.validate());
+
+        // Break the graph by dropping the ordinal edge, but not the entry in the order.
+        let ordering_node_index = graph
+            .ordering_node_index_for_container(
+                graph
+                    .get_node_index_by_id(container_id)
+                    .expect("Unable to get NodeIndex"),
+            )
+            .expect("Unable to find ordering node")
+            .expect("Container has no ordering node");
+        let ordinal_edge_index = graph
+            .graph
+            .find_edge(
+                ordering_node_index,
+                graph
+                    .get_node_index_by_id(element_id)
+                    .expect("Unable to get NodeIndex"),
+            )
+            .expect("Unable to find ordinal edge");
+        graph.graph.remove_edge(ordinal_edge_index);
+
+        let ordering_node_id = graph
+            .node_index_to_id(ordering_node_index)
+            .expect("Unable to get ordering node id");
+        assert_eq!(
+            ValidationReport {
+                issues: vec![ValidationIssue::OrderEntryWithoutOrdinalEdge {
+                    ordering_node_id,
+                    entry_id: element_id,
+                }],
+            },
+            graph.validate()
+        );
+    }
 }
@@ -0,0 +1,312 @@
+//! Checking the structural invariants of a [`WorkspaceSnapshotGraph`] that are implied by its
+//! [`EdgeWeightKinds`](EdgeWeightKind) and [`NodeWeights`](NodeWeight), so that a broken graph is
+//! reported as such rather than as confusing errors from whatever reads it next.
+
+use std::collections::HashSet;
+
+use petgraph::prelude::*;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::workspace_snapshot::edge_weight::{EdgeWeightKind, EdgeWeightKindDiscriminants};
+use crate::workspace_snapshot::graph::WorkspaceSnapshotGraph;
+use crate::workspace_snapshot::node_weight::{
+    NodeWeight, NodeWeightDiscriminants, OrderingNodeWeight,
+};
+
+/// A single broken invariant found by [`WorkspaceSnapshotGraph::validate()`].
+#[remain::sorted]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ValidationIssue {
+    /// An attribute value must be for exactly one prop or socket.
+    #[serde(rename_all = "camelCase")]
+    AttributeValueIsForCount {
+        attribute_value_id: Ulid,
+        count: usize,
+    },
+    /// A component must have exactly one root attribute value.
+    #[serde(rename_all = "camelCase")]
+    ComponentRootCount { component_id: Ulid, count: usize },
+    /// A container must have at most one ordering node.
+    #[serde(rename_all = "camelCase")]
+    ContainerOrderingNodeCount { container_id: Ulid, count: usize },
+    /// The graph must be acyclic.
+    Cycle,
+    /// An edge connects kinds of nodes that its kind never connects.
+    #[serde(rename_all = "camelCase")]
+    InvalidEdgeEndpoints {
+        edge_kind: EdgeWeightKindDiscriminants,
+        source_id: Ulid,
+        source_kind: NodeWeightDiscriminants,
+        target_id: Ulid,
+        target_kind: NodeWeightDiscriminants,
+    },
+    /// The index of nodes by id refers to a node that does not exist, or has another id.
+    #[serde(rename_all = "camelCase")]
+    NodeIndexMismatch { node_id: Ulid },
+    /// An ordering node lists the same element more than once.
+    #[serde(rename_all = "camelCase")]
+    OrderEntryDuplicated {
+        ordering_node_id: Ulid,
+        entry_id: Ulid,
+    },
+    /// An ordering node lists an element that its container does not contain.
+    #[serde(rename_all = "camelCase")]
+    OrderEntryNotChildOfContainer {
+        ordering_node_id: Ulid,
+        container_id: Ulid,
+        entry_id: Ulid,
+    },
+    /// An ordering node lists an element that it has no `Ordinal` edge to.
+    #[serde(rename_all = "camelCase")]
+    OrderEntryWithoutOrdinalEdge {
+        ordering_node_id: Ulid,
+        entry_id: Ulid,
+    },
+    /// An ordering node must belong to exactly one container.
+    #[serde(rename_all = "camelCase")]
+    OrderingNodeContainerCount {
+        ordering_node_id: Ulid,
+        count: usize,
+    },
+    /// An ordering node has an `Ordinal` edge to an element that it does not list.
+    #[serde(rename_all = "camelCase")]
+    OrdinalEdgeNotInOrder {
+        ordering_node_id: Ulid,
+        target_id: Ulid,
+    },
+    /// The root node of the graph does not exist.
+    RootNodeMissing,
+}
+
+/// Everything found to be wrong with a [`WorkspaceSnapshotGraph`] by
+/// [`WorkspaceSnapshotGraph::validate()`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl WorkspaceSnapshotGraph {
+    /// Checks the structural invariants of the graph. This walks every node and edge, so it is
+    /// meant for debugging and tests rather than for every read.
+    pub fn validate(&self) -> ValidationReport {
+        let mut issues = Vec::new();
+
+        if self.graph.node_weight(self.root_index).is_none() {
+            issues.push(ValidationIssue::RootNodeMissing);
+        }
+        if !self.is_acyclic_directed() {
+            issues.push(ValidationIssue::Cycle);
+        }
+        for (node_id, node_index) in &self.node_index_by_id {
+            if self.node_index_to_id(*node_index) != Some(*node_id) {
+                issues.push(ValidationIssue::NodeIndexMismatch { node_id: *node_id });
+            }
+        }
+
+        for node_index in self.graph.node_indices() {
+            let node_weight = match self.graph.node_weight(node_index) {
+                Some(node_weight) => node_weight,
+                None => continue,
+            };
+            let node_id = node_weight.id();
+
+            for edge_ref in self.graph.edges_directed(node_index, Outgoing) {
+                let target_weight = match self.graph.node_weight(edge_ref.target()) {
+                    Some(target_weight) => target_weight,
+                    None => continue,
+                };
+                if !edge_endpoints_are_valid(edge_ref.weight().kind(), node_weight, target_weight) {
+                    issues.push(ValidationIssue::InvalidEdgeEndpoints {
+                        edge_kind: edge_ref.weight().kind().into(),
+                        source_id: node_id,
+                        source_kind: node_weight.into(),
+                        target_id: target_weight.id(),
+                        target_kind: target_weight.into(),
+                    });
+                }
+            }
+
+            let ordering_node_count =
+                self.outgoing_edge_count(node_index, &[EdgeWeightKindDiscriminants::Ordering]);
+            if ordering_node_count > 1 {
+                issues.push(ValidationIssue::ContainerOrderingNodeCount {
+                    container_id: node_id,
+                    count: ordering_node_count,
+                });
+            }
+
+            match node_weight {
+                NodeWeight::AttributeValue(_) => {
+                    let count = self.outgoing_edge_count(
+                        node_index,
+                        &[
+                            EdgeWeightKindDiscriminants::Prop,
+                            EdgeWeightKindDiscriminants::Socket,
+                        ],
+                    );
+                    if count != 1 {
+                        issues.push(ValidationIssue::AttributeValueIsForCount {
+                            attribute_value_id: node_id,
+                            count,
+                        });
+                    }
+                }
+                NodeWeight::Component(_) => {
+                    let count =
+                        self.outgoing_edge_count(node_index, &[EdgeWeightKindDiscriminants::Root]);
+                    if count != 1 {
+                        issues.push(ValidationIssue::ComponentRootCount {
+                            component_id: node_id,
+                            count,
+                        });
+                    }
+                }
+                NodeWeight::Ordering(ordering_weight) => {
+                    self.validate_ordering_node(node_index, ordering_weight, &mut issues);
+                }
+                NodeWeight::AttributePrototypeArgument(_)
+                | NodeWeight::Category(_)
+                | NodeWeight::Content(_)
+                | NodeWeight::Func(_)
+                | NodeWeight::FuncArgument(_)
+                | NodeWeight::Prop(_) => {}
+            }
+        }
+
+        ValidationReport { issues }
+    }
+
+    fn outgoing_edge_count(
+        &self,
+        node_index: NodeIndex,
+        edge_kinds: &[EdgeWeightKindDiscriminants],
+    ) -> usize {
+        self.graph
+            .edges_directed(node_index, Outgoing)
+            .filter(|edge_ref| edge_kinds.contains(&edge_ref.weight().kind().into()))
+            .count()
+    }
+
+    fn validate_ordering_node(
+        &self,
+        ordering_node_index: NodeIndex,
+        ordering_weight: &OrderingNodeWeight,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        let ordering_node_id = ordering_weight.id();
+
+        let containers: Vec<NodeIndex> = self
+            .graph
+            .edges_directed(ordering_node_index, Incoming)
+            .filter(|edge_ref| edge_ref.weight().kind() == &EdgeWeightKind::Ordering)
+            .map(|edge_ref| edge_ref.source())
+            .collect();
+        let container = match containers.as_slice() {
+            [container] => Some(*container),
+            _ => {
+                issues.push(ValidationIssue::OrderingNodeContainerCount {
+                    ordering_node_id,
+                    count: containers.len(),
+                });
+                None
+            }
+        };
+
+        let ordinal_target_ids: HashSet<Ulid> = self
+            .graph
+            .edges_directed(ordering_node_index, Outgoing)
+            .filter(|edge_ref| edge_ref.weight().kind() == &EdgeWeightKind::Ordinal)
+            .filter_map(|edge_ref| self.node_index_to_id(edge_ref.target()))
+            .collect();
+        let container_child_ids: Option<HashSet<Ulid>> = container.map(|container| {
+            self.graph
+                .edges_directed(container, Outgoing)
+                .filter(|edge_ref| edge_ref.weight().kind() != &EdgeWeightKind::Ordering)
+                .filter_map(|edge_ref| self.node_index_to_id(edge_ref.target()))
+                .collect()
+        });
+
+        let mut seen_entry_ids = HashSet::new();
+        for entry_id in ordering_weight.order() {
+            if !seen_entry_ids.insert(*entry_id) {
+                issues.push(ValidationIssue::OrderEntryDuplicated {
+                    ordering_node_id,
+                    entry_id: *entry_id,
+                });
+                continue;
+            }
+            if !ordinal_target_ids.contains(entry_id) {
+                issues.push(ValidationIssue::OrderEntryWithoutOrdinalEdge {
+                    ordering_node_id,
+                    entry_id: *entry_id,
+                });
+            }
+            if let (Some(container), Some(container_child_ids)) = (container, &container_child_ids)
+            {
+                if !container_child_ids.contains(entry_id) {
+                    if let Some(container_id) = self.node_index_to_id(container) {
+                        issues.push(ValidationIssue::OrderEntryNotChildOfContainer {
+                            ordering_node_id,
+                            container_id,
+                            entry_id: *entry_id,
+                        });
+                    }
+                }
+            }
+        }
+
+        for target_id in ordinal_target_ids {
+            if !seen_entry_ids.contains(&target_id) {
+                issues.push(ValidationIssue::OrdinalEdgeNotInOrder {
+                    ordering_node_id,
+                    target_id,
+                });
+            }
+        }
+    }
+}
+
+/// Whether an edge of the given kind may connect the given nodes.
+fn edge_endpoints_are_valid(
+    kind: &EdgeWeightKind,
+    source: &NodeWeight,
+    target: &NodeWeight,
+) -> bool {
+    let source_is_ordering = matches!(source, NodeWeight::Ordering(_));
+    let target_is_ordering = matches!(target, NodeWeight::Ordering(_));
+
+    match kind {
+        EdgeWeightKind::Ordering => !source_is_ordering && target_is_ordering,
+        EdgeWeightKind::Ordinal => source_is_ordering && !target_is_ordering,
+        EdgeWeightKind::Prop => {
+            matches!(source, NodeWeight::AttributeValue(_)) && matches!(target, NodeWeight::Prop(_))
+        }
+        EdgeWeightKind::PrototypeArgument => {
+            matches!(target, NodeWeight::AttributePrototypeArgument(_))
+        }
+        EdgeWeightKind::Root => {
+            matches!(source, NodeWeight::Component(_))
+                && matches!(target, NodeWeight::AttributeValue(_))
+        }
+        EdgeWeightKind::Action
+        | EdgeWeightKind::ActionPrototype
+        | EdgeWeightKind::AuthenticationPrototype
+        | EdgeWeightKind::Contain(_)
+        | EdgeWeightKind::FrameContains
+        | EdgeWeightKind::Prototype(_)
+        | EdgeWeightKind::PrototypeArgumentValue
+        | EdgeWeightKind::Proxy
+        | EdgeWeightKind::Socket
+        | EdgeWeightKind::SocketValue
+        | EdgeWeightKind::Use { .. } => !source_is_ordering && !target_is_ordering,
+    }
+}
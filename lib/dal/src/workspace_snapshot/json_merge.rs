@@ -0,0 +1,159 @@
+//! Structural three-way merging of JSON values.
+//!
+//! Used when two change sets have both modified the content of the same
+//! [`AttributeValue`](crate::AttributeValue): rather than reporting the whole value as
+//! conflicting, the changes made on each side (relative to the common ancestor) are combined
+//! key-by-key, and only keys that were changed differently on both sides are reported.
+
+use serde_json::{Map, Value};
+
+/// The outcome of a [`three_way_merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonMergeOutcome {
+    /// The two sides could not be combined. Contains the
+    /// [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901) of every location that was
+    /// changed differently on both sides.
+    Conflicted(Vec<String>),
+    /// Both sides were combined into a single value (or into no value, if both sides agreed on
+    /// its removal).
+    Merged(Option<Value>),
+}
+
+/// Merge `ours` and `theirs`, both descended from `base`. `None` represents an absent value.
+///
+/// Objects are merged recursively by key. Any other value (including arrays, whose indices
+/// carry no identity) is treated as a single unit that conflicts if both sides changed it to
+/// different things.
+pub fn three_way_merge(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+) -> JsonMergeOutcome {
+    let mut conflicts = Vec::new();
+    let merged = merge_at("", base, ours, theirs, &mut conflicts);
+
+    if conflicts.is_empty() {
+        JsonMergeOutcome::Merged(merged)
+    } else {
+        JsonMergeOutcome::Conflicted(conflicts)
+    }
+}
+
+fn merge_at(
+    pointer: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<String>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+
+    match (ours, theirs) {
+        (Some(Value::Object(ours_map)), Some(Value::Object(theirs_map))) => {
+            // If both sides turned the value into an object, anything the ancestor had that was
+            // not an object is irrelevant to the keys within it.
+            let empty = Map::new();
+            let base_map = match base {
+                Some(Value::Object(base_map)) => base_map,
+                _ => &empty,
+            };
+
+            // Visit keys in "ours" order, followed by keys only "theirs" knows about. Keys that
+            // only exist in the ancestor were removed on both sides and need no visit.
+            let keys = ours_map
+                .keys()
+                .chain(theirs_map.keys().filter(|key| !ours_map.contains_key(*key)));
+
+            let mut merged = Map::new();
+            for key in keys {
+                let child_pointer = format!("{pointer}/{}", escape_pointer_token(key));
+                if let Some(value) = merge_at(
+                    &child_pointer,
+                    base_map.get(key),
+                    ours_map.get(key),
+                    theirs_map.get(key),
+                    conflicts,
+                ) {
+                    merged.insert(key.to_owned(), value);
+                }
+            }
+
+            Some(Value::Object(merged))
+        }
+        _ => {
+            conflicts.push(pointer.to_owned());
+            ours.cloned()
+        }
+    }
+}
+
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn disjoint_keys_merge() {
+        let base = json!({ "name": "poop", "tags": { "env": "dev" } });
+        let ours = json!({ "name": "canoe", "tags": { "env": "dev" } });
+        let theirs = json!({ "name": "poop", "tags": { "env": "dev", "owner": "pet shop boys" } });
+
+        assert_eq!(
+            JsonMergeOutcome::Merged(Some(json!({
+                "name": "canoe",
+                "tags": { "env": "dev", "owner": "pet shop boys" },
+            }))),
+            three_way_merge(Some(&base), Some(&ours), Some(&theirs)),
+        );
+    }
+
+    #[test]
+    fn removals_merge() {
+        let base = json!({ "a": 1, "b": 2, "c": 3 });
+        let ours = json!({ "a": 1, "c": 3 });
+        let theirs = json!({ "a": 1, "b": 2, "c": 4 });
+
+        assert_eq!(
+            JsonMergeOutcome::Merged(Some(json!({ "a": 1, "c": 4 }))),
+            three_way_merge(Some(&base), Some(&ours), Some(&theirs)),
+        );
+        assert_eq!(
+            JsonMergeOutcome::Merged(None),
+            three_way_merge(Some(&base), None, None),
+        );
+    }
+
+    #[test]
+    fn overlapping_keys_conflict() {
+        let base = json!({ "a": { "b/c": 1, "d": [1] }, "e": true });
+        let ours = json!({ "a": { "b/c": 2, "d": [1, 2] }, "e": false });
+        let theirs = json!({ "a": { "b/c": 3, "d": [1, 3] }, "e": false });
+
+        assert_eq!(
+            JsonMergeOutcome::Conflicted(vec!["/a/b~1c".to_string(), "/a/d".to_string()]),
+            three_way_merge(Some(&base), Some(&ours), Some(&theirs)),
+        );
+    }
+
+    #[test]
+    fn scalar_change_on_both_sides_conflicts() {
+        assert_eq!(
+            JsonMergeOutcome::Conflicted(vec!["".to_string()]),
+            three_way_merge(Some(&json!("a")), Some(&json!("b")), Some(&json!("c"))),
+        );
+        assert_eq!(
+            JsonMergeOutcome::Conflicted(vec!["".to_string()]),
+            three_way_merge(None, Some(&json!({})), Some(&json!("c"))),
+        );
+    }
+}
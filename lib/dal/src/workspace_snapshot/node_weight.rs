@@ -84,6 +84,26 @@ pub enum NodeWeight {
     Prop(PropNodeWeight),
 }
 
+/// Identifies a node by its id and lineage id. Unlike a
+/// [`NodeIndex`](petgraph::stable_graph::NodeIndex), this remains meaningful outside of the graph
+/// the node was found in, so it can be persisted or sent to other services.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeInformation {
+    pub id: Ulid,
+    pub lineage_id: Ulid,
+    pub node_weight_kind: NodeWeightDiscriminants,
+}
+
+impl From<&NodeWeight> for NodeInformation {
+    fn from(value: &NodeWeight) -> Self {
+        Self {
+            id: value.id(),
+            lineage_id: value.lineage_id(),
+            node_weight_kind: value.into(),
+        }
+    }
+}
+
 impl NodeWeight {
     pub fn content_hash(&self) -> ContentHash {
         match self {
@@ -113,6 +133,28 @@ impl NodeWeight {
         }
     }
 
+    /// Every [`ContentAddress`] the node refers to content in the content store by.
+    pub fn content_addresses(&self) -> Vec<ContentAddress> {
+        match self {
+            NodeWeight::AttributeValue(weight) => [
+                weight.unprocessed_value(),
+                weight.value(),
+                weight.materialized_view(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            NodeWeight::Component(weight) => vec![weight.content_address()],
+            NodeWeight::Content(weight) => vec![weight.content_address()],
+            NodeWeight::Func(weight) => vec![weight.content_address()],
+            NodeWeight::FuncArgument(weight) => vec![weight.content_address()],
+            NodeWeight::Prop(weight) => vec![weight.content_address()],
+            NodeWeight::AttributePrototypeArgument(_)
+            | NodeWeight::Category(_)
+            | NodeWeight::Ordering(_) => vec![],
+        }
+    }
+
     pub fn id(&self) -> Ulid {
         match self {
             NodeWeight::AttributePrototypeArgument(weight) => weight.id(),
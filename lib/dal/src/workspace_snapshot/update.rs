@@ -1,6 +1,8 @@
 use petgraph::prelude::*;
+use ulid::Ulid;
 
 use super::edge_weight::{EdgeWeight, EdgeWeightKindDiscriminants};
+use super::node_weight::NodeInformation;
 use serde::{Deserialize, Serialize};
 
 #[remain::sorted]
@@ -19,10 +21,53 @@ pub enum Update {
         destination: NodeIndex,
         edge_kind: EdgeWeightKindDiscriminants,
     },
+    /// Set the order of an ordering node in "to_rebase". Entries that are not (or are no longer)
+    /// children of the ordering node when the update is performed are dropped.
+    ReplaceOrder {
+        ordering: NodeIndex,
+        order: Vec<Ulid>,
+    },
     ReplaceSubgraph {
         onto: NodeIndex,
         // Check if already exists in "onto". Grab node weight from "to_rebase" and see if there is
         // an equivalent node (id and lineage) in "onto". If not, use "import_subgraph".
         to_rebase: NodeIndex,
     },
+    /// Replace the weight of a node in "to_rebase" with the weight of the node with the same id in
+    /// "onto", even though the "onto" weight is older. Used to revert changes.
+    RevertNode {
+        onto: NodeIndex,
+        to_rebase: NodeIndex,
+    },
+}
+
+/// An [`Update`] with each [`NodeIndex`] replaced by the [`NodeInformation`] of the node it
+/// referred to, so that it remains meaningful once the graphs it was detected between are gone.
+/// See [`WorkspaceSnapshotGraph::stable_update()`](crate::WorkspaceSnapshotGraph::stable_update)
+/// and [`WorkspaceSnapshotGraph::update_from_stable()`](crate::WorkspaceSnapshotGraph::update_from_stable).
+#[remain::sorted]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StableUpdate {
+    NewEdge {
+        source: NodeInformation,
+        destination: NodeInformation,
+        edge_weight: EdgeWeight,
+    },
+    RemoveEdge {
+        source: NodeInformation,
+        destination: NodeInformation,
+        edge_kind: EdgeWeightKindDiscriminants,
+    },
+    ReplaceOrder {
+        ordering: NodeInformation,
+        order: Vec<Ulid>,
+    },
+    ReplaceSubgraph {
+        onto: NodeInformation,
+        to_rebase: NodeInformation,
+    },
+    RevertNode {
+        onto: NodeInformation,
+        to_rebase: NodeInformation,
+    },
 }
@@ -1,9 +1,17 @@
+use dal::change_set::pointer_history::{ChangeSetPointerHistoryEntry, ChangeSetPointerOperation};
 use dal::change_set::view::OpenChangeSetsView;
-use dal::change_set::ChangeSet;
-use dal::{ChangeSetStatus, DalContext};
+use dal::change_set::{ChangeSet, ChangeSetError};
+use dal::layer_db_types::ContentTypes;
+use dal::workspace_snapshot::diff::SnapshotChange;
+use dal::workspace_snapshot::garbage_collection::GarbageCollectionOptions;
+use dal::{ChangeSetStatus, Component, DalContext, TransactionsError, WorkspaceSnapshot};
 use dal_test::test;
+use dal_test::test_harness::create_component_for_schema_name;
 use pretty_assertions_sorted::assert_eq;
+use si_events::CasValue;
+use si_layer_cache::error::LayerDbError;
 use std::collections::HashSet;
+use std::sync::Arc;
 
 #[test]
 async fn open_change_sets(ctx: &mut DalContext) {
@@ -162,3 +170,424 @@ async fn abandon_change_set(ctx: &mut DalContext) {
     let change_set_names = Vec::from_iter(view.change_sets.iter().map(|c| c.name.clone()));
     assert!(!change_set_names.contains(&change_set_name))
 }
+
+#[test]
+async fn read_change_set_as_of_pointer_history(ctx: &mut DalContext) {
+    let history_before = ChangeSetPointerHistoryEntry::list(ctx, ctx.change_set_id())
+        .await
+        .expect("could not list pointer history");
+    let as_of = history_before
+        .last()
+        .expect("change set has no pointer history")
+        .created_at;
+
+    let component = create_component_for_schema_name(ctx, "starfield", "in the future").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    // Committing moves the pointer and records who moved it and why.
+    let history_after = ChangeSetPointerHistoryEntry::list(ctx, ctx.change_set_id())
+        .await
+        .expect("could not list pointer history");
+    assert_eq!(
+        history_before.len() + 1, // expected
+        history_after.len()       // actual
+    );
+    let latest = history_after.last().expect("pointer history is empty");
+    assert_eq!(
+        ChangeSetPointerOperation::Commit, // expected
+        latest.operation                   // actual
+    );
+    assert_eq!(
+        ctx.events_actor(), // expected
+        latest.actor        // actual
+    );
+
+    // Reading as of before the commit does not see the component.
+    let mut historical_ctx = ctx.clone();
+    historical_ctx
+        .update_snapshot_to_change_set_history_as_of(as_of)
+        .await
+        .expect("could not update snapshot to pointer history");
+    assert!(historical_ctx.is_read_only());
+    let historical_component_ids: Vec<_> = Component::list(&historical_ctx)
+        .await
+        .expect("could not list components")
+        .iter()
+        .map(|c| c.id())
+        .collect();
+    assert!(!historical_component_ids.contains(&component.id()));
+
+    // ...and cannot be committed.
+    assert!(matches!(
+        historical_ctx.commit().await,
+        Err(TransactionsError::ReadOnly(_))
+    ));
+    assert!(matches!(
+        historical_ctx.commit_no_rebase().await,
+        Err(TransactionsError::ReadOnly(_))
+    ));
+
+    // Nor can anything be written to the content store through it...
+    let content: ContentTypes = CasValue::from(serde_json::json!("from the past")).into();
+    let write_result = historical_ctx
+        .layer_db()
+        .cas()
+        .write(
+            Arc::new(content),
+            None,
+            historical_ctx.events_tenancy(),
+            historical_ctx.events_actor(),
+        )
+        .await;
+    assert!(matches!(write_result, Err(LayerDbError::ReadOnly)));
+
+    // ...nor can it move the change set pointer.
+    let mut change_set = historical_ctx
+        .change_set()
+        .expect("could not get change set")
+        .clone();
+    let update_result = change_set
+        .update_pointer(
+            &historical_ctx,
+            latest.workspace_snapshot_address,
+            ChangeSetPointerOperation::Commit,
+        )
+        .await;
+    assert!(matches!(
+        update_result,
+        Err(ChangeSetError::Transactions(TransactionsError::ReadOnly(_)))
+    ));
+
+    // The current snapshot still sees the component.
+    let component_ids: Vec<_> = Component::list(ctx)
+        .await
+        .expect("could not list components")
+        .iter()
+        .map(|c| c.id())
+        .collect();
+    assert!(component_ids.contains(&component.id()));
+}
+
+#[test]
+async fn diff_snapshots(ctx: &mut DalContext) {
+    let before = WorkspaceSnapshot::find_for_change_set(ctx, ctx.change_set_id())
+        .await
+        .expect("could not find snapshot");
+
+    let component = create_component_for_schema_name(ctx, "starfield", "new kid").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    let after = WorkspaceSnapshot::find_for_change_set(ctx, ctx.change_set_id())
+        .await
+        .expect("could not find snapshot");
+
+    let changes = before
+        .diff(ctx, &after)
+        .await
+        .expect("could not diff snapshots");
+    assert!(changes.contains(&SnapshotChange::ComponentAdded {
+        component_id: component.id(),
+        name: "new kid".to_string(),
+    }));
+
+    // Going the other way removes it, and nothing changes when comparing a snapshot to itself.
+    let changes = after
+        .diff(ctx, &before)
+        .await
+        .expect("could not diff snapshots");
+    assert!(changes.contains(&SnapshotChange::ComponentRemoved {
+        component_id: component.id(),
+        name: "new kid".to_string(),
+    }));
+    assert!(after
+        .diff(ctx, &after)
+        .await
+        .expect("could not diff snapshots")
+        .is_empty());
+}
+
+#[test]
+async fn collect_garbage_dry_run_keeps_everything(ctx: &mut DalContext) {
+    let component = create_component_for_schema_name(ctx, "starfield", "new kid").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    let report = WorkspaceSnapshot::collect_garbage(
+        ctx,
+        GarbageCollectionOptions {
+            dry_run: true,
+            grace_period: chrono::Duration::zero(),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("could not collect garbage");
+    assert!(report.dry_run);
+    assert!(report.live_snapshots > 0);
+    assert!(report.live_content_hashes > 0);
+    assert_eq!(0, report.snapshots.removed);
+    assert_eq!(0, report.snapshot_deltas.removed);
+    assert_eq!(0, report.cas.removed);
+
+    // The change set's snapshot, and its contents, are still there.
+    let snapshot = WorkspaceSnapshot::find_for_change_set(ctx, ctx.change_set_id())
+        .await
+        .expect("could not find snapshot");
+    ctx.set_workspace_snapshot(snapshot);
+    assert_eq!(
+        "new kid",
+        Component::get_by_id(ctx, component.id())
+            .await
+            .expect("could not get component")
+            .name(ctx)
+            .await
+            .expect("could not get name")
+    );
+}
+
+#[test]
+async fn collect_garbage_removes_only_what_is_unreachable(ctx: &mut DalContext) {
+    let kept_change_set_id = ctx.change_set_id();
+    let kept = create_component_for_schema_name(ctx, "starfield", "kept").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    // Change something in another change set, and then abandon it.
+    let mut abandoned_change_set = ChangeSet::fork_head(ctx, "abandoned")
+        .await
+        .expect("could not fork head");
+    ctx.update_visibility_and_snapshot_to_visibility(abandoned_change_set.id)
+        .await
+        .expect("could not update visibility");
+    let abandoned = create_component_for_schema_name(ctx, "starfield", "abandoned").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    let abandoned_snapshot_address = ChangeSet::find(ctx, abandoned_change_set.id)
+        .await
+        .expect("could not find change set")
+        .expect("change set not found")
+        .workspace_snapshot_address
+        .expect("change set has no snapshot");
+    let abandoned_content_hash = WorkspaceSnapshot::find(ctx, abandoned_snapshot_address)
+        .await
+        .expect("could not find snapshot")
+        .get_node_weight_by_id(abandoned.id())
+        .await
+        .expect("could not get node weight")
+        .content_hash();
+
+    ctx.update_visibility_and_snapshot_to_visibility_no_editing_change_set(abandoned_change_set.id)
+        .await
+        .expect("could not update visibility");
+    abandoned_change_set
+        .update_status(ctx, ChangeSetStatus::Abandoned)
+        .await
+        .expect("could not abandon change set");
+    ctx.update_visibility_and_snapshot_to_visibility(kept_change_set_id)
+        .await
+        .expect("could not update visibility");
+    ctx.commit_no_rebase()
+        .await
+        .expect("could not perform commit");
+
+    let report = WorkspaceSnapshot::collect_garbage(
+        ctx,
+        GarbageCollectionOptions {
+            grace_period: chrono::Duration::zero(),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("could not collect garbage");
+    assert!(!report.dry_run);
+    assert!(report.snapshots.removed + report.snapshot_deltas.removed > 0);
+    assert!(report.cas.removed > 0);
+
+    // What only the abandoned change set could reach is gone...
+    assert!(ctx
+        .layer_db()
+        .workspace_snapshot()
+        .read(&abandoned_snapshot_address)
+        .await
+        .expect("could not read snapshot")
+        .is_none());
+    assert!(ctx
+        .layer_db()
+        .cas()
+        .read(&abandoned_content_hash)
+        .await
+        .expect("could not read content")
+        .is_none());
+
+    // ...but the change set in use, and its recent history, are intact.
+    for entry in ChangeSetPointerHistoryEntry::list(ctx, kept_change_set_id)
+        .await
+        .expect("could not list pointer history")
+    {
+        assert!(ctx
+            .layer_db()
+            .workspace_snapshot()
+            .read(&entry.workspace_snapshot_address)
+            .await
+            .expect("could not read snapshot")
+            .is_some());
+    }
+    let snapshot = WorkspaceSnapshot::find_for_change_set(ctx, kept_change_set_id)
+        .await
+        .expect("could not find snapshot");
+    ctx.set_workspace_snapshot(snapshot);
+    assert_eq!(
+        "kept",
+        Component::get_by_id(ctx, kept.id())
+            .await
+            .expect("could not get component")
+            .name(ctx)
+            .await
+            .expect("could not get name")
+    );
+}
+
+#[test]
+async fn collect_garbage_keeps_content_of_snapshots_not_yet_pointed_to(ctx: &mut DalContext) {
+    let kept_change_set_id = ctx.change_set_id();
+
+    // Write some content in another change set, and then abandon it.
+    let mut abandoned_change_set = ChangeSet::fork_head(ctx, "abandoned")
+        .await
+        .expect("could not fork head");
+    ctx.update_visibility_and_snapshot_to_visibility(abandoned_change_set.id)
+        .await
+        .expect("could not update visibility");
+    let reused = create_component_for_schema_name(ctx, "starfield", "reused").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    let abandoned_snapshot_address = ChangeSet::find(ctx, abandoned_change_set.id)
+        .await
+        .expect("could not find change set")
+        .expect("change set not found")
+        .workspace_snapshot_address
+        .expect("change set has no snapshot");
+    let abandoned_snapshot = WorkspaceSnapshot::find(ctx, abandoned_snapshot_address)
+        .await
+        .expect("could not find snapshot");
+    let reused_content_hash = abandoned_snapshot
+        .get_node_weight_by_id(reused.id())
+        .await
+        .expect("could not get node weight")
+        .content_hash();
+
+    ctx.update_visibility_and_snapshot_to_visibility_no_editing_change_set(abandoned_change_set.id)
+        .await
+        .expect("could not update visibility");
+    abandoned_change_set
+        .update_status(ctx, ChangeSetStatus::Abandoned)
+        .await
+        .expect("could not abandon change set");
+    ctx.update_visibility_and_snapshot_to_visibility(kept_change_set_id)
+        .await
+        .expect("could not update visibility");
+    ctx.commit_no_rebase()
+        .await
+        .expect("could not perform commit");
+
+    // Once the content is older than the grace period, write a new snapshot that still refers to
+    // it, as a commit does before it moves a change set pointer.
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let kept_change_set = ChangeSet::find(ctx, kept_change_set_id)
+        .await
+        .expect("could not find change set")
+        .expect("change set not found");
+    let unpointed_snapshot_address = abandoned_snapshot
+        .write(ctx, kept_change_set.vector_clock_id())
+        .await
+        .expect("could not write snapshot");
+    assert_ne!(abandoned_snapshot_address, unpointed_snapshot_address);
+
+    WorkspaceSnapshot::collect_garbage(
+        ctx,
+        GarbageCollectionOptions {
+            grace_period: chrono::Duration::seconds(1),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("could not collect garbage");
+
+    assert!(ctx
+        .layer_db()
+        .workspace_snapshot()
+        .read(&unpointed_snapshot_address)
+        .await
+        .expect("could not read snapshot")
+        .is_some());
+    assert!(ctx
+        .layer_db()
+        .cas()
+        .read(&reused_content_hash)
+        .await
+        .expect("could not read content")
+        .is_some());
+}
+
+#[test]
+async fn diff_change_set_against_fork_point(ctx: &mut DalContext) {
+    // Fork a change set from head before anything else is applied to it.
+    let forked_change_set = ChangeSet::fork_head(ctx, "forked")
+        .await
+        .expect("could not fork head");
+    ctx.commit_no_rebase()
+        .await
+        .expect("could not perform commit");
+
+    // Apply a component to head after forking.
+    create_component_for_schema_name(ctx, "starfield", "applied to head").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+    ChangeSet::apply_to_base_change_set(ctx, true)
+        .await
+        .expect("could not apply to base");
+
+    // Make a change of its own in the forked change set.
+    ctx.update_visibility_and_snapshot_to_visibility(forked_change_set.id)
+        .await
+        .expect("could not update visibility");
+    let forked = create_component_for_schema_name(ctx, "starfield", "made in the fork").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    let forked_change_set = ChangeSet::find(ctx, forked_change_set.id)
+        .await
+        .expect("could not find change set")
+        .expect("change set not found");
+    let changes = WorkspaceSnapshot::diff_change_set(ctx, &forked_change_set)
+        .await
+        .expect("could not diff change set");
+
+    // Only what the forked change set did is described: what was applied to head since forking is
+    // not mistaken for the forked change set removing it.
+    assert_eq!(
+        vec![SnapshotChange::ComponentAdded {
+            component_id: forked.id(),
+            name: "made in the fork".to_string(),
+        }], // expected
+        changes
+            .into_iter()
+            .filter(|change| matches!(
+                change,
+                SnapshotChange::ComponentAdded { .. } | SnapshotChange::ComponentRemoved { .. }
+            ))
+            .collect::<Vec<_>>() // actual
+    );
+}
@@ -3,10 +3,13 @@ use dal::component::{DEFAULT_COMPONENT_HEIGHT, DEFAULT_COMPONENT_WIDTH};
 use dal::diagram::Diagram;
 use dal::prop::{Prop, PropPath};
 use dal::property_editor::values::PropertyEditorValues;
+use dal::workspace_snapshot::edge_weight::{EdgeWeight, EdgeWeightKind};
+use dal::workspace_snapshot::graph::ValidationIssue;
+use dal::workspace_snapshot::WorkspaceSnapshotError;
 use dal::{AttributeValue, AttributeValueId, InputSocket, OutputSocket};
 use dal::{Component, DalContext, Schema, SchemaVariant};
 use dal_test::test;
-use dal_test::test_harness::create_component_for_schema_name;
+use dal_test::test_harness::{assert_snapshot_is_valid, create_component_for_schema_name};
 
 mod debug;
 mod get_code;
@@ -906,3 +909,62 @@ async fn undoing_deletion_updates_inputs(ctx: &mut DalContext) {
     assert!(!units_json_string.contains("docker.io/library/oysters in my pocket\\n"));
     assert!(units_json_string.contains("docker.io/library/were saving for lunch\\n"));
 }
+
+#[test]
+async fn created_components_leave_a_valid_snapshot(ctx: &mut DalContext) {
+    create_component_for_schema_name(ctx, "starfield", "valid starfield").await;
+    create_component_for_schema_name(ctx, "fallout", "valid fallout").await;
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+
+    assert_snapshot_is_valid(ctx).await;
+}
+
+#[test]
+async fn invalid_snapshots_are_rejected_when_validating(ctx: &mut DalContext) {
+    let mut builder = ctx.to_builder();
+    builder.set_validate_snapshots();
+    let validating_ctx = builder
+        .build(ctx.access_builder().build(*ctx.visibility()))
+        .await
+        .expect("could not build context");
+
+    let component =
+        create_component_for_schema_name(&validating_ctx, "starfield", "two roots").await;
+    let other = create_component_for_schema_name(&validating_ctx, "starfield", "lent root").await;
+    let other_root_id = Component::root_attribute_value_id(&validating_ctx, other.id())
+        .await
+        .expect("could not get root attribute value id");
+
+    // Give the first component a second root.
+    let change_set = validating_ctx
+        .change_set()
+        .expect("could not get change set");
+    let snapshot = validating_ctx
+        .workspace_snapshot()
+        .expect("could not get workspace snapshot");
+    snapshot
+        .add_edge(
+            component.id(),
+            EdgeWeight::new(change_set, EdgeWeightKind::Root).expect("could not create edge"),
+            other_root_id,
+        )
+        .await
+        .expect("could not add edge");
+
+    match snapshot
+        .write(&validating_ctx, change_set.vector_clock_id())
+        .await
+    {
+        Err(WorkspaceSnapshotError::InvalidGraph(report)) => {
+            assert!(report
+                .issues
+                .contains(&ValidationIssue::ComponentRootCount {
+                    component_id: component.id().into(),
+                    count: 2,
+                }));
+        }
+        other => panic!("expected the snapshot to be rejected as invalid, got: {other:?}"),
+    }
+}
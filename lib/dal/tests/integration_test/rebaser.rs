@@ -1,8 +1,16 @@
 use base64::{engine::general_purpose, Engine};
+use dal::change_set::pointer_history::ChangeSetPointerOperation;
+use dal::change_set::ChangeSet;
 use dal::func::argument::{FuncArgument, FuncArgumentKind};
-use dal::{DalContext, Func, FuncBackendKind, FuncBackendResponseType};
-use dal_test::test;
+use dal::{DalContext, Func, FuncBackendKind, FuncBackendResponseType, Schema, WorkspaceSnapshot};
+use dal_test::{test, TestContext};
 use pretty_assertions_sorted::assert_eq;
+use si_layer_cache::db::workspace_snapshot;
+use si_layer_cache::durable::DurableBackendConfig;
+use si_layer_cache::object_store::{LocalDirectoryObjectStore, ObjectStore};
+use si_layer_cache::LayerDbConfig;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
 #[test]
 async fn modify_func_node(ctx: &mut DalContext) {
@@ -480,3 +488,96 @@ async fn delete_func_node(ctx: &mut DalContext) {
 //         .close_stream_for_change_set(base_change_set.id.into())
 //         .await;
 // }
+
+// This sets up its own services rather than using `#[test]`, since the rebaser that `#[test]` runs
+// alongside each test uses the default layer db configuration and would compete for the request.
+#[tokio::test(flavor = "multi_thread")]
+async fn rebase_with_local_directory_snapshot_backend() {
+    let test_context =
+        TestContext::global(crate::TEST_PG_DBNAME, crate::SI_TEST_LAYER_CACHE_PG_DBNAME)
+            .await
+            .expect("could not build test context");
+    let snapshot_directory = tempfile::TempDir::new().expect("could not create tempdir");
+    let layer_db_config = LayerDbConfig {
+        workspace_snapshot_durable_backend: DurableBackendConfig::LocalDirectory {
+            path: snapshot_directory.path().to_path_buf(),
+        },
+        ..Default::default()
+    };
+    let token = CancellationToken::new();
+    let services_ctx = test_context
+        .create_services_context_with_layer_db_config(
+            token.clone(),
+            TaskTracker::new(),
+            layer_db_config,
+        )
+        .await;
+    let rebaser = dal_test::rebaser_server(&services_ctx).expect("could not build rebaser");
+    tokio::spawn(rebaser.run());
+
+    let mut ctx = services_ctx
+        .into_builder(false)
+        .build_default()
+        .await
+        .expect("could not build dal context");
+    let mut change_set = ChangeSet::new(&ctx, "object store", None)
+        .await
+        .expect("could not create change set");
+    let initial_snapshot = WorkspaceSnapshot::initial(&ctx, &change_set)
+        .await
+        .expect("could not create initial snapshot");
+    let initial_snapshot_address = initial_snapshot.id().await;
+    change_set
+        .update_pointer(
+            &ctx,
+            initial_snapshot_address,
+            ChangeSetPointerOperation::Create,
+        )
+        .await
+        .expect("could not update pointer");
+    ctx.update_visibility_and_snapshot_to_visibility(change_set.id)
+        .await
+        .expect("could not update visibility");
+    ctx.commit_no_rebase()
+        .await
+        .expect("could not perform commit");
+
+    // The snapshot was stored in the directory rather than in Postgres...
+    let object_store = LocalDirectoryObjectStore::new(snapshot_directory.path());
+    assert!(object_store
+        .get_object(&format!(
+            "{}/{initial_snapshot_address}",
+            workspace_snapshot::CACHE_NAME
+        ))
+        .await
+        .expect("could not get object")
+        .is_some());
+
+    // ...and the rebaser can build on it.
+    let schema = Schema::new(&ctx, "object store")
+        .await
+        .expect("could not create schema");
+    ctx.blocking_commit()
+        .await
+        .expect("could not perform commit");
+    ctx.update_snapshot_to_visibility()
+        .await
+        .expect("could not update snapshot to visibility");
+
+    let rebased_snapshot_address = ChangeSet::find(&ctx, change_set.id)
+        .await
+        .expect("could not find change set")
+        .expect("change set not found")
+        .workspace_snapshot_address
+        .expect("change set has no snapshot");
+    assert_ne!(initial_snapshot_address, rebased_snapshot_address);
+    assert_eq!(
+        "object store",
+        Schema::get_by_id(&ctx, schema.id())
+            .await
+            .expect("could not get schema")
+            .name()
+    );
+
+    token.cancel();
+}
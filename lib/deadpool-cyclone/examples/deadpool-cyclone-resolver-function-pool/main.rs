@@ -153,6 +153,7 @@ fn spec() -> Result<LocalUdsInstanceSpec, Box<(dyn std::error::Error + 'static)>
             .cyclone_decryption_key_path(cyclone_decryption_key_path)
             .try_lang_server_cmd_path(lang_server_cmd_path)?
             .ping()
+            .resolver()
             .build()
             .map_err(Into::into)
     } else if let Ok(dir) = env::var("CARGO_MANIFEST_DIR") {
@@ -182,6 +183,7 @@ fn spec() -> Result<LocalUdsInstanceSpec, Box<(dyn std::error::Error + 'static)>
             .cyclone_decryption_key_path(cyclone_decryption_key_path)
             .try_lang_server_cmd_path(lang_server_cmd_path)?
             .ping()
+            .resolver()
             .build()
             .map_err(Into::into)
     } else {
@@ -8,6 +8,8 @@ pub use local_uds::{
     LocalUdsInstance, LocalUdsInstanceError, LocalUdsInstanceSpec, LocalUdsInstanceSpecBuilder,
     LocalUdsRuntimeStrategy, LocalUdsSocketStrategy,
 };
+pub use resource_limits::ResourceLimits;
 
 mod local_http;
 mod local_uds;
+mod resource_limits;
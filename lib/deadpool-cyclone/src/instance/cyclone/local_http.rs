@@ -11,10 +11,10 @@ use cyclone_client::{
 };
 use cyclone_core::{
     process::{self, ShutdownError},
-    ActionRunRequest, ActionRunResultSuccess, CanonicalCommand, ReconciliationRequest,
-    ReconciliationResultSuccess, ResolverFunctionRequest, ResolverFunctionResultSuccess,
-    SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess, ValidationRequest,
-    ValidationResultSuccess,
+    ActionRunRequest, ActionRunResultSuccess, CanonicalCommand, ExecutionEnvelope, FunctionRequest,
+    ReconciliationRequest, ReconciliationResultSuccess, ResolverFunctionRequest,
+    ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
+    SchemaVariantDefinitionResultSuccess, ValidationRequest, ValidationResultSuccess,
 };
 use derive_builder::Builder;
 use futures::StreamExt;
@@ -219,6 +219,27 @@ impl CycloneClient<TcpStream> for LocalHttpInstance {
 
         result
     }
+
+    async fn execute_function<Request>(
+        &mut self,
+        request: Request,
+    ) -> result::Result<
+        Execution<TcpStream, ExecutionEnvelope<Request>, Request::Success>,
+        ClientError,
+    >
+    where
+        Request: FunctionRequest + Send + 'static,
+        Request::Success: Send,
+    {
+        self.ensure_healthy_client()
+            .await
+            .map_err(ClientError::unhealthy)?;
+
+        let result = self.client.execute_function(request).await;
+        self.count_request();
+
+        result
+    }
 }
 
 impl LocalHttpInstance {
@@ -367,11 +388,16 @@ impl LocalHttpInstanceSpec {
         if self.ping {
             cmd.arg("--enable-ping");
         }
+        // Cyclone enables these endpoints unless told otherwise
         if self.resolver {
             cmd.arg("--enable-resolver");
+        } else {
+            cmd.arg("--disable-resolver");
         }
         if self.action {
             cmd.arg("--enable-action-run");
+        } else {
+            cmd.arg("--disable-action-run");
         }
 
         cmd
@@ -22,10 +22,10 @@ use cyclone_client::{
 };
 use cyclone_core::{
     process::{self, ShutdownError},
-    ActionRunRequest, ActionRunResultSuccess, CanonicalCommand, ReconciliationRequest,
-    ReconciliationResultSuccess, ResolverFunctionRequest, ResolverFunctionResultSuccess,
-    SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess, ValidationRequest,
-    ValidationResultSuccess,
+    ActionRunRequest, ActionRunResultSuccess, CanonicalCommand, ExecutionEnvelope, FunctionRequest,
+    ReconciliationRequest, ReconciliationResultSuccess, ResolverFunctionRequest,
+    ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
+    SchemaVariantDefinitionResultSuccess, ValidationRequest, ValidationResultSuccess,
 };
 use derive_builder::Builder;
 use futures::StreamExt;
@@ -40,6 +40,7 @@ use tokio::{
 };
 use tracing::{trace, warn};
 
+use super::resource_limits::{Cgroup, CgroupJoin, ResourceLimits};
 use crate::instance::{Instance, Spec, SpecBuilder};
 use crate::PoolNoodle;
 
@@ -50,6 +51,9 @@ pub enum LocalUdsInstanceError {
     /// Spec builder error.
     #[error(transparent)]
     Builder(#[from] LocalUdsInstanceSpecBuilderError),
+    /// Failed to set up a cgroup for the child process.
+    #[error("failed to set up a cgroup for the cyclone child process")]
+    CgroupSetup(#[source] io::Error),
     /// Error when waiting for child process to shutdown.
     #[error(transparent)]
     ChildShutdown(#[from] ShutdownError),
@@ -83,6 +87,9 @@ pub enum LocalUdsInstanceError {
     /// Failed to write to firecracker-setup file.
     #[error("failed to write to firecracker-setup file")]
     FirecrackerSetupWrite(#[source] io::Error),
+    /// A memory limit was set without a cgroup to enforce it.
+    #[error("a memory limit needs a cgroup parent to be enforced")]
+    MemoryLimitWithoutCgroup,
     /// Instance has exhausted its predefined request count.
     #[error("no remaining requests, cyclone server is considered unhealthy")]
     NoRemainingRequests,
@@ -255,6 +262,27 @@ impl CycloneClient<UnixStream> for LocalUdsInstance {
 
         result
     }
+
+    async fn execute_function<Request>(
+        &mut self,
+        request: Request,
+    ) -> result::Result<
+        Execution<UnixStream, ExecutionEnvelope<Request>, Request::Success>,
+        ClientError,
+    >
+    where
+        Request: FunctionRequest + Send + 'static,
+        Request::Success: Send,
+    {
+        self.ensure_healthy_client()
+            .await
+            .map_err(ClientError::unhealthy)?;
+
+        let result = self.client.execute_function(request).await;
+        self.count_request();
+
+        result
+    }
 }
 
 impl LocalUdsInstance {
@@ -341,6 +369,23 @@ pub struct LocalUdsInstanceSpec {
     /// pool noodle
     #[builder(default)]
     pool_noodle: PoolNoodle,
+
+    /// Resource limits for a spawned Cyclone server, only enforced by the `LocalProcess` runtime.
+    #[builder(setter(into), default)]
+    resource_limits: ResourceLimits,
+}
+
+impl LocalUdsInstanceSpec {
+    /// Returns whether a spawned Cyclone server executes functions of the given kind.
+    pub fn executes(&self, kind: &str) -> bool {
+        if kind == ResolverFunctionRequest::KIND {
+            self.resolver
+        } else if kind == ActionRunRequest::KIND {
+            self.action
+        } else {
+            true
+        }
+    }
 }
 
 #[async_trait]
@@ -351,7 +396,14 @@ impl Spec for LocalUdsInstanceSpec {
     async fn setup(&mut self) -> result::Result<(), Self::Error> {
         match self.runtime_strategy {
             LocalUdsRuntimeStrategy::LocalDocker => Ok(()),
-            LocalUdsRuntimeStrategy::LocalProcess => Ok(()),
+            LocalUdsRuntimeStrategy::LocalProcess => {
+                // Better to refuse to start than to run functions without the limits they were
+                // configured with
+                if !self.resource_limits.are_enforceable() {
+                    return Err(LocalUdsInstanceError::MemoryLimitWithoutCgroup);
+                }
+                Ok(())
+            }
             LocalUdsRuntimeStrategy::LocalFirecracker => setup_firecracker(self).await,
         }
     }
@@ -584,6 +636,10 @@ pub trait LocalInstanceRuntime: Send + Sync {
 struct LocalProcessRuntime {
     cmd: Command,
     child: Option<Child>,
+    // Kept around as an RAII guard, so the cgroup is removed once the child has been shut down
+    cgroup: Option<Cgroup>,
+    cgroup_join: Option<CgroupJoin>,
+    resource_limits: ResourceLimits,
     socket: PathBuf,
 }
 
@@ -610,16 +666,41 @@ impl LocalProcessRuntime {
         if spec.ping {
             cmd.arg("--enable-ping");
         }
+        // Cyclone enables these endpoints unless told otherwise
         if spec.resolver {
             cmd.arg("--enable-resolver");
+        } else {
+            cmd.arg("--disable-resolver");
         }
         if spec.action {
             cmd.arg("--enable-action-run");
+        } else {
+            cmd.arg("--disable-action-run");
+        }
+        if let Some(output_bytes) = spec.resource_limits.output_bytes {
+            cmd.arg("--limit-output-bytes")
+                .arg(output_bytes.to_string());
+        }
+        if let Some(cpu_time_secs) = spec.resource_limits.cpu_time_secs {
+            cmd.arg("--limit-cpu-time-secs")
+                .arg(cpu_time_secs.to_string());
         }
+        if let Some(open_files) = spec.resource_limits.open_files {
+            cmd.arg("--limit-open-files").arg(open_files.to_string());
+        }
+        if let Some(memory_bytes) = spec.resource_limits.memory_bytes {
+            cmd.arg("--limit-memory-bytes")
+                .arg(memory_bytes.to_string());
+        }
+        spec.resource_limits.apply_rlimits(&mut cmd);
+        let cgroup_join = spec.resource_limits.join_cgroup_on_spawn(&mut cmd);
 
         Ok(Box::new(LocalProcessRuntime {
             cmd,
             child: None,
+            cgroup: None,
+            cgroup_join,
+            resource_limits: spec.resource_limits,
             socket: socket.to_path_buf(),
         }))
     }
@@ -641,11 +722,18 @@ impl LocalInstanceRuntime for LocalProcessRuntime {
                 .duration_since(UNIX_EPOCH)
                 .expect("time has gone backwards")
         );
-        self.child = Some(
-            self.cmd
-                .spawn()
-                .map_err(LocalUdsInstanceError::ChildSpawn)?,
-        );
+        let cgroup = self
+            .resource_limits
+            .create_cgroup()
+            .map_err(LocalUdsInstanceError::CgroupSetup)?;
+        // The child fails to spawn rather than run outside of its cgroup
+        let child = match (self.cgroup_join.as_ref(), cgroup.as_ref()) {
+            (Some(cgroup_join), Some(cgroup)) => cgroup_join.spawn(&mut self.cmd, cgroup),
+            _ => self.cmd.spawn(),
+        }
+        .map_err(LocalUdsInstanceError::ChildSpawn)?;
+        self.child = Some(child);
+        self.cgroup = cgroup;
         trace!(
             "cyclone-execution: spawn finished {:?}",
             SystemTime::now()
@@ -670,6 +758,7 @@ impl LocalInstanceRuntime for LocalProcessRuntime {
                         .expect("time has gone backwards")
                 );
                 process::child_shutdown(c, Some(process::Signal::SIGTERM), None).await?;
+                drop(self.cgroup.take());
                 Ok(())
             }
             None => Ok(()),
@@ -709,11 +798,16 @@ impl LocalDockerRuntime {
         if spec.ping {
             cmd.push(String::from("--enable-ping"));
         }
+        // Cyclone enables these endpoints unless told otherwise
         if spec.resolver {
             cmd.push(String::from("--enable-resolver"));
+        } else {
+            cmd.push(String::from("--disable-resolver"));
         }
         if spec.action {
             cmd.push(String::from("--enable-action-run"));
+        } else {
+            cmd.push(String::from("--disable-action-run"));
         }
 
         let docker = Docker::connect_with_local_defaults()?;
@@ -0,0 +1,355 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+};
+
+use nix::{
+    sys::resource::{setrlimit, Resource},
+    unistd,
+};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use tokio::process::{Child, Command};
+use tracing::warn;
+
+/// Limits on the resources a spawned Cyclone process, and so every function it executes, may use.
+///
+/// Memory is enforced with a cgroup (v2), so a memory limit needs `cgroup_parent` to be set. No
+/// rlimit can stand in for it, as V8 reserves far more address space than it ever uses. Cpu time
+/// is enforced with `RLIMIT_CPU` and open files with `RLIMIT_NOFILE`. The output limit is handed to
+/// Cyclone, which is the only party that sees a function's output. Any unset limit is left to
+/// whatever the host allows.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceLimits {
+    /// Maximum memory in bytes, which needs `cgroup_parent` to be set.
+    #[serde(default)]
+    pub memory_bytes: Option<u64>,
+    /// Maximum cpu time in seconds of any one process.
+    #[serde(default)]
+    pub cpu_time_secs: Option<u64>,
+    /// Maximum bytes of output a single function execution may produce.
+    #[serde(default)]
+    pub output_bytes: Option<u64>,
+    /// Maximum number of open files of any one process.
+    #[serde(default)]
+    pub open_files: Option<u64>,
+    /// A cgroup (v2) directory, delegated to the current user, under which a cgroup is created for
+    /// each spawned process.
+    #[serde(default)]
+    pub cgroup_parent: Option<PathBuf>,
+}
+
+impl ResourceLimits {
+    /// Applies the limits enforced through rlimits to the given command.
+    ///
+    /// The rlimits are set in the child between `fork` and `exec`, so they are inherited by every
+    /// process the child goes on to spawn.
+    pub(crate) fn apply_rlimits(&self, cmd: &mut Command) {
+        let cpu_time_secs = self.cpu_time_secs;
+        let open_files = self.open_files;
+
+        if cpu_time_secs.is_none() && open_files.is_none() {
+            return;
+        }
+
+        // SAFETY: the closure only makes `setrlimit` calls, which neither allocate nor take locks
+        // and so are safe to call in the forked child
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(secs) = cpu_time_secs {
+                    // The process is sent SIGXCPU at the soft limit and SIGKILL at the hard limit
+                    setrlimit(Resource::RLIMIT_CPU, secs, secs.saturating_add(1))?;
+                }
+                if let Some(open_files) = open_files {
+                    setrlimit(Resource::RLIMIT_NOFILE, open_files, open_files)?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Returns whether every limit can be enforced, which isn't so for a memory limit without a
+    /// cgroup.
+    pub(crate) fn are_enforceable(&self) -> bool {
+        self.memory_bytes.is_none() || self.cgroup_parent.is_some()
+    }
+
+    /// Makes each process spawned from the given command join a cgroup, if the limits call for
+    /// one, returning the [`CgroupJoin`] to spawn it with.
+    ///
+    /// The child joins the cgroup itself between `fork` and `exec`, so it never runs outside of it,
+    /// and every process it goes on to spawn starts out in the cgroup too.
+    pub(crate) fn join_cgroup_on_spawn(&self, cmd: &mut Command) -> Option<CgroupJoin> {
+        self.cgroup_parent.as_ref()?;
+
+        let procs_fd = Arc::new(AtomicI32::new(NO_CGROUP));
+        let child_procs_fd = procs_fd.clone();
+        // SAFETY: the closure only loads an atomic and makes a `write` call, neither of which
+        // allocate nor take locks and so are safe to call in the forked child
+        unsafe {
+            cmd.pre_exec(move || {
+                // Writing 0 to `cgroup.procs` moves the writing process into the cgroup. Without
+                // a cgroup to join the write fails, and so does the spawn.
+                unistd::write(child_procs_fd.load(Ordering::SeqCst), b"0")?;
+                Ok(())
+            });
+        }
+
+        Some(CgroupJoin { procs_fd })
+    }
+
+    /// Creates a cgroup for a spawned process if the limits call for one.
+    pub(crate) fn create_cgroup(&self) -> io::Result<Option<Cgroup>> {
+        match &self.cgroup_parent {
+            Some(parent) => Cgroup::create(parent, self.memory_bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+// Not a valid file descriptor, so writing to it fails
+const NO_CGROUP: RawFd = -1;
+
+/// Spawns processes from a command prepared with [`ResourceLimits::join_cgroup_on_spawn`], each
+/// into the cgroup it is spawned with.
+#[derive(Debug)]
+pub(crate) struct CgroupJoin {
+    // The open `cgroup.procs` file the child writes to, shared with the command's `pre_exec`
+    procs_fd: Arc<AtomicI32>,
+}
+
+impl CgroupJoin {
+    /// Spawns a process from the command, which joins the given cgroup before it runs.
+    pub(crate) fn spawn(&self, cmd: &mut Command, cgroup: &Cgroup) -> io::Result<Child> {
+        self.procs_fd
+            .store(cgroup.procs.as_raw_fd(), Ordering::SeqCst);
+        let child = cmd.spawn();
+        self.procs_fd.store(NO_CGROUP, Ordering::SeqCst);
+        child
+    }
+}
+
+/// A cgroup (v2) holding a single spawned process.
+///
+/// The cgroup is removed when dropped, which only succeeds once every process in it has exited.
+#[derive(Debug)]
+pub(crate) struct Cgroup {
+    path: PathBuf,
+    // Opened ahead of the spawn, as the forked child can't open a file itself. The file is opened
+    // close-on-exec, so it isn't leaked to the child past its `exec`.
+    procs: File,
+}
+
+impl Cgroup {
+    fn create(parent: &Path, memory_bytes: Option<u64>) -> io::Result<Self> {
+        let name: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(10)
+            .map(char::from)
+            .collect();
+        let path = parent.join(format!("cyclone-{name}"));
+
+        fs::create_dir(&path)?;
+        let procs = match OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path.join("cgroup.procs"))
+        {
+            Ok(procs) => procs,
+            Err(err) => {
+                let _ = fs::remove_dir(&path);
+                return Err(err);
+            }
+        };
+        let cgroup = Self { path, procs };
+        if let Some(bytes) = memory_bytes {
+            fs::write(cgroup.path.join("memory.max"), bytes.to_string())?;
+        }
+
+        Ok(cgroup)
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir(&self.path) {
+            warn!(error = ?err, path = %self.path.display(), "failed to remove cgroup");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Stdio;
+
+    use super::*;
+
+    const SHOW_LIMITS: &str = "ulimit -S -t; ulimit -H -t; ulimit -n; ulimit -d; ulimit -v";
+
+    async fn shell_limits(limits: Option<&ResourceLimits>) -> String {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(SHOW_LIMITS).stdout(Stdio::piped());
+        if let Some(limits) = limits {
+            limits.apply_rlimits(&mut cmd);
+        }
+        let output = cmd.output().await.expect("failed to run shell");
+        assert!(output.status.success(), "shell failed: {output:?}");
+        String::from_utf8(output.stdout).expect("shell output is not utf8")
+    }
+
+    #[tokio::test]
+    async fn applies_rlimits() {
+        let limits = ResourceLimits {
+            cpu_time_secs: Some(5),
+            open_files: Some(16),
+            ..Default::default()
+        };
+
+        // The soft and hard cpu time limits and the open files limit, with the data segment and
+        // address space limits left to the host
+        let host = shell_limits(None).await;
+        let host_memory: Vec<_> = host.lines().skip(3).collect();
+        assert_eq!(
+            format!("5\n6\n16\n{}\n", host_memory.join("\n")),
+            shell_limits(Some(&limits)).await
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_unset_rlimits_to_the_host() {
+        assert_eq!(
+            shell_limits(None).await,
+            shell_limits(Some(&ResourceLimits::default())).await
+        );
+    }
+
+    #[tokio::test]
+    async fn never_limits_memory_with_an_rlimit() {
+        let limits = ResourceLimits {
+            memory_bytes: Some(64 * 1024 * 1024),
+            ..Default::default()
+        };
+
+        assert_eq!(shell_limits(None).await, shell_limits(Some(&limits)).await);
+    }
+
+    #[test]
+    fn needs_a_cgroup_to_limit_memory() {
+        let mut limits = ResourceLimits {
+            memory_bytes: Some(1024),
+            ..Default::default()
+        };
+        assert!(!limits.are_enforceable());
+
+        limits.cgroup_parent = Some(PathBuf::from("/sys/fs/cgroup/unused"));
+        assert!(limits.are_enforceable());
+        assert!(ResourceLimits::default().are_enforceable());
+    }
+
+    #[test]
+    fn creates_no_cgroup_without_a_parent() {
+        let limits = ResourceLimits {
+            memory_bytes: Some(1024),
+            ..Default::default()
+        };
+
+        assert!(limits
+            .create_cgroup()
+            .expect("failed to create cgroup")
+            .is_none());
+    }
+
+    #[test]
+    fn creates_and_removes_a_cgroup() {
+        let parent = tempfile::tempdir().expect("failed to create temp dir");
+        let limits = ResourceLimits {
+            memory_bytes: Some(1024),
+            cgroup_parent: Some(parent.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let cgroup = limits
+            .create_cgroup()
+            .expect("failed to create cgroup")
+            .expect("no cgroup was created");
+        let path = cgroup.path.clone();
+        assert_eq!(Some(parent.path()), path.parent());
+        assert_eq!(
+            "1024",
+            fs::read_to_string(path.join("memory.max")).expect("failed to read memory.max")
+        );
+
+        // A cgroup's interface files go away along with it, whereas those of a plain directory
+        // have to be removed first
+        fs::remove_file(path.join("memory.max")).expect("failed to remove memory.max");
+        fs::remove_file(path.join("cgroup.procs")).expect("failed to remove cgroup.procs");
+        drop(cgroup);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn spawned_process_joins_its_cgroup_before_it_runs() {
+        let parent = tempfile::tempdir().expect("failed to create temp dir");
+        let limits = ResourceLimits {
+            cgroup_parent: Some(parent.path().to_path_buf()),
+            ..Default::default()
+        };
+        let mut cmd = Command::new("true");
+        let join = limits
+            .join_cgroup_on_spawn(&mut cmd)
+            .expect("no cgroup join was set up");
+        let cgroup = limits
+            .create_cgroup()
+            .expect("failed to create cgroup")
+            .expect("no cgroup was created");
+
+        let status = join
+            .spawn(&mut cmd, &cgroup)
+            .expect("failed to spawn process")
+            .wait()
+            .await
+            .expect("failed to wait on process");
+        assert!(status.success());
+
+        // Written by the child itself, which is what moves it into the cgroup
+        assert_eq!(
+            "0",
+            fs::read_to_string(cgroup.path.join("cgroup.procs"))
+                .expect("failed to read cgroup.procs")
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_to_spawn_without_a_cgroup_to_join() {
+        let parent = tempfile::tempdir().expect("failed to create temp dir");
+        let limits = ResourceLimits {
+            cgroup_parent: Some(parent.path().to_path_buf()),
+            ..Default::default()
+        };
+        let mut cmd = Command::new("true");
+        limits
+            .join_cgroup_on_spawn(&mut cmd)
+            .expect("no cgroup join was set up");
+
+        assert!(cmd.spawn().is_err());
+    }
+
+    #[test]
+    fn creates_a_cgroup_per_process_without_a_memory_limit() {
+        let parent = tempfile::tempdir().expect("failed to create temp dir");
+
+        let first = Cgroup::create(parent.path(), None).expect("failed to create cgroup");
+        let second = Cgroup::create(parent.path(), None).expect("failed to create cgroup");
+
+        assert_ne!(first.path, second.path);
+        assert!(!first.path.join("memory.max").exists());
+        assert!(!second.path.join("memory.max").exists());
+    }
+}
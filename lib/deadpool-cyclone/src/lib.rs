@@ -27,11 +27,12 @@ pub use cyclone_client::{
     ClientError, CycloneClient, CycloneEncryptionKey, CycloneEncryptionKeyError, ExecutionError,
 };
 pub use cyclone_core::{
-    ActionRunRequest, ActionRunResultSuccess, ComponentView, FunctionResult, FunctionResultFailure,
-    FunctionResultFailureError, OutputStream, ProgressMessage, ReconciliationRequest,
-    ReconciliationResultSuccess, ResolverFunctionRequest, ResolverFunctionResultSuccess,
-    ResourceStatus, SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess,
-    ValidationRequest, ValidationResultSuccess,
+    ActionRunRequest, ActionRunResultSuccess, ComponentView, ExecutionEnvelope, FunctionRequest,
+    FunctionResult, FunctionResultFailure, FunctionResultFailureError, OutputStream,
+    ProgressMessage, ReconciliationRequest, ReconciliationResultSuccess, ResolverFunctionRequest,
+    ResolverFunctionResultSuccess, ResourceStatus, SchemaVariantDefinitionRequest,
+    SchemaVariantDefinitionResultSuccess, ValidationRequest, ValidationResultSuccess,
+    EXECUTION_ENVELOPE_VERSION,
 };
 
 /// [`Instance`] implementations.
@@ -2,7 +2,14 @@ use std::{convert::Infallible, env, error, str, time::Duration};
 
 use futures::StreamExt;
 use naxum::{
-    extract::State, handler::Handler, middleware::trace::TraceLayer, BoxError, ServiceExt,
+    extract::State,
+    handler::Handler,
+    middleware::{
+        concurrency_limit::ConcurrencyLimitLayer,
+        subject_concurrency_limit::SubjectConcurrencyLimitLayer, timeout::TimeoutLayer,
+        trace::TraceLayer,
+    },
+    ServiceExt,
 };
 use tokio::{
     signal::unix::{self, SignalKind},
@@ -10,7 +17,7 @@ use tokio::{
 };
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tower::ServiceBuilder;
-use tracing::{error, info};
+use tracing::info;
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
     layer::SubscriberExt as _,
@@ -37,14 +44,6 @@ async fn default(
     Ok(())
 }
 
-async fn handle_error(err: BoxError) {
-    if err.is::<tower::timeout::error::Elapsed>() {
-        error!(error = ?err, "message took too long to process");
-    } else {
-        error!(error = ?err, "unknown error");
-    }
-}
-
 #[allow(clippy::disallowed_methods)] // env vars are supporting alternatives in an example
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn error::Error>> {
@@ -75,13 +74,15 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
         // stream of `Option<Result<Message, Infallible>>`
         .map(Ok::<_, Infallible>);
 
-    // Setup a Tower `Service` stack with some middleware
+    // Setup a Tower `Service` stack with some middleware. No more messages are taken from the
+    // subscription while 500 are in flight, and messages that take too long are answered with a
+    // timeout error response.
     let app = ServiceBuilder::new()
-        .concurrency_limit(500)
+        .layer(ConcurrencyLimitLayer::new(500))
+        .layer(SubjectConcurrencyLimitLayer::new(50))
         .layer(TraceLayer::new())
-        .timeout(Duration::from_millis(100))
-        .service(default.with_state(AppState {}))
-        .handle_error(handle_error);
+        .layer(TimeoutLayer::new(Duration::from_millis(100)))
+        .service(default.with_state(AppState {}));
 
     // Use a Tokio `TaskTracker` and `CancellationToken` to support signal handling and graceful
     // shutdown
@@ -96,6 +97,7 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
         );
         naxum::serve(messages, app.into_make_service())
             .with_graceful_shutdown(naxum::wait_on_cancelled(naxum_token))
+            .with_drain_timeout(Duration::from_secs(10))
             .await
     });
 
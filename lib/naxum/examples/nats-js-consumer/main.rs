@@ -1,8 +1,12 @@
 use std::{env, error, str, time::Duration};
 
-use async_nats::jetstream;
+use async_nats::{jetstream, StatusCode};
 use naxum::{
-    extract::State, handler::Handler, middleware::trace::TraceLayer, BoxError, ServiceExt,
+    extract::State,
+    handler::Handler,
+    middleware::{ack::AckLayer, trace::TraceLayer},
+    response::{status, Response},
+    BoxError, ServiceExt,
 };
 use tokio::{
     signal::unix::{self, SignalKind},
@@ -32,19 +36,18 @@ async fn default(
 
     time::sleep(Duration::from_millis(10)).await;
     let payload = str::from_utf8(&msg.payload).expect("TODO");
-
-    msg.ack().await.expect("TODO");
-
     info!(payload, "finished message");
 
     Ok(())
 }
 
-async fn handle_error(err: BoxError) {
+async fn handle_error(err: BoxError) -> Response {
     if err.is::<tower::timeout::error::Elapsed>() {
         error!(error = ?err, "message took too long to process");
+        Response::error(StatusCode::TIMEOUT, "message took too long to process")
     } else {
         error!(error = ?err, "unknown error");
+        Response::error(status::internal_server_error(), err.to_string())
     }
 }
 
@@ -92,6 +95,9 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
         .timeout(Duration::from_millis(100))
         .service(default.with_state(AppState {}))
         .handle_error(handle_error);
+    // Ack each message once it has been processed, or nak it to be redelivered later if
+    // processing failed
+    let app = ServiceBuilder::new().layer(AckLayer::new()).service(app);
 
     // Use a Tokio `TaskTracker` and `CancellationToken` to support signal handling and graceful
     // shutdown
@@ -0,0 +1,132 @@
+use std::ops;
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    extract::{
+        rejection::{CborRejection, FailedToDeserializeCbor},
+        FromMessage,
+    },
+    response::{status, IntoResponse, Response},
+    MessageHead,
+};
+
+const CONTENT_TYPE: &str = "Content-Type";
+const APPLICATION_CBOR: &str = "application/cbor";
+
+/// CBOR extractor and response.
+///
+/// As an extractor, deserializes the message payload as CBOR. As a response, serializes the
+/// value as the body of the reply.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor<T>(pub T);
+
+#[async_trait]
+impl<T, S, R> FromMessage<S, R> for Cbor<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    R: MessageHead + Send + 'static,
+{
+    type Rejection = CborRejection;
+
+    async fn from_message(req: R, _state: &S) -> Result<Self, Self::Rejection> {
+        let (_head, payload) = req.into_parts();
+        let value = si_cbor::decode(&payload).map_err(FailedToDeserializeCbor::from_err)?;
+
+        Ok(Self(value))
+    }
+}
+
+impl<T> IntoResponse for Cbor<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        match si_cbor::encode(&self.0) {
+            Ok(body) => {
+                let mut response = Response::new(body);
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, APPLICATION_CBOR);
+                response
+            }
+            Err(err) => Response::error(
+                status::internal_server_error(),
+                format!("failed to serialize response as cbor: {err}"),
+            ),
+        }
+    }
+}
+
+impl<T> ops::Deref for Cbor<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Cbor<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Cbor<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+#[tokio::test]
+async fn test_cbor_extracts_the_payload() {
+    use crate::message::test_message;
+
+    let payload = si_cbor::encode(&vec![1u32, 2]).expect("encodes");
+    let Cbor(value) = Cbor::<Vec<u32>>::from_message(test_message("veritech", None, payload), &())
+        .await
+        .expect("extracts");
+
+    assert_eq!(vec![1, 2], value);
+}
+
+#[tokio::test]
+async fn test_cbor_rejects_payloads_that_do_not_deserialize() {
+    use crate::message::test_message;
+
+    let not_a_list = si_cbor::encode(&"not a list").expect("encodes");
+    for payload in [Vec::new(), vec![0xff], not_a_list] {
+        let rejection =
+            Cbor::<Vec<u32>>::from_message(test_message("veritech", None, payload), &())
+                .await
+                .expect_err("rejects");
+
+        assert!(matches!(
+            rejection,
+            CborRejection::FailedToDeserializeCbor(_)
+        ));
+        assert_eq!(status::bad_request(), rejection.into_response().status());
+    }
+}
+
+#[test]
+fn test_cbor_responds_with_the_serialized_value() {
+    let response = Cbor(vec![1u32, 2]).into_response();
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        Some(APPLICATION_CBOR),
+        response
+            .headers()
+            .get(CONTENT_TYPE)
+            .map(|value| value.as_str())
+    );
+    assert_eq!(
+        vec![1u32, 2],
+        si_cbor::decode::<Vec<u32>>(response.body()).expect("decodes")
+    );
+}
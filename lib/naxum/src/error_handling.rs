@@ -104,7 +104,7 @@ where
 impl<S, R, F, Fut, Res> Service<R> for HandleError<S, F, ()>
 where
     S: Service<R> + Clone + Send + 'static,
-    S::Response: Send,
+    S::Response: IntoResponse + Send,
     S::Error: Send,
     S::Future: Send,
     F: FnOnce(S::Error) -> Fut + Clone + Send + 'static,
@@ -127,13 +127,8 @@ where
         let inner = std::mem::replace(&mut self.inner, clone);
 
         let future = Box::pin(async move {
-            // TODO(fnichol): at the moment the response type `Res` is generic, however we know
-            // that by the end we want our response type to be unit (i.e. `()`). Should we shut
-            // down the extra generics or allow the final response types to be dropped here?
-            // Unclear...
-            #[allow(clippy::unit_arg)]
             match inner.oneshot(req).await {
-                Ok(_res) => Ok(()),
+                Ok(res) => Ok(res.into_response()),
                 Err(err) => Ok(f(err).await.into_response()),
             }
         });
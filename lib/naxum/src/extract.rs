@@ -5,12 +5,17 @@ use async_trait::async_trait;
 use crate::{message::Head, response::IntoResponse, MessageHead};
 
 mod message_parts;
+mod path;
 pub mod rejection;
 mod state;
 mod tuple;
 
+pub use self::message_parts::{Headers, Length, Reply, StatusCode};
+pub use self::path::{FromPathParams, Path, PathParams};
 pub use self::state::State;
 
+pub(crate) use self::path::PATH_PARAMS;
+
 mod private {
     #[derive(Debug, Clone, Copy)]
     pub enum ViaHead {}
@@ -0,0 +1,309 @@
+use std::{collections::HashMap, ops, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{message::Head, BoxError};
+
+use super::{
+    rejection::{InvalidPathParam, MissingPathParams, PathRejection, WrongNumberOfPathParams},
+    FromMessageHead,
+};
+
+tokio::task_local! {
+    // `MessageHead::from_parts` can't carry extensions through to every message type, so the
+    // router hands the tokens it captured to the matched route's extractors this way.
+    pub(crate) static PATH_PARAMS: PathParams;
+}
+
+/// The subject tokens captured by the wildcards of the [`Router`](crate::Router) pattern that
+/// matched a message, in the order they appear in the subject.
+#[derive(Clone, Debug, Default)]
+pub struct PathParams {
+    params: Vec<(Option<Arc<str>>, String)>,
+}
+
+impl PathParams {
+    pub(crate) fn new(params: Vec<(Option<Arc<str>>, String)>) -> Self {
+        Self { params }
+    }
+
+    /// Returns the token captured as `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param_name, _)| param_name.as_deref() == Some(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the captured tokens along with their names, if they were given one.
+    pub fn iter(&self) -> impl Iterator<Item = (Option<&str>, &str)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_deref(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &str> {
+        self.params.iter().map(|(_, value)| value.as_str())
+    }
+}
+
+#[async_trait]
+impl<S> FromMessageHead<S> for PathParams
+where
+    S: Send + Sync,
+{
+    type Rejection = MissingPathParams;
+
+    async fn from_message_head(_head: &mut Head, _state: &S) -> Result<Self, Self::Rejection> {
+        PATH_PARAMS
+            .try_with(Clone::clone)
+            .map_err(|_| MissingPathParams)
+    }
+}
+
+/// Extractor that parses the subject tokens captured by the [`Router`](crate::Router) pattern
+/// that matched a message.
+///
+/// A single captured token can be extracted into any [`FromPathParams`] type, and several into a
+/// tuple, in the order they appear in the subject.
+///
+/// ```ignore
+/// // Routed with `.route("veritech.{function_kind}.{change_set_id}", handler)`
+/// async fn handler(Path((function_kind, change_set_id)): Path<(String, Ulid)>) {}
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromMessageHead<S> for Path<T>
+where
+    S: Send + Sync,
+    T: FromPathParams,
+{
+    type Rejection = PathRejection;
+
+    async fn from_message_head(head: &mut Head, state: &S) -> Result<Self, Self::Rejection> {
+        let params = PathParams::from_message_head(head, state).await?;
+        T::from_path_params(&params).map(Self)
+    }
+}
+
+impl<T> ops::Deref for Path<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Path<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Types that can be created from the subject tokens captured by a route.
+pub trait FromPathParams: Sized {
+    fn from_path_params(params: &PathParams) -> Result<Self, PathRejection>;
+}
+
+impl FromPathParams for Vec<String> {
+    fn from_path_params(params: &PathParams) -> Result<Self, PathRejection> {
+        Ok(params.values().map(ToOwned::to_owned).collect())
+    }
+}
+
+impl FromPathParams for HashMap<String, String> {
+    fn from_path_params(params: &PathParams) -> Result<Self, PathRejection> {
+        Ok(params
+            .iter()
+            .filter_map(|(name, value)| name.map(|name| (name.to_owned(), value.to_owned())))
+            .collect())
+    }
+}
+
+fn parse_param<T>(value: &str) -> Result<T, PathRejection>
+where
+    T: FromStr,
+    T::Err: Into<BoxError>,
+{
+    value
+        .parse()
+        .map_err(|err| InvalidPathParam::from_err(value, err).into())
+}
+
+fn expect_params(params: &PathParams, expected: usize) -> Result<(), PathRejection> {
+    if params.len() == expected {
+        Ok(())
+    } else {
+        Err(WrongNumberOfPathParams {
+            expected,
+            got: params.len(),
+        }
+        .into())
+    }
+}
+
+macro_rules! impl_from_path_params_for_scalar {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FromPathParams for $ty {
+                fn from_path_params(params: &PathParams) -> Result<Self, PathRejection> {
+                    expect_params(params, 1)?;
+                    parse_param(params.values().next().unwrap_or_default())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_path_params_for_scalar!(
+    String, bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64,
+);
+
+macro_rules! impl_from_path_params_for_tuple {
+    (
+        [$($ty:ident),*], $last:ident
+    ) => {
+        #[allow(non_snake_case)]
+        impl<$($ty,)* $last> FromPathParams for ($($ty,)* $last,)
+        where
+            $( $ty: FromStr, $ty::Err: Into<BoxError>, )*
+            $last: FromStr,
+            $last::Err: Into<BoxError>,
+        {
+            fn from_path_params(params: &PathParams) -> Result<Self, PathRejection> {
+                let expected = [$(stringify!($ty),)* stringify!($last)].len();
+                expect_params(params, expected)?;
+
+                let mut values = params.values();
+                $(
+                    let $ty = parse_param(values.next().unwrap_or_default())?;
+                )*
+                let $last = parse_param(values.next().unwrap_or_default())?;
+
+                Ok(($($ty,)* $last,))
+            }
+        }
+    };
+}
+
+all_the_tuples!(impl_from_path_params_for_tuple);
+
+#[cfg(test)]
+async fn test_extract<T: FromPathParams>(
+    params: Vec<(Option<&str>, &str)>,
+) -> Result<Path<T>, PathRejection> {
+    let params = PathParams::new(
+        params
+            .into_iter()
+            .map(|(name, value)| (name.map(Into::into), value.to_owned()))
+            .collect(),
+    );
+    let mut head = Head {
+        subject: "veritech".into(),
+        reply: None,
+        headers: None,
+        status: None,
+        description: None,
+        length: 0,
+        extensions: crate::message::Extensions::new(),
+    };
+
+    PATH_PARAMS
+        .scope(params, Path::<T>::from_message_head(&mut head, &()))
+        .await
+}
+
+#[tokio::test]
+async fn test_path_parses_captured_tokens() {
+    let Path(count) = test_extract::<u32>(vec![(None, "42")])
+        .await
+        .expect("extracts");
+    assert_eq!(42, count);
+
+    let Path((kind, count)) =
+        test_extract::<(String, u32)>(vec![(Some("kind"), "fn"), (None, "7")])
+            .await
+            .expect("extracts");
+    assert_eq!(("fn".to_owned(), 7), (kind, count));
+
+    let Path(values) = test_extract::<Vec<String>>(vec![(Some("kind"), "fn"), (None, "7")])
+        .await
+        .expect("extracts");
+    assert_eq!(vec!["fn".to_owned(), "7".to_owned()], values);
+
+    // Only named tokens make it into a map.
+    let Path(named) =
+        test_extract::<HashMap<String, String>>(vec![(Some("kind"), "fn"), (None, "7")])
+            .await
+            .expect("extracts");
+    assert_eq!(HashMap::from([("kind".to_owned(), "fn".to_owned())]), named);
+}
+
+#[tokio::test]
+async fn test_path_rejects_the_wrong_number_of_tokens() {
+    let rejection = test_extract::<u32>(vec![(None, "42"), (None, "7")])
+        .await
+        .expect_err("rejects");
+    assert!(matches!(
+        rejection,
+        PathRejection::WrongNumberOfPathParams(WrongNumberOfPathParams {
+            expected: 1,
+            got: 2
+        })
+    ));
+
+    let rejection = test_extract::<(u32, u32)>(vec![(None, "42")])
+        .await
+        .expect_err("rejects");
+    assert!(matches!(
+        rejection,
+        PathRejection::WrongNumberOfPathParams(WrongNumberOfPathParams {
+            expected: 2,
+            got: 1
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_path_rejects_tokens_that_do_not_parse() {
+    let rejection = test_extract::<(String, u32)>(vec![(None, "fn"), (None, "many")])
+        .await
+        .expect_err("rejects");
+
+    assert!(matches!(rejection, PathRejection::InvalidPathParam(_)));
+    assert_eq!(
+        crate::response::status::bad_request(),
+        crate::response::IntoResponse::into_response(rejection).status()
+    );
+}
+
+#[tokio::test]
+async fn test_path_rejects_messages_not_routed_by_a_router() {
+    let mut head = Head {
+        subject: "veritech".into(),
+        reply: None,
+        headers: None,
+        status: None,
+        description: None,
+        length: 0,
+        extensions: crate::message::Extensions::new(),
+    };
+
+    let rejection = Path::<u32>::from_message_head(&mut head, &())
+        .await
+        .expect_err("rejects");
+
+    assert!(matches!(rejection, PathRejection::MissingPathParams(_)));
+}
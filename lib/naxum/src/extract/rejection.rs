@@ -1,7 +1,7 @@
 use std::{error, fmt};
 
 use crate::{
-    response::{IntoResponse, Response},
+    response::{status, IntoResponse, Response},
     BoxError, Error,
 };
 
@@ -19,7 +19,7 @@ impl InvalidUtf8 {
 
 impl IntoResponse for InvalidUtf8 {
     fn into_response(self) -> Response {
-        // TODO: log rejection
+        Response::error(status::bad_request(), self.to_string())
     }
 }
 
@@ -65,3 +65,280 @@ impl error::Error for StringRejection {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct MissingPathParams;
+
+impl IntoResponse for MissingPathParams {
+    fn into_response(self) -> Response {
+        Response::error(status::internal_server_error(), self.to_string())
+    }
+}
+
+impl fmt::Display for MissingPathParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no path parameters found for message, was the handler called without a router?"
+        )
+    }
+}
+
+impl error::Error for MissingPathParams {}
+
+#[derive(Debug)]
+pub struct WrongNumberOfPathParams {
+    pub(crate) expected: usize,
+    pub(crate) got: usize,
+}
+
+impl IntoResponse for WrongNumberOfPathParams {
+    fn into_response(self) -> Response {
+        Response::error(status::internal_server_error(), self.to_string())
+    }
+}
+
+impl fmt::Display for WrongNumberOfPathParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wrong number of path parameters, expected {} but got {}",
+            self.expected, self.got
+        )
+    }
+}
+
+impl error::Error for WrongNumberOfPathParams {}
+
+#[derive(Debug)]
+pub struct InvalidPathParam {
+    value: String,
+    source: Error,
+}
+
+impl InvalidPathParam {
+    pub(crate) fn from_err<E>(value: impl Into<String>, err: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self {
+            value: value.into(),
+            source: Error::new(err),
+        }
+    }
+}
+
+impl IntoResponse for InvalidPathParam {
+    fn into_response(self) -> Response {
+        Response::error(status::bad_request(), self.to_string())
+    }
+}
+
+impl fmt::Display for InvalidPathParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse path parameter `{}`: {}",
+            self.value, self.source
+        )
+    }
+}
+
+impl error::Error for InvalidPathParam {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[derive(Debug)]
+pub enum PathRejection {
+    InvalidPathParam(InvalidPathParam),
+    MissingPathParams(MissingPathParams),
+    WrongNumberOfPathParams(WrongNumberOfPathParams),
+}
+
+impl IntoResponse for PathRejection {
+    fn into_response(self) -> crate::response::Response {
+        match self {
+            Self::InvalidPathParam(inner) => inner.into_response(),
+            Self::MissingPathParams(inner) => inner.into_response(),
+            Self::WrongNumberOfPathParams(inner) => inner.into_response(),
+        }
+    }
+}
+
+impl From<InvalidPathParam> for PathRejection {
+    fn from(value: InvalidPathParam) -> Self {
+        Self::InvalidPathParam(value)
+    }
+}
+
+impl From<MissingPathParams> for PathRejection {
+    fn from(value: MissingPathParams) -> Self {
+        Self::MissingPathParams(value)
+    }
+}
+
+impl From<WrongNumberOfPathParams> for PathRejection {
+    fn from(value: WrongNumberOfPathParams) -> Self {
+        Self::WrongNumberOfPathParams(value)
+    }
+}
+
+impl fmt::Display for PathRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPathParam(inner) => write!(f, "{inner}"),
+            Self::MissingPathParams(inner) => write!(f, "{inner}"),
+            Self::WrongNumberOfPathParams(inner) => write!(f, "{inner}"),
+        }
+    }
+}
+
+impl error::Error for PathRejection {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::InvalidPathParam(inner) => inner.source(),
+            Self::MissingPathParams(inner) => inner.source(),
+            Self::WrongNumberOfPathParams(inner) => inner.source(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FailedToDeserializeJson(Error);
+
+impl FailedToDeserializeJson {
+    pub(crate) fn from_err<E>(err: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self(Error::new(err))
+    }
+}
+
+impl IntoResponse for FailedToDeserializeJson {
+    fn into_response(self) -> Response {
+        Response::error(status::bad_request(), self.to_string())
+    }
+}
+
+impl fmt::Display for FailedToDeserializeJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to deserialize message payload as JSON: {}",
+            self.0
+        )
+    }
+}
+
+impl error::Error for FailedToDeserializeJson {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[derive(Debug)]
+pub enum JsonRejection {
+    FailedToDeserializeJson(FailedToDeserializeJson),
+}
+
+impl IntoResponse for JsonRejection {
+    fn into_response(self) -> crate::response::Response {
+        match self {
+            Self::FailedToDeserializeJson(inner) => inner.into_response(),
+        }
+    }
+}
+
+impl From<FailedToDeserializeJson> for JsonRejection {
+    fn from(value: FailedToDeserializeJson) -> Self {
+        Self::FailedToDeserializeJson(value)
+    }
+}
+
+impl fmt::Display for JsonRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FailedToDeserializeJson(inner) => write!(f, "{inner}"),
+        }
+    }
+}
+
+impl error::Error for JsonRejection {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::FailedToDeserializeJson(inner) => inner.source(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FailedToDeserializeCbor(Error);
+
+impl FailedToDeserializeCbor {
+    pub(crate) fn from_err<E>(err: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self(Error::new(err))
+    }
+}
+
+impl IntoResponse for FailedToDeserializeCbor {
+    fn into_response(self) -> Response {
+        Response::error(status::bad_request(), self.to_string())
+    }
+}
+
+impl fmt::Display for FailedToDeserializeCbor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to deserialize message payload as CBOR: {}",
+            self.0
+        )
+    }
+}
+
+impl error::Error for FailedToDeserializeCbor {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[derive(Debug)]
+pub enum CborRejection {
+    FailedToDeserializeCbor(FailedToDeserializeCbor),
+}
+
+impl IntoResponse for CborRejection {
+    fn into_response(self) -> crate::response::Response {
+        match self {
+            Self::FailedToDeserializeCbor(inner) => inner.into_response(),
+        }
+    }
+}
+
+impl From<FailedToDeserializeCbor> for CborRejection {
+    fn from(value: FailedToDeserializeCbor) -> Self {
+        Self::FailedToDeserializeCbor(value)
+    }
+}
+
+impl fmt::Display for CborRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FailedToDeserializeCbor(inner) => write!(f, "{inner}"),
+        }
+    }
+}
+
+impl error::Error for CborRejection {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::FailedToDeserializeCbor(inner) => inner.source(),
+        }
+    }
+}
@@ -124,7 +124,6 @@ where
     type Future = Ready<Response>;
 
     fn call(self, _req: R, _state: S) -> Self::Future {
-        #[allow(clippy::unit_arg)]
         ready(self.into_response())
     }
 }
@@ -0,0 +1,127 @@
+use std::ops;
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    extract::{
+        rejection::{FailedToDeserializeJson, JsonRejection},
+        FromMessage,
+    },
+    response::{status, IntoResponse, Response},
+    MessageHead,
+};
+
+const CONTENT_TYPE: &str = "Content-Type";
+const APPLICATION_JSON: &str = "application/json";
+
+/// JSON extractor and response.
+///
+/// As an extractor, deserializes the message payload as JSON. As a response, serializes the
+/// value as the body of the reply.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S, R> FromMessage<S, R> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    R: MessageHead + Send + 'static,
+{
+    type Rejection = JsonRejection;
+
+    async fn from_message(req: R, _state: &S) -> Result<Self, Self::Rejection> {
+        let (_head, payload) = req.into_parts();
+        let value = serde_json::from_slice(&payload).map_err(FailedToDeserializeJson::from_err)?;
+
+        Ok(Self(value))
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self.0) {
+            Ok(body) => {
+                let mut response = Response::new(body);
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, APPLICATION_JSON);
+                response
+            }
+            Err(err) => Response::error(
+                status::internal_server_error(),
+                format!("failed to serialize response as json: {err}"),
+            ),
+        }
+    }
+}
+
+impl<T> ops::Deref for Json<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Json<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Json<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+#[tokio::test]
+async fn test_json_extracts_the_payload() {
+    use crate::message::test_message;
+
+    let Json(value) = Json::<Vec<u32>>::from_message(test_message("veritech", None, "[1,2]"), &())
+        .await
+        .expect("extracts");
+
+    assert_eq!(vec![1, 2], value);
+}
+
+#[tokio::test]
+async fn test_json_rejects_payloads_that_do_not_deserialize() {
+    use crate::message::test_message;
+
+    for payload in ["", "[1,", "{\"not\":\"a list\"}"] {
+        let rejection =
+            Json::<Vec<u32>>::from_message(test_message("veritech", None, payload), &())
+                .await
+                .expect_err("rejects");
+
+        assert!(matches!(
+            rejection,
+            JsonRejection::FailedToDeserializeJson(_)
+        ));
+        assert_eq!(status::bad_request(), rejection.into_response().status());
+    }
+}
+
+#[test]
+fn test_json_responds_with_the_serialized_value() {
+    let response = Json(vec![1, 2]).into_response();
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        Some(APPLICATION_JSON),
+        response
+            .headers()
+            .get(CONTENT_TYPE)
+            .map(|value| value.as_str())
+    );
+    assert_eq!("[1,2]", response.body());
+}
@@ -2,21 +2,27 @@
 pub(crate) mod macros;
 
 mod cancellation;
+mod cbor;
 mod error;
 pub mod error_handling;
 pub mod extract;
 pub mod handler;
+mod json;
 mod make_service;
 mod message;
 pub mod middleware;
 pub mod response;
+pub mod routing;
 pub mod serve;
 mod service_ext;
 
 pub use self::cancellation::wait_on_cancelled;
+pub use self::cbor::Cbor;
 pub use self::error::Error;
+pub use self::json::Json;
 pub use self::make_service::IntoMakeService;
 pub use self::message::{Head, MessageHead};
+pub use self::routing::Router;
 pub use self::serve::serve;
 pub use self::service_ext::ServiceExt;
 pub use async_trait::async_trait;
@@ -7,7 +7,7 @@ mod extensions;
 
 pub use extensions::Extensions;
 
-use crate::response::{IntoResponse, Response};
+use crate::response::{status, IntoResponse, Response};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct FromPartsError(&'static str);
@@ -21,7 +21,9 @@ impl fmt::Display for FromPartsError {
 impl error::Error for FromPartsError {}
 
 impl IntoResponse for FromPartsError {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Response::error(status::internal_server_error(), self.to_string())
+    }
 }
 
 pub trait MessageHead {
@@ -216,3 +218,22 @@ pub struct Head {
     /// The message's extensions
     pub extensions: Extensions,
 }
+
+/// Builds a core NATS message, as a subscriber would receive it.
+#[cfg(test)]
+pub(crate) fn test_message(
+    subject: &str,
+    reply: Option<&str>,
+    payload: impl Into<Bytes>,
+) -> async_nats::Message {
+    let payload = payload.into();
+    async_nats::Message {
+        subject: subject.into(),
+        reply: reply.map(Into::into),
+        length: payload.len(),
+        payload,
+        headers: None,
+        status: None,
+        description: None,
+    }
+}
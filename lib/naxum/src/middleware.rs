@@ -1,4 +1,9 @@
+pub mod ack;
+pub mod concurrency_limit;
 pub mod delay;
+pub mod reply;
+pub mod subject_concurrency_limit;
+pub mod timeout;
 pub mod trace;
 
 #[non_exhaustive]
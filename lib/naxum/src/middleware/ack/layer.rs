@@ -0,0 +1,153 @@
+use std::{sync::Arc, time::Duration};
+
+use async_nats::StatusCode;
+use tower::Layer;
+
+use crate::response::Response;
+
+use super::service::Ack;
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_DELIVERIES: u64 = 5;
+
+#[derive(Clone, Debug)]
+pub struct AckLayer {
+    pub(crate) config: AckConfig,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AckConfig {
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) max_deliveries: Option<u64>,
+    pub(crate) dead_letter_subject: Option<String>,
+    pub(crate) retry_if: fn(&Response) -> bool,
+    pub(crate) double_ack: bool,
+}
+
+impl AckConfig {
+    /// The delay before redelivering a message that has been delivered `delivered` times, which
+    /// doubles with each delivery.
+    pub(crate) fn backoff(&self, delivered: u64) -> Duration {
+        let exponent = u32::try_from(delivered.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+impl AckLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the delay before the first redelivery of a failed message, and the most it can grow
+    /// to with later redeliveries.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.config.initial_backoff = initial;
+        self.config.max_backoff = max;
+        self
+    }
+
+    /// Sets how many times a message may be delivered before it is no longer retried, or `None`
+    /// to retry it for as long as the consumer redelivers it.
+    pub fn max_deliveries(mut self, max_deliveries: Option<u64>) -> Self {
+        self.config.max_deliveries = max_deliveries;
+        self
+    }
+
+    /// Sets the subject that messages which are no longer retried are republished to.
+    pub fn dead_letter_subject(mut self, subject: impl Into<String>) -> Self {
+        self.config.dead_letter_subject = Some(subject.into());
+        self
+    }
+
+    /// Sets which failed responses are retried.
+    ///
+    /// By default, client errors other than timeouts are not retried since the message would fail
+    /// the same way again.
+    pub fn retry_if(mut self, retry_if: fn(&Response) -> bool) -> Self {
+        self.config.retry_if = retry_if;
+        self
+    }
+
+    /// Sets whether successful messages are acked with a double ack, which waits for the server
+    /// to confirm the ack.
+    pub fn double_ack(mut self, double_ack: bool) -> Self {
+        self.config.double_ack = double_ack;
+        self
+    }
+}
+
+impl Default for AckLayer {
+    fn default() -> Self {
+        Self {
+            config: AckConfig {
+                initial_backoff: DEFAULT_INITIAL_BACKOFF,
+                max_backoff: DEFAULT_MAX_BACKOFF,
+                max_deliveries: Some(DEFAULT_MAX_DELIVERIES),
+                dead_letter_subject: None,
+                retry_if: default_retry_if,
+                double_ack: false,
+            },
+        }
+    }
+}
+
+impl<S> Layer<S> for AckLayer {
+    type Service = Ack<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Ack {
+            inner,
+            config: Arc::new(self.config.clone()),
+        }
+    }
+}
+
+fn default_retry_if(response: &Response) -> bool {
+    let status = response.status();
+    status == StatusCode::TIMEOUT || !status.is_client_error()
+}
+
+#[test]
+fn test_backoff_doubles_with_each_delivery_up_to_the_max() {
+    let config = AckLayer::new()
+        .backoff(Duration::from_secs(1), Duration::from_secs(60))
+        .config;
+
+    for (delivered, expected) in [
+        (0, 1),
+        (1, 1),
+        (2, 2),
+        (3, 4),
+        (6, 32),
+        (7, 60),
+        (64, 60),
+        (u64::MAX, 60),
+    ] {
+        assert_eq!(
+            Duration::from_secs(expected),
+            config.backoff(delivered),
+            "backoff after {delivered} deliveries"
+        );
+    }
+}
+
+#[test]
+fn test_default_retry_if_retries_all_but_client_errors() {
+    for (status, expected) in [
+        (crate::response::status::internal_server_error(), true),
+        (StatusCode::NO_RESPONDERS, true),
+        (StatusCode::TIMEOUT, true),
+        (crate::response::status::bad_request(), false),
+        (StatusCode::NOT_FOUND, false),
+    ] {
+        assert_eq!(
+            expected,
+            default_retry_if(&Response::error(status, "failed")),
+            "retry {status}"
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Acknowledges JetStream messages based on the response of the service that handled them.
+//!
+//! A message is acked when its response is a success. Otherwise, if the failure is retryable and
+//! the message has deliveries left, it is naked so that it is redelivered after an exponential
+//! backoff. Failures that aren't retryable, and messages that have run out of deliveries, are
+//! republished to a dead-letter subject if one is configured and then termed.
+
+mod future;
+mod layer;
+mod service;
+
+pub use self::{layer::AckLayer, service::Ack};
+
+/// Header holding the subject a dead-lettered message was originally published to.
+pub const ORIGINAL_SUBJECT: &str = "Naxum-Original-Subject";
+/// Header holding the stream a dead-lettered message was consumed from.
+pub const ORIGINAL_STREAM: &str = "Naxum-Original-Stream";
+/// Header holding the consumer a dead-lettered message was consumed by.
+pub const ORIGINAL_CONSUMER: &str = "Naxum-Original-Consumer";
+/// Header holding the stream sequence of a dead-lettered message.
+pub const ORIGINAL_STREAM_SEQUENCE: &str = "Naxum-Original-Stream-Sequence";
+/// Header holding how many times a dead-lettered message was delivered.
+pub const DELIVERIES: &str = "Naxum-Deliveries";
@@ -0,0 +1,334 @@
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_nats::{
+    jetstream::{self, AckKind},
+    service::{NATS_SERVICE_ERROR, NATS_SERVICE_ERROR_CODE},
+    HeaderMap,
+};
+use tower::Service;
+use tracing::{debug, error, warn};
+
+use crate::{response::Response, BoxError};
+
+use super::{
+    future::ResponseFuture, layer::AckConfig, DELIVERIES, ORIGINAL_CONSUMER, ORIGINAL_STREAM,
+    ORIGINAL_STREAM_SEQUENCE, ORIGINAL_SUBJECT,
+};
+
+#[derive(Clone, Debug)]
+pub struct Ack<S> {
+    pub(crate) inner: S,
+    pub(crate) config: Arc<AckConfig>,
+}
+
+impl<S> Service<jetstream::Message> for Ack<S>
+where
+    S: Service<jetstream::Message, Response = Response, Error = Infallible>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = ResponseFuture;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: jetstream::Message) -> Self::Future {
+        let response = self.inner.call(req.clone());
+        let config = self.config.clone();
+
+        let future = Box::pin(async move {
+            let response = response.await?;
+            acknowledge(&config, &req, &response).await;
+
+            Ok(response)
+        });
+
+        ResponseFuture { future }
+    }
+}
+
+/// What is done with a message once the service that handled it has responded.
+#[derive(Debug, PartialEq)]
+enum Disposition<'a> {
+    /// The message was handled.
+    Ack,
+    /// The message is redelivered after the delay.
+    Nak(Duration),
+    /// The message is republished to the dead-letter subject, then termed.
+    DeadLetter(&'a str),
+    /// The message is termed.
+    Term,
+}
+
+fn disposition<'a>(config: &'a AckConfig, response: &Response, delivered: u64) -> Disposition<'a> {
+    if response.status().is_success() {
+        return Disposition::Ack;
+    }
+
+    let retryable = (config.retry_if)(response);
+    let exhausted = config
+        .max_deliveries
+        .is_some_and(|max_deliveries| delivered >= max_deliveries);
+    if retryable && !exhausted {
+        return Disposition::Nak(config.backoff(delivered));
+    }
+
+    match &config.dead_letter_subject {
+        Some(dead_letter_subject) => Disposition::DeadLetter(dead_letter_subject),
+        None => Disposition::Term,
+    }
+}
+
+async fn acknowledge(config: &AckConfig, msg: &jetstream::Message, response: &Response) {
+    let subject = msg.subject.as_str();
+    let delivered = msg
+        .info()
+        .ok()
+        .and_then(|info| u64::try_from(info.delivered).ok())
+        .unwrap_or(1);
+
+    match disposition(config, response, delivered) {
+        Disposition::Ack => {
+            let result = if config.double_ack {
+                msg.double_ack().await
+            } else {
+                msg.ack().await
+            };
+            if let Err(err) = result {
+                warn!(error = ?err, subject, "failed to ack message");
+            }
+            return;
+        }
+        Disposition::Nak(delay) => {
+            debug!(
+                subject,
+                delivered,
+                status = response.status().as_u16(),
+                ?delay,
+                "message failed, naking for redelivery",
+            );
+            nak(msg, delay).await;
+            return;
+        }
+        Disposition::DeadLetter(dead_letter_subject) => {
+            if let Err(err) = dead_letter(dead_letter_subject, msg, response, delivered).await {
+                // Rather than losing the message, have it redelivered and try again
+                error!(
+                    error = ?err,
+                    subject,
+                    dead_letter_subject,
+                    "failed to dead-letter message, naking for redelivery",
+                );
+                nak(msg, config.backoff(delivered)).await;
+                return;
+            }
+        }
+        Disposition::Term => {}
+    }
+
+    warn!(
+        subject,
+        delivered,
+        status = response.status().as_u16(),
+        retryable = (config.retry_if)(response),
+        "message failed, terming",
+    );
+    if let Err(err) = msg.ack_with(AckKind::Term).await {
+        warn!(error = ?err, subject, "failed to term message");
+    }
+}
+
+async fn nak(msg: &jetstream::Message, delay: Duration) {
+    if let Err(err) = msg.ack_with(AckKind::Nak(Some(delay))).await {
+        warn!(error = ?err, subject = msg.subject.as_str(), "failed to nak message");
+    }
+}
+
+async fn dead_letter(
+    dead_letter_subject: &str,
+    msg: &jetstream::Message,
+    response: &Response,
+    delivered: u64,
+) -> Result<(), BoxError> {
+    msg.context
+        .publish_with_headers(
+            dead_letter_subject.to_owned(),
+            dead_letter_headers(msg, response, delivered),
+            msg.payload.clone(),
+        )
+        .await?
+        .await?;
+
+    Ok(())
+}
+
+fn dead_letter_headers(msg: &jetstream::Message, response: &Response, delivered: u64) -> HeaderMap {
+    let mut headers = msg.headers.clone().unwrap_or_default();
+    headers.insert(ORIGINAL_SUBJECT, msg.subject.as_str());
+    headers.insert(DELIVERIES, delivered.to_string().as_str());
+    if let Ok(info) = msg.info() {
+        headers.insert(ORIGINAL_STREAM, info.stream);
+        headers.insert(ORIGINAL_CONSUMER, info.consumer);
+        headers.insert(
+            ORIGINAL_STREAM_SEQUENCE,
+            info.stream_sequence.to_string().as_str(),
+        );
+    }
+    let (response_headers, _body) = response.clone().into_parts();
+    copy_header(&response_headers, &mut headers, NATS_SERVICE_ERROR);
+    copy_header(&response_headers, &mut headers, NATS_SERVICE_ERROR_CODE);
+
+    headers
+}
+
+fn copy_header(from: &HeaderMap, to: &mut HeaderMap, name: &str) {
+    if let Some(value) = from.get(name) {
+        to.insert(name, value.clone());
+    }
+}
+
+#[test]
+fn test_disposition_of_responses() {
+    use async_nats::StatusCode;
+
+    use crate::response::status;
+
+    use super::AckLayer;
+
+    let retries = AckLayer::new()
+        .backoff(Duration::from_secs(1), Duration::from_secs(60))
+        .max_deliveries(Some(3))
+        .config;
+    let dead_letters = AckLayer::new()
+        .backoff(Duration::from_secs(1), Duration::from_secs(60))
+        .max_deliveries(Some(3))
+        .dead_letter_subject("dead-letters")
+        .config;
+    let retries_forever = AckLayer::new()
+        .backoff(Duration::from_secs(1), Duration::from_secs(60))
+        .max_deliveries(None)
+        .config;
+    let never_retries = AckLayer::new().retry_if(|_| false).config;
+
+    let ok = Response::new("done");
+    let failed = Response::error(status::internal_server_error(), "failed");
+    let timed_out = Response::error(StatusCode::TIMEOUT, "timed out");
+    let bad_request = Response::error(status::bad_request(), "bad request");
+
+    for (config, response, delivered, expected) in [
+        (&retries, &ok, 1, Disposition::Ack),
+        (&retries, &ok, 3, Disposition::Ack),
+        (
+            &retries,
+            &failed,
+            1,
+            Disposition::Nak(Duration::from_secs(1)),
+        ),
+        (
+            &retries,
+            &failed,
+            2,
+            Disposition::Nak(Duration::from_secs(2)),
+        ),
+        (
+            &retries,
+            &timed_out,
+            2,
+            Disposition::Nak(Duration::from_secs(2)),
+        ),
+        (&retries, &failed, 3, Disposition::Term),
+        (&retries, &bad_request, 1, Disposition::Term),
+        (&dead_letters, &ok, 3, Disposition::Ack),
+        (
+            &dead_letters,
+            &failed,
+            2,
+            Disposition::Nak(Duration::from_secs(2)),
+        ),
+        (
+            &dead_letters,
+            &failed,
+            3,
+            Disposition::DeadLetter("dead-letters"),
+        ),
+        (
+            &dead_letters,
+            &failed,
+            4,
+            Disposition::DeadLetter("dead-letters"),
+        ),
+        (
+            &dead_letters,
+            &bad_request,
+            1,
+            Disposition::DeadLetter("dead-letters"),
+        ),
+        (
+            &retries_forever,
+            &failed,
+            1000,
+            Disposition::Nak(Duration::from_secs(60)),
+        ),
+        (&never_retries, &ok, 1, Disposition::Ack),
+        (&never_retries, &failed, 1, Disposition::Term),
+    ] {
+        assert_eq!(
+            expected,
+            disposition(config, response, delivered),
+            "{} after {delivered} deliveries",
+            response.status()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_dead_letter_headers_record_where_the_message_came_from() {
+    // Nothing is published, so the client never has to connect.
+    let client = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect("nats://127.0.0.1:1")
+        .await
+        .expect("creates client");
+    let mut headers = HeaderMap::new();
+    headers.insert("Trace-Id", "abc");
+    let msg = jetstream::Message {
+        message: async_nats::Message {
+            subject: "veritech.fn".into(),
+            reply: Some("$JS.ACK.VERITECH.workers.3.42.40.1700000000000000000.0".into()),
+            payload: "payload".into(),
+            headers: Some(headers),
+            status: None,
+            description: None,
+            length: 7,
+        },
+        context: jetstream::new(client),
+    };
+    let response = Response::error(crate::response::status::internal_server_error(), "failed");
+
+    let headers = dead_letter_headers(&msg, &response, 3);
+
+    for (name, expected) in [
+        ("Trace-Id", "abc"),
+        (ORIGINAL_SUBJECT, "veritech.fn"),
+        (ORIGINAL_STREAM, "VERITECH"),
+        (ORIGINAL_CONSUMER, "workers"),
+        (ORIGINAL_STREAM_SEQUENCE, "42"),
+        (DELIVERIES, "3"),
+        (NATS_SERVICE_ERROR, "failed"),
+        (NATS_SERVICE_ERROR_CODE, "500"),
+    ] {
+        assert_eq!(
+            Some(expected),
+            headers.get(name).map(|value| value.as_str()),
+            "{name} header"
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use tokio::sync::OwnedSemaphorePermit;
+
+pin_project! {
+    pub struct ResponseFuture<F> {
+        #[pin]
+        pub(crate) inner: F,
+        // Released once the response is ready, making room for another message
+        pub(crate) _permit: OwnedSemaphorePermit,
+    }
+}
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
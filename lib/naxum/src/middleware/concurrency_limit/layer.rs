@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tower::Layer;
+
+use super::service::ConcurrencyLimit;
+
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimitLayer {
+    pub(crate) max: usize,
+}
+
+impl ConcurrencyLimitLayer {
+    pub fn new(max: usize) -> Self {
+        Self { max }
+    }
+}
+
+impl<S> Layer<S> for ConcurrencyLimitLayer {
+    type Service = ConcurrencyLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConcurrencyLimit::with_semaphore(inner, Arc::new(Semaphore::new(self.max)))
+    }
+}
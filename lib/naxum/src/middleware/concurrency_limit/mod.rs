@@ -0,0 +1,10 @@
+//! Caps how many messages a service processes at once.
+//!
+//! The service is not ready while it is at capacity, so [`serve`](crate::serve) stops pulling
+//! messages from the stream until one finishes.
+
+mod future;
+mod layer;
+mod service;
+
+pub use self::{layer::ConcurrencyLimitLayer, service::ConcurrencyLimit};
@@ -0,0 +1,76 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_util::sync::PollSemaphore;
+use tower::Service;
+
+use super::future::ResponseFuture;
+
+#[derive(Debug)]
+pub struct ConcurrencyLimit<S> {
+    pub(crate) inner: S,
+    pub(crate) semaphore: PollSemaphore,
+    pub(crate) permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<S> ConcurrencyLimit<S> {
+    pub fn new(inner: S, max: usize) -> Self {
+        Self::with_semaphore(inner, Arc::new(Semaphore::new(max)))
+    }
+
+    pub(crate) fn with_semaphore(inner: S, semaphore: Arc<Semaphore>) -> Self {
+        Self {
+            inner,
+            semaphore: PollSemaphore::new(semaphore),
+            permit: None,
+        }
+    }
+}
+
+// Every clone shares the same semaphore, but a permit acquired when one was readied belongs to
+// that one alone.
+impl<S> Clone for ConcurrencyLimit<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            semaphore: self.semaphore.clone(),
+            permit: None,
+        }
+    }
+}
+
+impl<S, R> Service<R> for ConcurrencyLimit<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.permit.is_none() {
+            // The semaphore is never closed, so this only waits for a permit to be released
+            self.permit = futures::ready!(self.semaphore.poll_acquire(cx));
+        }
+
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let permit = self
+            .permit
+            .take()
+            .expect("max messages in flight; poll_ready must be called first");
+
+        ResponseFuture {
+            inner: self.inner.call(req),
+            _permit: permit,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use tower::Layer;
+
+use super::service::PublishReply;
+
+#[derive(Clone, Debug)]
+pub struct PublishReplyLayer {
+    pub(crate) client: async_nats::Client,
+}
+
+impl PublishReplyLayer {
+    pub fn new(client: async_nats::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl<S> Layer<S> for PublishReplyLayer {
+    type Service = PublishReply<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PublishReply {
+            inner,
+            client: self.client.clone(),
+        }
+    }
+}
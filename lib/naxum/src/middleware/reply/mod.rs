@@ -0,0 +1,10 @@
+//! Publishes the responses of a service to the reply subjects of the messages it handles.
+//!
+//! This is for core NATS request/reply. A JetStream message's reply subject is where it is
+//! acknowledged, so the responses of services consuming from a stream should not be published.
+
+mod future;
+mod layer;
+mod service;
+
+pub use self::{layer::PublishReplyLayer, service::PublishReply};
@@ -0,0 +1,104 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use async_nats::Subject;
+use tower::Service;
+use tracing::warn;
+
+use crate::{response::Response, MessageHead};
+
+use super::future::ResponseFuture;
+
+// JetStream messages have a reply subject as well, but it is for acknowledging the message, which
+// a response must not be taken for.
+const JETSTREAM_ACK_SUBJECT_PREFIX: &str = "$JS.ACK";
+
+#[derive(Clone, Debug)]
+pub struct PublishReply<S> {
+    pub(crate) inner: S,
+    pub(crate) client: async_nats::Client,
+}
+
+impl<S> PublishReply<S> {
+    pub fn new(inner: S, client: async_nats::Client) -> Self {
+        Self { inner, client }
+    }
+}
+
+impl<S, R> Service<R> for PublishReply<S>
+where
+    S: Service<R, Response = Response, Error = Infallible>,
+    S::Future: Send + 'static,
+    R: MessageHead,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = ResponseFuture;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let reply = reply_subject(&req);
+        let response = self.inner.call(req);
+        let client = self.client.clone();
+
+        let future = Box::pin(async move {
+            let response = response.await?;
+
+            if let Some(reply) = reply {
+                let (headers, body) = response.clone().into_parts();
+                let result = if headers.is_empty() {
+                    client.publish(reply.clone(), body).await
+                } else {
+                    client
+                        .publish_with_headers(reply.clone(), headers, body)
+                        .await
+                };
+                if let Err(err) = result {
+                    warn!(
+                        error = ?err,
+                        reply = reply.as_str(),
+                        "failed to publish reply to message",
+                    );
+                }
+            }
+
+            Ok(response)
+        });
+
+        ResponseFuture { future }
+    }
+}
+
+fn reply_subject<R>(req: &R) -> Option<Subject>
+where
+    R: MessageHead,
+{
+    req.reply()
+        .filter(|reply| !reply.as_str().starts_with(JETSTREAM_ACK_SUBJECT_PREFIX))
+        .cloned()
+}
+
+#[test]
+fn test_reply_subject_skips_jetstream_acks() {
+    use crate::message::test_message;
+
+    assert_eq!(
+        Some(Subject::from("_INBOX.01HXYZ")),
+        reply_subject(&test_message("veritech.fn", Some("_INBOX.01HXYZ"), "")),
+    );
+    assert_eq!(
+        None,
+        reply_subject(&test_message(
+            "veritech.fn",
+            Some("$JS.ACK.VERITECH.consumer.1.2.3.1718000000000000000.0"),
+            ""
+        )),
+    );
+    assert_eq!(None, reply_subject(&test_message("veritech.fn", None, "")));
+}
@@ -0,0 +1,22 @@
+use tower::Layer;
+
+use super::service::SubjectConcurrencyLimit;
+
+#[derive(Clone, Debug)]
+pub struct SubjectConcurrencyLimitLayer {
+    pub(crate) max_per_subject: usize,
+}
+
+impl SubjectConcurrencyLimitLayer {
+    pub fn new(max_per_subject: usize) -> Self {
+        Self { max_per_subject }
+    }
+}
+
+impl<S> Layer<S> for SubjectConcurrencyLimitLayer {
+    type Service = SubjectConcurrencyLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SubjectConcurrencyLimit::new(inner, self.max_per_subject)
+    }
+}
@@ -0,0 +1,13 @@
+//! Caps how many messages with the same subject a service processes at once.
+//!
+//! Messages with a subject that is at its limit wait for one of the others to finish, while
+//! messages with other subjects carry on. As a message's subject is only known once the service
+//! has been called with it, a waiting message keeps any capacity it was given by the services
+//! this wraps, so a [`ConcurrencyLimitLayer`](super::concurrency_limit::ConcurrencyLimitLayer)
+//! should be layered outside of this one rather than within it.
+
+mod future;
+mod layer;
+mod service;
+
+pub use self::{layer::SubjectConcurrencyLimitLayer, service::SubjectConcurrencyLimit};
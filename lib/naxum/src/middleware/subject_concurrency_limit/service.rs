@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Service, ServiceExt};
+
+use crate::MessageHead;
+
+use super::future::ResponseFuture;
+
+type Semaphores = Arc<Mutex<HashMap<String, Arc<Semaphore>>>>;
+
+#[derive(Clone, Debug)]
+pub struct SubjectConcurrencyLimit<S> {
+    pub(crate) inner: S,
+    pub(crate) max_per_subject: usize,
+    pub(crate) semaphores: Semaphores,
+}
+
+impl<S> SubjectConcurrencyLimit<S> {
+    pub fn new(inner: S, max_per_subject: usize) -> Self {
+        Self {
+            inner,
+            max_per_subject,
+            semaphores: Arc::default(),
+        }
+    }
+}
+
+impl<S, R> Service<R> for SubjectConcurrencyLimit<S>
+where
+    S: Service<R> + Clone + Send + 'static,
+    S::Future: Send,
+    R: MessageHead + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Response, S::Error>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let clone = self.inner.clone();
+        let inner = std::mem::replace(&mut self.inner, clone);
+
+        let mut subject = SubjectSemaphore::get(
+            self.semaphores.clone(),
+            req.subject().to_string(),
+            self.max_per_subject,
+        );
+
+        let future = Box::pin(async move {
+            subject.acquire().await;
+            let result = inner.oneshot(req).await;
+            drop(subject);
+
+            result
+        });
+
+        ResponseFuture { future }
+    }
+}
+
+// A handle on the semaphore of a subject, which forgets the subject once nothing else holds its
+// semaphore so that a service seeing many distinct subjects doesn't keep one for each of them.
+struct SubjectSemaphore {
+    semaphores: Semaphores,
+    subject: String,
+    semaphore: Arc<Semaphore>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl SubjectSemaphore {
+    fn get(semaphores: Semaphores, subject: String, max_per_subject: usize) -> Self {
+        let semaphore = lock(&semaphores)
+            .entry(subject.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(max_per_subject)))
+            .clone();
+
+        Self {
+            semaphores,
+            subject,
+            semaphore,
+            permit: None,
+        }
+    }
+
+    async fn acquire(&mut self) {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        self.permit = Some(permit);
+    }
+}
+
+impl Drop for SubjectSemaphore {
+    fn drop(&mut self) {
+        self.permit.take();
+        let mut semaphores = lock(&self.semaphores);
+        // Held by the map and this handle alone, so no other message with the subject is in flight
+        if Arc::strong_count(&self.semaphore) == 2 {
+            semaphores.remove(&self.subject);
+        }
+    }
+}
+
+fn lock(semaphores: &Semaphores) -> std::sync::MutexGuard<'_, HashMap<String, Arc<Semaphore>>> {
+    // The map is left consistent by every holder of the lock, so it's safe to use if poisoned
+    semaphores
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+#[derive(Clone, Default)]
+struct TestInFlight {
+    // The number of messages being processed, and the most there have been at once, by subject.
+    by_subject: Arc<Mutex<HashMap<String, (usize, usize)>>>,
+}
+
+#[cfg(test)]
+impl TestInFlight {
+    fn service(
+        &self,
+    ) -> impl Service<
+        async_nats::Message,
+        Response = (),
+        Error = std::convert::Infallible,
+        Future = impl Send,
+    > + Clone
+           + Send
+           + 'static {
+        let by_subject = self.by_subject.clone();
+        tower::service_fn(move |req: async_nats::Message| {
+            let by_subject = by_subject.clone();
+            async move {
+                let subject = req.subject.to_string();
+                {
+                    let mut by_subject = by_subject.lock().expect("lock is not poisoned");
+                    let (current, max) = by_subject.entry(subject.clone()).or_default();
+                    *current += 1;
+                    *max = (*max).max(*current);
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                if let Some((current, _)) = by_subject
+                    .lock()
+                    .expect("lock is not poisoned")
+                    .get_mut(&subject)
+                {
+                    *current -= 1;
+                }
+
+                Ok(())
+            }
+        })
+    }
+
+    fn max(&self, subject: &str) -> usize {
+        self.by_subject
+            .lock()
+            .expect("lock is not poisoned")
+            .get(subject)
+            .map_or(0, |(_, max)| *max)
+    }
+}
+
+#[tokio::test]
+async fn test_subject_concurrency_limit_limits_each_subject_separately() {
+    use crate::message::test_message;
+
+    let in_flight = TestInFlight::default();
+    let mut service = SubjectConcurrencyLimit::new(in_flight.service(), 2);
+
+    let calls: Vec<_> = ["a", "a", "a", "a", "b"]
+        .into_iter()
+        .map(|subject| service.call(test_message(subject, None, "")))
+        .collect();
+    for result in futures::future::join_all(calls).await {
+        result.expect("infallible");
+    }
+
+    assert_eq!(2, in_flight.max("a"));
+    assert_eq!(1, in_flight.max("b"));
+    assert!(lock(&service.semaphores).is_empty());
+}
+
+#[tokio::test]
+async fn test_subject_concurrency_limit_forgets_subjects_of_dropped_messages() {
+    use crate::message::test_message;
+
+    let in_flight = TestInFlight::default();
+    let mut service = SubjectConcurrencyLimit::new(in_flight.service(), 1);
+
+    let first = service.call(test_message("a", None, ""));
+    let mut waiting = service.call(test_message("a", None, ""));
+    let unstarted = service.call(test_message("b", None, ""));
+    assert_eq!(2, lock(&service.semaphores).len());
+
+    // The second message for the subject waits on the first, until it gives up.
+    let (result, _) = tokio::join!(first, async {
+        assert!(futures::poll!(&mut waiting).is_pending());
+        drop(waiting);
+    });
+    result.expect("infallible");
+    drop(unstarted);
+
+    assert_eq!(1, in_flight.max("a"));
+    assert_eq!(0, in_flight.max("b"));
+    assert!(lock(&service.semaphores).is_empty());
+}
@@ -0,0 +1,30 @@
+use std::{error, fmt, time::Duration};
+
+use async_nats::StatusCode;
+
+use crate::response::{IntoResponse, Response};
+
+/// The error returned when a message took longer to process than its timeout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Elapsed(pub(crate) Duration);
+
+impl Elapsed {
+    /// Returns the timeout that elapsed.
+    pub fn timeout(&self) -> Duration {
+        self.0
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message processing timed out after {:?}", self.0)
+    }
+}
+
+impl error::Error for Elapsed {}
+
+impl IntoResponse for Elapsed {
+    fn into_response(self) -> Response {
+        Response::error(StatusCode::TIMEOUT, self.to_string())
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use pin_project_lite::pin_project;
+use tokio::time::Sleep;
+
+use crate::response::{IntoResponse, Response};
+
+use super::error::Elapsed;
+
+pin_project! {
+    pub struct ResponseFuture<F> {
+        #[pin]
+        pub(crate) response: F,
+        #[pin]
+        pub(crate) sleep: Sleep,
+        pub(crate) timeout: Duration,
+    }
+}
+
+impl<F, T> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, Infallible>>,
+    T: IntoResponse,
+{
+    type Output = Result<Response, Infallible>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        // Poll the response first so that one which is ready wins over the timeout
+        if let Poll::Ready(result) = this.response.poll(cx) {
+            return Poll::Ready(result.map(IntoResponse::into_response));
+        }
+
+        match this.sleep.poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Ok(Elapsed(*this.timeout).into_response())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
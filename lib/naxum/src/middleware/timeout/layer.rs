@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use tower::Layer;
+
+use super::service::Timeout;
+
+#[derive(Clone, Debug)]
+pub struct TimeoutLayer {
+    pub(crate) timeout: Duration,
+}
+
+impl TimeoutLayer {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = Timeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Timeout {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
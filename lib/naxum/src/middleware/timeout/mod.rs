@@ -0,0 +1,11 @@
+//! Gives up on messages that a service takes too long to process.
+//!
+//! A message that times out is answered with an [`Elapsed`] error response, which has a
+//! `408 Timeout` status so that it can be told apart from a failure of the service itself.
+
+mod error;
+mod future;
+mod layer;
+mod service;
+
+pub use self::{error::Elapsed, layer::TimeoutLayer, service::Timeout};
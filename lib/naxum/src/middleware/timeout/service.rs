@@ -0,0 +1,99 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::time;
+use tower::Service;
+
+use crate::response::{IntoResponse, Response};
+
+use super::future::ResponseFuture;
+
+#[derive(Clone, Debug)]
+pub struct Timeout<S> {
+    pub(crate) inner: S,
+    pub(crate) timeout: Duration,
+}
+
+impl<S> Timeout<S> {
+    pub fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+impl<S, R> Service<R> for Timeout<S>
+where
+    S: Service<R, Error = Infallible>,
+    S::Response: IntoResponse,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let response = self.inner.call(req);
+        let sleep = time::sleep(self.timeout);
+
+        ResponseFuture {
+            response,
+            sleep,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_timeout_responds_with_elapsed_once_the_timeout_passes() {
+    use tower::ServiceExt;
+
+    use crate::message::test_message;
+
+    let timeout = Duration::from_millis(10);
+    let service = Timeout::new(
+        tower::service_fn(|_: async_nats::Message| {
+            std::future::pending::<Result<(), Infallible>>()
+        }),
+        timeout,
+    );
+
+    let response = match service.oneshot(test_message("a", None, "")).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    };
+
+    assert_eq!(async_nats::StatusCode::TIMEOUT, response.status());
+    assert_eq!(
+        Some(super::Elapsed(timeout).to_string().as_str()),
+        response
+            .headers()
+            .get(async_nats::service::NATS_SERVICE_ERROR)
+            .map(|value| value.as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_timeout_passes_on_responses_in_time() {
+    use tower::ServiceExt;
+
+    use crate::message::test_message;
+
+    let service = Timeout::new(
+        tower::service_fn(|_: async_nats::Message| async { Ok::<_, Infallible>("done") }),
+        Duration::from_secs(60),
+    );
+
+    let response = match service.oneshot(test_message("a", None, "")).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    };
+
+    assert!(response.status().is_success());
+    assert_eq!("done", response.body());
+}
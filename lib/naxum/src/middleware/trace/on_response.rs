@@ -59,7 +59,7 @@ impl DefaultOnResponse {
 }
 
 impl OnResponse for DefaultOnResponse {
-    fn on_response(self, _response: &Response, latency: Duration, _span: &Span) {
+    fn on_response(self, response: &Response, latency: Duration, _span: &Span) {
         let latency = Latency {
             unit: self.latency_unit,
             duration: latency,
@@ -68,6 +68,7 @@ impl OnResponse for DefaultOnResponse {
         event_dynamic_lvl!(
             self.level,
             %latency,
+            status = response.status().as_u16(),
             "finished processing mesasge"
         );
     }
@@ -1,8 +1,108 @@
+use async_nats::{
+    service::{NATS_SERVICE_ERROR, NATS_SERVICE_ERROR_CODE},
+    HeaderMap, StatusCode,
+};
+use bytes::Bytes;
+
 mod into_response;
 
 pub use self::into_response::IntoResponse;
 
-pub type Response = ();
+/// A reply to a message, made of a body and headers.
+///
+/// A response is published to the message's reply subject by the
+/// [`PublishReply`](crate::middleware::reply::PublishReply) middleware, if there is one. A
+/// response whose status is not a success is published with the `Nats-Service-Error-Code` header
+/// set, following the conventions of NATS services.
+#[derive(Clone, Debug, Default)]
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Response {
+    /// Creates a successful response with the given body.
+    pub fn new(body: impl Into<Bytes>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Creates an error response, describing the error with the `Nats-Service-Error` header.
+    pub fn error(status: StatusCode, description: impl AsRef<str>) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_SERVICE_ERROR, description.as_ref());
+
+        Self {
+            status,
+            headers,
+            body: Bytes::new(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn status_mut(&mut self) -> &mut StatusCode {
+        &mut self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut Bytes {
+        &mut self.body
+    }
+
+    /// Returns the headers and body to publish the response with.
+    pub fn into_parts(self) -> (HeaderMap, Bytes) {
+        let Self {
+            status,
+            mut headers,
+            body,
+        } = self;
+
+        if !status.is_success() && headers.get(NATS_SERVICE_ERROR_CODE).is_none() {
+            headers.insert(
+                NATS_SERVICE_ERROR_CODE,
+                status.as_u16().to_string().as_str(),
+            );
+        }
+
+        (headers, body)
+    }
+}
+
+/// Status codes for replies that the NATS server doesn't use itself, and so which have no
+/// constant on [`StatusCode`].
+pub mod status {
+    use async_nats::StatusCode;
+
+    pub fn bad_request() -> StatusCode {
+        status_code(400)
+    }
+
+    pub fn internal_server_error() -> StatusCode {
+        status_code(500)
+    }
+
+    fn status_code(code: u16) -> StatusCode {
+        StatusCode::from_u16(code).expect("status code is between 100 and 999")
+    }
+}
 
 pub type Result<T, E = ErrorResponse> = std::result::Result<T, E>;
 
@@ -26,7 +126,56 @@ where
     T: IntoResponse,
 {
     fn from(value: T) -> Self {
-        #[allow(clippy::unit_arg)]
         Self(value.into_response())
     }
 }
+
+#[test]
+fn test_response_into_parts_leaves_successes_alone() {
+    let mut response = Response::new("done");
+    response.headers_mut().insert("Content-Type", "text/plain");
+
+    let (headers, body) = response.into_parts();
+
+    assert_eq!("done", body);
+    assert_eq!(
+        Some("text/plain"),
+        headers.get("Content-Type").map(|value| value.as_str())
+    );
+    assert!(headers.get(NATS_SERVICE_ERROR).is_none());
+    assert!(headers.get(NATS_SERVICE_ERROR_CODE).is_none());
+}
+
+#[test]
+fn test_response_into_parts_sets_the_error_code_of_errors() {
+    let (headers, body) = Response::error(StatusCode::NOT_FOUND, "no route").into_parts();
+
+    assert!(body.is_empty());
+    assert_eq!(
+        Some("no route"),
+        headers.get(NATS_SERVICE_ERROR).map(|value| value.as_str())
+    );
+    assert_eq!(
+        Some("404"),
+        headers
+            .get(NATS_SERVICE_ERROR_CODE)
+            .map(|value| value.as_str())
+    );
+}
+
+#[test]
+fn test_response_into_parts_keeps_an_error_code_already_set() {
+    let mut response = Response::error(status::bad_request(), "bad input");
+    response
+        .headers_mut()
+        .insert(NATS_SERVICE_ERROR_CODE, "422");
+
+    let (headers, _) = response.into_parts();
+
+    assert_eq!(
+        Some("422"),
+        headers
+            .get(NATS_SERVICE_ERROR_CODE)
+            .map(|value| value.as_str())
+    );
+}
@@ -4,18 +4,24 @@ use std::{borrow::Cow, convert::Infallible};
 use async_nats::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use bytes::{buf::Chain, Buf, Bytes, BytesMut};
 
-use super::Response;
+use super::{status, Response};
 
 pub trait IntoResponse {
     fn into_response(self) -> Response;
 }
 
-impl IntoResponse for () {
+impl IntoResponse for Response {
     fn into_response(self) -> Response {
         self
     }
 }
 
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::default()
+    }
+}
+
 impl IntoResponse for Infallible {
     fn into_response(self) -> Response {
         match self {}
@@ -36,27 +42,42 @@ where
 }
 
 impl IntoResponse for &'static str {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Response::new(Bytes::from_static(self.as_bytes()))
+    }
 }
 
 impl IntoResponse for String {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Response::new(self)
+    }
 }
 
 impl IntoResponse for Box<str> {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        String::from(self).into_response()
+    }
 }
 
 impl IntoResponse for Cow<'static, str> {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        match self {
+            Cow::Borrowed(value) => value.into_response(),
+            Cow::Owned(value) => value.into_response(),
+        }
+    }
 }
 
 impl IntoResponse for Bytes {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Response::new(self)
+    }
 }
 
 impl IntoResponse for BytesMut {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        self.freeze().into_response()
+    }
 }
 
 impl<T, U> IntoResponse for Chain<T, U>
@@ -64,38 +85,82 @@ where
     T: Buf + Unpin + Send + 'static,
     U: Buf + Unpin + Send + 'static,
 {
-    fn into_response(self) -> Response {}
+    fn into_response(mut self) -> Response {
+        self.copy_to_bytes(self.remaining()).into_response()
+    }
 }
 
 impl IntoResponse for &'static [u8] {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Bytes::from_static(self).into_response()
+    }
 }
 
 impl<const N: usize> IntoResponse for [u8; N] {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        self.to_vec().into_response()
+    }
 }
 
 impl IntoResponse for Vec<u8> {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Response::new(self)
+    }
 }
 
 impl IntoResponse for Box<[u8]> {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        Vec::from(self).into_response()
+    }
 }
 
 impl IntoResponse for Cow<'static, [u8]> {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        match self {
+            Cow::Borrowed(value) => value.into_response(),
+            Cow::Owned(value) => value.into_response(),
+        }
+    }
 }
 
 impl<R> IntoResponse for (StatusCode, R)
 where
     R: IntoResponse,
 {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        let (status, res) = self;
+        let mut response = res.into_response();
+        *response.status_mut() = status;
+        response
+    }
 }
 
 impl IntoResponse for HeaderMap {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+impl<R> IntoResponse for (HeaderMap, R)
+where
+    R: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let (headers, res) = self;
+        let mut response = res.into_response();
+        extend_headers(response.headers_mut(), headers);
+        response
+    }
+}
+
+impl<R> IntoResponse for (StatusCode, HeaderMap, R)
+where
+    R: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let (status, headers, res) = self;
+        (status, (headers, res)).into_response()
+    }
 }
 
 impl<K, V, const N: usize> IntoResponse for [(K, V); N]
@@ -105,12 +170,67 @@ where
     V: TryInto<HeaderValue>,
     V::Error: fmt::Display,
 {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+impl<K, V, R, const N: usize> IntoResponse for ([(K, V); N], R)
+where
+    K: TryInto<HeaderName>,
+    K::Error: fmt::Display,
+    V: TryInto<HeaderValue>,
+    V::Error: fmt::Display,
+    R: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let (pairs, res) = self;
+
+        let mut headers = HeaderMap::new();
+        for (key, value) in pairs {
+            let key = match key.try_into() {
+                Ok(key) => key,
+                Err(err) => {
+                    return Response::error(
+                        status::internal_server_error(),
+                        format!("invalid header name: {err}"),
+                    )
+                }
+            };
+            let value = match value.try_into() {
+                Ok(value) => value,
+                Err(err) => {
+                    return Response::error(
+                        status::internal_server_error(),
+                        format!("invalid header value: {err}"),
+                    )
+                }
+            };
+            headers.insert(key, value);
+        }
+
+        (headers, res).into_response()
+    }
 }
 
 impl<R> IntoResponse for (R,)
 where
     R: IntoResponse,
 {
-    fn into_response(self) -> Response {}
+    fn into_response(self) -> Response {
+        self.0.into_response()
+    }
+}
+
+// Headers set explicitly replace any the inner response set with the same name
+fn extend_headers(headers: &mut HeaderMap, other: HeaderMap) {
+    for (name, values) in other.iter() {
+        let mut values = values.iter();
+        if let Some(value) = values.next() {
+            headers.insert(name.clone(), value.clone());
+        }
+        for value in values {
+            headers.append(name.clone(), value.clone());
+        }
+    }
 }
@@ -0,0 +1,411 @@
+//! Routing messages to handlers by their subject.
+
+use std::{
+    convert::Infallible,
+    fmt,
+    future::{ready, Ready},
+    task::{Context, Poll},
+};
+
+use async_nats::StatusCode;
+use futures::future::BoxFuture;
+use tower::Service;
+
+use crate::{
+    extract::{PathParams, PATH_PARAMS},
+    handler::Handler,
+    make_service::IntoMakeService,
+    response::{IntoResponse, Response},
+    MessageHead,
+};
+
+mod boxed;
+mod route;
+mod subject_pattern;
+
+pub use self::route::Route;
+
+use self::{boxed::BoxedIntoRoute, subject_pattern::SubjectPattern};
+
+/// A service that dispatches each message to the first route whose subject pattern matches the
+/// message's subject, or to a fallback when none do.
+///
+/// Routes are tried in the order they were added, so a route with a more specific pattern should
+/// be added before any route whose pattern would also match its subjects. See
+/// [`Router::route`] for the pattern syntax.
+///
+/// ```ignore
+/// let app = Router::new()
+///     .route("veritech.fn.resolverfunction.{change_set_id}", process_resolver_function)
+///     .route("veritech.fn.validation.*", process_validation)
+///     .nest("veritech.admin", admin_router)
+///     .fallback(unknown_request)
+///     .with_state(state);
+///
+/// naxum::serve(messages, app.into_make_service()).await?;
+/// ```
+pub struct Router<S = (), R = async_nats::Message> {
+    routes: Vec<(SubjectPattern, Endpoint<S, R>)>,
+    fallback: Endpoint<S, R>,
+    has_custom_fallback: bool,
+}
+
+impl<S, R> Router<S, R>
+where
+    S: Clone + Send + Sync + 'static,
+    R: MessageHead + Send + 'static,
+{
+    /// Creates a router without any routes, which replies to every message it receives with a
+    /// `404` error until some are added.
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            fallback: Endpoint::Route(Route::new(tower::service_fn(default_fallback::<R>))),
+            has_custom_fallback: false,
+        }
+    }
+
+    /// Adds a route sending messages whose subject matches `pattern` to `handler`.
+    ///
+    /// A pattern is made of `.`-separated subject tokens, each of which is one of:
+    ///
+    /// - a literal token, which must match exactly
+    /// - `*`, which matches any single token
+    /// - `{name}`, which matches any single token and names it
+    /// - `>`, which matches one or more trailing tokens
+    /// - `{*name}`, which matches one or more trailing tokens and names them
+    ///
+    /// Every token matched by a wildcard is captured, and can be extracted by the handler with
+    /// [`Path`](crate::extract::Path) or [`PathParams`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is not a valid subject pattern.
+    pub fn route<H, T>(self, pattern: &str, handler: H) -> Self
+    where
+        H: Handler<T, S, R>,
+        T: 'static,
+    {
+        self.push_route(
+            pattern,
+            Endpoint::Handler(BoxedIntoRoute::from_handler(handler)),
+        )
+    }
+
+    /// Adds a route sending messages whose subject matches `pattern` to `svc`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is not a valid subject pattern.
+    pub fn route_service<T>(self, pattern: &str, svc: T) -> Self
+    where
+        T: Service<R, Error = Infallible> + Clone + Send + 'static,
+        T::Response: IntoResponse + 'static,
+        T::Future: Send + 'static,
+    {
+        self.push_route(pattern, Endpoint::Route(Route::new(svc)))
+    }
+
+    /// Adds the routes of `router` with their patterns prefixed by `prefix`.
+    ///
+    /// If `router` has a fallback of its own, it receives the messages whose subject starts with
+    /// `prefix` but match none of `router`'s routes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is not a valid subject pattern, ends with a `>` wildcard or names a
+    /// captured token with the same name as a route of `router` does.
+    pub fn nest(mut self, prefix: &str, router: Router<S, R>) -> Self {
+        let prefix = parse_pattern(prefix);
+        let join = |pattern: &SubjectPattern| match prefix.join(pattern) {
+            Ok(pattern) => pattern,
+            Err(err) => panic!("{err}"),
+        };
+
+        let Router {
+            routes,
+            fallback,
+            has_custom_fallback,
+        } = router;
+        for (pattern, endpoint) in routes {
+            self.routes.push((join(&pattern), endpoint));
+        }
+        if has_custom_fallback {
+            match prefix.with_any_tail() {
+                Ok(pattern) => self.routes.push((pattern, fallback)),
+                Err(err) => panic!("{err}"),
+            }
+        }
+
+        self
+    }
+
+    /// Sets the handler that receives messages which match no route.
+    ///
+    /// The default fallback logs the subject of each such message and replies with a `404` error.
+    pub fn fallback<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, S, R>,
+        T: 'static,
+    {
+        self.fallback = Endpoint::Handler(BoxedIntoRoute::from_handler(handler));
+        self.has_custom_fallback = true;
+        self
+    }
+
+    /// Sets the service that receives messages which match no route.
+    pub fn fallback_service<T>(mut self, svc: T) -> Self
+    where
+        T: Service<R, Error = Infallible> + Clone + Send + 'static,
+        T::Response: IntoResponse + 'static,
+        T::Future: Send + 'static,
+    {
+        self.fallback = Endpoint::Route(Route::new(svc));
+        self.has_custom_fallback = true;
+        self
+    }
+
+    /// Provides the state for the router's handlers, returning a router that is missing no state.
+    pub fn with_state<S2>(self, state: S) -> Router<S2, R> {
+        let Self {
+            routes,
+            fallback,
+            has_custom_fallback,
+        } = self;
+
+        Router {
+            routes: routes
+                .into_iter()
+                .map(|(pattern, endpoint)| {
+                    (pattern, Endpoint::Route(endpoint.into_route(state.clone())))
+                })
+                .collect(),
+            fallback: Endpoint::Route(fallback.into_route(state)),
+            has_custom_fallback,
+        }
+    }
+
+    fn push_route(mut self, pattern: &str, endpoint: Endpoint<S, R>) -> Self {
+        self.routes.push((parse_pattern(pattern), endpoint));
+        self
+    }
+}
+
+impl<R> Router<(), R>
+where
+    R: MessageHead + Send + 'static,
+{
+    pub fn into_make_service(self) -> IntoMakeService<Self> {
+        IntoMakeService::new(self)
+    }
+}
+
+impl<S, R> Default for Router<S, R>
+where
+    S: Clone + Send + Sync + 'static,
+    R: MessageHead + Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, R> Clone for Router<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+            fallback: self.fallback.clone(),
+            has_custom_fallback: self.has_custom_fallback,
+        }
+    }
+}
+
+impl<S, R> fmt::Debug for Router<S, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router")
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|(pattern, _)| pattern.to_string())
+                    .collect::<Vec<_>>(),
+            )
+            .field("has_custom_fallback", &self.has_custom_fallback)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R> Service<R> for Router<(), R>
+where
+    R: MessageHead + Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Routes are called with `oneshot`, which waits for them to be ready
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let subject = req.subject().as_str();
+        let (endpoint, params) = self
+            .routes
+            .iter()
+            .find_map(|(pattern, endpoint)| {
+                pattern.matches(subject).map(|params| (endpoint, params))
+            })
+            .unwrap_or_else(|| (&self.fallback, PathParams::default()));
+        let route = endpoint.clone().into_route(());
+
+        Box::pin(PATH_PARAMS.scope(params, route.oneshot_inner(req)))
+    }
+}
+
+enum Endpoint<S, R> {
+    Route(Route<R>),
+    Handler(BoxedIntoRoute<S, R>),
+}
+
+impl<S, R> Endpoint<S, R> {
+    fn into_route(self, state: S) -> Route<R> {
+        match self {
+            Self::Route(route) => route,
+            Self::Handler(handler) => handler.into_route(state),
+        }
+    }
+}
+
+impl<S, R> Clone for Endpoint<S, R> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Route(route) => Self::Route(route.clone()),
+            Self::Handler(handler) => Self::Handler(handler.clone()),
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> SubjectPattern {
+    match SubjectPattern::parse(pattern) {
+        Ok(pattern) => pattern,
+        Err(err) => panic!("{err}"),
+    }
+}
+
+fn default_fallback<R>(req: R) -> Ready<Result<Response, Infallible>>
+where
+    R: MessageHead,
+{
+    tracing::warn!(
+        subject = req.subject().as_str(),
+        "no route matched message subject"
+    );
+    ready(Ok(Response::error(
+        StatusCode::NOT_FOUND,
+        "no route matched message subject",
+    )))
+}
+
+#[cfg(test)]
+async fn test_call(router: &Router, subject: &str) -> Response {
+    use tower::ServiceExt;
+
+    let message = crate::message::test_message(subject, None, "");
+    match router.clone().oneshot(message).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    }
+}
+
+#[tokio::test]
+async fn test_router_sends_messages_to_the_first_matching_route() {
+    let router = Router::new()
+        .route("veritech.fn.resolverfunction", || async { "resolver" })
+        .route("veritech.fn.*", || async { "any function" })
+        .route("veritech.fn.validation", || async { "unreachable" });
+
+    assert_eq!(
+        "resolver",
+        test_call(&router, "veritech.fn.resolverfunction")
+            .await
+            .body()
+    );
+    assert_eq!(
+        "any function",
+        test_call(&router, "veritech.fn.validation").await.body()
+    );
+}
+
+#[tokio::test]
+async fn test_router_falls_back_when_no_route_matches() {
+    let router = Router::new().route("veritech.fn.*", || async { "any function" });
+
+    let response = test_call(&router, "veritech.admin").await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let router = router.fallback(|| async { "fallback" });
+    assert_eq!(
+        "fallback",
+        test_call(&router, "veritech.admin").await.body()
+    );
+    assert_eq!(
+        "any function",
+        test_call(&router, "veritech.fn.validation").await.body()
+    );
+}
+
+#[tokio::test]
+async fn test_router_nests_routers_under_a_prefix() {
+    use crate::extract::Path;
+
+    let admin = Router::new()
+        .route(
+            "{command}",
+            |Path(command): Path<String>| async move { command },
+        )
+        .fallback(|| async { "admin fallback" });
+    let router = Router::new()
+        .nest("veritech.admin", admin)
+        .nest(
+            "veritech.fn",
+            Router::new().route("*", || async { "any function" }),
+        )
+        .route("veritech.*", || async { "unreachable by admin" })
+        .fallback(|| async { "fallback" });
+
+    assert_eq!(
+        "restart",
+        test_call(&router, "veritech.admin.restart").await.body()
+    );
+    // The nested router's fallback receives everything under its prefix.
+    assert_eq!(
+        "admin fallback",
+        test_call(&router, "veritech.admin.restart.now")
+            .await
+            .body()
+    );
+    assert_eq!(
+        "any function",
+        test_call(&router, "veritech.fn.validation").await.body()
+    );
+    // A nested router without a fallback of its own leaves the rest to the outer router.
+    assert_eq!(
+        "unreachable by admin",
+        test_call(&router, "veritech.status").await.body()
+    );
+    assert_eq!(
+        "fallback",
+        test_call(&router, "veritech.fn.validation.extra")
+            .await
+            .body()
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid subject pattern")]
+fn test_router_panics_on_an_invalid_pattern() {
+    let _ = Router::<()>::new().route("veritech.>.fn", || async {});
+}
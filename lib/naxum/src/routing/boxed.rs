@@ -0,0 +1,69 @@
+use std::marker::PhantomData;
+
+use crate::{handler::Handler, MessageHead};
+
+use super::Route;
+
+/// A handler which has not been given its state yet.
+pub(crate) struct BoxedIntoRoute<S, R>(Box<dyn ErasedIntoRoute<S, R>>);
+
+impl<S, R> BoxedIntoRoute<S, R>
+where
+    S: Clone + Send + Sync + 'static,
+    R: MessageHead + Send + 'static,
+{
+    pub(crate) fn from_handler<H, T>(handler: H) -> Self
+    where
+        H: Handler<T, S, R>,
+        T: 'static,
+    {
+        Self(Box::new(MakeErasedHandler {
+            handler,
+            into_route: |handler, state| Route::new(Handler::<T, S, R>::with_state(handler, state)),
+            _marker: PhantomData,
+        }))
+    }
+}
+
+impl<S, R> BoxedIntoRoute<S, R> {
+    pub(crate) fn into_route(self, state: S) -> Route<R> {
+        self.0.into_route(state)
+    }
+}
+
+impl<S, R> Clone for BoxedIntoRoute<S, R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+trait ErasedIntoRoute<S, R>: Send {
+    fn clone_box(&self) -> Box<dyn ErasedIntoRoute<S, R>>;
+
+    fn into_route(self: Box<Self>, state: S) -> Route<R>;
+}
+
+struct MakeErasedHandler<H, S, R> {
+    handler: H,
+    into_route: fn(H, S) -> Route<R>,
+    _marker: PhantomData<fn() -> (S, R)>,
+}
+
+impl<H, S, R> ErasedIntoRoute<S, R> for MakeErasedHandler<H, S, R>
+where
+    H: Clone + Send + 'static,
+    S: 'static,
+    R: 'static,
+{
+    fn clone_box(&self) -> Box<dyn ErasedIntoRoute<S, R>> {
+        Box::new(Self {
+            handler: self.handler.clone(),
+            into_route: self.into_route,
+            _marker: PhantomData,
+        })
+    }
+
+    fn into_route(self: Box<Self>, state: S) -> Route<R> {
+        (self.into_route)(self.handler, state)
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    convert::Infallible,
+    fmt,
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tower::{
+    util::{BoxCloneService, Oneshot},
+    Service, ServiceExt,
+};
+
+use crate::response::{IntoResponse, Response};
+
+/// A type-erased service that a [`Router`](super::Router) dispatches messages to.
+pub struct Route<R>(BoxCloneService<R, Response, Infallible>);
+
+impl<R> Route<R> {
+    pub(crate) fn new<T>(svc: T) -> Self
+    where
+        T: Service<R, Error = Infallible> + Clone + Send + 'static,
+        T::Response: IntoResponse + 'static,
+        T::Future: Send + 'static,
+    {
+        Self(BoxCloneService::new(
+            svc.map_response(IntoResponse::into_response),
+        ))
+    }
+
+    pub(crate) fn oneshot_inner(
+        self,
+        req: R,
+    ) -> Oneshot<BoxCloneService<R, Response, Infallible>, R> {
+        self.0.oneshot(req)
+    }
+}
+
+impl<R> Clone for Route<R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<R> fmt::Debug for Route<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route").finish()
+    }
+}
+
+impl<R> Service<R> for Route<R>
+where
+    R: 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, req: R) -> Self::Future {
+        self.0.call(req)
+    }
+}
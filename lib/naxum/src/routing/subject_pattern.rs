@@ -0,0 +1,299 @@
+use std::{collections::HashSet, error, fmt, sync::Arc};
+
+use crate::extract::PathParams;
+
+/// A NATS subject pattern that a route matches messages against.
+///
+/// Patterns are made of `.`-separated tokens, each of which is one of:
+///
+/// - a literal token, such as `veritech`, which must match exactly
+/// - `*`, which matches any single token and captures it
+/// - `{name}`, which matches any single token and captures it as `name`
+/// - `>`, which matches one or more trailing tokens and captures them, joined with `.`
+/// - `{*name}`, which is like `>` but captures the trailing tokens as `name`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SubjectPattern {
+    tokens: Vec<Token>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Literal(String),
+    Single(Option<Arc<str>>),
+    Tail(Option<Arc<str>>),
+}
+
+impl SubjectPattern {
+    pub(crate) fn parse(pattern: &str) -> Result<Self, InvalidSubjectPattern> {
+        let invalid = |reason| InvalidSubjectPattern {
+            pattern: pattern.to_owned(),
+            reason,
+        };
+
+        let mut tokens = Vec::new();
+        for token in pattern.split('.') {
+            let token = match token {
+                "" => return Err(invalid("subject tokens cannot be empty")),
+                "*" => Token::Single(None),
+                ">" => Token::Tail(None),
+                token if token.starts_with('{') && token.ends_with('}') => {
+                    let name = &token[1..token.len() - 1];
+                    let (name, is_tail) = match name.strip_prefix('*') {
+                        Some(name) => (name, true),
+                        None => (name, false),
+                    };
+                    if name.is_empty() {
+                        return Err(invalid("captured tokens must be named"));
+                    }
+                    if is_tail {
+                        Token::Tail(Some(name.into()))
+                    } else {
+                        Token::Single(Some(name.into()))
+                    }
+                }
+                token if token.contains(['*', '>', '{', '}', ' ']) => {
+                    return Err(invalid(
+                        "wildcards and captures must make up an entire subject token",
+                    ))
+                }
+                token => Token::Literal(token.to_owned()),
+            };
+            tokens.push(token);
+        }
+
+        Self::new(pattern, tokens)
+    }
+
+    /// Returns the pattern matching `self` followed by `pattern`.
+    pub(crate) fn join(&self, pattern: &Self) -> Result<Self, InvalidSubjectPattern> {
+        let tokens = self
+            .tokens
+            .iter()
+            .chain(pattern.tokens.iter())
+            .cloned()
+            .collect();
+
+        Self::new(&format!("{self}.{pattern}"), tokens)
+    }
+
+    /// Returns the pattern matching every subject that starts with `self`.
+    pub(crate) fn with_any_tail(&self) -> Result<Self, InvalidSubjectPattern> {
+        self.join(&Self {
+            tokens: vec![Token::Tail(None)],
+        })
+    }
+
+    /// Returns the tokens captured from `subject` if it matches the pattern.
+    pub(crate) fn matches(&self, subject: &str) -> Option<PathParams> {
+        let mut params = Vec::new();
+        let mut subject_tokens = subject.split('.');
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(literal) => {
+                    if subject_tokens.next()? != literal {
+                        return None;
+                    }
+                }
+                Token::Single(name) => {
+                    params.push((name.clone(), subject_tokens.next()?.to_owned()));
+                }
+                Token::Tail(name) => {
+                    let tail: Vec<_> = subject_tokens.collect();
+                    if tail.is_empty() {
+                        return None;
+                    }
+                    params.push((name.clone(), tail.join(".")));
+
+                    return Some(PathParams::new(params));
+                }
+            }
+        }
+
+        match subject_tokens.next() {
+            Some(_) => None,
+            None => Some(PathParams::new(params)),
+        }
+    }
+
+    fn new(pattern: &str, tokens: Vec<Token>) -> Result<Self, InvalidSubjectPattern> {
+        let invalid = |reason| InvalidSubjectPattern {
+            pattern: pattern.to_owned(),
+            reason,
+        };
+
+        let mut names = HashSet::new();
+        for (index, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Literal(_) => {}
+                Token::Single(name) => {
+                    if let Some(name) = name {
+                        if !names.insert(name.clone()) {
+                            return Err(invalid("captured token names must be unique"));
+                        }
+                    }
+                }
+                Token::Tail(name) => {
+                    if index != tokens.len() - 1 {
+                        return Err(invalid("`>` may only be the last subject token"));
+                    }
+                    if let Some(name) = name {
+                        if !names.insert(name.clone()) {
+                            return Err(invalid("captured token names must be unique"));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Self { tokens })
+    }
+}
+
+impl fmt::Display for SubjectPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, token) in self.tokens.iter().enumerate() {
+            if index > 0 {
+                f.write_str(".")?;
+            }
+            match token {
+                Token::Literal(literal) => f.write_str(literal)?,
+                Token::Single(None) => f.write_str("*")?,
+                Token::Single(Some(name)) => write!(f, "{{{name}}}")?,
+                Token::Tail(None) => f.write_str(">")?,
+                Token::Tail(Some(name)) => write!(f, "{{*{name}}}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct InvalidSubjectPattern {
+    pattern: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for InvalidSubjectPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid subject pattern `{}`: {}",
+            self.pattern, self.reason
+        )
+    }
+}
+
+impl error::Error for InvalidSubjectPattern {}
+
+#[test]
+fn test_subject_pattern_matches_literals() {
+    let pattern = SubjectPattern::parse("veritech.fn").expect("pattern is valid");
+
+    assert!(pattern.matches("veritech.fn").expect("matches").is_empty());
+    assert!(pattern.matches("veritech").is_none());
+    assert!(pattern.matches("veritech.fn.resolverfunction").is_none());
+    assert!(pattern.matches("veritech.other").is_none());
+}
+
+#[test]
+fn test_subject_pattern_captures_single_tokens() {
+    let pattern = SubjectPattern::parse("veritech.{kind}.*").expect("pattern is valid");
+
+    let params = pattern.matches("veritech.fn.01HXYZ").expect("matches");
+    assert_eq!(
+        vec![(Some("kind"), "fn"), (None, "01HXYZ")],
+        params.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(Some("fn"), params.get("kind"));
+    assert!(pattern.matches("veritech.fn").is_none());
+    assert!(pattern.matches("veritech.fn.01HXYZ.extra").is_none());
+}
+
+#[test]
+fn test_subject_pattern_captures_tails() {
+    let unnamed = SubjectPattern::parse("veritech.>").expect("pattern is valid");
+    let named = SubjectPattern::parse("veritech.{*rest}").expect("pattern is valid");
+
+    assert_eq!(
+        vec![(None, "fn.resolverfunction")],
+        unnamed
+            .matches("veritech.fn.resolverfunction")
+            .expect("matches")
+            .iter()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some("fn"),
+        named.matches("veritech.fn").expect("matches").get("rest")
+    );
+    // A tail matches at least one token.
+    assert!(unnamed.matches("veritech").is_none());
+    assert!(named.matches("veritech").is_none());
+}
+
+#[test]
+fn test_subject_pattern_rejects_invalid_patterns() {
+    for pattern in [
+        "",
+        "veritech..fn",
+        "veritech.",
+        "veritech.fn*",
+        "veritech.f>n",
+        "veritech.{kind",
+        "veritech.{}",
+        "veritech.{*}",
+        "veritech.with space",
+        "veritech.>.fn",
+        "veritech.{*rest}.fn",
+        "veritech.{id}.{id}",
+        "veritech.{id}.{*id}",
+    ] {
+        assert!(
+            SubjectPattern::parse(pattern).is_err(),
+            "`{pattern}` should be invalid"
+        );
+    }
+}
+
+#[test]
+fn test_subject_pattern_joins_patterns() {
+    let prefix = SubjectPattern::parse("veritech.{change_set_id}").expect("pattern is valid");
+
+    let joined = prefix
+        .join(&SubjectPattern::parse("fn.*").expect("pattern is valid"))
+        .expect("patterns join");
+    assert_eq!("veritech.{change_set_id}.fn.*", joined.to_string());
+    assert_eq!(
+        Some("01HXYZ"),
+        joined
+            .matches("veritech.01HXYZ.fn.resolverfunction")
+            .expect("matches")
+            .get("change_set_id")
+    );
+
+    assert_eq!(
+        "veritech.{change_set_id}.>",
+        prefix.with_any_tail().expect("patterns join").to_string()
+    );
+    assert!(prefix
+        .join(&SubjectPattern::parse("{change_set_id}").expect("pattern is valid"))
+        .is_err());
+    assert!(SubjectPattern::parse("veritech.>")
+        .expect("pattern is valid")
+        .join(&prefix)
+        .is_err());
+}
+
+#[test]
+fn test_subject_pattern_displays_as_parsed() {
+    for pattern in ["veritech", "veritech.*.{kind}.>", "veritech.{*rest}"] {
+        assert_eq!(
+            pattern,
+            SubjectPattern::parse(pattern)
+                .expect("pattern is valid")
+                .to_string()
+        );
+    }
+}
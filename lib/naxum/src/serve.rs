@@ -5,9 +5,11 @@ use std::{
     io,
     marker::PhantomData,
     ops,
+    time::Duration,
 };
 
 use futures::{Stream, TryStreamExt};
+use tokio::time;
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tower::{Service, ServiceExt};
 use tracing::{trace, warn};
@@ -16,6 +18,10 @@ use crate::{message::MessageHead, response::Response};
 
 const MAX_FAILED_MESSAGES: usize = 4;
 
+/// How long to wait for in-flight messages on shutdown, unless set with
+/// [`WithGracefulShutdown::with_drain_timeout`].
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub fn serve<M, S, T, E, R>(stream: T, make_service: M) -> Serve<M, S, T, E, R>
 where
     M: for<'a> Service<IncomingMessage<'a, R>, Error = Infallible, Response = S>,
@@ -63,6 +69,7 @@ impl<M, S, T, E, R> Serve<M, S, T, E, R> {
             stream: self.stream,
             make_service: self.make_service,
             signal,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
             _service_marker: PhantomData,
             _stream_error_marker: PhantomData,
             _request_marker: PhantomData,
@@ -71,11 +78,15 @@ impl<M, S, T, E, R> Serve<M, S, T, E, R> {
 }
 
 /// Serve future with supporting a  graceful shutdown.
+///
+/// Once the shutdown signal is received no more messages are taken from the stream, and the
+/// future waits for the messages already in flight to be processed before completing.
 #[must_use = "futures must be awaited or polled"]
 pub struct WithGracefulShutdown<M, S, T, E, R, F> {
     stream: T,
     make_service: M,
     signal: F,
+    drain_timeout: Duration,
     _service_marker: PhantomData<S>,
     _stream_error_marker: PhantomData<E>,
     _request_marker: PhantomData<R>,
@@ -91,10 +102,22 @@ where
         f.debug_struct("WithGracefulShutdown")
             .field("make_service", &self.make_service)
             .field("signal", &self.signal)
+            .field("drain_timeout", &self.drain_timeout)
             .finish_non_exhaustive()
     }
 }
 
+impl<M, S, T, E, R, F> WithGracefulShutdown<M, S, T, E, R, F> {
+    /// Limits how long to wait for in-flight messages on shutdown, after which any that are still
+    /// being processed are abandoned.
+    ///
+    /// Defaults to [`DEFAULT_DRAIN_TIMEOUT`].
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+}
+
 impl<M, S, T, E, R, F> IntoFuture for WithGracefulShutdown<M, S, T, E, R, F>
 where
     M: for<'a> Service<IncomingMessage<'a, R>, Error = Infallible, Response = S> + Send + 'static,
@@ -114,14 +137,17 @@ where
             mut stream,
             mut make_service,
             signal,
+            drain_timeout,
             ..
         } = self;
 
         let tracker = TaskTracker::new();
         let graceful_token = CancellationToken::new();
+        // Cancelled to abandon in-flight messages if they don't finish within the drain timeout
+        let abandon_token = CancellationToken::new();
 
         let token = graceful_token.clone();
-        tracker.spawn(async move {
+        let signal_handle = tokio::spawn(async move {
             signal.await;
             trace!("received graceful shutdown signal, telling tasks to shutdown");
             token.cancel();
@@ -153,7 +179,6 @@ where
                     }
                     _ = graceful_token.cancelled() => {
                         trace!("signal received, not accepting new messages");
-                        tracker.close();
                         break;
                     }
                 };
@@ -164,25 +189,50 @@ where
                     .await
                     .unwrap_or_else(|err| match err {});
 
-                let tower_svc = make_service
+                let mut tower_svc = make_service
                     .call(IncomingMessage { msg: &msg })
                     .await
                     .unwrap_or_else(|err| match err {});
 
-                let graceful_token = graceful_token.clone();
+                // Wait until the service has capacity for the message before spawning it, so
+                // that no more messages are taken from the stream while the service is saturated
+                tokio::select! {
+                    ready = tower_svc.ready() => {
+                        ready.unwrap_or_else(|err| match err {});
+                    }
+                    _ = graceful_token.cancelled() => {
+                        trace!(
+                            subject = msg.subject().as_str(),
+                            "signal received while waiting for service capacity, not processing message",
+                        );
+                        break;
+                    }
+                }
+
+                let abandon_token = abandon_token.clone();
                 tracker.spawn(async move {
                     tokio::select! {
-                        _result = tower_svc.oneshot(msg) => {
-                            // huh
-                        }
-                        _ = graceful_token.cancelled() => {
-                            trace!("signal received in task, starting graceful shutdown");
+                        _response = tower_svc.call(msg) => {}
+                        _ = abandon_token.cancelled() => {
+                            trace!("drain timeout elapsed, abandoning in-flight message");
                         }
                     }
                 });
             }
 
-            tracker.wait().await;
+            signal_handle.abort();
+            tracker.close();
+
+            trace!(in_flight = tracker.len(), "waiting for in-flight messages");
+            if time::timeout(drain_timeout, tracker.wait()).await.is_err() {
+                warn!(
+                    in_flight = tracker.len(),
+                    ?drain_timeout,
+                    "in-flight messages not processed within drain timeout, abandoning",
+                );
+                abandon_token.cancel();
+                tracker.wait().await;
+            }
 
             Ok(())
         }))
@@ -7,6 +7,7 @@ use si_crypto::{CryptoConfig, SymmetricCryptoServiceConfig, SymmetricCryptoServi
 use si_data_nats::NatsConfig;
 use si_data_pg::PgPoolConfig;
 use si_layer_cache::error::LayerDbError;
+use si_layer_cache::LayerDbConfig;
 use si_std::{CanonicalFile, CanonicalFileError};
 use telemetry::prelude::*;
 use thiserror::Error;
@@ -62,6 +63,9 @@ pub struct Config {
     #[builder(default = "si_layer_cache::default_pg_pool_config()")]
     layer_cache_pg_pool: PgPoolConfig,
 
+    #[builder(default = "LayerDbConfig::default()")]
+    layer_db: LayerDbConfig,
+
     layer_cache_sled_path: CanonicalFile,
 }
 
@@ -116,6 +120,12 @@ impl Config {
     pub fn layer_cache_sled_path(&self) -> &Path {
         self.layer_cache_sled_path.as_path()
     }
+
+    /// Gets a reference to the config's layer db config.
+    #[must_use]
+    pub fn layer_db(&self) -> &LayerDbConfig {
+        &self.layer_db
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -125,6 +135,8 @@ pub struct ConfigFile {
     #[serde(default = "si_layer_cache::default_pg_pool_config")]
     layer_cache_pg_pool: PgPoolConfig,
     #[serde(default)]
+    layer_db: LayerDbConfig,
+    #[serde(default)]
     nats: NatsConfig,
     #[serde(default)]
     crypto: CryptoConfig,
@@ -141,6 +153,7 @@ impl Default for ConfigFile {
         Self {
             pg: Default::default(),
             layer_cache_pg_pool: si_layer_cache::default_pg_pool_config(),
+            layer_db: Default::default(),
             nats: Default::default(),
             concurrency_limit: default_concurrency_limit(),
             crypto: Default::default(),
@@ -163,6 +176,7 @@ impl TryFrom<ConfigFile> for Config {
         let mut config = Config::builder();
         config.pg_pool(value.pg);
         config.layer_cache_pg_pool(value.layer_cache_pg_pool);
+        config.layer_db(value.layer_db);
         config.nats(value.nats);
         config.crypto(value.crypto);
         config.concurrency(value.concurrency_limit);
@@ -119,10 +119,11 @@ impl Server {
         let symmetric_crypto_service =
             Self::create_symmetric_crypto_service(config.symmetric_crypto_service()).await?;
 
-        let (layer_db, layer_db_graceful_shutdown) = LayerDb::initialize(
+        let (layer_db, layer_db_graceful_shutdown) = LayerDb::initialize_with_config(
             config.layer_cache_sled_path(),
             PgPool::new(config.layer_cache_pg_pool()).await?,
             nats.clone(),
+            config.layer_db().clone(),
             token,
         )
         .await?;
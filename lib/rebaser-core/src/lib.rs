@@ -56,6 +56,10 @@ pub enum ReplyRebaseMessage {
     Success {
         /// The serialized updates performed when rebasing.
         updates_performed: Value,
+        /// A serialized list of the conflicts that were resolved automatically, along with the
+        /// strategy used to resolve each of them.
+        #[serde(default)]
+        conflicts_resolved: Value,
     },
     /// Conflicts found when processing the request.
     ConflictsFound {
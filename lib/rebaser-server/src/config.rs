@@ -1,6 +1,7 @@
 use std::{env, path::Path};
 
 use buck2_resources::Buck2Resources;
+use dal::workspace_snapshot::conflict::ConflictResolutionPolicy;
 use derive_builder::Builder;
 use rebaser_core::RebaserMessagingConfig;
 use serde::{Deserialize, Serialize};
@@ -8,6 +9,7 @@ use si_crypto::{SymmetricCryptoServiceConfig, SymmetricCryptoServiceConfigFile};
 use si_data_nats::NatsConfig;
 use si_data_pg::PgPoolConfig;
 use si_layer_cache::error::LayerDbError;
+use si_layer_cache::LayerDbConfig;
 use si_std::{CanonicalFile, CanonicalFileError};
 use telemetry::prelude::*;
 use thiserror::Error;
@@ -58,7 +60,16 @@ pub struct Config {
     #[builder(default = "si_layer_cache::default_pg_pool_config()")]
     layer_cache_pg_pool: PgPoolConfig,
 
+    #[builder(default = "LayerDbConfig::default()")]
+    layer_db: LayerDbConfig,
+
     layer_cache_sled_path: CanonicalFile,
+
+    #[builder(default)]
+    conflict_resolution_policy: ConflictResolutionPolicy,
+
+    #[builder(default)]
+    validate_snapshots: bool,
 }
 
 impl StandardConfig for Config {
@@ -110,6 +121,24 @@ impl Config {
     pub fn layer_cache_sled_path(&self) -> &Path {
         self.layer_cache_sled_path.as_path()
     }
+
+    /// Gets a reference to the config's layer db config.
+    #[must_use]
+    pub fn layer_db(&self) -> &LayerDbConfig {
+        &self.layer_db
+    }
+
+    /// Gets a reference to the policy used to automatically resolve conflicts when rebasing.
+    #[must_use]
+    pub fn conflict_resolution_policy(&self) -> &ConflictResolutionPolicy {
+        &self.conflict_resolution_policy
+    }
+
+    /// Whether workspace snapshot graphs are validated before they are written.
+    #[must_use]
+    pub fn validate_snapshots(&self) -> bool {
+        self.validate_snapshots
+    }
 }
 
 /// The configuration file for creating a [`Server`].
@@ -120,6 +149,8 @@ pub struct ConfigFile {
     #[serde(default = "si_layer_cache::default_pg_pool_config")]
     layer_cache_pg_pool: PgPoolConfig,
     #[serde(default)]
+    layer_db: LayerDbConfig,
+    #[serde(default)]
     nats: NatsConfig,
     #[serde(default = "default_cyclone_encryption_key_path")]
     cyclone_encryption_key_path: String,
@@ -127,6 +158,10 @@ pub struct ConfigFile {
     symmetric_crypto_service: SymmetricCryptoServiceConfigFile,
     #[serde(default)]
     messaging_config: RebaserMessagingConfig,
+    #[serde(default)]
+    conflict_resolution_policy: ConflictResolutionPolicy,
+    #[serde(default)]
+    validate_snapshots: bool,
 }
 
 impl Default for ConfigFile {
@@ -134,10 +169,13 @@ impl Default for ConfigFile {
         Self {
             pg: Default::default(),
             layer_cache_pg_pool: si_layer_cache::default_pg_pool_config(),
+            layer_db: Default::default(),
             nats: Default::default(),
             cyclone_encryption_key_path: default_cyclone_encryption_key_path(),
             symmetric_crypto_service: default_symmetric_crypto_config(),
             messaging_config: Default::default(),
+            conflict_resolution_policy: Default::default(),
+            validate_snapshots: false,
         }
     }
 }
@@ -155,9 +193,12 @@ impl TryFrom<ConfigFile> for Config {
         let mut config = Config::builder();
         config.pg_pool(value.pg);
         config.layer_cache_pg_pool(value.layer_cache_pg_pool);
+        config.layer_db(value.layer_db);
         config.nats(value.nats);
         config.cyclone_encryption_key_path(value.cyclone_encryption_key_path.try_into()?);
         config.symmetric_crypto_service(value.symmetric_crypto_service.try_into()?);
+        config.conflict_resolution_policy(value.conflict_resolution_policy);
+        config.validate_snapshots(value.validate_snapshots);
         config.layer_cache_sled_path = Some(si_layer_cache::default_sled_path()?);
         config.build().map_err(Into::into)
     }
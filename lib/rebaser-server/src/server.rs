@@ -1,5 +1,6 @@
 use std::{future::IntoFuture, io, path::Path, sync::Arc};
 
+use dal::workspace_snapshot::conflict::ConflictResolutionPolicy;
 use dal::{DalLayerDb, InitializationError, JobQueueProcessor, NatsProcessor};
 use si_crypto::SymmetricCryptoServiceConfig;
 use si_crypto::{SymmetricCryptoError, SymmetricCryptoService};
@@ -75,6 +76,10 @@ pub struct Server {
     graceful_shutdown_rx: oneshot::Receiver<()>,
     /// The layer db
     layer_db: DalLayerDb,
+    /// The policy used to automatically resolve conflicts found when rebasing
+    conflict_resolution_policy: ConflictResolutionPolicy,
+    /// Whether workspace snapshot graphs are validated before they are written
+    validate_snapshots: bool,
 }
 
 impl Server {
@@ -96,10 +101,11 @@ impl Server {
         let symmetric_crypto_service =
             Self::create_symmetric_crypto_service(config.symmetric_crypto_service()).await?;
 
-        let (layer_db, layer_db_graceful_shutdown) = DalLayerDb::initialize(
+        let (layer_db, layer_db_graceful_shutdown) = DalLayerDb::initialize_with_config(
             config.layer_cache_sled_path(),
             PgPool::new(config.layer_cache_pg_pool()).await?,
             nats.clone(),
+            config.layer_db().clone(),
             token,
         )
         .await?;
@@ -113,6 +119,8 @@ impl Server {
             job_processor,
             symmetric_crypto_service,
             layer_db,
+            *config.conflict_resolution_policy(),
+            config.validate_snapshots(),
         )
     }
 
@@ -127,6 +135,8 @@ impl Server {
         job_processor: Box<dyn JobQueueProcessor + Send + Sync>,
         symmetric_crypto_service: SymmetricCryptoService,
         layer_db: DalLayerDb,
+        conflict_resolution_policy: ConflictResolutionPolicy,
+        validate_snapshots: bool,
     ) -> ServerResult<Self> {
         // An mpsc channel which can be used to externally shut down the server.
         let (external_shutdown_tx, external_shutdown_rx) = mpsc::channel(4);
@@ -151,6 +161,8 @@ impl Server {
             external_shutdown_tx,
             graceful_shutdown_rx,
             layer_db,
+            conflict_resolution_policy,
+            validate_snapshots,
         })
     }
 
@@ -166,6 +178,8 @@ impl Server {
             self.encryption_key,
             self.shutdown_watch_rx,
             self.layer_db,
+            self.conflict_resolution_policy,
+            self.validate_snapshots,
         )
         .await?;
 
@@ -1,3 +1,4 @@
+use dal::workspace_snapshot::conflict::ConflictResolutionPolicy;
 use dal::{
     DalContext, DalContextBuilder, DalLayerDb, JobQueueProcessor, ServicesContext, Tenancy,
     TransactionsError, Visibility, WorkspacePk,
@@ -52,8 +53,10 @@ pub(crate) async fn setup_and_run_core_loop(
     encryption_key: Arc<veritech_client::CycloneEncryptionKey>,
     shutdown_watch_rx: watch::Receiver<()>,
     layer_db: DalLayerDb,
+    conflict_resolution_policy: ConflictResolutionPolicy,
+    validate_snapshots: bool,
 ) -> CoreLoopSetupResult<()> {
-    let services_context = ServicesContext::new(
+    let mut services_context = ServicesContext::new(
         pg_pool,
         nats.clone(),
         job_processor,
@@ -64,6 +67,9 @@ pub(crate) async fn setup_and_run_core_loop(
         symmetric_crypto_service,
         layer_db.clone(),
     );
+    if validate_snapshots {
+        services_context.set_validate_snapshots();
+    }
 
     info!("getting dal context builder");
     let ctx_builder = DalContext::builder(services_context.clone(), false);
@@ -72,7 +78,13 @@ pub(crate) async fn setup_and_run_core_loop(
     let stream = layer_db.activity().rebase().subscribe_work_queue().await?;
 
     info!("setup complete, entering core loop");
-    core_loop_infallible(ctx_builder, stream, shutdown_watch_rx).await;
+    core_loop_infallible(
+        ctx_builder,
+        stream,
+        shutdown_watch_rx,
+        conflict_resolution_policy,
+    )
+    .await;
     info!("exited core loop");
 
     Ok(())
@@ -82,6 +94,7 @@ async fn core_loop_infallible(
     ctx_builder: DalContextBuilder,
     stream: RebaserRequestsWorkQueueStream,
     mut shutdown_watch_rx: watch::Receiver<()>,
+    conflict_resolution_policy: ConflictResolutionPolicy,
 ) {
     let mut stream = stream.take_until_if(Box::pin(shutdown_watch_rx.changed().map(|_| true)));
 
@@ -100,7 +113,8 @@ async fn core_loop_infallible(
 
         let ctx_builder = ctx_builder.clone();
         tokio::spawn(async move {
-            perform_rebase_and_reply_infallible(ctx_builder, &message).await;
+            perform_rebase_and_reply_infallible(ctx_builder, &message, &conflict_resolution_policy)
+                .await;
             if let Err(err) = message.ack_with(AckKind::Ack).await {
                 error!(?message, ?err, "failing acking message");
             }
@@ -111,6 +125,7 @@ async fn core_loop_infallible(
 async fn perform_rebase_and_reply_infallible(
     ctx_builder: DalContextBuilder,
     message: &AckRebaseRequest,
+    conflict_resolution_policy: &ConflictResolutionPolicy,
 ) {
     let start = Instant::now();
 
@@ -124,7 +139,7 @@ async fn perform_rebase_and_reply_infallible(
     ctx.update_visibility_deprecated(Visibility::new_head());
     ctx.update_tenancy(Tenancy::new(WorkspacePk::NONE));
 
-    let rebase_status = perform_rebase(&mut ctx, message)
+    let rebase_status = perform_rebase(&mut ctx, message, conflict_resolution_policy)
         .await
         .unwrap_or_else(|err| {
             error!(error = ?err, ?message, "performing rebase failed, attempting to reply");
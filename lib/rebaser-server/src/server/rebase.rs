@@ -1,4 +1,9 @@
+use dal::change_set::pointer_history::ChangeSetPointerOperation;
 use dal::change_set::{ChangeSet, ChangeSetError, ChangeSetId};
+use dal::workspace_snapshot::conflict::{
+    Conflict, ConflictChoice, ConflictResolutionPolicy, ConflictResolutions, ResolvedConflict,
+    StableConflict,
+};
 use dal::workspace_snapshot::vector_clock::VectorClockId;
 use dal::workspace_snapshot::WorkspaceSnapshotError;
 use dal::{
@@ -36,8 +41,12 @@ type RebaseResult<T> = Result<T, RebaseError>;
 pub(crate) async fn perform_rebase(
     ctx: &mut DalContext,
     message: &AckRebaseRequest,
+    conflict_resolution_policy: &ConflictResolutionPolicy,
 ) -> RebaseResult<RebaseStatus> {
     let start = Instant::now();
+    // Attribute everything we record to whoever requested the rebase.
+    ctx.update_history_actor(message.metadata.actor.clone().into());
+
     // Gather everything we need to detect conflicts and updates from the inbound message.
     let mut to_rebase_change_set =
         ChangeSet::find(ctx, message.payload.to_rebase_change_set_id.into())
@@ -68,7 +77,7 @@ pub(crate) async fn perform_rebase(
 
     // Perform the conflicts and updates detection.
     let onto_vector_clock_id: VectorClockId = message.payload.onto_vector_clock_id.into();
-    let (conflicts, updates) = to_rebase_workspace_snapshot
+    let (conflicts, mut updates) = to_rebase_workspace_snapshot
         .detect_conflicts_and_updates(
             to_rebase_change_set.vector_clock_id(),
             &onto_workspace_snapshot,
@@ -82,10 +91,103 @@ pub(crate) async fn perform_rebase(
         start.elapsed()
     );
 
-    // If there are conflicts, immediately assemble a reply message that conflicts were found.
-    // Otherwise, we can perform updates and assemble a "success" reply message.
-    let message: RebaseStatus = if conflicts.is_empty() {
+    // Attempt to merge conflicting attribute value content against the content in the snapshot
+    // the onto change set was forked from, if we know it. The merges are only applied if the
+    // rebase goes ahead.
+    // Only persisted change sets can be found: when an edit session is committed, the onto
+    // vector clock belongs to an ephemeral editing change set.
+    let onto_change_set_id: ChangeSetId = Ulid::from(onto_vector_clock_id).into();
+    let onto_change_set = ChangeSet::find(ctx, onto_change_set_id).await?;
+    let ancestor_workspace_snapshot_address = onto_change_set
+        .as_ref()
+        .and_then(|onto_change_set| onto_change_set.base_workspace_snapshot_address);
+    let (merges, conflicts) = match ancestor_workspace_snapshot_address {
+        Some(ancestor_workspace_snapshot_address) if !conflicts.is_empty() => {
+            let ancestor_workspace_snapshot =
+                WorkspaceSnapshot::find(ctx, ancestor_workspace_snapshot_address).await?;
+            to_rebase_workspace_snapshot
+                .preview_attribute_value_merges(
+                    ctx,
+                    &onto_workspace_snapshot,
+                    &ancestor_workspace_snapshot,
+                    conflicts,
+                )
+                .await?
+        }
+        _ => (vec![], conflicts),
+    };
+    let content_merged = merges.len();
+    info!(
+        "count: conflicts merged ({}), {:?}",
+        content_merged,
+        start.elapsed()
+    );
+
+    // Attempt to resolve the conflicts found using the configured policy, unless the requester
+    // chose how to resolve them.
+    let conflict_choices: Vec<ConflictChoice> = message
+        .payload
+        .conflict_choices
+        .iter()
+        .copied()
+        .map(Into::into)
+        .collect();
+    let pointer_operation: Option<ChangeSetPointerOperation> =
+        message.payload.pointer_operation.map(Into::into);
+    let ConflictResolutions {
+        resolved,
+        unresolved,
+        updates: resolution_updates,
+    } = to_rebase_workspace_snapshot
+        .resolve_conflicts(
+            &onto_workspace_snapshot,
+            conflicts.as_slice(),
+            conflict_resolution_policy,
+            conflict_choices.as_slice(),
+        )
+        .await?;
+    info!(
+        "count: conflicts resolved ({}) and unresolved ({}), {:?}",
+        resolved.len(),
+        unresolved.len(),
+        start.elapsed()
+    );
+
+    // If there are unresolved conflicts, immediately assemble a reply message that conflicts were
+    // found. Otherwise, we can perform updates (including the ones that resolve conflicts) and
+    // assemble a "success" reply message.
+    let message: RebaseStatus = if unresolved.is_empty() {
+        updates.extend(resolution_updates);
+
+        // Translate the updates and conflicts into their stable forms before performing the
+        // updates, since the node indices they refer to will not survive writing the snapshot.
+        let stable_updates = to_rebase_workspace_snapshot
+            .stable_updates(&onto_workspace_snapshot, updates.as_slice())
+            .await?;
+        let resolved_conflicts: Vec<Conflict> = resolved
+            .iter()
+            .map(|resolved_conflict| resolved_conflict.conflict)
+            .collect();
+        let stable_resolved: Vec<ResolvedConflict<StableConflict>> = to_rebase_workspace_snapshot
+            .stable_conflicts(&onto_workspace_snapshot, resolved_conflicts.as_slice())
+            .await?
+            .into_iter()
+            .zip(resolved.iter())
+            .map(|(conflict, resolved_conflict)| ResolvedConflict {
+                conflict,
+                strategy: resolved_conflict.strategy,
+            })
+            .collect();
+
         // TODO(nick): store the offset with the change set.
+        to_rebase_workspace_snapshot
+            .apply_attribute_value_merges(
+                ctx,
+                &to_rebase_change_set,
+                &onto_workspace_snapshot,
+                merges,
+            )
+            .await?;
         to_rebase_workspace_snapshot
             .perform_updates(
                 &to_rebase_change_set,
@@ -95,7 +197,13 @@ pub(crate) async fn perform_rebase(
             .await?;
         info!("updates complete: {:?}", start.elapsed());
 
-        if !updates.is_empty() {
+        // An undo or redo is recorded even if there turned out to be nothing to revert, so that
+        // it still moves through the requester's undo history.
+        let undo_or_redo = matches!(
+            pointer_operation,
+            Some(ChangeSetPointerOperation::Undo | ChangeSetPointerOperation::Redo)
+        );
+        if !updates.is_empty() || content_merged > 0 || undo_or_redo {
             // Once all updates have been performed, we can write out, mark everything as recently seen
             // and update the pointer.
             to_rebase_workspace_snapshot
@@ -103,18 +211,30 @@ pub(crate) async fn perform_rebase(
                 .await?;
             info!("snapshot written: {:?}", start.elapsed());
             to_rebase_change_set
-                .update_pointer(ctx, to_rebase_workspace_snapshot.id().await)
+                .update_pointer(
+                    ctx,
+                    to_rebase_workspace_snapshot.id().await,
+                    pointer_operation.unwrap_or(ChangeSetPointerOperation::Rebase),
+                )
                 .await?;
             info!("pointer updated: {:?}", start.elapsed());
         }
 
         RebaseStatus::Success {
-            updates_performed: serde_json::to_value(updates)?.to_string(),
+            updates_performed: serde_json::to_value(stable_updates)?.to_string(),
+            conflicts_resolved: serde_json::to_value(stable_resolved)?.to_string(),
         }
     } else {
+        let stable_unresolved = to_rebase_workspace_snapshot
+            .stable_conflicts(&onto_workspace_snapshot, unresolved.as_slice())
+            .await?;
+        let stable_updates = to_rebase_workspace_snapshot
+            .stable_updates(&onto_workspace_snapshot, updates.as_slice())
+            .await?;
+
         RebaseStatus::ConflictsFound {
-            conflicts_found: serde_json::to_value(conflicts)?.to_string(),
-            updates_found_and_skipped: serde_json::to_value(updates)?.to_string(),
+            conflicts_found: serde_json::to_value(stable_unresolved)?.to_string(),
+            updates_found_and_skipped: serde_json::to_value(stable_updates)?.to_string(),
         }
     };
 
@@ -1,6 +1,7 @@
 use dal::jwt_key::JwtConfig;
 use si_crypto::CryptoConfig;
 use si_layer_cache::error::LayerDbError;
+use si_layer_cache::LayerDbConfig;
 use std::{
     env,
     net::{SocketAddr, ToSocketAddrs},
@@ -84,6 +85,12 @@ pub struct Config {
     #[builder(default = "si_layer_cache::default_pg_pool_config()")]
     layer_cache_pg_pool: PgPoolConfig,
 
+    #[builder(default = "LayerDbConfig::default()")]
+    layer_db: LayerDbConfig,
+
+    #[builder(default)]
+    validate_snapshots: bool,
+
     layer_cache_sled_path: CanonicalFile,
 
     signup_secret: SensitiveString,
@@ -168,6 +175,18 @@ impl Config {
     pub fn layer_cache_sled_path(&self) -> &Path {
         self.layer_cache_sled_path.as_path()
     }
+
+    /// Gets a reference to the config's layer db config.
+    #[must_use]
+    pub fn layer_db(&self) -> &LayerDbConfig {
+        &self.layer_db
+    }
+
+    /// Whether workspace snapshot graphs are validated before they are written.
+    #[must_use]
+    pub fn validate_snapshots(&self) -> bool {
+        self.validate_snapshots
+    }
 }
 
 impl ConfigBuilder {
@@ -187,6 +206,10 @@ pub struct ConfigFile {
     #[serde(default = "si_layer_cache::default_pg_pool_config")]
     layer_cache_pg_pool: PgPoolConfig,
     #[serde(default)]
+    layer_db: LayerDbConfig,
+    #[serde(default)]
+    validate_snapshots: bool,
+    #[serde(default)]
     pub nats: NatsConfig,
     #[serde(default)]
     pub migration_mode: MigrationMode,
@@ -211,6 +234,8 @@ impl Default for ConfigFile {
         Self {
             pg: Default::default(),
             layer_cache_pg_pool: si_layer_cache::default_pg_pool_config(),
+            layer_db: Default::default(),
+            validate_snapshots: false,
             nats: Default::default(),
             migration_mode: Default::default(),
             jwt_signing_public_key: Default::default(),
@@ -237,6 +262,8 @@ impl TryFrom<ConfigFile> for Config {
         let mut config = Config::builder();
         config.pg_pool(value.pg);
         config.layer_cache_pg_pool(value.layer_cache_pg_pool);
+        config.layer_db(value.layer_db);
+        config.validate_snapshots(value.validate_snapshots);
         config.nats(value.nats);
         config.migration_mode(value.migration_mode);
         config.jwt_signing_public_key(value.jwt_signing_public_key);
@@ -4,7 +4,7 @@ use dal::jwt_key::JwtConfig;
 use dal::ServicesContext;
 use dal::{
     builtins, BuiltinsError, DalContext, JwtPublicSigningKey, TransactionsError, Workspace,
-    WorkspaceError,
+    WorkspaceError, WorkspaceSnapshot,
 };
 use hyper::server::{accept::Accept, conn::AddrIncoming};
 use module_index_client::{types::BuiltinsDetailsResponse, IndexClient, ModuleDetailsResponse};
@@ -16,6 +16,7 @@ use si_crypto::{
 };
 use si_data_nats::{NatsClient, NatsConfig, NatsError};
 use si_data_pg::{PgError, PgPool, PgPoolConfig, PgPoolError};
+use si_layer_cache::WarmUpConfig;
 use si_pkg::{SiPkg, SiPkgError};
 use si_posthog::{PosthogClient, PosthogConfig};
 use si_std::SensitiveString;
@@ -33,6 +34,7 @@ use tokio::{
     task::{JoinError, JoinSet},
     time,
 };
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tower_http::trace::TraceLayer;
 use ulid::Ulid;
 use veritech_client::Client as VeritechClient;
@@ -209,6 +211,39 @@ impl Server<(), ()> {
         Ok(dal::init()?)
     }
 
+    /// Prefetches the snapshots of recently active change sets in the background, if enabled.
+    /// The warm-up is abandoned if the server shuts down before it finishes.
+    pub fn start_layer_db_warm_up(
+        services_context: ServicesContext,
+        config: WarmUpConfig,
+        tracker: &TaskTracker,
+        token: CancellationToken,
+    ) {
+        if !config.enabled {
+            return;
+        }
+
+        tracker.spawn(async move {
+            let ctx = match services_context.into_builder(false).build_default().await {
+                Ok(ctx) => ctx,
+                Err(err) => {
+                    warn!(error = ?err, "could not build context for layer db warm-up");
+                    return;
+                }
+            };
+            tokio::select! {
+                _ = token.cancelled() => {
+                    debug!("layer db warm-up abandoned on shutdown");
+                }
+                result = WorkspaceSnapshot::warm_up(&ctx, &config) => {
+                    if let Err(err) = result {
+                        warn!(error = ?err, "layer db warm-up failed");
+                    }
+                }
+            }
+        });
+    }
+
     pub async fn start_posthog(config: &PosthogConfig) -> Result<PosthogClient> {
         let (posthog_client, posthog_sender) = si_posthog::from_config(config)?;
 
@@ -4,6 +4,9 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use dal::attribute::value::AttributeValueError;
+use dal::workspace_snapshot::diff::SnapshotDiffError;
+use dal::workspace_snapshot::WorkspaceSnapshotError;
 use dal::{
     ActionError, ActionPrototypeError, ChangeSetApplyError as DalChangeSetApplyError,
     ChangeSetError as DalChangeSetError, ComponentError, FuncError, StandardModelError,
@@ -22,10 +25,13 @@ pub mod apply_change_set;
 // mod begin_abandon_approval_process;
 // mod begin_approval_process;
 pub mod create_change_set;
+pub mod list_changes;
+pub mod list_conflicts;
 pub mod list_open_change_sets;
 pub mod list_queued_actions;
 // mod merge_vote;
 pub mod remove_action;
+pub mod resolve_conflicts;
 
 #[remain::sorted]
 #[derive(Debug, Error)]
@@ -34,6 +40,8 @@ pub enum ChangeSetError {
     Action(#[from] ActionError),
     #[error("action prototype error: {0}")]
     ActionPrototype(#[from] ActionPrototypeError),
+    #[error("attribute value error: {0}")]
+    AttributeValue(#[from] AttributeValueError),
     #[error("change set not found")]
     ChangeSetNotFound,
     #[error("component error: {0}")]
@@ -46,10 +54,14 @@ pub enum ChangeSetError {
     Func(#[from] FuncError),
     #[error("invalid header name {0}")]
     Hyper(#[from] hyper::http::Error),
+    #[error("snapshot diff error: {0}")]
+    SnapshotDiff(#[from] SnapshotDiffError),
     #[error("standard model error: {0}")]
     StandardModel(#[from] StandardModelError),
     #[error("transactions error: {0}")]
     Transactions(#[from] TransactionsError),
+    #[error("workspace snapshot error: {0}")]
+    WorkspaceSnapshot(#[from] WorkspaceSnapshotError),
     #[error("ws event error: {0}")]
     WsEvent(#[from] WsEventError),
 }
@@ -60,6 +72,9 @@ impl IntoResponse for ChangeSetError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             ChangeSetError::ChangeSetNotFound => (StatusCode::NOT_FOUND, self.to_string()),
+            ChangeSetError::DalChangeSetApply(DalChangeSetApplyError::ChangeSet(
+                DalChangeSetError::ConflictsFound(_),
+            )) => (StatusCode::CONFLICT, self.to_string()),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
 
@@ -95,6 +110,12 @@ pub fn routes() -> Router<AppState> {
             "/abandon_change_set",
             post(abandon_change_set::abandon_change_set),
         )
+        .route("/list_changes", get(list_changes::list_changes))
+        .route("/list_conflicts", get(list_conflicts::list_conflicts))
+        .route(
+            "/resolve_conflicts",
+            post(resolve_conflicts::resolve_conflicts),
+        )
     // .route(
     //     "/begin_approval_process",
     //     post(begin_approval_process::begin_approval_process),
@@ -0,0 +1,39 @@
+use axum::extract::Query;
+use axum::Json;
+use dal::change_set::ChangeSet;
+use dal::workspace_snapshot::diff::SnapshotChange;
+use dal::{Visibility, WorkspaceSnapshot};
+use serde::{Deserialize, Serialize};
+
+use super::{ChangeSetError, ChangeSetResult};
+use crate::server::extract::{AccessBuilder, HandlerContext};
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ListChangesRequest {
+    #[serde(flatten)]
+    pub visibility: Visibility,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ListChangesResponse {
+    pub changes: Vec<SnapshotChange>,
+}
+
+/// Lists what the change set changes compared to the snapshot it was forked from, for review
+/// before it is applied.
+pub async fn list_changes(
+    HandlerContext(builder): HandlerContext,
+    AccessBuilder(request_ctx): AccessBuilder,
+    Query(request): Query<ListChangesRequest>,
+) -> ChangeSetResult<Json<ListChangesResponse>> {
+    let ctx = builder.build(request_ctx.build(request.visibility)).await?;
+
+    let change_set = ChangeSet::find(&ctx, ctx.change_set_id())
+        .await?
+        .ok_or(ChangeSetError::ChangeSetNotFound)?;
+    let changes = WorkspaceSnapshot::diff_change_set(&ctx, &change_set).await?;
+
+    Ok(Json(ListChangesResponse { changes }))
+}
@@ -0,0 +1,176 @@
+use axum::extract::Query;
+use axum::Json;
+use dal::change_set::ChangeSet;
+use dal::workspace_snapshot::conflict::ConflictDiscriminants;
+use dal::workspace_snapshot::edge_weight::EdgeWeightKindDiscriminants;
+use dal::workspace_snapshot::graph::WorkspaceSnapshotGraphError;
+use dal::workspace_snapshot::node_weight::NodeWeight;
+use dal::workspace_snapshot::WorkspaceSnapshotError;
+use dal::{
+    AttributeValue, AttributeValueId, Component, ComponentId, DalContext, Visibility,
+    WorkspaceSnapshot,
+};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::{ChangeSetError, ChangeSetResult};
+use crate::server::extract::{AccessBuilder, HandlerContext};
+
+/// A conflict between a change set and its base change set, described in terms of what it is
+/// about rather than where it is in either graph.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictView {
+    pub kind: ConflictDiscriminants,
+    /// Identifies the conflict when choosing how to resolve it.
+    pub node_id: Ulid,
+    pub component_name: Option<String>,
+    /// The prop path of the attribute value in conflict, if the conflict is about one.
+    pub path: Option<String>,
+    pub base_value: Option<serde_json::Value>,
+    pub change_set_value: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ListConflictsRequest {
+    #[serde(flatten)]
+    pub visibility: Visibility,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ListConflictsResponse {
+    pub conflicts: Vec<ConflictView>,
+}
+
+pub async fn list_conflicts(
+    HandlerContext(builder): HandlerContext,
+    AccessBuilder(request_ctx): AccessBuilder,
+    Query(request): Query<ListConflictsRequest>,
+) -> ChangeSetResult<Json<ListConflictsResponse>> {
+    let ctx = builder.build(request_ctx.build(request.visibility)).await?;
+
+    let change_set = ChangeSet::find(&ctx, ctx.change_set_id())
+        .await?
+        .ok_or(ChangeSetError::ChangeSetNotFound)?;
+    let base_change_set_id = change_set
+        .base_change_set_id
+        .ok_or(dal::ChangeSetError::NoBaseChangeSet(change_set.id))?;
+    let base_change_set = ChangeSet::find(&ctx, base_change_set_id)
+        .await?
+        .ok_or(ChangeSetError::ChangeSetNotFound)?;
+
+    let mut base_ctx = ctx.clone_with_new_visibility(Visibility::new(base_change_set_id));
+    base_ctx.update_snapshot_to_visibility().await?;
+
+    // Detect conflicts the same way the rebaser will when the change set is applied: the base
+    // change set is rebased onto the change set. Attribute value content that can be merged is
+    // only previewed, so nothing is written.
+    let base_snapshot = WorkspaceSnapshot::find_for_change_set(&ctx, base_change_set.id).await?;
+    let change_set_snapshot = WorkspaceSnapshot::find_for_change_set(&ctx, change_set.id).await?;
+    let (conflicts, _) = base_snapshot
+        .detect_conflicts_and_updates(
+            base_change_set.vector_clock_id(),
+            &change_set_snapshot,
+            change_set.vector_clock_id(),
+        )
+        .await?;
+    let conflicts = match change_set.base_workspace_snapshot_address {
+        Some(ancestor_address) if !conflicts.is_empty() => {
+            let ancestor_snapshot = WorkspaceSnapshot::find(&ctx, ancestor_address).await?;
+            let (_, conflicts) = base_snapshot
+                .preview_attribute_value_merges(
+                    &ctx,
+                    &change_set_snapshot,
+                    &ancestor_snapshot,
+                    conflicts,
+                )
+                .await?;
+            conflicts
+        }
+        _ => conflicts,
+    };
+
+    let mut views = Vec::with_capacity(conflicts.len());
+    for conflict in conflicts {
+        let node_id = base_snapshot
+            .conflict_node_id(&change_set_snapshot, &conflict)
+            .await?;
+
+        let change_set_description = describe_node(&ctx, node_id).await?;
+        let base_description = describe_node(&base_ctx, node_id).await?;
+
+        views.push(ConflictView {
+            kind: ConflictDiscriminants::from(&conflict),
+            node_id,
+            component_name: change_set_description
+                .component_name
+                .or(base_description.component_name),
+            path: change_set_description.path.or(base_description.path),
+            base_value: base_description.value,
+            change_set_value: change_set_description.value,
+        });
+    }
+
+    Ok(Json(ListConflictsResponse { conflicts: views }))
+}
+
+#[derive(Default)]
+struct NodeDescription {
+    component_name: Option<String>,
+    path: Option<String>,
+    value: Option<serde_json::Value>,
+}
+
+/// Describes the node with the given id as it exists in the snapshot for the [`DalContext`]. An
+/// ordering node is described by the container it orders.
+async fn describe_node(ctx: &DalContext, node_id: Ulid) -> ChangeSetResult<NodeDescription> {
+    let workspace_snapshot = ctx.workspace_snapshot()?;
+
+    let mut node_weight = match workspace_snapshot.get_node_weight_by_id(node_id).await {
+        Ok(node_weight) => node_weight,
+        Err(WorkspaceSnapshotError::WorkspaceSnapshotGraph(
+            WorkspaceSnapshotGraphError::NodeWithIdNotFound(_),
+        )) => return Ok(NodeDescription::default()),
+        Err(err) => return Err(err.into()),
+    };
+    if let NodeWeight::Ordering(_) = node_weight {
+        match workspace_snapshot
+            .incoming_sources_for_edge_weight_kind(node_id, EdgeWeightKindDiscriminants::Ordering)
+            .await?
+            .first()
+        {
+            Some(container_index) => {
+                node_weight = workspace_snapshot.get_node_weight(*container_index).await?;
+            }
+            None => return Ok(NodeDescription::default()),
+        }
+    }
+
+    Ok(match node_weight {
+        NodeWeight::AttributeValue(attribute_value_weight) => {
+            let attribute_value_id: AttributeValueId = attribute_value_weight.id().into();
+            let component_id = AttributeValue::component_id(ctx, attribute_value_id).await?;
+            let attribute_value = AttributeValue::get_by_id(ctx, attribute_value_id).await?;
+
+            NodeDescription {
+                component_name: Some(component_name(ctx, component_id).await?),
+                path: AttributeValue::get_path_for_id(ctx, attribute_value_id).await?,
+                value: attribute_value.materialized_view(ctx).await?,
+            }
+        }
+        NodeWeight::Component(component_weight) => NodeDescription {
+            component_name: Some(component_name(ctx, component_weight.id().into()).await?),
+            ..Default::default()
+        },
+        _ => NodeDescription::default(),
+    })
+}
+
+async fn component_name(ctx: &DalContext, component_id: ComponentId) -> ChangeSetResult<String> {
+    Ok(Component::get_by_id(ctx, component_id)
+        .await?
+        .name(ctx)
+        .await?)
+}
@@ -0,0 +1,97 @@
+use axum::extract::OriginalUri;
+use axum::Json;
+use dal::change_set::ChangeSet;
+use dal::workspace_snapshot::conflict::{
+    ConflictChoice, ConflictDiscriminants, ConflictResolutionStrategy,
+};
+use dal::Visibility;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::ChangeSetResult;
+use crate::server::extract::{AccessBuilder, HandlerContext, PosthogClient};
+use crate::server::tracking::track;
+
+/// Which side of a conflict to keep.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSide {
+    /// Keep what is in the base change set.
+    Base,
+    /// Keep what is in the change set being applied.
+    ChangeSet,
+}
+
+/// The side to keep for a conflict listed by
+/// [`list_conflicts`](super::list_conflicts::list_conflicts).
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictSideChoice {
+    pub kind: ConflictDiscriminants,
+    pub node_id: Ulid,
+    pub side: ConflictSide,
+}
+
+impl From<&ConflictSideChoice> for ConflictChoice {
+    fn from(value: &ConflictSideChoice) -> Self {
+        // The base change set is rebased onto the change set being applied.
+        let strategy = match value.side {
+            ConflictSide::Base => ConflictResolutionStrategy::ToRebaseWins,
+            ConflictSide::ChangeSet => ConflictResolutionStrategy::OntoWins,
+        };
+
+        Self {
+            kind: value.kind,
+            node_id: value.node_id,
+            strategy,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveConflictsRequest {
+    pub choices: Vec<ConflictSideChoice>,
+    #[serde(flatten)]
+    pub visibility: Visibility,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveConflictsResponse {
+    pub change_set: ChangeSet,
+}
+
+/// Applies the change set again, resolving conflicts with its base change set as chosen.
+pub async fn resolve_conflicts(
+    HandlerContext(builder): HandlerContext,
+    AccessBuilder(request_ctx): AccessBuilder,
+    PosthogClient(posthog_client): PosthogClient,
+    OriginalUri(original_uri): OriginalUri,
+    Json(request): Json<ResolveConflictsRequest>,
+) -> ChangeSetResult<Json<ResolveConflictsResponse>> {
+    let mut ctx = builder.build(request_ctx.build(request.visibility)).await?;
+
+    let conflict_choices = request.choices.iter().map(Into::into).collect();
+    let change_set = ChangeSet::apply_to_base_change_set_with_conflict_choices(
+        &mut ctx,
+        false,
+        conflict_choices,
+    )
+    .await?;
+
+    track(
+        &posthog_client,
+        &ctx,
+        &original_uri,
+        "resolve_conflicts",
+        serde_json::json!({
+            "merged_change_set": request.visibility.change_set_id,
+            "conflict_choice_count": request.choices.len(),
+        }),
+    );
+
+    ctx.commit().await?;
+
+    Ok(Json(ResolveConflictsResponse { change_set }))
+}
@@ -7,6 +7,7 @@ use dal::{
     workspace_snapshot::{
         content_address::ContentAddressDiscriminants,
         edge_weight::EdgeWeightKindDiscriminants,
+        graph::ValidationReport,
         node_weight::{NodeWeight, NodeWeightDiscriminants},
         WorkspaceSnapshotError,
     },
@@ -471,6 +472,19 @@ pub async fn nodes_edges(
     Ok(Json(response))
 }
 
+/// Checks the structural invariants of the workspace snapshot graph for the change set.
+pub async fn validate(
+    HandlerContext(builder): HandlerContext,
+    AccessBuilder(request_ctx): AccessBuilder,
+    Query(request): Query<GraphVizRequest>,
+) -> GraphVizResult<Json<ValidationReport>> {
+    let ctx = builder.build(request_ctx.build(request.visibility)).await?;
+
+    let report = ctx.workspace_snapshot()?.validate().await;
+
+    Ok(Json(report))
+}
+
 impl_default_error_into_response!(GraphVizError);
 
 pub fn routes() -> Router<AppState> {
@@ -478,4 +492,5 @@ pub fn routes() -> Router<AppState> {
         .route("/schema_variant", get(schema_variant))
         .route("/nodes_edges", get(nodes_edges))
         .route("/components", get(components))
+        .route("/validate", get(validate))
 }
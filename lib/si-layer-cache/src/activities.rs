@@ -27,7 +27,7 @@ use crate::{
 };
 
 use self::{
-    rebase::{RebaseFinished, RebaseRequest},
+    rebase::{LegacyRebaseFinished, LegacyRebaseRequest, RebaseFinished, RebaseRequest},
     test::{IntegrationTest, IntegrationTestAlt},
 };
 
@@ -103,6 +103,16 @@ impl Activity {
         }
     }
 
+    /// Deserializes an activity, including one published by a version of this crate from before
+    /// rebase activities carried conflict choices, pointer operations or resolved conflicts.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Activity, postcard::Error> {
+        postcard::from_bytes::<Activity>(bytes).or_else(|err| {
+            postcard::from_bytes::<LegacyActivity>(bytes)
+                .map(Into::into)
+                .map_err(|_| err)
+        })
+    }
+
     pub fn rebase(request: RebaseRequest, metadata: LayeredEventMetadata) -> Activity {
         Activity::new(ActivityPayload::RebaseRequest(request), metadata, None)
     }
@@ -120,6 +130,49 @@ impl Activity {
     }
 }
 
+/// An [`Activity`] as published before rebase activities changed shape. Since activities are
+/// serialized with postcard, which has no field names to go by, the shapes have to be tried in
+/// turn.
+#[derive(Debug, Deserialize)]
+struct LegacyActivity {
+    id: ActivityId,
+    payload: LegacyActivityPayload,
+    metadata: LayeredEventMetadata,
+    parent_activity_id: Option<ActivityId>,
+}
+
+// The variants must stay in the same order as those of `ActivityPayload`.
+#[derive(Debug, Deserialize)]
+enum LegacyActivityPayload {
+    RebaseRequest(LegacyRebaseRequest),
+    RebaseFinished(LegacyRebaseFinished),
+    IntegrationTest(IntegrationTest),
+    IntegrationTestAlt(IntegrationTestAlt),
+}
+
+impl From<LegacyActivity> for Activity {
+    fn from(value: LegacyActivity) -> Self {
+        let payload = match value.payload {
+            LegacyActivityPayload::RebaseRequest(request) => {
+                ActivityPayload::RebaseRequest(request.into())
+            }
+            LegacyActivityPayload::RebaseFinished(finished) => {
+                ActivityPayload::RebaseFinished(finished.into())
+            }
+            LegacyActivityPayload::IntegrationTest(test) => ActivityPayload::IntegrationTest(test),
+            LegacyActivityPayload::IntegrationTestAlt(test) => {
+                ActivityPayload::IntegrationTestAlt(test)
+            }
+        };
+        Activity {
+            id: value.id,
+            payload,
+            metadata: value.metadata,
+            parent_activity_id: value.parent_activity_id,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, EnumDiscriminants, PartialEq, Eq)]
 pub enum ActivityPayload {
     RebaseRequest(RebaseRequest),
@@ -404,7 +457,7 @@ impl Stream for ActivityStream {
             Poll::Ready(Some(Ok(msg))) => {
                 let (msg, acker) = msg.split();
 
-                match postcard::from_bytes::<Activity>(&msg.payload) {
+                match Activity::from_bytes(&msg.payload) {
                     // Successfully deserialized into an activity
                     Ok(inner) => Poll::Ready(Some(Ok(AckActivity {
                         inner,
@@ -509,7 +562,7 @@ impl Stream for RebaserRequestsWorkQueueStream {
             Poll::Ready(Some(Ok(msg))) => {
                 let (msg, acker) = msg.split();
 
-                match postcard::from_bytes::<Activity>(&msg.payload) {
+                match Activity::from_bytes(&msg.payload) {
                     // Successfully deserialized into an activity
                     Ok(activity) => match activity.payload {
                         // Correct variant, convert to work-specific type
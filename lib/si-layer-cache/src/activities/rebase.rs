@@ -12,7 +12,7 @@ use crate::activity_client::ActivityClient;
 use crate::{error::LayerDbResult, event::LayeredEventMetadata};
 
 /// The message that the server receives to perform a rebase.
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct RebaseRequest {
     /// Corresponds to the change set whose pointer is to be updated.
     pub to_rebase_change_set_id: Ulid,
@@ -23,6 +23,14 @@ pub struct RebaseRequest {
     /// last change set before edits were made, or the change set that you are trying to rebase
     /// onto base.
     pub onto_vector_clock_id: Ulid,
+    /// Resolutions chosen for specific conflicts, which take precedence over how the server would
+    /// otherwise handle those conflicts.
+    #[serde(default)]
+    pub conflict_choices: Vec<RebaseConflictChoice>,
+    /// The operation to record in the pointer history of the change set being rebased. The
+    /// server records a [`Rebase`](RebasePointerOperation::Rebase) if unset.
+    #[serde(default)]
+    pub pointer_operation: Option<RebasePointerOperation>,
 }
 
 impl RebaseRequest {
@@ -30,15 +38,81 @@ impl RebaseRequest {
         to_rebase_change_set_id: Ulid,
         onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
         onto_vector_clock_id: Ulid,
+        conflict_choices: Vec<RebaseConflictChoice>,
+        pointer_operation: Option<RebasePointerOperation>,
     ) -> RebaseRequest {
         RebaseRequest {
             to_rebase_change_set_id,
             onto_workspace_snapshot_address,
             onto_vector_clock_id,
+            conflict_choices,
+            pointer_operation,
         }
     }
 }
 
+/// A [`RebaseRequest`] as sent before requests carried conflict choices or a pointer operation.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LegacyRebaseRequest {
+    to_rebase_change_set_id: Ulid,
+    onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
+    onto_vector_clock_id: Ulid,
+}
+
+impl From<LegacyRebaseRequest> for RebaseRequest {
+    fn from(value: LegacyRebaseRequest) -> Self {
+        Self::new(
+            value.to_rebase_change_set_id,
+            value.onto_workspace_snapshot_address,
+            value.onto_vector_clock_id,
+            vec![],
+            None,
+        )
+    }
+}
+
+/// A resolution chosen for one specific conflict found when rebasing. The conflict is identified
+/// by its kind and the id of the node it is about.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RebaseConflictChoice {
+    pub kind: RebaseConflictKind,
+    pub node_id: Ulid,
+    pub strategy: RebaseConflictStrategy,
+}
+
+/// The kind of conflict a [`RebaseConflictChoice`] is for.
+#[remain::sorted]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseConflictKind {
+    ChildOrder,
+    ModifyRemovedItem,
+    NodeContent,
+    RemoveModifiedItem,
+}
+
+/// How to resolve the conflict a [`RebaseConflictChoice`] is for.
+#[remain::sorted]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseConflictStrategy {
+    Fail,
+    Interleave,
+    OntoWins,
+    ToRebaseWins,
+}
+
+/// The operation to record in the pointer history of the change set being rebased.
+#[remain::sorted]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RebasePointerOperation {
+    Apply,
+    Commit,
+    Create,
+    Fork,
+    Rebase,
+    Redo,
+    Undo,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct RebaseFinished {
     status: RebaseStatus,
@@ -80,6 +154,10 @@ pub enum RebaseStatus {
     Success {
         /// The serialized updates performed when rebasing.
         updates_performed: String,
+        /// A serialized list of the conflicts that were resolved automatically, along with the
+        /// strategy used to resolve each of them.
+        #[serde(default)]
+        conflicts_resolved: String,
     },
     /// Conflicts found when processing the request.
     ConflictsFound {
@@ -96,6 +174,52 @@ pub enum RebaseStatus {
     },
 }
 
+/// A [`RebaseFinished`] as sent before successful rebases reported the conflicts they resolved.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LegacyRebaseFinished {
+    status: LegacyRebaseStatus,
+    to_rebase_change_set_id: Ulid,
+    onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
+}
+
+#[derive(Debug, Deserialize)]
+enum LegacyRebaseStatus {
+    Success {
+        updates_performed: String,
+    },
+    ConflictsFound {
+        conflicts_found: String,
+        updates_found_and_skipped: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl From<LegacyRebaseFinished> for RebaseFinished {
+    fn from(value: LegacyRebaseFinished) -> Self {
+        let status = match value.status {
+            LegacyRebaseStatus::Success { updates_performed } => RebaseStatus::Success {
+                updates_performed,
+                conflicts_resolved: String::new(),
+            },
+            LegacyRebaseStatus::ConflictsFound {
+                conflicts_found,
+                updates_found_and_skipped,
+            } => RebaseStatus::ConflictsFound {
+                conflicts_found,
+                updates_found_and_skipped,
+            },
+            LegacyRebaseStatus::Error { message } => RebaseStatus::Error { message },
+        };
+        Self::new(
+            status,
+            value.to_rebase_change_set_id,
+            value.onto_workspace_snapshot_address,
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct ActivityRebase<'a> {
     activity_base: &'a ActivityClient,
@@ -112,12 +236,16 @@ impl<'a> ActivityRebase<'a> {
         to_rebase_change_set_id: Ulid,
         onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
         onto_vector_clock_id: Ulid,
+        conflict_choices: Vec<RebaseConflictChoice>,
+        pointer_operation: Option<RebasePointerOperation>,
         metadata: LayeredEventMetadata,
     ) -> LayerDbResult<Activity> {
         let payload = RebaseRequest::new(
             to_rebase_change_set_id,
             onto_workspace_snapshot_address,
             onto_vector_clock_id,
+            conflict_choices,
+            pointer_operation,
         );
         let activity = Activity::rebase(payload, metadata);
         self.activity_base.publish(&activity).await?;
@@ -130,12 +258,16 @@ impl<'a> ActivityRebase<'a> {
         to_rebase_change_set_id: Ulid,
         onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
         onto_vector_clock_id: Ulid,
+        conflict_choices: Vec<RebaseConflictChoice>,
+        pointer_operation: Option<RebasePointerOperation>,
         metadata: LayeredEventMetadata,
     ) -> LayerDbResult<Activity> {
         let payload = RebaseRequest::new(
             to_rebase_change_set_id,
             onto_workspace_snapshot_address,
             onto_vector_clock_id,
+            conflict_choices,
+            pointer_operation,
         );
         let activity = Activity::rebase(payload, metadata);
         debug!(?activity, "sending rebase and waiting for response");
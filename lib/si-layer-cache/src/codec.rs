@@ -0,0 +1,331 @@
+//! How values are encoded on disk and in the durable tier of a cache.
+//!
+//! Values can be written with a short header recording how their payload was serialized and
+//! compressed and the schema version of the value it holds, so that values written one way can
+//! still be read after a cache starts writing them another. Values written without the header are
+//! plain postcard, which is what is written until every instance reading a cache can read headers
+//! (see [`ValueCodec::with_headers`]). Durable tables record which of the two each value is in
+//! their `serialization_lib` column, and values read from them are given a header according to it
+//! (see [`from_stored`]). Anywhere else, a value without the header is read as plain postcard, and
+//! so is one whose header cannot be read, since plain postcard can start like a header by chance.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use si_events::CasValue;
+use strum::Display;
+
+use crate::error::LayerDbResult;
+use crate::LayerDbError;
+
+const MAGIC: [u8; 3] = [0xff, b's', b'i'];
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 10;
+
+/// Payloads shorter than this are stored uncompressed, since compressing them saves little.
+const MIN_COMPRESSED_LEN: usize = 512;
+
+/// What the `serialization_lib` column of a durable table holds for values written without a
+/// header.
+pub const LEGACY_SERIALIZATION_LIB: &str = "postcard";
+
+/// How a payload is serialized.
+#[remain::sorted]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum Codec {
+    Postcard,
+}
+
+impl Codec {
+    fn id(self) -> u8 {
+        match self {
+            Self::Postcard => 0,
+        }
+    }
+
+    fn from_id(id: u8) -> LayerDbResult<Self> {
+        match id {
+            0 => Ok(Self::Postcard),
+            _ => Err(LayerDbError::UnsupportedValueEncoding(format!(
+                "unknown codec id {id}"
+            ))),
+        }
+    }
+}
+
+/// How a serialized payload is compressed.
+#[remain::sorted]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Display, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    Deflate,
+    None,
+}
+
+impl Compression {
+    fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Deflate => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> LayerDbResult<Self> {
+        match id {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Deflate),
+            _ => Err(LayerDbError::UnsupportedValueEncoding(format!(
+                "unknown compression id {id}"
+            ))),
+        }
+    }
+}
+
+/// How a stored value was encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueHeader {
+    pub codec: Codec,
+    pub compression: Compression,
+    pub schema_version: u32,
+}
+
+impl ValueHeader {
+    /// The header of a value written before headers existed.
+    pub const LEGACY: Self = Self {
+        codec: Codec::Postcard,
+        compression: Compression::None,
+        schema_version: 0,
+    };
+
+    /// Reads the header a value starts with, if it starts with one.
+    pub fn read(bytes: &[u8]) -> Option<LayerDbResult<Self>> {
+        if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+            return None;
+        }
+
+        Some(Self::parse(&bytes[MAGIC.len()..HEADER_LEN]))
+    }
+
+    fn parse(header: &[u8]) -> LayerDbResult<Self> {
+        if header[0] != FORMAT_VERSION {
+            return Err(LayerDbError::UnsupportedValueEncoding(format!(
+                "unknown format version {}",
+                header[0]
+            )));
+        }
+
+        Ok(Self {
+            codec: Codec::from_id(header[1])?,
+            compression: Compression::from_id(header[2])?,
+            schema_version: u32::from_le_bytes([header[3], header[4], header[5], header[6]]),
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&MAGIC);
+        out.push(FORMAT_VERSION);
+        out.push(self.codec.id());
+        out.push(self.compression.id());
+        out.extend_from_slice(&self.schema_version.to_le_bytes());
+    }
+
+    /// What the `serialization_lib` column of a durable table records for the value.
+    pub fn serialization_lib(&self) -> String {
+        format!("{}+{}", self.codec, self.compression)
+    }
+}
+
+/// What the `serialization_lib` column of a durable table records for an encoded value.
+pub fn serialization_lib(bytes: &[u8]) -> String {
+    match ValueHeader::read(bytes) {
+        Some(Ok(header)) => header.serialization_lib(),
+        _ => LEGACY_SERIALIZATION_LIB.to_string(),
+    }
+}
+
+/// Gives a value read from a durable table the header that its `serialization_lib` column says
+/// it was written with, so that values written without a header are never mistaken for ones with
+/// a header. Any other value is returned as it is, to be decoded like a value read from anywhere
+/// else.
+pub fn from_stored(bytes: Vec<u8>, serialization_lib: &str) -> Vec<u8> {
+    if serialization_lib != LEGACY_SERIALIZATION_LIB {
+        return bytes;
+    }
+
+    let mut headed = Vec::with_capacity(HEADER_LEN + bytes.len());
+    ValueHeader::LEGACY.write(&mut headed);
+    headed.extend_from_slice(&bytes);
+    headed
+}
+
+/// A value whose serialized form may change over time.
+///
+/// Bump [`SCHEMA_VERSION`](Self::SCHEMA_VERSION) when the serialized form changes, and teach
+/// [`deserialize_schema_version`](Self::deserialize_schema_version) to read the forms written at
+/// earlier versions. Values written before schema versions were recorded are at version 0.
+pub trait SchemaVersioned: Serialize + DeserializeOwned {
+    /// The schema version values are written at.
+    const SCHEMA_VERSION: u32 = 0;
+
+    /// Deserializes a value written at the given schema version.
+    fn deserialize_schema_version(schema_version: u32, bytes: &[u8]) -> LayerDbResult<Self> {
+        let _ = schema_version;
+        Ok(postcard::from_bytes(bytes)?)
+    }
+}
+
+impl SchemaVersioned for String {}
+
+impl SchemaVersioned for CasValue {}
+
+impl<T> SchemaVersioned for Arc<T>
+where
+    T: SchemaVersioned,
+{
+    const SCHEMA_VERSION: u32 = T::SCHEMA_VERSION;
+
+    fn deserialize_schema_version(schema_version: u32, bytes: &[u8]) -> LayerDbResult<Self> {
+        T::deserialize_schema_version(schema_version, bytes).map(Arc::new)
+    }
+}
+
+/// Encodes and decodes the values of a single cache.
+pub struct ValueCodec<V> {
+    compression: Compression,
+    write_headers: bool,
+    schema_version: u32,
+    deserialize: fn(u32, &[u8]) -> LayerDbResult<V>,
+}
+
+impl<V> ValueCodec<V>
+where
+    V: SchemaVersioned,
+{
+    /// Values are written as plain postcard, uncompressed, unless [headers](Self::with_headers)
+    /// are turned on.
+    pub fn new(compression: Compression) -> Self {
+        Self {
+            compression,
+            write_headers: false,
+            schema_version: V::SCHEMA_VERSION,
+            deserialize: V::deserialize_schema_version,
+        }
+    }
+}
+
+impl<V> ValueCodec<V> {
+    /// Writes values with a header, compressed as configured. Values with a header cannot be read
+    /// by versions that predate it, so this should only be turned on once no instance running
+    /// them reads the cache.
+    pub fn with_headers(mut self, write_headers: bool) -> Self {
+        self.write_headers = write_headers;
+        self
+    }
+
+    /// Encodes a value that has already been serialized with postcard.
+    pub fn encode(&self, serialized: &[u8]) -> LayerDbResult<Vec<u8>> {
+        if !self.write_headers {
+            return Ok(serialized.to_vec());
+        }
+
+        let header = ValueHeader {
+            codec: Codec::Postcard,
+            compression: if serialized.len() < MIN_COMPRESSED_LEN {
+                Compression::None
+            } else {
+                self.compression
+            },
+            schema_version: self.schema_version,
+        };
+
+        let mut encoded = Vec::with_capacity(HEADER_LEN + serialized.len());
+        header.write(&mut encoded);
+        match header.compression {
+            Compression::Deflate => {
+                let mut encoder = DeflateEncoder::new(encoded, flate2::Compression::fast());
+                encoder.write_all(serialized)?;
+                encoded = encoder.finish()?;
+            }
+            Compression::None => encoded.extend_from_slice(serialized),
+        }
+
+        Ok(encoded)
+    }
+
+    /// Decodes a value, along with the length of its serialized form.
+    pub fn decode(&self, bytes: &[u8]) -> LayerDbResult<(V, usize)> {
+        self.decode_verified(bytes, |_| true)
+    }
+
+    /// Like [`decode`](Self::decode), but fails with [`LayerDbError::ValueKeyMismatch`] unless
+    /// `verify` accepts the serialized form of the value.
+    ///
+    /// A value that starts like a header but cannot be decoded by it is decoded as plain postcard
+    /// instead, and fails with the error from its header if that fails too.
+    pub fn decode_verified(
+        &self,
+        bytes: &[u8],
+        verify: impl Fn(&[u8]) -> bool,
+    ) -> LayerDbResult<(V, usize)> {
+        match ValueHeader::read(bytes) {
+            Some(header) => header
+                .and_then(|header| self.decode_payload(header, &bytes[HEADER_LEN..], &verify))
+                .or_else(|err| {
+                    self.decode_payload(ValueHeader::LEGACY, bytes, &verify)
+                        .map_err(|_| err)
+                }),
+            None => self.decode_payload(ValueHeader::LEGACY, bytes, &verify),
+        }
+    }
+
+    fn decode_payload(
+        &self,
+        header: ValueHeader,
+        payload: &[u8],
+        verify: &impl Fn(&[u8]) -> bool,
+    ) -> LayerDbResult<(V, usize)> {
+        let decompressed;
+        let serialized = match header.compression {
+            Compression::Deflate => {
+                let mut buf = Vec::new();
+                DeflateDecoder::new(payload).read_to_end(&mut buf)?;
+                decompressed = buf;
+                decompressed.as_slice()
+            }
+            Compression::None => payload,
+        };
+        if !verify(serialized) {
+            return Err(LayerDbError::ValueKeyMismatch);
+        }
+
+        let value = match header.codec {
+            Codec::Postcard => (self.deserialize)(header.schema_version, serialized)?,
+        };
+
+        Ok((value, serialized.len()))
+    }
+}
+
+impl<V> Clone for ValueCodec<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for ValueCodec<V> {}
+
+impl<V> fmt::Debug for ValueCodec<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValueCodec")
+            .field("compression", &self.compression)
+            .field("write_headers", &self.write_headers)
+            .field("schema_version", &self.schema_version)
+            .finish_non_exhaustive()
+    }
+}
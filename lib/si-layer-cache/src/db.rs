@@ -1,8 +1,10 @@
-use std::{future::IntoFuture, io, path::Path, sync::Arc};
+use std::{collections::HashSet, future::IntoFuture, io, path::Path, sync::Arc, time::Duration};
 
-use serde::{de::DeserializeOwned, Serialize};
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use si_data_nats::NatsClient;
 use si_data_pg::PgPool;
+use si_events::{ContentHash, WorkspaceSnapshotAddress};
 use telemetry::prelude::*;
 use tokio::sync::mpsc;
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
@@ -11,18 +13,122 @@ use ulid::Ulid;
 use crate::db::encrypted_secret::EncryptedSecretDb;
 use crate::{
     activity_client::ActivityClient,
+    codec::{Compression, SchemaVersioned},
+    durable::{DurableBackend, DurableBackendConfig, DurableBackends},
     error::LayerDbResult,
+    integrity::{ScrubReport, VerifyReadsConfig},
     layer_cache::LayerCache,
-    persister::{PersisterClient, PersisterTask},
+    memory_cache::{MemoryCacheConfig, MemoryCacheStats},
+    persister::{InFlightWrites, PersisterClient, PersisterTask},
+    pg::PgLayer,
 };
 
-use self::{cache_updates::CacheUpdatesTask, cas::CasDb, workspace_snapshot::WorkspaceSnapshotDb};
+use self::{
+    cache_updates::CacheUpdatesTask,
+    cas::CasDb,
+    workspace_snapshot::{
+        SnapshotContent, SnapshotDelta, SnapshotDeltaRecord, WorkspaceSnapshotDb,
+    },
+};
 
 mod cache_updates;
 pub mod cas;
 pub mod encrypted_secret;
 pub mod workspace_snapshot;
 
+/// How many content hashes a prefetch reads at a time.
+const PREFETCH_BATCH_SIZE: usize = 500;
+
+/// How often the usage of each memory cache is reported.
+const MEMORY_CACHE_STATS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How each cache of a [`LayerDb`] is configured.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LayerDbConfig {
+    pub cas_memory_cache: MemoryCacheConfig,
+    pub encrypted_secret_memory_cache: MemoryCacheConfig,
+    pub workspace_snapshot_memory_cache: MemoryCacheConfig,
+    pub workspace_snapshot_delta_memory_cache: MemoryCacheConfig,
+    /// Where whole workspace snapshots are kept. Every other cache is kept in Postgres.
+    pub workspace_snapshot_durable_backend: DurableBackendConfig,
+    /// Whether values are written with a header recording how they were encoded. Versions that
+    /// predate the header cannot read values written with one, so turn this on only once every
+    /// instance reading the caches can. Until then, values are written as plain postcard and are
+    /// not compressed.
+    pub write_value_headers: bool,
+    /// How values are compressed on disk and in the durable tier, once they are written with a
+    /// header. Values already written stay as they were written, and can still be read after this
+    /// is changed.
+    pub compression: Compression,
+    /// Which tiers have the values read from them checked before they are trusted.
+    pub verify_reads: VerifyReadsConfig,
+    /// How often every value on disk is checked. Never, if unset.
+    pub scrub_interval_secs: Option<u64>,
+    /// Which snapshots are prefetched when a service starts.
+    pub warm_up: WarmUpConfig,
+    /// Whether the content of a snapshot is prefetched in the background the first time the
+    /// snapshot is read.
+    pub prefetch_on_read: bool,
+}
+
+impl Default for LayerDbConfig {
+    fn default() -> Self {
+        Self {
+            cas_memory_cache: MemoryCacheConfig::default(),
+            encrypted_secret_memory_cache: MemoryCacheConfig::with_max_capacity_bytes(
+                16 * 1024 * 1024,
+            ),
+            workspace_snapshot_memory_cache: MemoryCacheConfig::with_max_capacity_bytes(
+                1024 * 1024 * 1024,
+            ),
+            workspace_snapshot_delta_memory_cache: MemoryCacheConfig::with_max_capacity_bytes(
+                64 * 1024 * 1024,
+            ),
+            workspace_snapshot_durable_backend: DurableBackendConfig::default(),
+            write_value_headers: false,
+            compression: Compression::default(),
+            verify_reads: VerifyReadsConfig::default(),
+            scrub_interval_secs: None,
+            warm_up: WarmUpConfig::default(),
+            prefetch_on_read: false,
+        }
+    }
+}
+
+/// Which snapshots are [prefetched](LayerDb::prefetch) when a service starts: those of the change
+/// sets most recently written to.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct WarmUpConfig {
+    /// Whether to warm up at all. Off unless configured, since it reads from the durable tier
+    /// every time a service starts.
+    pub enabled: bool,
+    /// Only change sets written to within this long are warmed up.
+    pub active_within_secs: u64,
+    pub max_change_sets: u32,
+}
+
+impl Default for WarmUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            active_within_secs: 24 * 60 * 60,
+            max_change_sets: 20,
+        }
+    }
+}
+
+/// What [`LayerDb::prefetch`] found and loaded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrefetchReport {
+    /// Distinct content hashes the snapshot refers to.
+    pub content_hashes: usize,
+    pub already_in_memory: usize,
+    /// Content read into memory from disk or the durable tier.
+    pub loaded: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct LayerDb<CasValue, EncryptedSecretValue, WorkspaceSnapshotValue>
 where
@@ -39,20 +145,40 @@ where
     persister_client: PersisterClient,
     activity: ActivityClient,
     instance_id: Ulid,
+    prefetch_on_read: bool,
+    tracker: TaskTracker,
+    token: CancellationToken,
 }
 
 impl<CasValue, EncryptedSecretValue, WorkspaceSnapshotValue>
     LayerDb<CasValue, EncryptedSecretValue, WorkspaceSnapshotValue>
 where
-    CasValue: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
-    EncryptedSecretValue: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
-    WorkspaceSnapshotValue: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    CasValue: SchemaVersioned + Clone + Send + Sync + 'static,
+    EncryptedSecretValue: SchemaVersioned + Clone + Send + Sync + 'static,
+    WorkspaceSnapshotValue: SchemaVersioned + Clone + Send + Sync + 'static,
 {
     pub async fn initialize(
         disk_path: impl AsRef<Path>,
         pg_pool: PgPool,
         nats_client: NatsClient,
         token: CancellationToken,
+    ) -> LayerDbResult<(Self, LayerDbGracefulShutdown)> {
+        Self::initialize_with_config(
+            disk_path,
+            pg_pool,
+            nats_client,
+            LayerDbConfig::default(),
+            token,
+        )
+        .await
+    }
+
+    pub async fn initialize_with_config(
+        disk_path: impl AsRef<Path>,
+        pg_pool: PgPool,
+        nats_client: NatsClient,
+        config: LayerDbConfig,
+        token: CancellationToken,
     ) -> LayerDbResult<(Self, LayerDbGracefulShutdown)> {
         let instance_id = Ulid::new();
 
@@ -64,18 +190,123 @@ where
         let (tx, rx) = mpsc::unbounded_channel();
         let persister_client = PersisterClient::new(tx);
 
-        let cas_cache: LayerCache<Arc<CasValue>> =
-            LayerCache::new(cas::CACHE_NAME, sled.clone(), pg_pool.clone()).await?;
+        let cas_durable: Arc<dyn DurableBackend> =
+            Arc::new(PgLayer::new(pg_pool.clone(), cas::CACHE_NAME));
+        let encrypted_secret_durable: Arc<dyn DurableBackend> =
+            Arc::new(PgLayer::new(pg_pool.clone(), encrypted_secret::CACHE_NAME));
+        let snapshot_durable = config
+            .workspace_snapshot_durable_backend
+            .build(workspace_snapshot::CACHE_NAME, pg_pool.clone());
+        let snapshot_delta_durable: Arc<dyn DurableBackend> = Arc::new(PgLayer::new(
+            pg_pool.clone(),
+            workspace_snapshot::DELTAS_CACHE_NAME,
+        ));
+
+        let durable_backends: DurableBackends = [
+            (cas::DBNAME, cas_durable.clone()),
+            (encrypted_secret::DBNAME, encrypted_secret_durable.clone()),
+            (workspace_snapshot::DBNAME, snapshot_durable.clone()),
+            (
+                workspace_snapshot::DELTAS_DBNAME,
+                snapshot_delta_durable.clone(),
+            ),
+        ]
+        .into_iter()
+        .map(|(db_name, durable)| (db_name.to_string(), durable))
+        .collect();
+
+        let in_flight = InFlightWrites::default();
+
+        let cas_cache: LayerCache<Arc<CasValue>> = LayerCache::new(
+            cas::CACHE_NAME,
+            sled.clone(),
+            cas_durable,
+            config.cas_memory_cache,
+            config.compression,
+        )
+        .await?
+        .with_value_headers(config.write_value_headers)
+        .with_verify_reads(config.verify_reads, Some(cas::verify_key))
+        .with_in_flight_writes(in_flight.clone());
 
-        let encrypted_secret_cache: LayerCache<Arc<EncryptedSecretValue>> =
-            LayerCache::new(encrypted_secret::CACHE_NAME, sled.clone(), pg_pool.clone()).await?;
+        let encrypted_secret_cache: LayerCache<Arc<EncryptedSecretValue>> = LayerCache::new(
+            encrypted_secret::CACHE_NAME,
+            sled.clone(),
+            encrypted_secret_durable,
+            config.encrypted_secret_memory_cache,
+            config.compression,
+        )
+        .await?
+        .with_value_headers(config.write_value_headers)
+        .with_verify_reads(config.verify_reads, None)
+        .with_in_flight_writes(in_flight.clone());
 
         let snapshot_cache: LayerCache<Arc<WorkspaceSnapshotValue>> = LayerCache::new(
             workspace_snapshot::CACHE_NAME,
             sled.clone(),
-            pg_pool.clone(),
+            snapshot_durable,
+            config.workspace_snapshot_memory_cache,
+            config.compression,
         )
-        .await?;
+        .await?
+        .with_value_headers(config.write_value_headers)
+        .with_verify_reads(config.verify_reads, Some(workspace_snapshot::verify_key))
+        .with_in_flight_writes(in_flight.clone());
+
+        let snapshot_delta_cache: LayerCache<Arc<SnapshotDeltaRecord>> = LayerCache::new(
+            workspace_snapshot::DELTAS_CACHE_NAME,
+            sled.clone(),
+            snapshot_delta_durable,
+            config.workspace_snapshot_delta_memory_cache,
+            config.compression,
+        )
+        .await?
+        .with_value_headers(config.write_value_headers)
+        .with_verify_reads(config.verify_reads, None)
+        .with_in_flight_writes(in_flight.clone());
+
+        let memory_cache_stats: Vec<(&'static str, MemoryCacheStatsFn)> = vec![
+            (cas::CACHE_NAME, {
+                let memory_cache = cas_cache.memory_cache();
+                Box::new(move || memory_cache.stats())
+            }),
+            (encrypted_secret::CACHE_NAME, {
+                let memory_cache = encrypted_secret_cache.memory_cache();
+                Box::new(move || memory_cache.stats())
+            }),
+            (workspace_snapshot::CACHE_NAME, {
+                let memory_cache = snapshot_cache.memory_cache();
+                Box::new(move || memory_cache.stats())
+            }),
+            (workspace_snapshot::DELTAS_CACHE_NAME, {
+                let memory_cache = snapshot_delta_cache.memory_cache();
+                Box::new(move || memory_cache.stats())
+            }),
+        ];
+        tracker.spawn(report_memory_cache_stats(memory_cache_stats, token.clone()));
+
+        if let Some(secs) = config.scrub_interval_secs {
+            let scrubbers: Vec<(&'static str, ScrubFn)> = vec![
+                (cas::CACHE_NAME, scrub_fn(cas_cache.clone())),
+                (
+                    encrypted_secret::CACHE_NAME,
+                    scrub_fn(encrypted_secret_cache.clone()),
+                ),
+                (
+                    workspace_snapshot::CACHE_NAME,
+                    scrub_fn(snapshot_cache.clone()),
+                ),
+                (
+                    workspace_snapshot::DELTAS_CACHE_NAME,
+                    scrub_fn(snapshot_delta_cache.clone()),
+                ),
+            ];
+            tracker.spawn(scrub_disk_caches(
+                scrubbers,
+                Duration::from_secs(secs),
+                token.clone(),
+            ));
+        }
 
         let cache_updates_task = CacheUpdatesTask::create(
             instance_id,
@@ -83,6 +314,7 @@ where
             cas_cache.clone(),
             encrypted_secret_cache.clone(),
             snapshot_cache.clone(),
+            snapshot_delta_cache.clone(),
             token.clone(),
         )
         .await?;
@@ -91,9 +323,10 @@ where
         let persister_task = PersisterTask::create(
             rx,
             sled.clone(),
-            pg_pool.clone(),
+            Arc::new(durable_backends),
             &nats_client,
             instance_id,
+            in_flight,
             token.clone(),
         )
         .await?;
@@ -102,10 +335,17 @@ where
         let cas = CasDb::new(cas_cache, persister_client.clone());
         let encrypted_secret =
             EncryptedSecretDb::new(encrypted_secret_cache, persister_client.clone());
-        let workspace_snapshot = WorkspaceSnapshotDb::new(snapshot_cache, persister_client.clone());
+        let workspace_snapshot = WorkspaceSnapshotDb::new(
+            snapshot_cache,
+            snapshot_delta_cache,
+            persister_client.clone(),
+        );
 
         let activity = ActivityClient::new(instance_id, nats_client.clone(), token.clone());
-        let graceful_shutdown = LayerDbGracefulShutdown { tracker, token };
+        let graceful_shutdown = LayerDbGracefulShutdown {
+            tracker: tracker.clone(),
+            token: token.clone(),
+        };
 
         let layerdb = LayerDb {
             activity,
@@ -117,11 +357,41 @@ where
             persister_client,
             nats_client,
             instance_id,
+            prefetch_on_read: config.prefetch_on_read,
+            tracker,
+            token,
         };
 
         Ok((layerdb, graceful_shutdown))
     }
 
+    /// A handle to the same caches which refuses every write, for callers that must only read.
+    pub fn read_only(&self) -> Self {
+        let persister_client = self.persister_client.read_only();
+
+        LayerDb {
+            activity: self.activity.clone(),
+            cas: CasDb::new(self.cas.cache.clone(), persister_client.clone()),
+            encrypted_secret: EncryptedSecretDb::new(
+                self.encrypted_secret.cache.clone(),
+                persister_client.clone(),
+            ),
+            workspace_snapshot: WorkspaceSnapshotDb::new(
+                self.workspace_snapshot.cache.clone(),
+                self.workspace_snapshot.delta_cache.clone(),
+                persister_client.clone(),
+            ),
+            sled: self.sled.clone(),
+            pg_pool: self.pg_pool.clone(),
+            persister_client,
+            nats_client: self.nats_client.clone(),
+            instance_id: self.instance_id,
+            prefetch_on_read: self.prefetch_on_read,
+            tracker: self.tracker.clone(),
+            token: self.token.clone(),
+        }
+    }
+
     pub fn sled(&self) -> &sled::Db {
         &self.sled
     }
@@ -162,12 +432,110 @@ where
     pub async fn pg_migrate(&self) -> LayerDbResult<()> {
         // This will do all migrations, not just "cas" migrations. We might want
         // to think about restructuring this
-        self.cas.cache.pg().migrate().await?;
+        PgLayer::new(self.pg_pool.clone(), cas::DBNAME)
+            .migrate()
+            .await?;
 
         Ok(())
     }
 }
 
+impl<CasValue, EncryptedSecretValue, WorkspaceSnapshotValue>
+    LayerDb<CasValue, EncryptedSecretValue, WorkspaceSnapshotValue>
+where
+    CasValue: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    EncryptedSecretValue: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    WorkspaceSnapshotValue: Serialize
+        + DeserializeOwned
+        + Clone
+        + Send
+        + Sync
+        + 'static
+        + SnapshotDelta
+        + SnapshotContent,
+{
+    /// Reads a snapshot, and the content it refers to, into memory ahead of it being needed, so
+    /// that the content is not read one piece at a time as the snapshot is used. Returns `None`
+    /// if there is no such snapshot.
+    pub async fn prefetch(
+        &self,
+        address: &WorkspaceSnapshotAddress,
+    ) -> LayerDbResult<Option<PrefetchReport>> {
+        let snapshot = match self.workspace_snapshot.read(address).await? {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+
+        let content_hashes: HashSet<ContentHash> = snapshot.content_hashes().into_iter().collect();
+        let not_in_memory: Vec<ContentHash> = content_hashes
+            .iter()
+            .filter(|hash| !self.cas.cache.contains(&hash.to_string()))
+            .copied()
+            .collect();
+
+        let mut loaded = 0;
+        for batch in not_in_memory.chunks(PREFETCH_BATCH_SIZE) {
+            loaded += self.cas.cache.get_bulk(batch).await?.len();
+        }
+
+        Ok(Some(PrefetchReport {
+            content_hashes: content_hashes.len(),
+            already_in_memory: content_hashes.len() - not_in_memory.len(),
+            loaded,
+        }))
+    }
+
+    /// [Prefetches](Self::prefetch) a snapshot in the background if
+    /// [`LayerDbConfig::prefetch_on_read`] is set. The prefetch is tracked along with the layer
+    /// db's other tasks, and abandoned when it shuts down.
+    pub fn spawn_prefetch(&self, address: WorkspaceSnapshotAddress) {
+        if !self.prefetch_on_read {
+            return;
+        }
+
+        let layer_db = self.clone();
+        self.tracker.spawn(async move {
+            tokio::select! {
+                _ = layer_db.token.cancelled() => {}
+                result = layer_db.prefetch(&address) => {
+                    if let Err(err) = result {
+                        warn!(error = ?err, %address, "snapshot content prefetch failed");
+                    }
+                }
+            }
+        });
+    }
+}
+
+type MemoryCacheStatsFn = Box<dyn Fn() -> MemoryCacheStats + Send + Sync>;
+
+/// Periodically reports the usage of each memory cache until the token is cancelled.
+async fn report_memory_cache_stats(
+    memory_cache_stats: Vec<(&'static str, MemoryCacheStatsFn)>,
+    token: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(MEMORY_CACHE_STATS_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {
+                for (cache_name, stats) in &memory_cache_stats {
+                    let stats = stats();
+                    info!(
+                        layer_cache.cache_name = *cache_name,
+                        layer_cache.memory.hits = stats.hits,
+                        layer_cache.memory.misses = stats.misses,
+                        layer_cache.memory.evictions = stats.evictions,
+                        layer_cache.memory.entry_count = stats.entry_count,
+                        layer_cache.memory.weighted_size_bytes = stats.weighted_size_bytes,
+                        "layer cache memory usage"
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[must_use = "graceful shutdown must be spawned on runtime"]
 #[derive(Debug, Clone)]
 pub struct LayerDbGracefulShutdown {
@@ -225,3 +593,49 @@ mod private {
         }
     }
 }
+
+type ScrubFn = Box<dyn Fn() -> BoxFuture<'static, LayerDbResult<ScrubReport>> + Send + Sync>;
+
+fn scrub_fn<V>(cache: LayerCache<V>) -> ScrubFn
+where
+    V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    Box::new(move || {
+        let cache = cache.clone();
+        Box::pin(async move { cache.scrub().await })
+    })
+}
+
+/// Periodically scrubs the disk tier of each cache until the token is cancelled.
+async fn scrub_disk_caches(
+    scrubbers: Vec<(&'static str, ScrubFn)>,
+    period: Duration,
+    token: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(period);
+    // The first tick completes immediately; there is no need to scrub as soon as we start.
+    interval.tick().await;
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {
+                for (cache_name, scrub) in &scrubbers {
+                    match scrub().await {
+                        Ok(report) => info!(
+                            layer_cache.cache_name = *cache_name,
+                            layer_cache.scrub.examined = report.examined,
+                            layer_cache.scrub.corrupt = report.corrupt,
+                            layer_cache.scrub.repaired = report.repaired,
+                            "layer cache disk scrub finished"
+                        ),
+                        Err(err) => error!(
+                            layer_cache.cache_name = *cache_name,
+                            error = ?err,
+                            "layer cache disk scrub failed"
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
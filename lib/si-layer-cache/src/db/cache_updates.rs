@@ -13,6 +13,7 @@ use ulid::Ulid;
 
 use crate::{
     chunking_nats::{self, ChunkedMessagesStream, ChunkingNats},
+    db::workspace_snapshot::SnapshotDeltaRecord,
     error::LayerDbResult,
     event::LayeredEvent,
     layer_cache::LayerCache,
@@ -26,6 +27,7 @@ use crate::{
 enum CacheName {
     Cas,
     EncryptedSecret,
+    WorkspaceSnapshotDeltas,
     WorkspaceSnapshots,
 }
 
@@ -40,6 +42,7 @@ where
     cas_cache: LayerCache<Arc<CasValue>>,
     encrypted_secret_cache: LayerCache<Arc<EncryptedSecretValue>>,
     snapshot_cache: LayerCache<Arc<WorkspaceSnapshotValue>>,
+    snapshot_delta_cache: LayerCache<Arc<SnapshotDeltaRecord>>,
 }
 
 impl<CasValue, EncryptedSecretValue, WorkspaceSnapshotValue>
@@ -57,6 +60,7 @@ where
         cas_cache: LayerCache<Arc<CasValue>>,
         encrypted_secret_cache: LayerCache<Arc<EncryptedSecretValue>>,
         snapshot_cache: LayerCache<Arc<WorkspaceSnapshotValue>>,
+        snapshot_delta_cache: LayerCache<Arc<SnapshotDeltaRecord>>,
         shutdown_token: CancellationToken,
     ) -> LayerDbResult<Self> {
         let context = jetstream::new(nats_client.as_inner().clone());
@@ -76,6 +80,7 @@ where
             cas_cache,
             encrypted_secret_cache,
             snapshot_cache,
+            snapshot_delta_cache,
         })
     }
 
@@ -91,6 +96,7 @@ where
                         self.cas_cache.clone(),
                         self.encrypted_secret_cache.clone(),
                         self.snapshot_cache.clone(),
+                        self.snapshot_delta_cache.clone(),
                     );
                     // Turns out I think it's probably dangerous to do this spawned, since we want
                     // to make sure we insert things into the cache in the order we receive them.
@@ -135,6 +141,7 @@ where
     cas_cache: LayerCache<Arc<Q>>,
     encrypted_secret_cache: LayerCache<Arc<R>>,
     snapshot_cache: LayerCache<Arc<S>>,
+    snapshot_delta_cache: LayerCache<Arc<SnapshotDeltaRecord>>,
 }
 
 impl<Q, R, S> CacheUpdateTask<Q, R, S>
@@ -148,12 +155,14 @@ where
         cas_cache: LayerCache<Arc<Q>>,
         encrypted_secret_cache: LayerCache<Arc<R>>,
         snapshot_cache: LayerCache<Arc<S>>,
+        snapshot_delta_cache: LayerCache<Arc<SnapshotDeltaRecord>>,
     ) -> CacheUpdateTask<Q, R, S> {
         CacheUpdateTask {
             instance_id,
             cas_cache,
             encrypted_secret_cache,
             snapshot_cache,
+            snapshot_delta_cache,
         }
     }
 
@@ -180,51 +189,40 @@ where
                             CacheName::Cas => {
                                 if !self.cas_cache.contains(key) {
                                     let event: LayeredEvent = postcard::from_bytes(&msg.payload)?;
-                                    let memory_value = self
-                                        .cas_cache
-                                        .deserialize_memory_value(&event.payload.value)?;
                                     let serialized_value = Arc::try_unwrap(event.payload.value)
                                         .unwrap_or_else(|arc| (*arc).clone());
                                     self.cas_cache
-                                        .insert_from_cache_updates(
-                                            key.into(),
-                                            memory_value,
-                                            serialized_value,
-                                        )
+                                        .insert_from_cache_updates(key.into(), serialized_value)
                                         .await?;
                                 }
                             }
                             CacheName::EncryptedSecret => {
                                 if !self.encrypted_secret_cache.contains(key) {
                                     let event: LayeredEvent = postcard::from_bytes(&msg.payload)?;
-                                    let memory_value = self
-                                        .encrypted_secret_cache
-                                        .deserialize_memory_value(&event.payload.value)?;
                                     let serialized_value = Arc::try_unwrap(event.payload.value)
                                         .unwrap_or_else(|arc| (*arc).clone());
                                     self.encrypted_secret_cache
-                                        .insert_from_cache_updates(
-                                            key.into(),
-                                            memory_value,
-                                            serialized_value,
-                                        )
+                                        .insert_from_cache_updates(key.into(), serialized_value)
+                                        .await?;
+                                }
+                            }
+                            CacheName::WorkspaceSnapshotDeltas => {
+                                if !self.snapshot_delta_cache.contains(key) {
+                                    let event: LayeredEvent = postcard::from_bytes(&msg.payload)?;
+                                    let serialized_value = Arc::try_unwrap(event.payload.value)
+                                        .unwrap_or_else(|arc| (*arc).clone());
+                                    self.snapshot_delta_cache
+                                        .insert_from_cache_updates(key.into(), serialized_value)
                                         .await?;
                                 }
                             }
                             CacheName::WorkspaceSnapshots => {
                                 if !self.snapshot_cache.contains(key) {
                                     let event: LayeredEvent = postcard::from_bytes(&msg.payload)?;
-                                    let memory_value = self
-                                        .snapshot_cache
-                                        .deserialize_memory_value(&event.payload.value)?;
                                     let serialized_value = Arc::try_unwrap(event.payload.value)
                                         .unwrap_or_else(|arc| (*arc).clone());
                                     self.snapshot_cache
-                                        .insert_from_cache_updates(
-                                            key.into(),
-                                            memory_value,
-                                            serialized_value,
-                                        )
+                                        .insert_from_cache_updates(key.into(), serialized_value)
                                         .await?;
                                 }
                             }
@@ -16,6 +16,11 @@ pub const DBNAME: &str = "cas";
 pub const CACHE_NAME: &str = "cas";
 pub const PARTITION_KEY: &str = "cas";
 
+/// Checks that a serialized value is the one its [`ContentHash`] was made from.
+pub fn verify_key(key: &str, serialized: &[u8]) -> bool {
+    ContentHash::new(serialized).to_string() == key
+}
+
 #[derive(Debug, Clone)]
 pub struct CasDb<V>
 where
@@ -43,17 +48,21 @@ where
         tenancy: Tenancy,
         actor: Actor,
     ) -> LayerDbResult<(ContentHash, PersisterStatusReader)> {
+        self.persister_client.ensure_writable()?;
         let postcard_value = postcard::to_stdvec(&value)?;
         let key = ContentHash::new(&postcard_value);
         let cache_key: Arc<str> = key.to_string().into();
 
-        self.cache.insert(cache_key.clone(), value.clone()).await;
+        self.cache
+            .insert_serialized(cache_key.clone(), value.clone(), postcard_value.len())
+            .await;
+        let encoded_value = self.cache.encode(&postcard_value)?;
 
         let event = LayeredEvent::new(
             LayeredEventKind::CasInsertion,
             Arc::new(DBNAME.to_string()),
             cache_key,
-            Arc::new(postcard_value),
+            Arc::new(encoded_value),
             Arc::new("cas".to_string()),
             web_events,
             tenancy,
@@ -48,17 +48,21 @@ where
         tenancy: Tenancy,
         actor: Actor,
     ) -> LayerDbResult<PersisterStatusReader> {
+        self.persister_client.ensure_writable()?;
         let postcard_value = postcard::to_stdvec(&value)?;
 
         let cache_key: Arc<str> = key.to_string().into();
 
-        self.cache.insert(cache_key.clone(), value.clone()).await;
+        self.cache
+            .insert_serialized(cache_key.clone(), value.clone(), postcard_value.len())
+            .await;
+        let encoded_value = self.cache.encode(&postcard_value)?;
 
         let event = LayeredEvent::new(
             LayeredEventKind::EncryptedSecretInsertion,
             Arc::new(DBNAME.to_string()),
             cache_key,
-            Arc::new(postcard_value),
+            Arc::new(encoded_value),
             Arc::new(SORT_KEY.to_string()),
             web_events,
             tenancy,
@@ -1,25 +1,73 @@
 use std::sync::Arc;
 
-use serde::{de::DeserializeOwned, Serialize};
-use si_events::{Actor, Tenancy, WebEvent, WorkspaceSnapshotAddress};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use si_events::{Actor, ContentHash, Tenancy, WebEvent, WorkspaceSnapshotAddress};
 
 use crate::{
+    codec::SchemaVersioned,
     error::LayerDbResult,
     event::{LayeredEvent, LayeredEventKind},
     layer_cache::LayerCache,
     persister::{PersisterClient, PersisterStatusReader},
+    LayerDbError,
 };
 
 pub const DBNAME: &str = "workspace_snapshots";
 pub const CACHE_NAME: &str = "workspace_snapshots";
 pub const PARTITION_KEY: &str = "workspace_snapshots";
 
+pub const DELTAS_DBNAME: &str = "workspace_snapshot_deltas";
+pub const DELTAS_CACHE_NAME: &str = "workspace_snapshot_deltas";
+
+/// The longest chain of deltas a snapshot may be stored at the end of. Writing a snapshot that
+/// would make the chain any longer stores it in full instead, which compacts the chain.
+pub const MAX_DELTA_CHAIN_LENGTH: u32 = 16;
+
+/// Checks that a serialized snapshot is the one its [`WorkspaceSnapshotAddress`] was made from.
+/// Deltas are keyed by the address of the snapshot they make, not by their own contents, so they
+/// cannot be checked this way.
+pub fn verify_key(key: &str, serialized: &[u8]) -> bool {
+    WorkspaceSnapshotAddress::new(serialized).to_string() == key
+}
+
+/// A snapshot that can be stored as the difference between itself and the snapshot it was
+/// derived from.
+pub trait SnapshotDelta: Sized {
+    type Delta: Serialize + DeserializeOwned;
+    type Error: std::fmt::Display;
+
+    /// Describes how to get from `base` to `self`.
+    fn delta_from(&self, base: &Self) -> Result<Self::Delta, Self::Error>;
+
+    /// Reconstructs a snapshot from the snapshot a delta was made against.
+    fn apply_delta(base: &Self, delta: Self::Delta) -> Result<Self, Self::Error>;
+}
+
+/// A snapshot that refers to content stored in the cas.
+pub trait SnapshotContent {
+    /// Every content hash the snapshot refers to, in any order and possibly more than once.
+    fn content_hashes(&self) -> Vec<ContentHash>;
+}
+
+/// How a snapshot stored as a delta is found: the address of the snapshot the delta applies to,
+/// and how many deltas it takes to get from a snapshot stored in full to this one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDeltaRecord {
+    pub base: WorkspaceSnapshotAddress,
+    pub chain_length: u32,
+    /// The serialized [`SnapshotDelta::Delta`].
+    pub delta: Vec<u8>,
+}
+
+impl SchemaVersioned for SnapshotDeltaRecord {}
+
 #[derive(Debug, Clone)]
 pub struct WorkspaceSnapshotDb<V>
 where
     V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
 {
     pub cache: LayerCache<Arc<V>>,
+    pub delta_cache: LayerCache<Arc<SnapshotDeltaRecord>>,
     persister_client: PersisterClient,
 }
 
@@ -27,13 +75,19 @@ impl<V> WorkspaceSnapshotDb<V>
 where
     V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
 {
-    pub fn new(cache: LayerCache<Arc<V>>, persister_client: PersisterClient) -> Self {
+    pub fn new(
+        cache: LayerCache<Arc<V>>,
+        delta_cache: LayerCache<Arc<SnapshotDeltaRecord>>,
+        persister_client: PersisterClient,
+    ) -> Self {
         Self {
             cache,
+            delta_cache,
             persister_client,
         }
     }
 
+    /// Stores the whole snapshot.
     pub async fn write(
         &self,
         value: Arc<V>,
@@ -41,28 +95,189 @@ where
         tenancy: Tenancy,
         actor: Actor,
     ) -> LayerDbResult<(WorkspaceSnapshotAddress, PersisterStatusReader)> {
+        self.persister_client.ensure_writable()?;
         let postcard_value = postcard::to_stdvec(&value)?;
         let key = WorkspaceSnapshotAddress::new(&postcard_value);
+
+        let reader = self
+            .write_full(key, value, postcard_value, web_events, tenancy, actor)
+            .await?;
+
+        Ok((key, reader))
+    }
+
+    async fn write_full(
+        &self,
+        key: WorkspaceSnapshotAddress,
+        value: Arc<V>,
+        postcard_value: Vec<u8>,
+        web_events: Option<Vec<WebEvent>>,
+        tenancy: Tenancy,
+        actor: Actor,
+    ) -> LayerDbResult<PersisterStatusReader> {
         let cache_key: Arc<str> = key.to_string().into();
+        let encoded_value = self.cache.encode(&postcard_value)?;
 
-        self.cache.insert(cache_key.clone(), value.clone()).await;
+        self.cache
+            .insert_serialized(cache_key.clone(), value, postcard_value.len())
+            .await;
 
         let event = LayeredEvent::new(
             LayeredEventKind::SnapshotWrite,
             Arc::new(DBNAME.to_string()),
             cache_key,
-            Arc::new(postcard_value),
+            Arc::new(encoded_value),
             Arc::new("workspace_snapshot".to_string()),
             web_events,
             tenancy,
             actor,
         );
-        let reader = self.persister_client.write_event(event)?;
+        self.persister_client.write_event(event)
+    }
+}
+
+impl<V> WorkspaceSnapshotDb<V>
+where
+    V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static + SnapshotDelta,
+{
+    /// Stores the snapshot as a delta against `base`, the snapshot it was derived from. The
+    /// snapshot is stored in full instead if there is no base, if the delta would be no smaller
+    /// than the snapshot, or if the chain of deltas would grow past [`MAX_DELTA_CHAIN_LENGTH`].
+    ///
+    /// Either way, the address is that of the whole snapshot, so readers cannot tell how it was
+    /// stored.
+    pub async fn write_delta(
+        &self,
+        value: Arc<V>,
+        base: WorkspaceSnapshotAddress,
+        web_events: Option<Vec<WebEvent>>,
+        tenancy: Tenancy,
+        actor: Actor,
+    ) -> LayerDbResult<(WorkspaceSnapshotAddress, PersisterStatusReader)> {
+        self.persister_client.ensure_writable()?;
+        let postcard_value = postcard::to_stdvec(&value)?;
+        let key = WorkspaceSnapshotAddress::new(&postcard_value);
+
+        let delta_record = if base == WorkspaceSnapshotAddress::nil() || base == key {
+            None
+        } else {
+            let chain_length = match self.delta_cache.get(base.to_string().into()).await? {
+                Some(base_record) => base_record.chain_length + 1,
+                None => 1,
+            };
+            match self.read(&base).await? {
+                Some(base_value) if chain_length <= MAX_DELTA_CHAIN_LENGTH => {
+                    let delta = value
+                        .delta_from(&base_value)
+                        .map_err(|err| LayerDbError::SnapshotDelta(err.to_string()))?;
+                    let delta = postcard::to_stdvec(&delta)?;
+                    (delta.len() < postcard_value.len()).then_some(SnapshotDeltaRecord {
+                        base,
+                        chain_length,
+                        delta,
+                    })
+                }
+                _ => None,
+            }
+        };
+
+        let reader = match delta_record {
+            Some(delta_record) => {
+                let cache_key: Arc<str> = key.to_string().into();
+                let encoded_record = self
+                    .delta_cache
+                    .encode(&postcard::to_stdvec(&delta_record)?)?;
+
+                self.cache
+                    .insert_serialized(cache_key.clone(), value, postcard_value.len())
+                    .await;
+
+                let event = LayeredEvent::new(
+                    LayeredEventKind::SnapshotDeltaWrite,
+                    Arc::new(DELTAS_DBNAME.to_string()),
+                    cache_key,
+                    Arc::new(encoded_record),
+                    Arc::new("workspace_snapshot_delta".to_string()),
+                    web_events,
+                    tenancy,
+                    actor,
+                );
+                self.persister_client.write_event(event)?
+            }
+            None => {
+                self.write_full(key, value, postcard_value, web_events, tenancy, actor)
+                    .await?
+            }
+        };
 
         Ok((key, reader))
     }
 
+    /// Reads a snapshot, reconstructing it from the chain of deltas it is stored at the end of if
+    /// it was not stored in full.
     pub async fn read(&self, key: &WorkspaceSnapshotAddress) -> LayerDbResult<Option<Arc<V>>> {
-        self.cache.get(key.to_string().into()).await
+        if let Some(value) = self.cache.get(key.to_string().into()).await? {
+            return Ok(Some(value));
+        }
+
+        // Walk back along the chain until we reach a snapshot we have in full.
+        let mut chain = Vec::new();
+        let mut current = *key;
+        let mut value = loop {
+            match self.delta_cache.get(current.to_string().into()).await? {
+                Some(record) => {
+                    current = record.base;
+                    chain.push(record);
+                }
+                None if chain.is_empty() => return Ok(None),
+                None => return Err(LayerDbError::SnapshotDeltaBaseNotFound(current)),
+            }
+
+            if let Some(value) = self.cache.get(current.to_string().into()).await? {
+                break value;
+            }
+        };
+
+        for record in chain.iter().rev() {
+            let delta: V::Delta = postcard::from_bytes(&record.delta)?;
+            value = Arc::new(
+                V::apply_delta(&value, delta)
+                    .map_err(|err| LayerDbError::SnapshotDelta(err.to_string()))?,
+            );
+        }
+
+        self.cache
+            .insert(key.to_string().into(), value.clone())
+            .await;
+
+        Ok(Some(value))
+    }
+
+    /// Stores a snapshot that is at the end of a chain of deltas in full, so that reading it no
+    /// longer requires reading the rest of the chain. Returns `None` if there was nothing to do.
+    pub async fn compact(
+        &self,
+        key: &WorkspaceSnapshotAddress,
+        tenancy: Tenancy,
+        actor: Actor,
+    ) -> LayerDbResult<Option<PersisterStatusReader>> {
+        if self
+            .delta_cache
+            .get(key.to_string().into())
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let value = self
+            .read(key)
+            .await?
+            .ok_or(LayerDbError::SnapshotDeltaBaseNotFound(*key))?;
+        let postcard_value = postcard::to_stdvec(&value)?;
+
+        Ok(Some(
+            self.write_full(*key, value, postcard_value, None, tenancy, actor)
+                .await?,
+        ))
     }
 }
@@ -7,15 +7,21 @@ pub fn default_sled_path() -> LayerDbResult<CanonicalFile> {
     Ok(tempfile::tempdir()?.into_path().try_into()?)
 }
 
+/// Appended to the name of a disk cache's tree to name the tree its quarantined values are kept in.
+const QUARANTINE_TREE_SUFFIX: &[u8] = b".quarantine";
+
 #[derive(Clone, Debug)]
 pub struct DiskCache {
     tree: sled::Tree,
+    quarantine: sled::Tree,
 }
 
 impl DiskCache {
     pub fn new(sled_db: Db, tree_name: impl AsRef<[u8]>) -> LayerDbResult<Self> {
-        let tree = sled_db.open_tree(tree_name.as_ref())?;
-        Ok(Self { tree })
+        let tree_name = tree_name.as_ref();
+        let tree = sled_db.open_tree(tree_name)?;
+        let quarantine = sled_db.open_tree([tree_name, QUARANTINE_TREE_SUFFIX].concat())?;
+        Ok(Self { tree, quarantine })
     }
 
     pub fn get(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>> {
@@ -35,4 +41,35 @@ impl DiskCache {
         let removed_value = self.tree.remove(key.as_bytes())?;
         Ok(removed_value.map(|v| v.to_vec()))
     }
+
+    /// Every key and value in the cache, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = LayerDbResult<(String, Vec<u8>)>> {
+        self.tree.iter().map(|entry| {
+            let (key, value) = entry?;
+            Ok((String::from_utf8_lossy(&key).into_owned(), value.to_vec()))
+        })
+    }
+
+    /// Every key in the cache, in key order.
+    pub fn keys(&self) -> impl Iterator<Item = LayerDbResult<String>> {
+        self.tree.iter().keys().map(|key| {
+            let key = key?;
+            Ok(String::from_utf8_lossy(&key).into_owned())
+        })
+    }
+
+    /// Sets aside a value that could not be trusted, so that it is no longer read but can still
+    /// be looked at.
+    pub fn quarantine(&self, key: &str, value: &[u8]) -> LayerDbResult<()> {
+        self.quarantine.insert(key.as_bytes(), value)?;
+        self.tree.remove(key.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn get_quarantined(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>> {
+        Ok(self
+            .quarantine
+            .get(key.as_bytes())?
+            .map(|bytes| bytes.to_vec()))
+    }
 }
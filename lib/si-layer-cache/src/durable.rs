@@ -0,0 +1,77 @@
+//! The durable tier of a [`LayerCache`](crate::layer_cache::LayerCache): where values are kept
+//! once they have fallen out of every local cache.
+//!
+//! Postgres ([`PgLayer`](crate::pg::PgLayer)) is the default. Caches whose values are too large
+//! to sit comfortably in a `bytea` column can use an
+//! [`ObjectStoreLayer`](crate::object_store::ObjectStoreLayer) instead.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use si_data_pg::PgPool;
+
+use crate::error::LayerDbResult;
+use crate::object_store::{LocalDirectoryObjectStore, ObjectStoreLayer};
+use crate::pg::PgLayer;
+
+/// Stores serialized values by key for a single cache.
+#[async_trait]
+pub trait DurableBackend: Debug + Send + Sync + 'static {
+    async fn get(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>>;
+
+    /// Returns `None` if none of the keys were found.
+    async fn get_many(&self, keys: &[Arc<str>]) -> LayerDbResult<Option<HashMap<String, Vec<u8>>>>;
+
+    /// Stores a value. Storing a value that is already stored counts as writing it again for
+    /// [`keys_created_before`](Self::keys_created_before).
+    async fn insert(&self, key: &str, sort_key: &str, value: &[u8]) -> LayerDbResult<()>;
+
+    async fn contains_key(&self, key: &str) -> LayerDbResult<bool>;
+
+    /// Lists the keys of every value written before the given time.
+    async fn keys_created_before(&self, before: DateTime<Utc>) -> LayerDbResult<Vec<String>>;
+
+    /// Lists the keys of every value written at or after the given time.
+    async fn keys_created_since(&self, since: DateTime<Utc>) -> LayerDbResult<Vec<String>>;
+
+    /// Deletes the values for the given keys, unless they have been written again since the
+    /// given time. Returns how many were deleted.
+    async fn delete_many_created_before(
+        &self,
+        keys: &[Arc<str>],
+        before: DateTime<Utc>,
+    ) -> LayerDbResult<u64>;
+}
+
+/// Which [`DurableBackend`] a cache uses.
+#[remain::sorted]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum DurableBackendConfig {
+    /// An object store laid out in a local directory, with one object per value.
+    LocalDirectory { path: PathBuf },
+    /// A Postgres table named after the cache.
+    #[default]
+    Postgres,
+}
+
+impl DurableBackendConfig {
+    /// Creates the backend for the named cache.
+    pub fn build(&self, name: &str, pg_pool: PgPool) -> Arc<dyn DurableBackend> {
+        match self {
+            Self::LocalDirectory { path } => Arc::new(ObjectStoreLayer::new(
+                Arc::new(LocalDirectoryObjectStore::new(path)),
+                name,
+            )),
+            Self::Postgres => Arc::new(PgLayer::new(pg_pool, name)),
+        }
+    }
+}
+
+/// The [`DurableBackend`] of each cache, by the name of its database.
+pub type DurableBackends = HashMap<String, Arc<dyn DurableBackend>>;
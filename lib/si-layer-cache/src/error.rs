@@ -3,6 +3,7 @@ use std::error;
 use si_data_nats::async_nats::jetstream;
 use si_data_pg::{PgError, PgPoolError};
 use si_events::content_hash::ContentHashParseError;
+use si_events::WorkspaceSnapshotAddress;
 use si_std::CanonicalFileError;
 use thiserror::Error;
 use tokio_stream::Elapsed;
@@ -31,10 +32,14 @@ pub enum LayerDbError {
     ContentConversion(String),
     #[error("could not convert to key from string")]
     CouldNotConvertToKeyFromString(String),
+    #[error("corrupt value in the durable tier of {0} for key {1}")]
+    CorruptDurableValue(String, String),
     #[error("failed to parse content hash from str: {0}")]
     HashParse(#[from] ContentHashParseError),
     #[error("invalid cache name: {0}")]
     InvalidCacheName(String),
+    #[error("invalid object key: {0}")]
+    InvalidObjectKey(String),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("join error: {0}")]
@@ -69,12 +74,22 @@ pub enum LayerDbError {
     PgPool(#[from] PgPoolError),
     #[error("postcard error: {0}")]
     Postcard(#[from] postcard::Error),
+    #[error("this layer db is read-only")]
+    ReadOnly,
     #[error("sled error: {0}")]
     SledError(#[from] sled::Error),
+    #[error("snapshot delta error: {0}")]
+    SnapshotDelta(String),
+    #[error("snapshot delta base not found: {0}")]
+    SnapshotDeltaBaseNotFound(WorkspaceSnapshotAddress),
     #[error("tokio oneshot recv error: {0}")]
     TokioOneShotRecv(#[from] tokio::sync::oneshot::error::RecvError),
     #[error("unexpected activity variant; expected={0}, actual={1}")]
     UnexpectedActivityVariant(String, String),
+    #[error("unsupported value encoding: {0}")]
+    UnsupportedValueEncoding(String),
+    #[error("value does not match its key")]
+    ValueKeyMismatch,
 }
 
 impl LayerDbError {
@@ -52,7 +52,8 @@ impl std::str::FromStr for LayeredEventId {
     }
 }
 
-#[remain::sorted]
+// Events are serialized with postcard, which identifies variants by their position, so new
+// variants go at the end rather than in sorted order.
 #[derive(AsRefStr, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
@@ -61,6 +62,7 @@ pub enum LayeredEventKind {
     EncryptedSecretInsertion,
     Raw,
     SnapshotWrite,
+    SnapshotDeltaWrite,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,28 @@
+//! Checking what a cache reads from its disk and durable tiers before it is trusted.
+//!
+//! A value that fails the check is moved to the disk cache's quarantine, where it can be looked at
+//! later, and is read again from the next tier down instead.
+
+use serde::{Deserialize, Serialize};
+
+/// Checks that a serialized value is the one its key was made for. Only caches keyed by the hash
+/// of their values have one; values of other caches are only checked to decode.
+pub type KeyVerifier = fn(&str, &[u8]) -> bool;
+
+/// Which tiers have the values read from them checked.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct VerifyReadsConfig {
+    pub disk: bool,
+    /// There is no tier below this one to repair from, so a value that fails here is an error.
+    pub durable: bool,
+}
+
+/// What a scrub of a cache's disk tier found.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScrubReport {
+    pub examined: u64,
+    pub corrupt: u64,
+    /// Corrupt values replaced with a good copy from the durable tier.
+    pub repaired: u64,
+}
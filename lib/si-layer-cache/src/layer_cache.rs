@@ -1,12 +1,22 @@
-use std::{collections::HashMap, fmt::Display, path::Path, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+    sync::Arc,
+};
 
+use chrono::{DateTime, Utc};
 use serde::{de::DeserializeOwned, Serialize};
 use si_data_pg::{PgPool, PgPoolConfig};
+use telemetry::prelude::*;
 
+use crate::codec::{Compression, SchemaVersioned, ValueCodec};
 use crate::disk_cache::DiskCache;
+use crate::durable::DurableBackend;
 use crate::error::LayerDbResult;
-use crate::memory_cache::MemoryCache;
-use crate::pg::PgLayer;
+use crate::integrity::{KeyVerifier, ScrubReport, VerifyReadsConfig};
+use crate::memory_cache::{MemoryCache, MemoryCacheConfig};
+use crate::persister::InFlightWrites;
 use crate::LayerDbError;
 
 #[derive(Debug, Clone)]
@@ -16,24 +26,69 @@ where
 {
     memory_cache: MemoryCache<V>,
     disk_cache: Arc<DiskCache>,
-    pg: PgLayer,
+    durable: Arc<dyn DurableBackend>,
+    codec: ValueCodec<V>,
+    name: Arc<str>,
+    verify_reads: VerifyReadsConfig,
+    verify_key: Option<KeyVerifier>,
+    in_flight: InFlightWrites,
 }
 
 impl<V> LayerCache<V>
 where
-    V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    V: SchemaVersioned + Clone + Send + Sync + 'static,
 {
-    pub async fn new(name: &str, fast_disk: sled::Db, pg_pool: PgPool) -> LayerDbResult<Self> {
+    pub async fn new(
+        name: &str,
+        fast_disk: sled::Db,
+        durable: Arc<dyn DurableBackend>,
+        memory_cache_config: MemoryCacheConfig,
+        compression: Compression,
+    ) -> LayerDbResult<Self> {
         let disk_cache = Arc::new(DiskCache::new(fast_disk, name.as_bytes())?);
 
-        let pg = PgLayer::new(pg_pool.clone(), name);
-
         Ok(LayerCache {
-            memory_cache: MemoryCache::new(),
+            memory_cache: MemoryCache::new(memory_cache_config),
             disk_cache,
-            pg,
+            durable,
+            codec: ValueCodec::new(compression),
+            name: name.into(),
+            verify_reads: VerifyReadsConfig::default(),
+            verify_key: None,
+            in_flight: InFlightWrites::default(),
         })
     }
+}
+
+impl<V> LayerCache<V>
+where
+    V: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    /// Writes values with a header recording how they were encoded, which only versions that know
+    /// about headers can read. See [`ValueCodec::with_headers`].
+    pub fn with_value_headers(mut self, write_headers: bool) -> Self {
+        self.codec = self.codec.with_headers(write_headers);
+        self
+    }
+
+    /// Checks values read from the given tiers before they are trusted, against their keys if
+    /// `verify_key` is given.
+    pub fn with_verify_reads(
+        mut self,
+        verify_reads: VerifyReadsConfig,
+        verify_key: Option<KeyVerifier>,
+    ) -> Self {
+        self.verify_reads = verify_reads;
+        self.verify_key = verify_key;
+        self
+    }
+
+    /// Tracks the values still being written to the durable layer, so that they are not taken for
+    /// orphans while they are on disk alone.
+    pub fn with_in_flight_writes(mut self, in_flight: InFlightWrites) -> Self {
+        self.in_flight = in_flight;
+        self
+    }
 
     async fn spawn_disk_cache_write_vec(&self, key: Arc<str>, value: Vec<u8>) -> LayerDbResult<()> {
         let self_clone = self.clone();
@@ -45,29 +100,31 @@ where
     }
 
     pub async fn get(&self, key: Arc<str>) -> LayerDbResult<Option<V>> {
-        Ok(match self.memory_cache.get(&key).await {
-            Some(memory_value) => Some(memory_value),
-            None => match self.disk_cache.get(&key)? {
-                Some(value) => {
-                    let deserialized: V = postcard::from_bytes(&value)?;
-
-                    self.memory_cache.insert(key, deserialized.clone()).await;
-                    Some(deserialized)
-                }
-                None => match self.pg.get(&key).await? {
-                    Some(value) => {
-                        let deserialized: V = postcard::from_bytes(&value)?;
+        if let Some(memory_value) = self.memory_cache.get(&key).await {
+            return Ok(Some(memory_value));
+        }
 
-                        self.memory_cache
-                            .insert(key.clone(), deserialized.clone())
-                            .await;
-                        self.spawn_disk_cache_write_vec(key.clone(), value).await?;
+        if let Some(value) = self.disk_cache.get(&key)? {
+            if let Some((deserialized, serialized_len)) = self.decode_from_disk(&key, &value)? {
+                self.memory_cache
+                    .insert_serialized(key, deserialized.clone(), serialized_len)
+                    .await;
+                return Ok(Some(deserialized));
+            }
+        }
 
-                        Some(deserialized)
-                    }
-                    None => None,
-                },
-            },
+        Ok(match self.durable.get(&key).await? {
+            Some(value) => {
+                let (deserialized, serialized_len) = self.decode_from_durable(&key, &value)?;
+
+                self.memory_cache
+                    .insert_serialized(key.clone(), deserialized.clone(), serialized_len)
+                    .await;
+                self.spawn_disk_cache_write_vec(key.clone(), value).await?;
+
+                Some(deserialized)
+            }
+            None => None,
         })
     }
 
@@ -84,14 +141,22 @@ where
             if let Some(found) = match self.memory_cache.get(&key_str).await {
                 Some(memory_value) => Some(memory_value),
                 None => match self.disk_cache.get(&key_str)? {
-                    Some(value) => {
-                        let deserialized: V = postcard::from_bytes(&value)?;
-
-                        self.memory_cache
-                            .insert(key_str.clone(), deserialized.clone())
-                            .await;
-                        Some(deserialized)
-                    }
+                    Some(value) => match self.decode_from_disk(&key_str, &value)? {
+                        Some((deserialized, serialized_len)) => {
+                            self.memory_cache
+                                .insert_serialized(
+                                    key_str.clone(),
+                                    deserialized.clone(),
+                                    serialized_len,
+                                )
+                                .await;
+                            Some(deserialized)
+                        }
+                        None => {
+                            not_found.push(key_str.clone());
+                            None
+                        }
+                    },
                     None => {
                         not_found.push(key_str.clone());
                         None
@@ -103,11 +168,11 @@ where
         }
 
         if !not_found.is_empty() {
-            if let Some(pg_found) = self.pg.get_many(&not_found).await? {
-                for (k, v) in pg_found {
-                    let deserialized: V = postcard::from_bytes(&v)?;
+            if let Some(durable_found) = self.durable.get_many(&not_found).await? {
+                for (k, v) in durable_found {
+                    let (deserialized, serialized_len) = self.decode_from_durable(&k, &v)?;
                     self.memory_cache
-                        .insert(k.clone().into(), deserialized.clone())
+                        .insert_serialized(k.clone().into(), deserialized.clone(), serialized_len)
                         .await;
                     self.spawn_disk_cache_write_vec(k.clone().into(), v).await?;
                     found_keys.insert(
@@ -123,8 +188,126 @@ where
         Ok(found_keys)
     }
 
+    /// Decodes a value read from disk. If the value cannot be decoded, or the disk tier is
+    /// verified and the value fails, it is quarantined and `None` is returned so that it is read
+    /// from the durable tier instead.
+    fn decode_from_disk(&self, key: &str, value: &[u8]) -> LayerDbResult<Option<(V, usize)>> {
+        let decoded = if self.verify_reads.disk {
+            self.decode_verified(key, value)
+        } else {
+            self.codec.decode(value)
+        };
+
+        match decoded {
+            Ok(decoded) => Ok(Some(decoded)),
+            Err(err) => {
+                warn!(
+                    layer_cache.cache_name = %self.name,
+                    layer_cache.key = key,
+                    error = %err,
+                    "corrupt value on disk; quarantining and reading from the durable tier"
+                );
+                self.disk_cache.quarantine(key, value)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Decodes a value read from the durable tier. If the durable tier is verified and the value
+    /// fails, it is quarantined on disk and an error is returned, since there is nowhere left to
+    /// read it from.
+    fn decode_from_durable(&self, key: &str, value: &[u8]) -> LayerDbResult<(V, usize)> {
+        if !self.verify_reads.durable {
+            return self.codec.decode(value);
+        }
+
+        self.decode_verified(key, value).map_err(|err| {
+            error!(
+                layer_cache.cache_name = %self.name,
+                layer_cache.key = key,
+                error = %err,
+                "corrupt value in the durable tier"
+            );
+            if let Err(err) = self.disk_cache.quarantine(key, value) {
+                warn!(error = %err, "could not quarantine corrupt value");
+            }
+            LayerDbError::CorruptDurableValue(self.name.to_string(), key.to_string())
+        })
+    }
+
+    fn decode_verified(&self, key: &str, value: &[u8]) -> LayerDbResult<(V, usize)> {
+        match self.verify_key {
+            Some(verify_key) => self
+                .codec
+                .decode_verified(value, |serialized| verify_key(key, serialized)),
+            None => self.codec.decode(value),
+        }
+    }
+
+    /// Checks every value in the disk tier, whether or not reads from it are verified. Corrupt
+    /// values are quarantined and replaced with a good copy from the durable tier, if there is
+    /// one.
+    pub async fn scrub(&self) -> LayerDbResult<ScrubReport> {
+        let self_clone = self.clone();
+        let (examined, corrupt_keys) = tokio::task::spawn_blocking(move || {
+            let mut examined = 0;
+            let mut corrupt_keys = Vec::new();
+            for entry in self_clone.disk_cache.iter() {
+                let (key, value) = entry?;
+                examined += 1;
+                if self_clone.decode_verified(&key, &value).is_err() {
+                    self_clone.disk_cache.quarantine(&key, &value)?;
+                    corrupt_keys.push(key);
+                }
+            }
+            Ok::<_, LayerDbError>((examined, corrupt_keys))
+        })
+        .await??;
+
+        let mut report = ScrubReport {
+            examined,
+            corrupt: corrupt_keys.len() as u64,
+            repaired: 0,
+        };
+        for key in corrupt_keys {
+            warn!(
+                layer_cache.cache_name = %self.name,
+                layer_cache.key = key,
+                "scrub found a corrupt value on disk"
+            );
+            // The copy in memory may have been read from the corrupt one.
+            self.memory_cache.remove(&key).await;
+
+            if let Some(value) = self.durable.get(&key).await? {
+                match self.decode_verified(&key, &value) {
+                    Ok(_) => {
+                        self.spawn_disk_cache_write_vec(key.into(), value).await?;
+                        report.repaired += 1;
+                    }
+                    Err(err) => {
+                        error!(
+                            layer_cache.cache_name = %self.name,
+                            layer_cache.key = key,
+                            error = %err,
+                            "corrupt value in the durable tier"
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Encodes a value that has already been serialized with postcard for the disk and durable
+    /// layers.
+    pub fn encode(&self, serialized: &[u8]) -> LayerDbResult<Vec<u8>> {
+        self.codec.encode(serialized)
+    }
+
+    /// Decodes a value as read from the disk or durable layer.
     pub fn deserialize_memory_value(&self, bytes: &[u8]) -> LayerDbResult<V> {
-        postcard::from_bytes(bytes).map_err(Into::into)
+        Ok(self.codec.decode(bytes)?.0)
     }
 
     pub fn memory_cache(&self) -> MemoryCache<V> {
@@ -135,14 +318,92 @@ where
         self.disk_cache.clone()
     }
 
-    pub fn pg(&self) -> PgLayer {
-        self.pg.clone()
+    pub fn durable(&self) -> Arc<dyn DurableBackend> {
+        self.durable.clone()
     }
 
     pub async fn remove_from_memory(&self, key: &str) {
         self.memory_cache.remove(key).await;
     }
 
+    /// Removes the values for the given keys from every layer, unless they have been written to
+    /// the durable layer again since the given time. Returns how many were removed from the
+    /// durable layer.
+    ///
+    /// Only this instance's memory and disk caches can be cleared; other instances keep serving
+    /// their copies until they are evicted.
+    pub async fn remove_many_created_before(
+        &self,
+        keys: &[Arc<str>],
+        before: DateTime<Utc>,
+    ) -> LayerDbResult<u64> {
+        let removed = self
+            .durable
+            .delete_many_created_before(keys, before)
+            .await?;
+
+        let self_clone = self.clone();
+        let disk_keys = keys.to_vec();
+        tokio::task::spawn_blocking(move || {
+            for key in disk_keys {
+                self_clone.disk_cache.remove(&key)?;
+            }
+            Ok::<_, LayerDbError>(())
+        })
+        .await??;
+
+        for key in keys {
+            self.memory_cache.remove(key).await;
+        }
+
+        Ok(removed)
+    }
+
+    /// Removes every value from this instance's disk cache that is neither in `keep` nor in the
+    /// durable layer, such as those left behind when another instance removed them from the
+    /// durable layer. Returns how many there were; nothing is removed for a dry run.
+    ///
+    /// Values the persister is still writing to the durable layer are kept, as are copies in
+    /// memory, which are left to be evicted.
+    pub async fn remove_disk_orphans(
+        &self,
+        keep: &HashSet<String>,
+        dry_run: bool,
+    ) -> LayerDbResult<u64> {
+        let disk_cache = self.disk_cache.clone();
+        let disk_keys = tokio::task::spawn_blocking(move || {
+            disk_cache.keys().collect::<LayerDbResult<Vec<String>>>()
+        })
+        .await??;
+
+        let mut orphans = Vec::new();
+        for key in disk_keys {
+            // In-flight writes are checked before the durable layer: a write that finishes between
+            // the two checks is then found in the durable layer.
+            if keep.contains(&key)
+                || self.in_flight.contains(&self.name, &key).await
+                || self.durable.contains_key(&key).await?
+            {
+                continue;
+            }
+            orphans.push(key);
+        }
+        let orphan_count = orphans.len() as u64;
+
+        if !dry_run {
+            let disk_cache = self.disk_cache.clone();
+            tokio::task::spawn_blocking(move || {
+                for key in orphans {
+                    disk_cache.remove(&key)?;
+                }
+                Ok::<_, LayerDbError>(())
+            })
+            .await??;
+        }
+
+        Ok(orphan_count)
+    }
+
     pub fn contains(&self, key: &str) -> bool {
         self.memory_cache.contains(key)
     }
@@ -153,14 +414,27 @@ where
         }
     }
 
+    /// Like [`insert`](Self::insert), for when the value has already been serialized, so that it
+    /// need not be serialized again to weigh it.
+    pub async fn insert_serialized(&self, key: Arc<str>, value: V, serialized_len: usize) {
+        if !self.memory_cache.contains(&key) {
+            self.memory_cache
+                .insert_serialized(key, value, serialized_len)
+                .await;
+        }
+    }
+
+    /// Inserts a value written by another instance into the memory and disk layers.
     pub async fn insert_from_cache_updates(
         &self,
         key: Arc<str>,
-        memory_value: V,
-        serialize_value: Vec<u8>,
+        encoded_value: Vec<u8>,
     ) -> LayerDbResult<()> {
-        self.memory_cache.insert(key.clone(), memory_value).await;
-        self.spawn_disk_cache_write_vec(key.clone(), serialize_value)
+        let (memory_value, serialized_len) = self.codec.decode(&encoded_value)?;
+        self.memory_cache
+            .insert_serialized(key.clone(), memory_value, serialized_len)
+            .await;
+        self.spawn_disk_cache_write_vec(key.clone(), encoded_value)
             .await
     }
 }
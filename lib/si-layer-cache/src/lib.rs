@@ -2,9 +2,10 @@
 //!
 //! It should have 3 layers of caching:
 //!
-//! * Moka, an in-memory LRU style cache.
+//! * Moka, an in-memory LRU style cache, bounded by the size of the serialized values it holds.
 //! * Sled, an on-disk memory-mapped cache, to keep more data locally than can be held in memory
-//! * Postgres, our final persistant storage layer.
+//! * Postgres, our final persistant storage layer, or an object store laid out in a local
+//! directory for caches whose values are too large to keep in Postgres.
 //!
 //! When a write is requested, the following happens:
 //!
@@ -26,17 +27,23 @@
 pub mod activities;
 mod activity_client;
 pub mod chunking_nats;
+pub mod codec;
 pub mod db;
 pub mod disk_cache;
+pub mod durable;
 pub mod error;
 pub mod event;
+pub mod integrity;
 pub mod layer_cache;
 pub mod memory_cache;
 mod nats;
+pub mod object_store;
 pub mod persister;
 pub mod pg;
 
-pub use db::LayerDb;
+pub use codec::{Compression, SchemaVersioned};
+pub use db::{LayerDb, LayerDbConfig, PrefetchReport, WarmUpConfig};
 pub use disk_cache::default_sled_path;
 pub use error::LayerDbError;
+pub use memory_cache::MemoryCacheConfig;
 pub use pg::{default_pg_pool_config, APPLICATION_NAME, DBNAME};
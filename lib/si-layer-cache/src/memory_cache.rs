@@ -1,14 +1,86 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use moka::future::Cache;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use telemetry::prelude::*;
+
+const DEFAULT_MAX_CAPACITY_BYTES: u64 = 256 * 1024 * 1024;
+/// What a value that cannot be serialized to be weighed is charged against the byte budget. It is
+/// deliberately large so that such values cannot quietly pile up in memory.
+const UNWEIGHABLE_SIZE_BYTES: usize = 16 * 1024 * 1024;
+
+/// How much a [`MemoryCache`] may hold, and for how long.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MemoryCacheConfig {
+    /// The most the serialized values held may add up to.
+    #[serde(default = "default_max_capacity_bytes")]
+    pub max_capacity_bytes: u64,
+    /// How long a value is held after it was inserted.
+    #[serde(default)]
+    pub time_to_live_secs: Option<u64>,
+    /// How long a value is held after it was last read or inserted.
+    #[serde(default)]
+    pub time_to_idle_secs: Option<u64>,
+}
+
+impl Default for MemoryCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_capacity_bytes: default_max_capacity_bytes(),
+            time_to_live_secs: None,
+            time_to_idle_secs: None,
+        }
+    }
+}
+
+impl MemoryCacheConfig {
+    pub fn with_max_capacity_bytes(max_capacity_bytes: u64) -> Self {
+        Self {
+            max_capacity_bytes,
+            ..Default::default()
+        }
+    }
+}
+
+fn default_max_capacity_bytes() -> u64 {
+    DEFAULT_MAX_CAPACITY_BYTES
+}
+
+/// Counts of how a [`MemoryCache`] has been used since it was created, along with what it holds
+/// now.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Values removed to stay within the byte budget, or because they expired.
+    pub evictions: u64,
+    pub entry_count: u64,
+    pub weighted_size_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct MemoryCacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// A value along with the size it was weighed at when it was inserted.
+#[derive(Clone, Debug)]
+struct Weighed<V> {
+    value: V,
+    size: u32,
+}
 
 #[derive(Clone, Debug)]
 pub struct MemoryCache<V>
 where
     V: Serialize + DeserializeOwned + Clone + Send + Sync + Clone + 'static,
 {
-    cache: Cache<Arc<str>, V>,
+    cache: Cache<Arc<str>, Weighed<V>>,
+    counters: Arc<MemoryCacheCounters>,
 }
 
 impl<V> Default for MemoryCache<V>
@@ -16,7 +88,7 @@ where
     V: Serialize + DeserializeOwned + Clone + Send + Sync + Clone + 'static,
 {
     fn default() -> Self {
-        Self::new()
+        Self::new(MemoryCacheConfig::default())
     }
 }
 
@@ -24,18 +96,65 @@ impl<V> MemoryCache<V>
 where
     V: Serialize + DeserializeOwned + Clone + Send + Sync + Clone + 'static,
 {
-    pub fn new() -> Self {
+    pub fn new(config: MemoryCacheConfig) -> Self {
+        let counters = Arc::new(MemoryCacheCounters::default());
+
+        let eviction_counters = counters.clone();
+        let mut builder = Cache::builder()
+            .max_capacity(config.max_capacity_bytes)
+            .weigher(|_key: &Arc<str>, weighed: &Weighed<V>| weighed.size)
+            .eviction_listener(move |_key, _value, cause| {
+                if cause.was_evicted() {
+                    eviction_counters.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        if let Some(secs) = config.time_to_live_secs {
+            builder = builder.time_to_live(Duration::from_secs(secs));
+        }
+        if let Some(secs) = config.time_to_idle_secs {
+            builder = builder.time_to_idle(Duration::from_secs(secs));
+        }
+
         Self {
-            cache: Cache::new(u64::MAX),
+            cache: builder.build(),
+            counters,
         }
     }
 
     pub async fn get(&self, key: &str) -> Option<V> {
-        self.cache.get(key).await
+        match self.cache.get(key).await {
+            Some(weighed) => {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                Some(weighed.value)
+            }
+            None => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
     }
 
+    /// Inserts a value, weighing it by serializing it. Prefer
+    /// [`insert_serialized`](Self::insert_serialized) when the serialized value is at hand.
     pub async fn insert(&self, key: Arc<str>, value: V) {
-        self.cache.insert(key, value).await;
+        let serialized_len = match postcard::to_stdvec(&value) {
+            Ok(bytes) => bytes.len(),
+            Err(err) => {
+                warn!(
+                    error = ?err,
+                    %key,
+                    "could not serialize memory cache value to weigh it; charging {UNWEIGHABLE_SIZE_BYTES} bytes",
+                );
+                UNWEIGHABLE_SIZE_BYTES
+            }
+        };
+        self.insert_serialized(key, value, serialized_len).await;
+    }
+
+    /// Inserts a value, weighing it by the length of its serialized form.
+    pub async fn insert_serialized(&self, key: Arc<str>, value: V, serialized_len: usize) {
+        let size = u32::try_from(serialized_len).unwrap_or(u32::MAX);
+        self.cache.insert(key, Weighed { value, size }).await;
     }
 
     pub async fn remove(&self, key: &str) {
@@ -45,4 +164,20 @@ where
     pub fn contains(&self, key: &str) -> bool {
         self.cache.contains_key(key)
     }
+
+    /// Applies any evictions and expirations that are still pending, which [`stats`](Self::stats)
+    /// otherwise only reflects eventually.
+    pub async fn run_pending_tasks(&self) {
+        self.cache.run_pending_tasks().await;
+    }
+
+    pub fn stats(&self) -> MemoryCacheStats {
+        MemoryCacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            entry_count: self.cache.entry_count(),
+            weighted_size_bytes: self.cache.weighted_size(),
+        }
+    }
 }
@@ -0,0 +1,10 @@
+CREATE TABLE workspace_snapshot_deltas
+(
+    key               text                      NOT NULL PRIMARY KEY,
+    sort_key          text                      NOT NULL,
+    created_at        timestamp with time zone  NOT NULL DEFAULT CLOCK_TIMESTAMP(),
+    value             bytea                     NOT NULL,
+    serialization_lib text                      NOT NULL DEFAULT 'postcard'
+);
+
+CREATE INDEX IF NOT EXISTS workspace_snapshot_deltas_sort_key ON workspace_snapshot_deltas (sort_key);
@@ -0,0 +1,312 @@
+//! An object store as the [`DurableBackend`] of a cache, with one object per value.
+//!
+//! The [`ObjectStore`] trait is shaped after the handful of S3 operations the layer cache needs.
+//! The only implementation is [`LocalDirectoryObjectStore`], which lays the objects out in a local
+//! directory for development and tests; there is no client for a hosted object store yet.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use tokio::sync::Mutex;
+use ulid::Ulid;
+
+use crate::durable::DurableBackend;
+use crate::error::LayerDbResult;
+use crate::LayerDbError;
+
+/// What an object store knows about an object without reading it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectMetadata {
+    pub key: String,
+    pub last_modified: DateTime<Utc>,
+    pub size: u64,
+    /// Changes whenever the object is written.
+    pub etag: String,
+}
+
+/// The operations of an S3-compatible object store that an [`ObjectStoreLayer`] is built on.
+#[async_trait]
+pub trait ObjectStore: Debug + Send + Sync + 'static {
+    /// Stores an object, replacing any object with the same key.
+    async fn put_object(&self, key: &str, body: &[u8]) -> LayerDbResult<()>;
+
+    async fn get_object(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>>;
+
+    async fn head_object(&self, key: &str) -> LayerDbResult<Option<ObjectMetadata>>;
+
+    /// Deletes an object. Deleting an object that does not exist is not an error.
+    async fn delete_object(&self, key: &str) -> LayerDbResult<()>;
+
+    /// Deletes an object only if its etag still matches, as an S3 delete with `If-Match` does, so
+    /// that an object written again since it was last looked at is kept. Returns whether it was
+    /// deleted.
+    async fn delete_object_if_match(&self, key: &str, etag: &str) -> LayerDbResult<bool>;
+
+    /// Lists every object whose key starts with the prefix.
+    async fn list_objects(&self, prefix: &str) -> LayerDbResult<Vec<ObjectMetadata>>;
+}
+
+/// How many objects [`ObjectStoreLayer::get_many`](DurableBackend::get_many) reads at once.
+const GET_MANY_CONCURRENCY: usize = 32;
+
+/// Stores the values of a cache as objects named `<cache name>/<key>`.
+#[derive(Clone, Debug)]
+pub struct ObjectStoreLayer {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+}
+
+impl ObjectStoreLayer {
+    pub fn new(store: Arc<dyn ObjectStore>, name: impl AsRef<str>) -> Self {
+        Self {
+            store,
+            prefix: format!("{}/", name.as_ref()),
+        }
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+#[async_trait]
+impl DurableBackend for ObjectStoreLayer {
+    async fn get(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>> {
+        self.store.get_object(&self.object_key(key)).await
+    }
+
+    async fn get_many(&self, keys: &[Arc<str>]) -> LayerDbResult<Option<HashMap<String, Vec<u8>>>> {
+        let mut gets = stream::iter(keys.iter().cloned())
+            .map(|key| async move {
+                self.get(&key)
+                    .await
+                    .map(|value| value.map(|value| (key.to_string(), value)))
+            })
+            .buffer_unordered(GET_MANY_CONCURRENCY);
+
+        let mut result = HashMap::new();
+        while let Some(found) = gets.try_next().await? {
+            result.extend(found);
+        }
+
+        if result.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(result))
+    }
+
+    async fn insert(&self, key: &str, _sort_key: &str, value: &[u8]) -> LayerDbResult<()> {
+        self.store.put_object(&self.object_key(key), value).await
+    }
+
+    async fn contains_key(&self, key: &str) -> LayerDbResult<bool> {
+        Ok(self
+            .store
+            .head_object(&self.object_key(key))
+            .await?
+            .is_some())
+    }
+
+    async fn keys_created_before(&self, before: DateTime<Utc>) -> LayerDbResult<Vec<String>> {
+        Ok(self
+            .store
+            .list_objects(&self.prefix)
+            .await?
+            .into_iter()
+            .filter(|object| object.last_modified < before)
+            .filter_map(|object| {
+                object
+                    .key
+                    .strip_prefix(self.prefix.as_str())
+                    .map(ToOwned::to_owned)
+            })
+            .collect())
+    }
+
+    async fn keys_created_since(&self, since: DateTime<Utc>) -> LayerDbResult<Vec<String>> {
+        Ok(self
+            .store
+            .list_objects(&self.prefix)
+            .await?
+            .into_iter()
+            .filter(|object| object.last_modified >= since)
+            .filter_map(|object| {
+                object
+                    .key
+                    .strip_prefix(self.prefix.as_str())
+                    .map(ToOwned::to_owned)
+            })
+            .collect())
+    }
+
+    async fn delete_many_created_before(
+        &self,
+        keys: &[Arc<str>],
+        before: DateTime<Utc>,
+    ) -> LayerDbResult<u64> {
+        let mut deleted = 0;
+        for key in keys {
+            let object_key = self.object_key(key);
+            match self.store.head_object(&object_key).await? {
+                Some(object) if object.last_modified < before => {
+                    if self
+                        .store
+                        .delete_object_if_match(&object_key, &object.etag)
+                        .await?
+                    {
+                        deleted += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(deleted)
+    }
+}
+
+/// An [`ObjectStore`] that keeps each object in a file under a local directory, with the `/`s in
+/// its key as directory separators.
+#[derive(Clone, Debug)]
+pub struct LocalDirectoryObjectStore {
+    root: PathBuf,
+    /// Held while an object is replaced or deleted, so that a conditional delete cannot remove an
+    /// object written between its check and its removal.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl LocalDirectoryObjectStore {
+    /// Partially written objects are kept here, so that they are never listed or read.
+    const STAGING_DIR: &'static str = ".staging";
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn path_for(&self, key: &str) -> LayerDbResult<PathBuf> {
+        let valid = !key.is_empty()
+            && key
+                .split('/')
+                .all(|part| !part.is_empty() && part != "." && part != "..")
+            && !key.starts_with(Self::STAGING_DIR);
+        if !valid {
+            return Err(LayerDbError::InvalidObjectKey(key.to_string()));
+        }
+
+        Ok(self.root.join(key))
+    }
+
+    async fn metadata_for(key: String, path: &Path) -> LayerDbResult<Option<ObjectMetadata>> {
+        match tokio::fs::metadata(path).await {
+            // Every write renames a new file into place, so the inode changes with each one.
+            Ok(metadata) => Ok(Some(ObjectMetadata {
+                key,
+                last_modified: metadata.modified()?.into(),
+                size: metadata.len(),
+                etag: format!("{}-{}", metadata.ino(), metadata.mtime_nsec()),
+            })),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalDirectoryObjectStore {
+    async fn put_object(&self, key: &str, body: &[u8]) -> LayerDbResult<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let staging_dir = self.root.join(Self::STAGING_DIR);
+        tokio::fs::create_dir_all(&staging_dir).await?;
+        let staging_path = staging_dir.join(Ulid::new().to_string());
+        tokio::fs::write(&staging_path, body).await?;
+        let _guard = self.write_lock.lock().await;
+        tokio::fs::rename(&staging_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path_for(key)?).await {
+            Ok(body) => Ok(Some(body)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn head_object(&self, key: &str) -> LayerDbResult<Option<ObjectMetadata>> {
+        Self::metadata_for(key.to_string(), &self.path_for(key)?).await
+    }
+
+    async fn delete_object(&self, key: &str) -> LayerDbResult<()> {
+        let path = self.path_for(key)?;
+        let _guard = self.write_lock.lock().await;
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete_object_if_match(&self, key: &str, etag: &str) -> LayerDbResult<bool> {
+        let path = self.path_for(key)?;
+        let _guard = self.write_lock.lock().await;
+        match Self::metadata_for(key.to_string(), &path).await? {
+            Some(object) if object.etag == etag => match tokio::fs::remove_file(path).await {
+                Ok(()) => Ok(true),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+                Err(err) => Err(err.into()),
+            },
+            _ => Ok(false),
+        }
+    }
+
+    async fn list_objects(&self, prefix: &str) -> LayerDbResult<Vec<ObjectMetadata>> {
+        // Only the directory the prefix ends in needs to be walked, along with any below it.
+        let dir_prefix = match prefix.rfind('/') {
+            Some(index) => &prefix[..=index],
+            None => "",
+        };
+        let mut objects = Vec::new();
+        let mut dirs = vec![dir_prefix.to_string()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(self.root.join(&dir)).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if dir.is_empty() && name == Self::STAGING_DIR {
+                    continue;
+                }
+                let key = format!("{dir}{name}");
+                if entry.file_type().await?.is_dir() {
+                    if key.starts_with(prefix) || prefix.starts_with(&key) {
+                        dirs.push(format!("{key}/"));
+                    }
+                } else if key.starts_with(prefix) {
+                    if let Some(object) = Self::metadata_for(key, &entry.path()).await? {
+                        objects.push(object);
+                    }
+                }
+            }
+        }
+
+        Ok(objects)
+    }
+}
@@ -1,24 +1,23 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use si_data_nats::{async_nats::jetstream, HeaderMap, NatsClient};
-use si_data_pg::PgPool;
 use telemetry::prelude::*;
 use tokio::{
     join,
-    sync::{mpsc, oneshot},
+    sync::{mpsc, oneshot, Mutex},
 };
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use ulid::Ulid;
 
 use crate::{
     chunking_nats::ChunkingNats,
+    durable::DurableBackends,
     error::{LayerDbError, LayerDbResult},
     event::LayeredEvent,
     nats::{
         layerdb_events_stream, subject, NATS_HEADER_DB_NAME, NATS_HEADER_INSTANCE_ID,
         NATS_HEADER_KEY,
     },
-    pg::PgLayer,
 };
 
 #[derive(Debug)]
@@ -66,11 +65,32 @@ impl PersisterStatusReader {
 #[derive(Debug, Clone)]
 pub struct PersisterClient {
     tx: mpsc::UnboundedSender<PersistMessage>,
+    read_only: bool,
 }
 
 impl PersisterClient {
     pub fn new(tx: mpsc::UnboundedSender<PersistMessage>) -> PersisterClient {
-        PersisterClient { tx }
+        PersisterClient {
+            tx,
+            read_only: false,
+        }
+    }
+
+    /// A client for the same persister which refuses every write.
+    pub fn read_only(&self) -> PersisterClient {
+        PersisterClient {
+            tx: self.tx.clone(),
+            read_only: true,
+        }
+    }
+
+    /// Fails if this client refuses writes. Callers check this before writing anything, even to
+    /// the memory cache, so that a refused write leaves no trace.
+    pub fn ensure_writable(&self) -> LayerDbResult<()> {
+        if self.read_only {
+            return Err(LayerDbError::ReadOnly);
+        }
+        Ok(())
     }
 
     fn get_status_channels(&self) -> (PersisterStatusWriter, PersisterStatusReader) {
@@ -82,19 +102,58 @@ impl PersisterClient {
     }
 
     pub fn write_event(&self, event: LayeredEvent) -> LayerDbResult<PersisterStatusReader> {
+        self.ensure_writable()?;
         let (status_write, status_read) = self.get_status_channels();
         self.tx.send(PersistMessage::Write((event, status_write)))?;
         Ok(status_read)
     }
 }
 
+/// The values the persister has started writing and not yet finished writing to every layer, by
+/// database name and key. Until it has finished, a value can be on disk without being in the
+/// durable layer.
+#[derive(Clone, Debug, Default)]
+pub struct InFlightWrites {
+    counts: Arc<Mutex<HashMap<(String, String), usize>>>,
+}
+
+impl InFlightWrites {
+    pub async fn contains(&self, db_name: &str, key: &str) -> bool {
+        self.counts
+            .lock()
+            .await
+            .contains_key(&(db_name.to_string(), key.to_string()))
+    }
+
+    async fn start(&self, db_name: &str, key: &str) {
+        *self
+            .counts
+            .lock()
+            .await
+            .entry((db_name.to_string(), key.to_string()))
+            .or_default() += 1;
+    }
+
+    async fn finish(&self, db_name: &str, key: &str) {
+        let mut counts = self.counts.lock().await;
+        let entry = (db_name.to_string(), key.to_string());
+        if let Some(count) = counts.get_mut(&entry) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&entry);
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PersisterTask {
     messages: mpsc::UnboundedReceiver<PersistMessage>,
     sled: sled::Db,
-    pg_pool: PgPool,
+    durable_backends: Arc<DurableBackends>,
     nats: ChunkingNats,
     instance_id: Ulid,
+    in_flight: InFlightWrites,
     tracker: TaskTracker,
     shutdown_token: CancellationToken,
 }
@@ -105,9 +164,10 @@ impl PersisterTask {
     pub async fn create(
         messages: mpsc::UnboundedReceiver<PersistMessage>,
         sled: sled::Db,
-        pg_pool: PgPool,
+        durable_backends: Arc<DurableBackends>,
         nats_client: &NatsClient,
         instance_id: Ulid,
+        in_flight: InFlightWrites,
         shutdown_token: CancellationToken,
     ) -> LayerDbResult<Self> {
         let tracker = TaskTracker::new();
@@ -127,9 +187,10 @@ impl PersisterTask {
         Ok(Self {
             messages,
             sled,
-            pg_pool,
+            durable_backends,
             nats,
             instance_id,
+            in_flight,
             tracker,
             shutdown_token,
         })
@@ -167,9 +228,10 @@ impl PersisterTask {
                 PersistMessage::Write((event, status_tx)) => {
                     let task = PersistEventTask::new(
                         self.sled.clone(),
-                        self.pg_pool.clone(),
+                        self.durable_backends.clone(),
                         self.nats.clone(),
                         self.instance_id,
+                        self.in_flight.clone(),
                     );
                     self.tracker.spawn(task.write_layers(event, status_tx));
                 }
@@ -181,25 +243,33 @@ impl PersisterTask {
 #[derive(Debug, Clone)]
 pub struct PersisterTaskError {
     pub disk_error: Option<String>,
-    pub pg_error: Option<String>,
+    pub durable_error: Option<String>,
     pub nats_error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct PersistEventTask {
     sled: sled::Db,
-    pg_pool: PgPool,
+    durable_backends: Arc<DurableBackends>,
     nats: ChunkingNats,
     instance_id: Ulid,
+    in_flight: InFlightWrites,
 }
 
 impl PersistEventTask {
-    pub fn new(sled: sled::Db, pg_pool: PgPool, nats: ChunkingNats, instance_id: Ulid) -> Self {
+    pub fn new(
+        sled: sled::Db,
+        durable_backends: Arc<DurableBackends>,
+        nats: ChunkingNats,
+        instance_id: Ulid,
+        in_flight: InFlightWrites,
+    ) -> Self {
         PersistEventTask {
             sled,
-            pg_pool,
+            durable_backends,
             nats,
             instance_id,
+            in_flight,
         }
     }
 
@@ -211,6 +281,17 @@ impl PersistEventTask {
     }
 
     pub async fn try_write_layers(&self, event: LayeredEvent) -> LayerDbResult<()> {
+        let db_name = event.payload.db_name.clone();
+        let key = event.payload.key.clone();
+
+        self.in_flight.start(&db_name, &key).await;
+        let result = self.try_write_every_layer(event).await;
+        self.in_flight.finish(&db_name, &key).await;
+
+        result
+    }
+
+    async fn try_write_every_layer(&self, event: LayeredEvent) -> LayerDbResult<()> {
         let event = Arc::new(event);
 
         // Write to disk cache
@@ -234,47 +315,48 @@ impl PersistEventTask {
                 .await
         });
 
-        // Write to pg
-        let pg_self = self.clone();
-        let pg_event = event.clone();
-        let pg_join = tokio::task::spawn(async move { pg_self.write_to_pg(pg_event).await });
+        // Write to the durable layer
+        let durable_self = self.clone();
+        let durable_event = event.clone();
+        let durable_join =
+            tokio::task::spawn(async move { durable_self.write_to_durable(durable_event).await });
 
-        match join![disk_join, pg_join, nats_join] {
+        match join![disk_join, durable_join, nats_join] {
             (Ok(_), Ok(_), Ok(_)) => Ok(()),
             (Err(e), Ok(_), Ok(_)) => Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                 disk_error: Some(e.to_string()),
-                pg_error: None,
+                durable_error: None,
                 nats_error: None,
             })),
             (Ok(_), Err(e), Ok(_)) => Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                 disk_error: None,
-                pg_error: Some(e.to_string()),
+                durable_error: Some(e.to_string()),
                 nats_error: None,
             })),
             (Ok(_), Ok(_), Err(e)) => Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                 disk_error: None,
-                pg_error: None,
+                durable_error: None,
                 nats_error: Some(e.to_string()),
             })),
             (Err(d), Err(p), Ok(_)) => Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                 disk_error: Some(d.to_string()),
-                pg_error: Some(p.to_string()),
+                durable_error: Some(p.to_string()),
                 nats_error: None,
             })),
             (Ok(_), Err(p), Err(n)) => Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                 disk_error: None,
-                pg_error: Some(p.to_string()),
+                durable_error: Some(p.to_string()),
                 nats_error: Some(n.to_string()),
             })),
             (Err(d), Ok(_), Err(n)) => Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                 disk_error: Some(d.to_string()),
-                pg_error: None,
+                durable_error: None,
                 nats_error: Some(n.to_string()),
             })),
             (Err(d), Err(p), Err(n)) => {
                 Err(LayerDbError::PersisterTaskFailed(PersisterTaskError {
                     disk_error: Some(d.to_string()),
-                    pg_error: Some(p.to_string()),
+                    durable_error: Some(p.to_string()),
                     nats_error: Some(n.to_string()),
                 }))
             }
@@ -287,13 +369,16 @@ impl PersistEventTask {
         Ok(())
     }
 
-    // Write an event to the pg layer
-    pub async fn write_to_pg(&self, event: Arc<LayeredEvent>) -> LayerDbResult<()> {
-        let pg_layer = PgLayer::new(self.pg_pool.clone(), event.payload.db_name.as_ref());
-        pg_layer
+    // Write an event to the durable layer of its database
+    pub async fn write_to_durable(&self, event: Arc<LayeredEvent>) -> LayerDbResult<()> {
+        let durable = self
+            .durable_backends
+            .get(event.payload.db_name.as_str())
+            .ok_or_else(|| LayerDbError::InvalidCacheName(event.payload.db_name.to_string()))?;
+        durable
             .insert(
                 &event.payload.key,
-                event.payload.sort_key.as_ref(),
+                event.payload.sort_key.as_str(),
                 &event.payload.value[..],
             )
             .await?;
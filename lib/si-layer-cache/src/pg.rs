@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use si_data_pg::{PgPool, PgPoolConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use si_data_pg::{PgPool, PgPoolConfig, PgRow};
 
+use crate::codec;
+use crate::durable::DurableBackend;
 use crate::error::LayerDbResult;
 
 mod embedded {
@@ -31,6 +35,9 @@ pub struct PgLayer {
     insert_value_query: String,
     contains_key_query: String,
     search_query: String,
+    keys_created_before_query: String,
+    keys_created_since_query: String,
+    delete_many_query: String,
 }
 
 impl PgLayer {
@@ -38,11 +45,26 @@ impl PgLayer {
         let table_name = table_name.into();
         Self {
             pool: Arc::new(pg_pool),
-            get_value_query: format!("SELECT value FROM {table_name} WHERE key = $1 LIMIT 1"),
-            get_value_many_query: format!("SELECT key, value FROM {table_name} WHERE key = any($1)"),
-            insert_value_query: format!("INSERT INTO {table_name} (key, sort_key, value) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"),
+            get_value_query: format!(
+                "SELECT value, serialization_lib FROM {table_name} WHERE key = $1 LIMIT 1"
+            ),
+            get_value_many_query: format!(
+                "SELECT key, value, serialization_lib FROM {table_name} WHERE key = any($1)"
+            ),
+            // Writing a value that is already stored counts as writing it again for garbage
+            // collection, which only removes values written long enough ago.
+            insert_value_query: format!("INSERT INTO {table_name} (key, sort_key, value, serialization_lib) VALUES ($1, $2, $3, $4) ON CONFLICT (key) DO UPDATE SET created_at = CLOCK_TIMESTAMP()"),
             contains_key_query: format!("SELECT key FROM {table_name} WHERE key = $1 LIMIT 1"),
-            search_query: format!("SELECT value FROM {table_name} WHERE sort_key LIKE $1"),
+            search_query: format!(
+                "SELECT value, serialization_lib FROM {table_name} WHERE sort_key LIKE $1"
+            ),
+            keys_created_before_query: format!("SELECT key FROM {table_name} WHERE created_at < $1"),
+            keys_created_since_query: format!(
+                "SELECT key FROM {table_name} WHERE created_at >= $1"
+            ),
+            delete_many_query: format!(
+                "DELETE FROM {table_name} WHERE key = any($1) AND created_at < $2"
+            ),
             table_name,
         }
     }
@@ -58,7 +80,7 @@ impl PgLayer {
         let maybe_row = client.query_opt(&self.get_value_query, &[&key]).await?;
 
         match maybe_row {
-            Some(row) => Ok(Some(row.get("value"))),
+            Some(row) => Ok(Some(Self::stored_value(&row))),
             None => Ok(None),
         }
     }
@@ -78,7 +100,7 @@ impl PgLayer {
         {
             result.insert(
                 row.get::<&str, String>("key").to_owned(),
-                row.get::<&str, Vec<u8>>("value"),
+                Self::stored_value(&row),
             );
         }
 
@@ -94,7 +116,13 @@ impl PgLayer {
         let client = self.pool.get().await?;
         let rows = client.query(&self.search_query, &[&sort_key_like]).await?;
 
-        Ok(rows.into_iter().map(|r| r.get("value")).collect())
+        Ok(rows.iter().map(Self::stored_value).collect())
+    }
+
+    /// Reads a value along with its `serialization_lib`, giving it the header the column says it
+    /// was written with.
+    fn stored_value(row: &PgRow) -> Vec<u8> {
+        codec::from_stored(row.get("value"), row.get("serialization_lib"))
     }
 
     pub async fn insert(
@@ -105,8 +133,13 @@ impl PgLayer {
     ) -> LayerDbResult<()> {
         let client = self.pool.get().await?;
         let sort_key = sort_key.as_ref();
+        // Recorded so that how the stored values were encoded can be seen from the table alone.
+        let serialization_lib = codec::serialization_lib(value);
         client
-            .query(&self.insert_value_query, &[&key, &sort_key, &value])
+            .query(
+                &self.insert_value_query,
+                &[&key, &sort_key, &value, &serialization_lib],
+            )
             .await?;
         Ok(())
     }
@@ -117,4 +150,74 @@ impl PgLayer {
 
         Ok(maybe_row.is_some())
     }
+
+    /// Lists the keys of every value written before the given time.
+    pub async fn keys_created_before(&self, before: DateTime<Utc>) -> LayerDbResult<Vec<String>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(&self.keys_created_before_query, &[&before])
+            .await?;
+
+        Ok(rows.into_iter().map(|r| r.get("key")).collect())
+    }
+
+    /// Lists the keys of every value written at or after the given time.
+    pub async fn keys_created_since(&self, since: DateTime<Utc>) -> LayerDbResult<Vec<String>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(&self.keys_created_since_query, &[&since])
+            .await?;
+
+        Ok(rows.into_iter().map(|r| r.get("key")).collect())
+    }
+
+    /// Deletes the values for the given keys, unless they have been written again since the
+    /// given time. Returns how many were deleted.
+    pub async fn delete_many_created_before(
+        &self,
+        keys: &[Arc<str>],
+        before: DateTime<Utc>,
+    ) -> LayerDbResult<u64> {
+        let client = self.pool.get().await?;
+        let key_refs: Vec<&str> = keys.iter().map(|key_arc| key_arc.as_ref()).collect();
+
+        Ok(client
+            .execute(&self.delete_many_query, &[&key_refs, &before])
+            .await?)
+    }
+}
+
+#[async_trait]
+impl DurableBackend for PgLayer {
+    async fn get(&self, key: &str) -> LayerDbResult<Option<Vec<u8>>> {
+        PgLayer::get(self, key).await
+    }
+
+    async fn get_many(&self, keys: &[Arc<str>]) -> LayerDbResult<Option<HashMap<String, Vec<u8>>>> {
+        PgLayer::get_many(self, keys).await
+    }
+
+    async fn insert(&self, key: &str, sort_key: &str, value: &[u8]) -> LayerDbResult<()> {
+        PgLayer::insert(self, key, sort_key, value).await
+    }
+
+    async fn contains_key(&self, key: &str) -> LayerDbResult<bool> {
+        PgLayer::contains_key(self, key).await
+    }
+
+    async fn keys_created_before(&self, before: DateTime<Utc>) -> LayerDbResult<Vec<String>> {
+        PgLayer::keys_created_before(self, before).await
+    }
+
+    async fn keys_created_since(&self, since: DateTime<Utc>) -> LayerDbResult<Vec<String>> {
+        PgLayer::keys_created_since(self, since).await
+    }
+
+    async fn delete_many_created_before(
+        &self,
+        keys: &[Arc<str>],
+        before: DateTime<Utc>,
+    ) -> LayerDbResult<u64> {
+        PgLayer::delete_many_created_before(self, keys, before).await
+    }
 }
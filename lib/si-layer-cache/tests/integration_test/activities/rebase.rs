@@ -1,8 +1,16 @@
 use std::sync::Arc;
 
 use futures::StreamExt;
+use serde::Serialize;
 use si_events::{Actor, ChangeSetId, Tenancy, WorkspacePk, WorkspaceSnapshotAddress};
-use si_layer_cache::{activities::ActivityId, event::LayeredEventMetadata, LayerDb};
+use si_layer_cache::{
+    activities::{
+        rebase::{RebaseFinished, RebaseRequest, RebaseStatus},
+        Activity, ActivityId, ActivityPayload,
+    },
+    event::LayeredEventMetadata,
+    LayerDb,
+};
 use tokio_util::sync::CancellationToken;
 use ulid::Ulid;
 
@@ -77,6 +85,8 @@ async fn subscribe_rebaser_requests_work_queue() {
             Ulid::new(),
             WorkspaceSnapshotAddress::new(b"poop"),
             Ulid::new(),
+            vec![],
+            None,
             metadata.clone(),
         )
         .await
@@ -189,6 +199,8 @@ async fn rebase_and_wait() {
                 Ulid::new(),
                 WorkspaceSnapshotAddress::new(b"poop"),
                 Ulid::new(),
+                vec![],
+                None,
                 metadata_for_task,
             )
             .await
@@ -224,3 +236,116 @@ async fn rebase_and_wait() {
 
     assert_eq!(received_finish_activity, rebase_finished_activity);
 }
+
+// The shape of rebase activities before requests carried conflict choices or a pointer operation
+// and successful rebases reported the conflicts they resolved.
+#[derive(Serialize)]
+struct OldActivity {
+    id: ActivityId,
+    payload: OldActivityPayload,
+    metadata: LayeredEventMetadata,
+    parent_activity_id: Option<ActivityId>,
+}
+
+#[derive(Serialize)]
+enum OldActivityPayload {
+    RebaseRequest(OldRebaseRequest),
+    RebaseFinished(OldRebaseFinished),
+}
+
+#[derive(Serialize)]
+struct OldRebaseRequest {
+    to_rebase_change_set_id: Ulid,
+    onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
+    onto_vector_clock_id: Ulid,
+}
+
+#[derive(Serialize)]
+struct OldRebaseFinished {
+    status: OldRebaseStatus,
+    to_rebase_change_set_id: Ulid,
+    onto_workspace_snapshot_address: WorkspaceSnapshotAddress,
+}
+
+#[derive(Serialize)]
+enum OldRebaseStatus {
+    Success { updates_performed: String },
+}
+
+fn metadata() -> LayeredEventMetadata {
+    LayeredEventMetadata::new(
+        Tenancy::new(WorkspacePk::new(), ChangeSetId::new()),
+        Actor::System,
+    )
+}
+
+#[test]
+fn deserializes_rebase_request_in_old_format() {
+    let id = ActivityId::new();
+    let to_rebase_change_set_id = Ulid::new();
+    let onto_workspace_snapshot_address = WorkspaceSnapshotAddress::new(b"poop");
+    let onto_vector_clock_id = Ulid::new();
+    let metadata = metadata();
+    let bytes = postcard::to_stdvec(&OldActivity {
+        id,
+        payload: OldActivityPayload::RebaseRequest(OldRebaseRequest {
+            to_rebase_change_set_id,
+            onto_workspace_snapshot_address,
+            onto_vector_clock_id,
+        }),
+        metadata: metadata.clone(),
+        parent_activity_id: None,
+    })
+    .expect("cannot serialize old activity");
+
+    let activity = Activity::from_bytes(&bytes).expect("cannot deserialize old activity");
+
+    assert_eq!(id, activity.id);
+    assert_eq!(metadata, activity.metadata);
+    assert_eq!(
+        ActivityPayload::RebaseRequest(RebaseRequest::new(
+            to_rebase_change_set_id,
+            onto_workspace_snapshot_address,
+            onto_vector_clock_id,
+            vec![],
+            None,
+        )),
+        activity.payload
+    );
+}
+
+#[test]
+fn deserializes_rebase_finished_in_old_format() {
+    let id = ActivityId::new();
+    let parent_activity_id = ActivityId::new();
+    let to_rebase_change_set_id = Ulid::new();
+    let onto_workspace_snapshot_address = WorkspaceSnapshotAddress::new(b"skid row");
+    let bytes = postcard::to_stdvec(&OldActivity {
+        id,
+        payload: OldActivityPayload::RebaseFinished(OldRebaseFinished {
+            status: OldRebaseStatus::Success {
+                updates_performed: "[]".to_string(),
+            },
+            to_rebase_change_set_id,
+            onto_workspace_snapshot_address,
+        }),
+        metadata: metadata(),
+        parent_activity_id: Some(parent_activity_id),
+    })
+    .expect("cannot serialize old activity");
+
+    let activity = Activity::from_bytes(&bytes).expect("cannot deserialize old activity");
+
+    assert_eq!(Some(parent_activity_id), activity.parent_activity_id);
+    assert_eq!(
+        ActivityPayload::RebaseFinished(RebaseFinished::new(
+            RebaseStatus::Success {
+                updates_performed: "[]".to_string(),
+                conflicts_resolved: String::new(),
+            },
+            to_rebase_change_set_id,
+            onto_workspace_snapshot_address,
+        )),
+        activity.payload
+    );
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use si_layer_cache::codec::{self, ValueCodec, ValueHeader, LEGACY_SERIALIZATION_LIB};
+use si_layer_cache::error::LayerDbResult;
+use si_layer_cache::{Compression, LayerDbError, SchemaVersioned};
+
+/// A value that was a number of fans at schema version 0, and became a band name at version 1.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Band(String);
+
+impl SchemaVersioned for Band {
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn deserialize_schema_version(schema_version: u32, bytes: &[u8]) -> LayerDbResult<Self> {
+        match schema_version {
+            0 => Ok(Self(format!(
+                "{} fans",
+                postcard::from_bytes::<u32>(bytes)?
+            ))),
+            _ => Ok(postcard::from_bytes(bytes)?),
+        }
+    }
+}
+
+/// Raw bytes, whose serialized form can be made to start like a header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Blob(Vec<u8>);
+
+impl SchemaVersioned for Blob {}
+
+/// A blob whose serialized length prefix is `[0xff, b's']`, followed by `b'i'`, so that it starts
+/// like a header.
+fn blob_that_starts_like_a_header() -> Blob {
+    let mut bytes = vec![0; 14847];
+    bytes[0] = b'i';
+    Blob(bytes)
+}
+
+#[test]
+fn compresses_large_values() {
+    let codec: ValueCodec<String> = ValueCodec::new(Compression::Deflate).with_headers(true);
+    let value = "all hail the mighty deftones ".repeat(100);
+    let serialized = postcard::to_stdvec(&value).expect("cannot serialize");
+
+    let encoded = codec.encode(&serialized).expect("cannot encode");
+    assert!(encoded.len() < serialized.len());
+    let header = ValueHeader::read(&encoded)
+        .expect("no header")
+        .expect("invalid header");
+    assert_eq!(Compression::Deflate, header.compression);
+
+    let (decoded, serialized_len) = codec.decode(&encoded).expect("cannot decode");
+    assert_eq!(value, decoded);
+    assert_eq!(serialized.len(), serialized_len);
+}
+
+#[test]
+fn leaves_small_values_uncompressed() {
+    let codec: ValueCodec<String> = ValueCodec::new(Compression::Deflate).with_headers(true);
+    let serialized = postcard::to_stdvec("korn").expect("cannot serialize");
+
+    let encoded = codec.encode(&serialized).expect("cannot encode");
+    let header = ValueHeader::read(&encoded)
+        .expect("no header")
+        .expect("invalid header");
+    assert_eq!(Compression::None, header.compression);
+
+    let (decoded, _) = codec.decode(&encoded).expect("cannot decode");
+    assert_eq!("korn", decoded);
+}
+
+#[test]
+fn writes_values_without_a_header_unless_told_to() {
+    let codec: ValueCodec<String> = ValueCodec::new(Compression::Deflate);
+    let value = "all hail the mighty deftones ".repeat(100);
+    let serialized = postcard::to_stdvec(&value).expect("cannot serialize");
+
+    let encoded = codec.encode(&serialized).expect("cannot encode");
+    assert_eq!(serialized, encoded);
+    assert_eq!(LEGACY_SERIALIZATION_LIB, codec::serialization_lib(&encoded));
+
+    let (decoded, _) = codec.decode(&encoded).expect("cannot decode");
+    assert_eq!(value, decoded);
+}
+
+#[test]
+fn decodes_values_without_a_header() {
+    let codec: ValueCodec<String> = ValueCodec::new(Compression::Deflate).with_headers(true);
+    let serialized = postcard::to_stdvec("system of a down").expect("cannot serialize");
+
+    let (decoded, _) = codec.decode(&serialized).expect("cannot decode");
+    assert_eq!("system of a down", decoded);
+}
+
+#[test]
+fn decodes_older_schema_versions() {
+    let codec: ValueCodec<Band> = ValueCodec::new(Compression::Deflate).with_headers(true);
+
+    let legacy = postcard::to_stdvec(&1999u32).expect("cannot serialize");
+    let (decoded, _) = codec.decode(&legacy).expect("cannot decode");
+    assert_eq!(Band("1999 fans".to_string()), decoded);
+
+    let current = codec
+        .encode(&postcard::to_stdvec(&Band("slipknot".to_string())).expect("cannot serialize"))
+        .expect("cannot encode");
+    let header = ValueHeader::read(&current)
+        .expect("no header")
+        .expect("invalid header");
+    assert_eq!(1, header.schema_version);
+    let (decoded, _) = codec.decode(&current).expect("cannot decode");
+    assert_eq!(Band("slipknot".to_string()), decoded);
+}
+
+#[test]
+fn decodes_values_without_a_header_that_start_like_one() {
+    let codec: ValueCodec<Blob> = ValueCodec::new(Compression::Deflate).with_headers(true);
+    let blob = blob_that_starts_like_a_header();
+    let legacy = postcard::to_stdvec(&blob).expect("cannot serialize");
+
+    let (decoded, _) = codec.decode(&legacy).expect("cannot decode");
+    assert_eq!(blob, decoded);
+}
+
+#[test]
+fn rejects_malformed_headers() {
+    let codec: ValueCodec<String> = ValueCodec::new(Compression::Deflate).with_headers(true);
+    let mut encoded = codec
+        .encode(&postcard::to_stdvec("mudvayne").expect("cannot serialize"))
+        .expect("cannot encode");
+    // An unknown compression id, on a payload that is not plain postcard either.
+    encoded[5] = 0xff;
+
+    let result = codec.decode(&encoded);
+    assert!(
+        matches!(result, Err(LayerDbError::UnsupportedValueEncoding(_))),
+        "expected an unsupported encoding, got {result:?}"
+    );
+}
+
+#[test]
+fn reads_stored_values_by_their_serialization_lib() {
+    let codec: ValueCodec<Blob> = ValueCodec::new(Compression::Deflate).with_headers(true);
+    let blob = blob_that_starts_like_a_header();
+    let serialized = postcard::to_stdvec(&blob).expect("cannot serialize");
+
+    // Stored before headers existed, and only readable because the table says so.
+    let stored = codec::from_stored(serialized.clone(), LEGACY_SERIALIZATION_LIB);
+    let (decoded, _) = codec.decode(&stored).expect("cannot decode");
+    assert_eq!(blob, decoded);
+
+    let encoded = codec.encode(&serialized).expect("cannot encode");
+    let stored = codec::from_stored(encoded.clone(), &codec::serialization_lib(&encoded));
+    assert_eq!(encoded, stored);
+    let (decoded, _) = codec.decode(&stored).expect("cannot decode");
+    assert_eq!(blob, decoded);
+
+    // A header that disagrees with the table is read by the header.
+    let stored = codec::from_stored(encoded.clone(), "postcard+none");
+    assert_eq!(encoded, stored);
+    let (decoded, _) = codec.decode(&stored).expect("cannot decode");
+    assert_eq!(blob, decoded);
+}
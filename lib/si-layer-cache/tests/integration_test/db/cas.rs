@@ -1,7 +1,7 @@
 use std::{sync::Arc, time::Duration};
 
 use si_events::{Actor, CasValue, ChangeSetId, ContentHash, Tenancy, UserPk, WorkspacePk};
-use si_layer_cache::{persister::PersistStatus, LayerDb};
+use si_layer_cache::{error::LayerDbError, persister::PersistStatus, LayerDb};
 use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 
@@ -48,29 +48,107 @@ async fn write_to_db() {
     assert_eq!(Some(cas_value.clone()), in_memory);
 
     // Are we on disk?
-    let on_disk_postcard = ldb
+    let on_disk_bytes = ldb
         .cas()
         .cache
         .disk_cache()
         .get(&cas_pk_str)
         .expect("cannot get from disk cache")
         .expect("cas pk not found in disk cache");
-    let on_disk: CasValue =
-        postcard::from_bytes(&on_disk_postcard[..]).expect("cannot deserialize data");
-    assert_eq!(cas_value.as_ref(), &on_disk);
+    let on_disk = ldb
+        .cas()
+        .cache
+        .deserialize_memory_value(&on_disk_bytes)
+        .expect("cannot deserialize data");
+    assert_eq!(cas_value, on_disk);
 
     // Are we in pg?
-    let in_pg_postcard = ldb
+    let in_pg_bytes = ldb
         .cas()
         .cache
-        .pg()
+        .durable()
         .get(&cas_pk_str)
         .await
         .expect("error getting data from pg")
         .expect("no cas object in pg");
-    let in_pg: CasValue =
-        postcard::from_bytes(&in_pg_postcard[..]).expect("cannot deserialize data");
-    assert_eq!(cas_value.as_ref(), &in_pg);
+    let in_pg = ldb
+        .cas()
+        .cache
+        .deserialize_memory_value(&in_pg_bytes)
+        .expect("cannot deserialize data");
+    assert_eq!(cas_value, in_pg);
+}
+
+#[tokio::test]
+async fn read_only_refuses_writes() {
+    let token = CancellationToken::new();
+
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let (ldb, _): (TestLayerDb, _) = LayerDb::initialize(
+        tempdir,
+        setup_pg_db("cas_read_only_refuses_writes").await,
+        setup_nats_client(Some("cas_read_only_refuses_writes".to_string())).await,
+        token,
+    )
+    .await
+    .expect("cannot create layerdb");
+    ldb.pg_migrate().await.expect("migrate layer db");
+
+    let tenancy = Tenancy::new(WorkspacePk::new(), ChangeSetId::new());
+    let actor = Actor::User(UserPk::new());
+    let written: Arc<CasValue> = Arc::new(serde_json::json!("slipknot").into());
+    let (written_pk, status) = ldb
+        .cas()
+        .write(written.clone(), None, tenancy, actor.clone())
+        .await
+        .expect("failed to write to layerdb");
+    match status.get_status().await.expect("failed to get status") {
+        PersistStatus::Finished => {}
+        PersistStatus::Error(e) => panic!("Write failed; {e}"),
+    }
+
+    let read_only = ldb.read_only();
+
+    // Reads still see what was written...
+    let read = read_only
+        .cas()
+        .read(&written_pk)
+        .await
+        .expect("cannot read from layerdb");
+    assert_eq!(
+        Some(written), // expected
+        read,          // actual
+    );
+
+    // ...but writes are refused before anything is cached.
+    let refused: Arc<CasValue> = Arc::new(serde_json::json!("mudvayne").into());
+    let result = read_only
+        .cas()
+        .write(refused, None, tenancy, actor.clone())
+        .await;
+    assert!(matches!(result, Err(LayerDbError::ReadOnly)));
+    let refused_pk = ContentHash::new(
+        &postcard::to_stdvec(&CasValue::from(serde_json::json!("mudvayne")))
+            .expect("cannot serialize value"),
+    );
+    assert!(ldb
+        .cas()
+        .cache
+        .memory_cache()
+        .get(&refused_pk.to_string())
+        .await
+        .is_none());
+
+    // The handle it was made from can still write.
+    ldb.cas()
+        .write(
+            Arc::new(serde_json::json!("mudvayne").into()),
+            None,
+            tenancy,
+            actor,
+        )
+        .await
+        .expect("failed to write to layerdb");
 }
 
 #[tokio::test]
@@ -191,29 +269,35 @@ async fn cold_read_from_db() {
     assert_eq!(Some(cas_value.clone()), in_memory);
 
     // Are we on disk?
-    let on_disk_postcard = ldb
+    let on_disk_bytes = ldb
         .cas()
         .cache
         .disk_cache()
         .get(&cas_pk_str)
         .expect("cannot get from disk cache")
         .expect("cas pk not found in disk cache");
-    let on_disk: CasValue =
-        postcard::from_bytes(&on_disk_postcard[..]).expect("cannot deserialize data");
-    assert_eq!(cas_value.as_ref(), &on_disk);
+    let on_disk = ldb
+        .cas()
+        .cache
+        .deserialize_memory_value(&on_disk_bytes)
+        .expect("cannot deserialize data");
+    assert_eq!(cas_value, on_disk);
 
     // Are we in pg?
-    let in_pg_postcard = ldb
+    let in_pg_bytes = ldb
         .cas()
         .cache
-        .pg()
+        .durable()
         .get(&cas_pk_str)
         .await
         .expect("error getting data from pg")
         .expect("no cas object in pg");
-    let in_pg: CasValue =
-        postcard::from_bytes(&in_pg_postcard[..]).expect("cannot deserialize data");
-    assert_eq!(cas_value.as_ref(), &in_pg);
+    let in_pg = ldb
+        .cas()
+        .cache
+        .deserialize_memory_value(&in_pg_bytes)
+        .expect("cannot deserialize data");
+    assert_eq!(cas_value, in_pg);
 }
 
 #[tokio::test]
@@ -298,10 +382,13 @@ async fn writes_are_gossiped() {
             .get(&cas_pk_str)
             .expect("cannot get from disk cache")
         {
-            Some(on_disk_postcard) => {
-                let on_disk: CasValue =
-                    postcard::from_bytes(&on_disk_postcard[..]).expect("cannot deserialize data");
-                assert_eq!(cas_value.as_ref(), &on_disk);
+            Some(on_disk_bytes) => {
+                let on_disk = ldb_axl
+                    .cas()
+                    .cache
+                    .deserialize_memory_value(&on_disk_bytes)
+                    .expect("cannot deserialize data");
+                assert_eq!(cas_value, on_disk);
                 break;
             }
             None => {
@@ -316,15 +403,18 @@ async fn writes_are_gossiped() {
     );
 
     // Are we in pg?
-    let in_pg_postcard = ldb_axl
+    let in_pg_bytes = ldb_axl
         .cas()
         .cache
-        .pg()
+        .durable()
         .get(&cas_pk_str)
         .await
         .expect("error getting data from pg")
         .expect("no cas object in pg");
-    let in_pg: CasValue =
-        postcard::from_bytes(&in_pg_postcard[..]).expect("cannot deserialize data");
-    assert_eq!(cas_value.as_ref(), &in_pg);
+    let in_pg = ldb_axl
+        .cas()
+        .cache
+        .deserialize_memory_value(&in_pg_bytes)
+        .expect("cannot deserialize data");
+    assert_eq!(cas_value, in_pg);
 }
@@ -1 +1,2 @@
 mod cas;
+mod workspace_snapshot;
@@ -0,0 +1,275 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use si_events::{Actor, ChangeSetId, ContentHash, Tenancy, UserPk, WorkspacePk};
+use si_layer_cache::{
+    db::workspace_snapshot::{SnapshotContent, SnapshotDelta},
+    durable::DurableBackendConfig,
+    persister::PersistStatus,
+    persister::PersisterStatusReader,
+    pg::PgLayer,
+    LayerDb, LayerDbConfig, SchemaVersioned,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::integration_test::{setup_nats_client, setup_pg_db};
+
+/// A snapshot that only ever grows, whose deltas are the entries added since its base.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct TestSnapshot(Vec<String>);
+
+impl SchemaVersioned for TestSnapshot {}
+
+/// Entries that are content hashes refer to content in the cas.
+impl SnapshotContent for TestSnapshot {
+    fn content_hashes(&self) -> Vec<ContentHash> {
+        self.0
+            .iter()
+            .filter_map(|entry| ContentHash::from_str(entry).ok())
+            .collect()
+    }
+}
+
+impl SnapshotDelta for TestSnapshot {
+    type Delta = Vec<String>;
+    type Error = String;
+
+    fn delta_from(&self, base: &Self) -> Result<Self::Delta, Self::Error> {
+        match self.0.strip_prefix(base.0.as_slice()) {
+            Some(added) => Ok(added.to_vec()),
+            None => Err("snapshot is not derived from its base".to_string()),
+        }
+    }
+
+    fn apply_delta(base: &Self, delta: Self::Delta) -> Result<Self, Self::Error> {
+        let mut entries = base.0.clone();
+        entries.extend(delta);
+        Ok(Self(entries))
+    }
+}
+
+type TestLayerDb = LayerDb<String, String, TestSnapshot>;
+
+async fn wait_for(status: PersisterStatusReader) {
+    match status.get_status().await.expect("failed to get status") {
+        PersistStatus::Finished => {}
+        PersistStatus::Error(e) => panic!("Write failed; {e}"),
+    }
+}
+
+#[tokio::test]
+async fn write_delta_and_reconstruct() {
+    let token = CancellationToken::new();
+
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let (ldb, _): (TestLayerDb, _) = LayerDb::initialize(
+        tempdir,
+        setup_pg_db("workspace_snapshot_write_delta_and_reconstruct").await,
+        setup_nats_client(Some(
+            "workspace_snapshot_write_delta_and_reconstruct".to_string(),
+        ))
+        .await,
+        token,
+    )
+    .await
+    .expect("cannot create layerdb");
+    ldb.pg_migrate().await.expect("migrate layerdb");
+
+    let tenancy = Tenancy::new(WorkspacePk::new(), ChangeSetId::new());
+    let actor = Actor::User(UserPk::new());
+
+    let base = Arc::new(TestSnapshot(
+        (0..100).map(|i| format!("entry {i}")).collect(),
+    ));
+    let (base_address, status) = ldb
+        .workspace_snapshot()
+        .write(base.clone(), None, tenancy, actor.clone())
+        .await
+        .expect("failed to write base snapshot");
+    wait_for(status).await;
+
+    let mut entries = base.0.clone();
+    entries.push("fresh entry".to_string());
+    let snapshot = Arc::new(TestSnapshot(entries));
+    let (address, status) = ldb
+        .workspace_snapshot()
+        .write_delta(snapshot.clone(), base_address, None, tenancy, actor.clone())
+        .await
+        .expect("failed to write snapshot delta");
+    wait_for(status).await;
+
+    // Only the delta was stored.
+    let address_str = address.to_string();
+    assert!(ldb
+        .workspace_snapshot()
+        .cache
+        .durable()
+        .get(&address_str)
+        .await
+        .expect("error getting data from pg")
+        .is_none());
+    assert!(ldb
+        .workspace_snapshot()
+        .delta_cache
+        .durable()
+        .get(&address_str)
+        .await
+        .expect("error getting data from pg")
+        .is_some());
+
+    // Reading it without it in memory rebuilds it from its base.
+    ldb.workspace_snapshot()
+        .cache
+        .remove_from_memory(&address_str)
+        .await;
+    let read = ldb
+        .workspace_snapshot()
+        .read(&address)
+        .await
+        .expect("cannot read from layerdb")
+        .expect("snapshot not in layerdb");
+    assert_eq!(snapshot, read);
+
+    // Compacting stores it in full.
+    let status = ldb
+        .workspace_snapshot()
+        .compact(&address, tenancy, actor)
+        .await
+        .expect("cannot compact snapshot")
+        .expect("snapshot was not stored as a delta");
+    wait_for(status).await;
+    assert!(ldb
+        .workspace_snapshot()
+        .cache
+        .durable()
+        .get(&address_str)
+        .await
+        .expect("error getting data from pg")
+        .is_some());
+}
+
+#[tokio::test]
+async fn snapshots_in_local_directory() {
+    let token = CancellationToken::new();
+
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let object_dir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let pg_pool = setup_pg_db("workspace_snapshot_snapshots_in_local_directory").await;
+    let (ldb, _): (TestLayerDb, _) = LayerDb::initialize_with_config(
+        tempdir,
+        pg_pool.clone(),
+        setup_nats_client(Some(
+            "workspace_snapshot_snapshots_in_local_directory".to_string(),
+        ))
+        .await,
+        LayerDbConfig {
+            workspace_snapshot_durable_backend: DurableBackendConfig::LocalDirectory {
+                path: object_dir.path().to_path_buf(),
+            },
+            ..Default::default()
+        },
+        token,
+    )
+    .await
+    .expect("cannot create layerdb");
+    ldb.pg_migrate().await.expect("migrate layerdb");
+
+    let tenancy = Tenancy::new(WorkspacePk::new(), ChangeSetId::new());
+    let actor = Actor::User(UserPk::new());
+
+    let snapshot = Arc::new(TestSnapshot(vec!["entry".to_string()]));
+    let (address, status) = ldb
+        .workspace_snapshot()
+        .write(snapshot.clone(), None, tenancy, actor)
+        .await
+        .expect("failed to write snapshot");
+    wait_for(status).await;
+
+    // The snapshot is kept in the directory rather than in postgres.
+    let address_str = address.to_string();
+    assert!(object_dir
+        .path()
+        .join("workspace_snapshots")
+        .join(&address_str)
+        .exists());
+    assert!(PgLayer::new(pg_pool, "workspace_snapshots")
+        .get(&address_str)
+        .await
+        .expect("error getting data from pg")
+        .is_none());
+
+    ldb.workspace_snapshot()
+        .cache
+        .remove_from_memory(&address_str)
+        .await;
+    ldb.workspace_snapshot()
+        .cache
+        .disk_cache()
+        .remove(&address_str)
+        .expect("cannot remove from disk cache");
+    let read = ldb
+        .workspace_snapshot()
+        .read(&address)
+        .await
+        .expect("cannot read from layerdb")
+        .expect("snapshot not in layerdb");
+    assert_eq!(snapshot, read);
+}
+
+#[tokio::test]
+async fn prefetch_loads_content_into_memory() {
+    let token = CancellationToken::new();
+
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let (ldb, _): (TestLayerDb, _) = LayerDb::initialize(
+        tempdir,
+        setup_pg_db("workspace_snapshot_prefetch_loads_content").await,
+        setup_nats_client(Some(
+            "workspace_snapshot_prefetch_loads_content".to_string(),
+        ))
+        .await,
+        token,
+    )
+    .await
+    .expect("cannot create layerdb");
+    ldb.pg_migrate().await.expect("migrate layerdb");
+
+    let tenancy = Tenancy::new(WorkspacePk::new(), ChangeSetId::new());
+    let actor = Actor::User(UserPk::new());
+
+    let mut entries = vec!["not content".to_string()];
+    for value in ["skid row", "kid scrow"] {
+        let (hash, status) = ldb
+            .cas()
+            .write(Arc::new(value.to_string()), None, tenancy, actor.clone())
+            .await
+            .expect("failed to write content");
+        wait_for(status).await;
+        ldb.cas().cache.remove_from_memory(&hash.to_string()).await;
+        entries.push(hash.to_string());
+    }
+    let (address, status) = ldb
+        .workspace_snapshot()
+        .write(
+            Arc::new(TestSnapshot(entries.clone())),
+            None,
+            tenancy,
+            actor,
+        )
+        .await
+        .expect("failed to write snapshot");
+    wait_for(status).await;
+
+    let report = ldb
+        .prefetch(&address)
+        .await
+        .expect("cannot prefetch")
+        .expect("snapshot not found");
+    assert_eq!(2, report.content_hashes);
+    assert_eq!(0, report.already_in_memory);
+    assert_eq!(2, report.loaded);
+    for hash in &entries[1..] {
+        assert!(ldb.cas().cache.memory_cache().contains(hash));
+    }
+}
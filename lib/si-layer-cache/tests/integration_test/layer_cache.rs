@@ -1,17 +1,32 @@
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use std::collections::HashSet;
 use std::sync::Arc;
 
+use si_events::ContentHash;
+use si_layer_cache::db::cas;
+use si_layer_cache::integrity::{ScrubReport, VerifyReadsConfig};
 use si_layer_cache::layer_cache::LayerCache;
+use si_layer_cache::memory_cache::MemoryCacheConfig;
+use si_layer_cache::pg::PgLayer;
+use si_layer_cache::Compression;
 
 async fn make_layer_cache(db_name: &str) -> LayerCache<String> {
     let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
     let db = sled::open(tempdir).expect("unable to open sled database");
 
-    let layer_cache = LayerCache::new("cas", db, super::setup_pg_db(db_name).await)
-        .await
-        .expect("cannot create layer cache");
-    layer_cache.pg().migrate().await.expect("migrate");
+    let pg = PgLayer::new(super::setup_pg_db(db_name).await, "cas");
+    pg.migrate().await.expect("migrate");
+
+    let layer_cache = LayerCache::new(
+        "cas",
+        db,
+        Arc::new(pg),
+        MemoryCacheConfig::default(),
+        Compression::default(),
+    )
+    .await
+    .expect("cannot create layer cache");
 
     layer_cache
 }
@@ -139,13 +154,13 @@ async fn get_bulk_from_db() {
         values.shuffle(&mut rng);
         for value in &values {
             let _ = layer_cache
-                .pg()
+                .durable()
                 .insert(value, "cas", value.as_ref().as_bytes())
                 .await;
         }
 
         let get_values = layer_cache
-            .pg()
+            .durable()
             .get_many(&values)
             .await
             .expect("should get bulk")
@@ -156,3 +171,229 @@ async fn get_bulk_from_db() {
         }
     }
 }
+
+#[tokio::test]
+async fn remove_many_from_every_layer() {
+    let layer_cache = make_layer_cache("remove_many_from_every_layer").await;
+
+    let skid_row: Arc<str> = "skid row".into();
+    let kid_scrow: Arc<str> = "kid scrow".into();
+    for key in [&skid_row, &kid_scrow] {
+        let postcard_serialized = postcard::to_stdvec(&key.to_string()).expect("should serialize");
+        layer_cache
+            .durable()
+            .insert(key, "cas", &postcard_serialized)
+            .await
+            .expect("failed to insert to pg");
+        layer_cache
+            .get(key.clone())
+            .await
+            .expect("error finding object")
+            .expect("cannot find object in cache");
+    }
+
+    let before = chrono::Utc::now();
+    let old_keys = layer_cache
+        .durable()
+        .keys_created_before(before)
+        .await
+        .expect("should list keys");
+    assert_eq!(2, old_keys.len());
+
+    let removed = layer_cache
+        .remove_many_created_before(&[skid_row.clone()], before)
+        .await
+        .expect("should remove");
+    assert_eq!(1, removed);
+
+    assert!(!layer_cache.memory_cache().contains(&skid_row));
+    assert!(layer_cache
+        .disk_cache()
+        .get(&skid_row)
+        .expect("cannot read from disk cache")
+        .is_none());
+    assert!(layer_cache
+        .get(skid_row)
+        .await
+        .expect("error finding object")
+        .is_none());
+    assert!(layer_cache
+        .get(kid_scrow)
+        .await
+        .expect("error finding object")
+        .is_some());
+}
+
+#[tokio::test]
+async fn remove_disk_orphans_keeps_durable_and_kept_keys() {
+    let layer_cache = make_layer_cache("remove_disk_orphans").await;
+
+    let postcard_serialized = postcard::to_stdvec("skid row").expect("should serialize");
+    // In the durable layer, and so not an orphan.
+    layer_cache
+        .durable()
+        .insert("durable", "cas", &postcard_serialized)
+        .await
+        .expect("failed to insert to pg");
+    for key in ["durable", "kept", "orphan"] {
+        layer_cache
+            .disk_cache()
+            .insert(key, &postcard_serialized)
+            .expect("failed to insert to disk cache");
+    }
+    let keep = HashSet::from(["kept".to_string()]);
+
+    // A dry run only counts the orphan...
+    let orphans = layer_cache
+        .remove_disk_orphans(&keep, true)
+        .await
+        .expect("cannot remove disk orphans");
+    assert_eq!(1, orphans);
+    assert!(layer_cache
+        .disk_cache()
+        .contains_key("orphan")
+        .expect("cannot read from disk cache"));
+
+    // ...and otherwise only the orphan is removed.
+    let orphans = layer_cache
+        .remove_disk_orphans(&keep, false)
+        .await
+        .expect("cannot remove disk orphans");
+    assert_eq!(1, orphans);
+    for (key, expected) in [("durable", true), ("kept", true), ("orphan", false)] {
+        assert_eq!(
+            expected,
+            layer_cache
+                .disk_cache()
+                .contains_key(key)
+                .expect("cannot read from disk cache"),
+            "{key}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn corrupt_disk_values_are_quarantined_and_read_from_durable() {
+    let layer_cache = make_layer_cache("corrupt_disk_values_are_quarantined")
+        .await
+        .with_verify_reads(
+            VerifyReadsConfig {
+                disk: true,
+                durable: true,
+            },
+            Some(cas::verify_key),
+        );
+
+    let postcard_serialized = postcard::to_stdvec("slave to the grind").expect("should serialize");
+    let key: Arc<str> = ContentHash::new(&postcard_serialized).to_string().into();
+    layer_cache
+        .durable()
+        .insert(&key, "cas", &postcard_serialized)
+        .await
+        .expect("failed to insert to pg");
+
+    let corrupt = postcard::to_stdvec("slave to the grime").expect("should serialize");
+    layer_cache
+        .disk_cache()
+        .insert(&key, &corrupt)
+        .expect("failed to insert to disk cache");
+
+    let result = layer_cache
+        .get(key.clone())
+        .await
+        .expect("error getting object from cache")
+        .expect("object not in cache");
+    assert_eq!("slave to the grind", result);
+
+    assert_eq!(
+        Some(corrupt),
+        layer_cache
+            .disk_cache()
+            .get_quarantined(&key)
+            .expect("cannot read quarantine")
+    );
+    let repaired = layer_cache
+        .disk_cache()
+        .get(&key)
+        .expect("cannot read from disk cache")
+        .expect("object not repaired on disk");
+    assert_eq!(postcard_serialized, repaired);
+}
+
+#[tokio::test]
+async fn undecodable_disk_values_are_read_from_durable_without_verification() {
+    let layer_cache = make_layer_cache("undecodable_disk_values").await;
+
+    let postcard_serialized = postcard::to_stdvec("slave to the grind").expect("should serialize");
+    let key: Arc<str> = ContentHash::new(&postcard_serialized).to_string().into();
+    layer_cache
+        .durable()
+        .insert(&key, "cas", &postcard_serialized)
+        .await
+        .expect("failed to insert to pg");
+
+    let undecodable = vec![0xff, 0xff, 0xff];
+    layer_cache
+        .disk_cache()
+        .insert(&key, &undecodable)
+        .expect("failed to insert to disk cache");
+
+    let result = layer_cache
+        .get(key.clone())
+        .await
+        .expect("error getting object from cache")
+        .expect("object not in cache");
+    assert_eq!("slave to the grind", result);
+    assert_eq!(
+        Some(undecodable),
+        layer_cache
+            .disk_cache()
+            .get_quarantined(&key)
+            .expect("cannot read quarantine")
+    );
+}
+
+#[tokio::test]
+async fn scrub_repairs_corrupt_disk_values() {
+    let layer_cache = make_layer_cache("scrub_repairs_corrupt_disk_values")
+        .await
+        .with_verify_reads(VerifyReadsConfig::default(), Some(cas::verify_key));
+
+    let mut keys = Vec::new();
+    for value in ["skid row", "kid scrow"] {
+        let postcard_serialized = postcard::to_stdvec(value).expect("should serialize");
+        let key = ContentHash::new(&postcard_serialized).to_string();
+        layer_cache
+            .durable()
+            .insert(&key, "cas", &postcard_serialized)
+            .await
+            .expect("failed to insert to pg");
+        layer_cache
+            .disk_cache()
+            .insert(&key, &postcard_serialized)
+            .expect("failed to insert to disk cache");
+        keys.push(key);
+    }
+    layer_cache
+        .disk_cache()
+        .insert(&keys[0], b"corrupt")
+        .expect("failed to insert to disk cache");
+
+    let report = layer_cache.scrub().await.expect("cannot scrub");
+    assert_eq!(
+        ScrubReport {
+            examined: 2,
+            corrupt: 1,
+            repaired: 1,
+        },
+        report
+    );
+    assert_eq!(
+        postcard::to_stdvec("skid row").expect("should serialize"),
+        layer_cache
+            .disk_cache()
+            .get(&keys[0])
+            .expect("cannot read from disk cache")
+            .expect("object not repaired on disk")
+    );
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use serde::{ser::Error as _, Deserialize, Serialize, Serializer};
+use si_layer_cache::memory_cache::{MemoryCache, MemoryCacheConfig};
+
+#[tokio::test]
+async fn counts_hits_and_misses() {
+    let memory_cache: MemoryCache<String> = MemoryCache::new(MemoryCacheConfig::default());
+
+    memory_cache
+        .insert("skid row".into(), "slave to the grind".into())
+        .await;
+
+    assert_eq!(
+        Some("slave to the grind".to_string()),
+        memory_cache.get("skid row").await
+    );
+    assert_eq!(None, memory_cache.get("kid scrow").await);
+
+    let stats = memory_cache.stats();
+    assert_eq!(1, stats.hits);
+    assert_eq!(1, stats.misses);
+}
+
+#[tokio::test]
+async fn evicts_to_stay_within_byte_budget() {
+    let memory_cache: MemoryCache<String> =
+        MemoryCache::new(MemoryCacheConfig::with_max_capacity_bytes(100));
+
+    for key in ["axl", "slash", "duff", "izzy", "steven"] {
+        memory_cache
+            .insert_serialized(key.into(), key.to_string(), 40)
+            .await;
+    }
+    memory_cache.run_pending_tasks().await;
+
+    let stats = memory_cache.stats();
+    assert!(stats.weighted_size_bytes <= 100);
+    assert!(stats.entry_count <= 2);
+    assert!(stats.evictions > 0);
+}
+
+#[tokio::test]
+async fn expires_after_time_to_live() {
+    let memory_cache: MemoryCache<String> = MemoryCache::new(MemoryCacheConfig {
+        time_to_live_secs: Some(1),
+        ..Default::default()
+    });
+
+    memory_cache
+        .insert("skid row".into(), "youth gone wild".into())
+        .await;
+    assert!(memory_cache.get("skid row").await.is_some());
+
+    tokio::time::sleep(Duration::from_millis(1_500)).await;
+    assert_eq!(None, memory_cache.get("skid row").await);
+}
+
+#[tokio::test]
+async fn expires_after_time_to_idle() {
+    let memory_cache: MemoryCache<String> = MemoryCache::new(MemoryCacheConfig {
+        time_to_idle_secs: Some(1),
+        ..Default::default()
+    });
+
+    memory_cache
+        .insert("skid row".into(), "18 and life".into())
+        .await;
+
+    // Reading it keeps it alive for longer than it would live without being read...
+    for _ in 0..3 {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(memory_cache.get("skid row").await.is_some());
+    }
+
+    // ...but not once it is left alone.
+    tokio::time::sleep(Duration::from_millis(1_500)).await;
+    assert_eq!(None, memory_cache.get("skid row").await);
+}
+
+/// A value that refuses to be serialized, and so cannot be weighed.
+#[derive(Clone, Debug, Deserialize)]
+struct Unweighable;
+
+impl Serialize for Unweighable {
+    fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(S::Error::custom("cannot be weighed"))
+    }
+}
+
+#[tokio::test]
+async fn charges_unweighable_values_conservatively() {
+    let memory_cache: MemoryCache<Unweighable> = MemoryCache::new(MemoryCacheConfig::default());
+
+    memory_cache.insert("unweighable".into(), Unweighable).await;
+    memory_cache.run_pending_tasks().await;
+
+    assert_eq!(16 * 1024 * 1024, memory_cache.stats().weighted_size_bytes);
+}
@@ -8,9 +8,12 @@ use crate::TEST_PG_DBNAME;
 
 mod activities;
 mod chunking_nats;
+mod codec;
 mod db;
 mod disk_cache;
 mod layer_cache;
+mod memory_cache;
+mod object_store;
 
 const DEFAULT_TEST_PG_USER: &str = "si_test";
 const DEFAULT_TEST_PG_PORT_STR: &str = "6432";
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use si_layer_cache::durable::DurableBackend;
+use si_layer_cache::object_store::{LocalDirectoryObjectStore, ObjectStore, ObjectStoreLayer};
+use si_layer_cache::LayerDbError;
+
+#[tokio::test]
+async fn local_directory_round_trip() {
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let layer = ObjectStoreLayer::new(
+        Arc::new(LocalDirectoryObjectStore::new(tempdir.path())),
+        "workspace_snapshots",
+    );
+
+    layer
+        .insert("skid row", "skid row", b"slave to the grind")
+        .await
+        .expect("failed to insert");
+    layer
+        .insert("kid scrow", "kid scrow", b"youth gone wild")
+        .await
+        .expect("failed to insert");
+
+    assert!(tempdir.path().join("workspace_snapshots/skid row").exists());
+    assert_eq!(
+        Some(b"slave to the grind".to_vec()),
+        layer.get("skid row").await.expect("failed to get")
+    );
+    assert!(layer
+        .contains_key("kid scrow")
+        .await
+        .expect("failed to check"));
+    assert!(!layer
+        .contains_key("march for macragge")
+        .await
+        .expect("failed to check"));
+
+    let found = layer
+        .get_many(&["skid row".into(), "march for macragge".into()])
+        .await
+        .expect("failed to get many")
+        .expect("should have results");
+    assert_eq!(1, found.len());
+    assert_eq!(b"slave to the grind".to_vec(), found["skid row"]);
+
+    let before = chrono::Utc::now() + chrono::Duration::seconds(1);
+    let mut keys = layer
+        .keys_created_before(before)
+        .await
+        .expect("failed to list keys");
+    keys.sort();
+    assert_eq!(vec!["kid scrow".to_string(), "skid row".to_string()], keys);
+
+    assert_eq!(
+        1,
+        layer
+            .delete_many_created_before(&["skid row".into()], before)
+            .await
+            .expect("failed to delete")
+    );
+    assert!(layer
+        .get("skid row")
+        .await
+        .expect("failed to get")
+        .is_none());
+    assert!(layer
+        .keys_created_before(chrono::Utc::now() - chrono::Duration::hours(1))
+        .await
+        .expect("failed to list keys")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn local_directory_rejects_keys_outside_its_root() {
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let store = LocalDirectoryObjectStore::new(tempdir.path());
+
+    for key in ["../escape", "a//b", "", ".staging/object"] {
+        assert!(matches!(
+            store.put_object(key, b"nope").await,
+            Err(LayerDbError::InvalidObjectKey(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn local_directory_keeps_objects_written_again_before_a_conditional_delete() {
+    let tempdir = tempfile::TempDir::new_in("/tmp").expect("cannot create tempdir");
+    let store = LocalDirectoryObjectStore::new(tempdir.path());
+
+    store
+        .put_object("cas/poison", b"nothin' but a good time")
+        .await
+        .expect("failed to put");
+    let stale = store
+        .head_object("cas/poison")
+        .await
+        .expect("failed to head")
+        .expect("object should exist");
+
+    store
+        .put_object("cas/poison", b"talk dirty to me")
+        .await
+        .expect("failed to put");
+    let current = store
+        .head_object("cas/poison")
+        .await
+        .expect("failed to head")
+        .expect("object should exist");
+    assert_ne!(stale.etag, current.etag);
+
+    assert!(!store
+        .delete_object_if_match("cas/poison", &stale.etag)
+        .await
+        .expect("failed to delete"));
+    assert_eq!(
+        Some(b"talk dirty to me".to_vec()),
+        store.get_object("cas/poison").await.expect("failed to get")
+    );
+
+    assert!(store
+        .delete_object_if_match("cas/poison", &current.etag)
+        .await
+        .expect("failed to delete"));
+    assert!(store
+        .get_object("cas/poison")
+        .await
+        .expect("failed to get")
+        .is_none());
+}
@@ -7,19 +7,20 @@ use telemetry_nats::propagation;
 use thiserror::Error;
 use tokio::sync::mpsc;
 use veritech_core::{
-    nats_action_run_subject, nats_reconciliation_subject, nats_resolver_function_subject,
+    nats_action_run_subject, nats_cancel_execution_subject, nats_execution_subject,
+    nats_reconciliation_subject, nats_resolver_function_subject,
     nats_schema_variant_definition_subject, nats_subject, nats_validation_subject,
     reply_mailbox_for_output, reply_mailbox_for_result, FINAL_MESSAGE_HEADER_KEY,
 };
 
 pub use cyclone_core::{
     encrypt_value_tree, ActionRunRequest, ActionRunResultSuccess, BeforeFunction, ComponentKind,
-    ComponentView, CycloneValueDecryptError, CycloneValueEncryptError, FunctionResult,
-    FunctionResultFailure, OutputStream, ReconciliationRequest, ReconciliationResultSuccess,
-    ResolverFunctionComponent, ResolverFunctionRequest, ResolverFunctionResponseType,
-    ResolverFunctionResultSuccess, ResourceStatus, SchemaVariantDefinitionRequest,
-    SchemaVariantDefinitionResultSuccess, SensitiveContainer, ValidationRequest,
-    ValidationResultSuccess,
+    ComponentView, CycloneValueDecryptError, CycloneValueEncryptError, ExecutionEnvelope,
+    FunctionRequest, FunctionResult, FunctionResultFailure, FunctionResultFailureError,
+    OutputStream, ReconciliationRequest, ReconciliationResultSuccess, ResolverFunctionComponent,
+    ResolverFunctionRequest, ResolverFunctionResponseType, ResolverFunctionResultSuccess,
+    ResourceStatus, SchemaVariantDefinitionRequest, SchemaVariantDefinitionResultSuccess,
+    SensitiveContainer, ValidationRequest, ValidationResultSuccess, EXECUTION_ENVELOPE_VERSION,
 };
 pub use si_crypto::{CycloneEncryptionKey, CycloneEncryptionKeyError};
 
@@ -221,6 +222,52 @@ impl Client {
         .await
     }
 
+    /// Executes a function of any kind by publishing its request, wrapped in a kind-tagged
+    /// [`ExecutionEnvelope`], on the execution subject.
+    #[instrument(name = "client.execute_function", level = "info", skip_all)]
+    pub async fn execute_function<R>(
+        &self,
+        output_tx: mpsc::Sender<OutputStream>,
+        request: &R,
+    ) -> ClientResult<FunctionResult<R::Success>>
+    where
+        R: FunctionRequest,
+    {
+        let envelope = ExecutionEnvelope {
+            version: EXECUTION_ENVELOPE_VERSION,
+            kind: R::KIND.to_string(),
+            request,
+        };
+        self.execute_request(
+            nats_execution_subject(self.nats_subject_prefix()),
+            output_tx,
+            &envelope,
+        )
+        .await
+    }
+
+    /// Cancels the function execution with the given execution id, if it is still running.
+    ///
+    /// The execution's result is a failure of the
+    /// [`KIND_CANCELLED`](FunctionResultFailureError::KIND_CANCELLED) kind.
+    #[instrument(name = "client.cancel_execution", level = "info", skip_all)]
+    pub async fn cancel_execution(&self, execution_id: &str) -> ClientResult<()> {
+        let subject = nats_cancel_execution_subject(self.nats_subject_prefix(), execution_id);
+        trace!(
+            messaging.destination = &subject.as_str(),
+            "publishing cancel message"
+        );
+        self.nats
+            .publish_with_headers(
+                subject,
+                propagation::empty_injected_headers(),
+                Vec::new().into(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
     async fn execute_request<R, S>(
         &self,
         subject: impl Into<String>,
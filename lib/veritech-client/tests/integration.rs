@@ -1,14 +1,14 @@
-use std::env;
+use std::{env, time::Duration};
 
 use base64::{engine::general_purpose, Engine};
 use cyclone_core::{
-    ComponentKind, ComponentView, FunctionResult, ResolverFunctionComponent,
-    ResolverFunctionRequest, ResolverFunctionResponseType, SchemaVariantDefinitionRequest,
-    ValidationRequest,
+    ComponentKind, ComponentView, FunctionRequest, FunctionResult, FunctionResultFailureError,
+    ResolverFunctionComponent, ResolverFunctionRequest, ResolverFunctionResponseType,
+    SchemaVariantDefinitionRequest, ValidationRequest,
 };
 use si_data_nats::{NatsClient, NatsConfig};
 use test_log::test;
-use tokio::{sync::mpsc, task::JoinHandle};
+use tokio::{sync::mpsc, task::JoinHandle, time};
 use tracing::info;
 use uuid::Uuid;
 use veritech_client::Client;
@@ -36,22 +36,22 @@ fn nats_prefix() -> String {
     Uuid::new_v4().as_simple().to_string()
 }
 
-async fn veritech_server_for_uds_cyclone(subject_prefix: String) -> Server {
+async fn veritech_server_for_uds_cyclone(subject_prefix: String, resolver: bool) -> Server {
     let mut config_file = veritech_server::ConfigFile::default_local_uds();
     veritech_server::detect_and_configure_development(&mut config_file)
         .expect("failed to determine test configuration");
 
-    let cyclone_spec = CycloneSpec::LocalUds(
-        LocalUdsInstance::spec()
-            .try_cyclone_cmd_path(config_file.cyclone.cyclone_cmd_path())
-            .expect("failed to setup cyclone_cmd_path")
-            .cyclone_decryption_key_path(config_file.cyclone.cyclone_decryption_key_path())
-            .try_lang_server_cmd_path(config_file.cyclone.lang_server_cmd_path())
-            .expect("failed to setup lang_js_cmd_path")
-            .all_endpoints()
-            .build()
-            .expect("failed to build cyclone spec"),
-    );
+    let mut spec = LocalUdsInstance::spec();
+    spec.try_cyclone_cmd_path(config_file.cyclone.cyclone_cmd_path())
+        .expect("failed to setup cyclone_cmd_path")
+        .cyclone_decryption_key_path(config_file.cyclone.cyclone_decryption_key_path())
+        .try_lang_server_cmd_path(config_file.cyclone.lang_server_cmd_path())
+        .expect("failed to setup lang_js_cmd_path")
+        .action();
+    if resolver {
+        spec.resolver();
+    }
+    let cyclone_spec = CycloneSpec::LocalUds(spec.build().expect("failed to build cyclone spec"));
     let config = Config::builder()
         .nats(nats_config(subject_prefix.clone()))
         .cyclone_spec(cyclone_spec)
@@ -69,7 +69,21 @@ async fn client(subject_prefix: String) -> Client {
 async fn run_veritech_server_for_uds_cyclone(
     subject_prefix: String,
 ) -> JoinHandle<Result<(), ServerError>> {
-    tokio::spawn(veritech_server_for_uds_cyclone(subject_prefix).await.run())
+    tokio::spawn(
+        veritech_server_for_uds_cyclone(subject_prefix, true)
+            .await
+            .run(),
+    )
+}
+
+async fn run_veritech_server_for_uds_cyclone_without_resolver(
+    subject_prefix: String,
+) -> JoinHandle<Result<(), ServerError>> {
+    tokio::spawn(
+        veritech_server_for_uds_cyclone(subject_prefix, false)
+            .await
+            .run(),
+    )
 }
 
 fn base64_encode(input: impl AsRef<[u8]>) -> String {
@@ -93,6 +107,7 @@ async fn executes_simple_resolver_function() {
 
     let request = ResolverFunctionRequest {
         execution_id: "1234".to_string(),
+        timeout_secs: None,
         handler: "numberOfInputs".to_string(),
         component: ResolverFunctionComponent {
             data: ComponentView {
@@ -162,6 +177,7 @@ async fn type_checks_resolve_function() {
 
         let request = ResolverFunctionRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "returnInputValue".to_string(),
             component: ResolverFunctionComponent {
                 data: ComponentView {
@@ -224,6 +240,7 @@ async fn type_checks_resolve_function() {
 
         let request = ResolverFunctionRequest {
             execution_id: "1234".to_string(),
+            timeout_secs: None,
             handler: "returnInputValue".to_string(),
             component: ResolverFunctionComponent {
                 data: ComponentView {
@@ -272,6 +289,7 @@ async fn executes_simple_validation() {
 
     let request = ValidationRequest {
         execution_id: "31337".to_string(),
+        timeout_secs: None,
         handler: "isThirtyThree".to_string(),
         value: 33.into(),
         code_base64: base64_encode(
@@ -313,6 +331,7 @@ async fn executes_simple_schema_variant_definition() {
 
     let request = SchemaVariantDefinitionRequest {
         execution_id: "8badf00d".to_string(),
+        timeout_secs: None,
         handler: "asset".to_string(),
         code_base64: base64_encode(
             "function asset() {
@@ -349,3 +368,247 @@ async fn executes_simple_schema_variant_definition() {
         }
     }
 }
+
+fn loop_forever_request(execution_id: &str, timeout_secs: Option<u64>) -> ResolverFunctionRequest {
+    ResolverFunctionRequest {
+        execution_id: execution_id.to_string(),
+        timeout_secs,
+        handler: "loopForever".to_string(),
+        component: ResolverFunctionComponent {
+            data: ComponentView {
+                properties: serde_json::json!({}),
+                kind: ComponentKind::Standard,
+            },
+            parents: vec![],
+        },
+        response_type: ResolverFunctionResponseType::Integer,
+        code_base64: base64_encode("function loopForever(input) { while (true) {} }"),
+        before: vec![],
+    }
+}
+
+#[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+#[test(tokio::test)]
+async fn times_out_a_function_that_runs_too_long() {
+    let prefix = nats_prefix();
+    run_veritech_server_for_uds_cyclone(prefix.clone()).await;
+    let client = client(prefix).await;
+
+    // Not going to check output here--we aren't emitting anything
+    let (tx, mut rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(output) = rx.recv().await {
+            info!("output: {:?}", output)
+        }
+    });
+
+    let request = loop_forever_request("1234", Some(1));
+
+    let result = time::timeout(
+        Duration::from_secs(30),
+        client.execute_resolver_function(tx, &request),
+    )
+    .await
+    .expect("function was not timed out")
+    .expect("failed to execute resolver function");
+
+    match result {
+        FunctionResult::Success(success) => {
+            panic!("function succeeded and should have timed out: {success:?}")
+        }
+        FunctionResult::Failure(failure) => {
+            assert_eq!(failure.error.kind, FunctionResultFailureError::KIND_TIMEOUT);
+            assert_eq!(failure.execution_id, "1234");
+        }
+    }
+}
+
+#[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+#[test(tokio::test)]
+async fn cancels_a_running_function() {
+    let prefix = nats_prefix();
+    run_veritech_server_for_uds_cyclone(prefix.clone()).await;
+    let client = client(prefix).await;
+
+    // Not going to check output here--we aren't emitting anything
+    let (tx, mut rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(output) = rx.recv().await {
+            info!("output: {:?}", output)
+        }
+    });
+
+    // An execution id which would not be a single subject token if it weren't encoded
+    let execution_id = "cancel.me *";
+    let request = loop_forever_request(execution_id, None);
+
+    // Cancel once the function has been running for a while
+    let canceller = {
+        let client = client.clone();
+        tokio::spawn(async move {
+            time::sleep(Duration::from_secs(1)).await;
+            client
+                .cancel_execution(execution_id)
+                .await
+                .expect("failed to cancel execution");
+        })
+    };
+
+    let result = time::timeout(
+        Duration::from_secs(30),
+        client.execute_resolver_function(tx, &request),
+    )
+    .await
+    .expect("function was not cancelled")
+    .expect("failed to execute resolver function");
+    canceller.await.expect("canceller task failed");
+
+    match result {
+        FunctionResult::Success(success) => {
+            panic!("function succeeded and should have been cancelled: {success:?}")
+        }
+        FunctionResult::Failure(failure) => {
+            assert_eq!(
+                failure.error.kind,
+                FunctionResultFailureError::KIND_CANCELLED
+            );
+            assert_eq!(failure.execution_id, execution_id);
+        }
+    }
+}
+
+#[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+#[test(tokio::test)]
+async fn cancels_a_function_right_after_it_is_submitted() {
+    let prefix = nats_prefix();
+    run_veritech_server_for_uds_cyclone(prefix.clone()).await;
+    let client = client(prefix).await;
+
+    // Not going to check output here--we aren't emitting anything
+    let (tx, mut rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(output) = rx.recv().await {
+            info!("output: {:?}", output)
+        }
+    });
+
+    let execution_id = "cancel-right-away";
+    let request = loop_forever_request(execution_id, None);
+
+    let execution = {
+        let client = client.clone();
+        tokio::spawn(async move {
+            time::timeout(
+                Duration::from_secs(30),
+                client.execute_resolver_function(tx, &request),
+            )
+            .await
+        })
+    };
+    // Likely to reach the server before the execution starts, if not before the request itself
+    client
+        .cancel_execution(execution_id)
+        .await
+        .expect("failed to cancel execution");
+
+    let result = execution
+        .await
+        .expect("execution task failed")
+        .expect("function was not cancelled")
+        .expect("failed to execute resolver function");
+
+    match result {
+        FunctionResult::Success(success) => {
+            panic!("function succeeded and should have been cancelled: {success:?}")
+        }
+        FunctionResult::Failure(failure) => {
+            assert_eq!(
+                failure.error.kind,
+                FunctionResultFailureError::KIND_CANCELLED
+            );
+            assert_eq!(failure.execution_id, execution_id);
+        }
+    }
+}
+
+#[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+#[test(tokio::test)]
+async fn executes_validation_through_execution_subject() {
+    let prefix = nats_prefix();
+    run_veritech_server_for_uds_cyclone(prefix.clone()).await;
+    let client = client(prefix).await;
+
+    // Not going to check output here--we aren't emitting anything
+    let (tx, mut rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(output) = rx.recv().await {
+            info!("output: {:?}", output)
+        }
+    });
+
+    let request = ValidationRequest {
+        execution_id: "31337".to_string(),
+        timeout_secs: None,
+        handler: "isThirtyThree".to_string(),
+        value: 33.into(),
+        code_base64: base64_encode(
+            "function isThirtyThree(value) { return { valid: value === 33 }; };",
+        ),
+        before: vec![],
+    };
+
+    let result = client
+        .execute_function(tx, &request)
+        .await
+        .expect("failed to execute validation");
+
+    match result {
+        FunctionResult::Success(success) => {
+            assert_eq!(success.execution_id, "31337");
+            assert!(success.valid);
+        }
+        FunctionResult::Failure(failure) => {
+            panic!("function did not succeed and should have: {failure:?}")
+        }
+    }
+}
+
+#[allow(clippy::disallowed_methods)] // `$RUST_LOG` is checked for in macro
+#[test(tokio::test)]
+async fn fails_kind_disabled_in_cyclone_on_execution_subject() {
+    let prefix = nats_prefix();
+    run_veritech_server_for_uds_cyclone_without_resolver(prefix.clone()).await;
+    let client = client(prefix).await;
+
+    // Not going to check output here--we aren't emitting anything
+    let (tx, mut rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(output) = rx.recv().await {
+            info!("output: {:?}", output)
+        }
+    });
+
+    let request = loop_forever_request("1234", None);
+
+    let result = client
+        .execute_function(tx, &request)
+        .await
+        .expect("failed to execute resolver function");
+
+    match result {
+        FunctionResult::Success(success) => {
+            panic!("function succeeded and its kind should be unsupported: {success:?}")
+        }
+        FunctionResult::Failure(failure) => {
+            assert_eq!("1234", failure.execution_id);
+            assert_eq!("veritechServer", failure.error.kind);
+            assert_eq!(
+                failure.error.message,
+                format!(
+                    "unsupported function kind: {}",
+                    ResolverFunctionRequest::KIND
+                )
+            );
+        }
+    }
+}
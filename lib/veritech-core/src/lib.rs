@@ -12,7 +12,9 @@
 )]
 
 const NATS_ACTION_RUN_DEFAULT_SUBJECT: &str = "veritech.fn.actionrun";
+const NATS_CANCEL_EXECUTION_DEFAULT_SUBJECT_PREFIX: &str = "veritech.cancel";
 const NATS_CONCILIATION_DEFAULT_SUBJECT: &str = "veritech.fn.reconciliation";
+const NATS_EXECUTION_DEFAULT_SUBJECT: &str = "veritech.fn.execute";
 const NATS_RESOLVER_FUNCTION_DEFAULT_SUBJECT: &str = "veritech.fn.resolverfunction";
 const NATS_SCHEMA_VARIANT_DEFINITION_DEFAULT_SUBJECT: &str = "veritech.fn.schemavariantdefinition";
 const NATS_VALIDATION_DEFAULT_SUBJECT: &str = "veritech.fn.validation";
@@ -47,6 +49,57 @@ pub fn nats_schema_variant_definition_subject(prefix: Option<&str>) -> String {
     nats_subject(prefix, NATS_SCHEMA_VARIANT_DEFINITION_DEFAULT_SUBJECT)
 }
 
+/// Returns the subject on which function executions of any kind are requested, each wrapped in an
+/// execution envelope.
+pub fn nats_execution_subject(prefix: Option<&str>) -> String {
+    nats_subject(prefix, NATS_EXECUTION_DEFAULT_SUBJECT)
+}
+
+/// Returns the subject to publish to in order to cancel the function execution with the given
+/// execution id.
+///
+/// The execution id is encoded as a single subject token, so an id containing `.`, wildcards or
+/// whitespace neither makes an invalid subject nor one matching the executions of other ids.
+pub fn nats_cancel_execution_subject(prefix: Option<&str>, execution_id: &str) -> String {
+    nats_subject(
+        prefix,
+        format!(
+            "{NATS_CANCEL_EXECUTION_DEFAULT_SUBJECT_PREFIX}.{}",
+            subject_token(execution_id)
+        ),
+    )
+}
+
+/// Returns a subject matching the cancel subject of every function execution, as returned by
+/// [`nats_cancel_execution_subject`].
+pub fn nats_any_cancel_execution_subject(prefix: Option<&str>) -> String {
+    nats_subject(
+        prefix,
+        format!("{NATS_CANCEL_EXECUTION_DEFAULT_SUBJECT_PREFIX}.*"),
+    )
+}
+
+// Percent-encodes the characters with a meaning in NATS subjects, along with `%` so that encoded
+// values can't collide. An empty value, which no other value encodes to, is a lone `%`.
+fn subject_token(value: &str) -> String {
+    if value.is_empty() {
+        return "%".to_owned();
+    }
+
+    let mut token = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '.' | '*' | '>' | '%') || c.is_whitespace() || c.is_control() {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                token.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            token.push(c);
+        }
+    }
+
+    token
+}
+
 pub fn nats_subject(prefix: Option<&str>, suffix: impl AsRef<str>) -> String {
     let suffix = suffix.as_ref();
     match prefix {
@@ -54,3 +107,36 @@ pub fn nats_subject(prefix: Option<&str>, suffix: impl AsRef<str>) -> String {
         None => suffix.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_execution_subject_has_one_token_for_the_execution_id() {
+        for (execution_id, expected) in [
+            ("01HXYZ", "veritech.cancel.01HXYZ"),
+            ("", "veritech.cancel.%"),
+            ("a.b", "veritech.cancel.a%2Eb"),
+            ("*", "veritech.cancel.%2A"),
+            ("a.>", "veritech.cancel.a%2E%3E"),
+            ("with space\ttab", "veritech.cancel.with%20space%09tab"),
+            ("100%", "veritech.cancel.100%25"),
+            ("non\u{a0}breaking", "veritech.cancel.non%C2%A0breaking"),
+        ] {
+            assert_eq!(
+                expected,
+                nats_cancel_execution_subject(None, execution_id),
+                "subject for {execution_id:?}"
+            );
+        }
+        assert_eq!(
+            "prefix.veritech.cancel.a%2Eb",
+            nats_cancel_execution_subject(Some("prefix"), "a.b")
+        );
+        assert_eq!(
+            "prefix.veritech.cancel.*",
+            nats_any_cancel_execution_subject(Some("prefix"))
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use si_data_nats::{NatsClient, Subscriber};
+use telemetry::prelude::*;
+use tokio::sync::{broadcast, oneshot};
+use veritech_core::{nats_any_cancel_execution_subject, nats_cancel_execution_subject};
+
+/// How long a cancel message for an execution which isn't running is remembered, so that the
+/// execution is still cancelled if its request arrives afterwards.
+const EARLY_CANCEL_TTL: Duration = Duration::from_secs(60);
+
+/// Tracks which function executions to cancel, from a single subscription to the cancel subject of
+/// every execution.
+///
+/// The subscription is made before any requests are received, so a cancel message published right
+/// after its request is never missed. A cancel message for an execution which isn't running yet is
+/// remembered for a while, in case its request is still on its way.
+#[derive(Clone, Debug)]
+pub struct Cancellations {
+    subject_prefix: Option<String>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    // Keyed by cancel subject, which encodes the execution id
+    running: HashMap<String, (u64, oneshot::Sender<()>)>,
+    early: HashMap<String, Instant>,
+    next_registration: u64,
+}
+
+impl Cancellations {
+    /// Subscribes to the cancel subject of every execution.
+    pub async fn subscribe(nats: &NatsClient) -> si_data_nats::Result<(Self, Subscriber)> {
+        let subject_prefix = nats.metadata().subject_prefix().map(ToOwned::to_owned);
+        let subject = nats_any_cancel_execution_subject(subject_prefix.as_deref());
+        debug!(
+            messaging.destination = &subject.as_str(),
+            "subscribing for cancel messages"
+        );
+        let subscriber = nats.subscribe(subject).await?;
+
+        Ok((
+            Self {
+                subject_prefix,
+                inner: Default::default(),
+            },
+            subscriber,
+        ))
+    }
+
+    /// Processes the cancel messages of the subscription until shutdown.
+    pub async fn process(
+        &self,
+        mut subscriber: Subscriber,
+        mut shutdown_broadcast_rx: broadcast::Receiver<()>,
+    ) {
+        loop {
+            tokio::select! {
+                _ = shutdown_broadcast_rx.recv() => {
+                    trace!("process cancellations task received shutdown");
+                    break;
+                }
+                msg = subscriber.next() => match msg {
+                    Some(msg) => self.cancel(msg.subject().as_str()),
+                    None => {
+                        trace!("cancel subscriber stream has closed");
+                        break;
+                    }
+                },
+            }
+        }
+
+        if let Err(err) = subscriber.unsubscribe().await {
+            warn!(error = ?err, "failed to unsubscribe from cancel subjects");
+        }
+    }
+
+    /// Registers a running execution, returning a [`Cancellation`] which completes once the
+    /// execution is cancelled, including by a cancel message received before it was registered.
+    pub fn register(&self, execution_id: &str) -> Cancellation {
+        let subject = nats_cancel_execution_subject(self.subject_prefix.as_deref(), execution_id);
+        let (tx, rx) = oneshot::channel();
+
+        let mut inner = self.lock();
+        let registration = inner.next_registration;
+        inner.next_registration += 1;
+        match inner.early.remove(&subject) {
+            Some(received_at) if received_at.elapsed() < EARLY_CANCEL_TTL => {
+                debug!(execution_id, "execution was cancelled before it started");
+                let _ = tx.send(());
+            }
+            _ => {
+                inner.running.insert(subject.clone(), (registration, tx));
+            }
+        }
+
+        Cancellation {
+            subject,
+            registration,
+            inner: self.inner.clone(),
+            rx,
+        }
+    }
+
+    fn cancel(&self, subject: &str) {
+        let mut inner = self.lock();
+        match inner.running.remove(subject) {
+            Some((_, tx)) => {
+                let _ = tx.send(());
+            }
+            None => {
+                trace!(
+                    subject,
+                    "remembering cancel message for execution not running"
+                );
+                inner
+                    .early
+                    .retain(|_, received_at| received_at.elapsed() < EARLY_CANCEL_TTL);
+                inner.early.insert(subject.to_owned(), Instant::now());
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A registered execution, which is unregistered when dropped.
+#[derive(Debug)]
+pub struct Cancellation {
+    subject: String,
+    registration: u64,
+    inner: Arc<Mutex<Inner>>,
+    rx: oneshot::Receiver<()>,
+}
+
+impl Cancellation {
+    /// Completes once the execution is cancelled.
+    pub async fn cancelled(&mut self) {
+        if (&mut self.rx).await.is_err() {
+            // Replaced by a later registration of the same execution id, so it can no longer be
+            // cancelled
+            futures::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for Cancellation {
+    fn drop(&mut self) {
+        let mut inner = self
+            .inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Only remove our own registration, not one made since for the same execution id
+        if matches!(
+            inner.running.get(&self.subject),
+            Some((registration, _)) if *registration == self.registration
+        ) {
+            inner.running.remove(&self.subject);
+        }
+    }
+}
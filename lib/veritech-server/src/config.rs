@@ -11,7 +11,7 @@ use buck2_resources::Buck2Resources;
 use deadpool_cyclone::{
     instance::cyclone::{
         LocalHttpInstance, LocalHttpInstanceSpec, LocalHttpSocketStrategy, LocalUdsInstance,
-        LocalUdsInstanceSpec, LocalUdsRuntimeStrategy, LocalUdsSocketStrategy,
+        LocalUdsInstanceSpec, LocalUdsRuntimeStrategy, LocalUdsSocketStrategy, ResourceLimits,
     },
     Instance,
 };
@@ -212,6 +212,8 @@ pub enum CycloneConfig {
         pool_size: u16,
         #[serde(default)]
         connect_timeout: u64,
+        #[serde(default)]
+        resource_limits: ResourceLimits,
     },
 }
 
@@ -244,6 +246,7 @@ impl CycloneConfig {
             action: default_enable_endpoint(),
             pool_size: default_pool_size(),
             connect_timeout: default_connect_timeout(),
+            resource_limits: Default::default(),
         }
     }
 
@@ -374,6 +377,7 @@ impl TryFrom<CycloneConfig> for CycloneSpec {
                 action,
                 pool_size,
                 connect_timeout,
+                resource_limits,
             } => {
                 let mut builder = LocalUdsInstance::spec();
                 //we only need these if running local process. Maybe the builder should handle
@@ -405,6 +409,7 @@ impl TryFrom<CycloneConfig> for CycloneSpec {
                 builder.pool_size(pool_size);
                 builder.connect_timeout(connect_timeout);
                 builder.pool_noodle(PoolNoodle::new(pool_size.into()));
+                builder.resource_limits(resource_limits);
 
                 Ok(Self::LocalUds(
                     builder.build().map_err(ConfigError::cyclone_spec_build)?,
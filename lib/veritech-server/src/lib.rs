@@ -1,8 +1,14 @@
+mod cancellation;
 mod config;
 mod publisher;
 mod server;
 mod subscriber;
 
+pub(crate) use crate::{
+    cancellation::Cancellations,
+    publisher::{Publisher, PublisherError},
+    subscriber::FunctionSubscriber,
+};
 pub use crate::{
     config::{
         detect_and_configure_development, Config, ConfigBuilder, ConfigError, ConfigFile,
@@ -10,8 +16,4 @@ pub use crate::{
     },
     server::{Server, ServerError, VeritechShutdownHandle},
 };
-pub(crate) use crate::{
-    publisher::{Publisher, PublisherError},
-    subscriber::FunctionSubscriber,
-};
 pub use deadpool_cyclone::{instance::cyclone::LocalUdsInstance, Instance};
@@ -1,15 +1,19 @@
 use chrono::Utc;
 use deadpool_cyclone::{
-    instance::cyclone::LocalUdsInstanceSpec, ActionRunRequest, ActionRunResultSuccess,
-    CycloneClient, FunctionResult, FunctionResultFailure, FunctionResultFailureError, Manager,
-    Pool, ProgressMessage, ReconciliationRequest, ReconciliationResultSuccess,
-    ResolverFunctionRequest, ResolverFunctionResultSuccess, SchemaVariantDefinitionRequest,
-    SchemaVariantDefinitionResultSuccess, ValidationRequest, ValidationResultSuccess,
+    instance::cyclone::LocalUdsInstanceSpec, ActionRunRequest, CycloneClient, ExecutionEnvelope,
+    FunctionRequest, FunctionResult, FunctionResultFailure, FunctionResultFailureError, Manager,
+    Pool, ProgressMessage, ReconciliationRequest, ResolverFunctionRequest,
+    SchemaVariantDefinitionRequest, ValidationRequest, EXECUTION_ENVELOPE_VERSION,
 };
-use futures::{channel::oneshot, join, StreamExt};
-use nats_subscriber::Request;
-use si_data_nats::NatsClient;
-use std::{io, sync::Arc};
+use futures::{
+    channel::oneshot,
+    future::{join_all, BoxFuture},
+    Future, StreamExt,
+};
+use nats_subscriber::{Request, Subscriber};
+use serde::de::DeserializeOwned;
+use si_data_nats::{NatsClient, Subscriber as NatsSubscriber};
+use std::{collections::HashMap, fmt, io, sync::Arc};
 use telemetry::prelude::*;
 use thiserror::Error;
 use tokio::{
@@ -17,13 +21,18 @@ use tokio::{
     sync::{broadcast, mpsc},
 };
 
-use crate::{config::CycloneSpec, Config, FunctionSubscriber, Publisher, PublisherError};
+use veritech_core::{
+    nats_action_run_subject, nats_reconciliation_subject, nats_resolver_function_subject,
+    nats_schema_variant_definition_subject, nats_validation_subject,
+};
+
+use crate::{
+    config::CycloneSpec, Cancellations, Config, FunctionSubscriber, Publisher, PublisherError,
+};
 
 #[remain::sorted]
 #[derive(Error, Debug)]
 pub enum ServerError {
-    #[error("action run error: {0}")]
-    ActionRun(#[from] deadpool_cyclone::ExecutionError<ActionRunResultSuccess>),
     #[error("cyclone error: {0}")]
     Cyclone(#[from] deadpool_cyclone::ClientError),
     #[error("cyclone pool error: {0}")]
@@ -34,24 +43,16 @@ pub enum ServerError {
     CycloneSpec(#[source] Box<dyn std::error::Error + Sync + Send + 'static>),
     #[error("error connecting to nats: {0}")]
     NatsConnect(#[source] si_data_nats::NatsError),
+    #[error("error subscribing to nats subject: {0}")]
+    NatsSubscribe(#[source] si_data_nats::NatsError),
     #[error("no reply mailbox found")]
     NoReplyMailboxFound,
     #[error(transparent)]
     Publisher(#[from] PublisherError),
-    #[error(transparent)]
-    Reconciliation(#[from] deadpool_cyclone::ExecutionError<ReconciliationResultSuccess>),
-    #[error(transparent)]
-    ResolverFunction(#[from] deadpool_cyclone::ExecutionError<ResolverFunctionResultSuccess>),
-    #[error(transparent)]
-    SchemaVariantDefinition(
-        #[from] deadpool_cyclone::ExecutionError<SchemaVariantDefinitionResultSuccess>,
-    ),
     #[error("failed to setup signal handler")]
     Signal(#[source] io::Error),
     #[error(transparent)]
     Subscriber(#[from] nats_subscriber::SubscriberError),
-    #[error(transparent)]
-    Validation(#[from] deadpool_cyclone::ExecutionError<ValidationResultSuccess>),
     #[error("wrong cyclone spec type for {0} spec: {1:?}")]
     WrongCycloneSpec(&'static str, Box<CycloneSpec>),
 }
@@ -62,6 +63,9 @@ pub struct Server {
     nats: NatsClient,
     subject_prefix: Option<String>,
     cyclone_pool: Pool<LocalUdsInstanceSpec>,
+    kinds: Arc<ExecutionKinds>,
+    cancellations: Cancellations,
+    cancel_subscriber: NatsSubscriber,
     shutdown_broadcast_tx: broadcast::Sender<()>,
     shutdown_tx: mpsc::Sender<ShutdownSource>,
     shutdown_rx: oneshot::Receiver<()>,
@@ -108,6 +112,11 @@ impl Server {
                 let (shutdown_broadcast_tx, _) = broadcast::channel(16);
 
                 let nats = connect_to_nats(&config).await?;
+                // Subscribed to before any requests, so that no cancel message is missed
+                let (cancellations, cancel_subscriber) = Cancellations::subscribe(&nats)
+                    .await
+                    .map_err(ServerError::NatsSubscribe)?;
+                let kinds = ExecutionKinds::for_spec(spec);
                 let mut manager = Manager::new(spec.clone());
                 manager
                     .setup()
@@ -130,6 +139,9 @@ impl Server {
                     nats,
                     subject_prefix: config.subject_prefix().map(|s| s.to_string()),
                     cyclone_pool,
+                    kinds: Arc::new(kinds),
+                    cancellations,
+                    cancel_subscriber,
                     shutdown_broadcast_tx,
                     shutdown_tx,
                     shutdown_rx: graceful_shutdown_rx,
@@ -153,43 +165,39 @@ impl Server {
 
 impl Server {
     pub async fn run(self) -> ServerResult<()> {
-        let _ = join!(
-            process_resolver_function_requests_task(
-                self.metadata.clone(),
-                self.nats.clone(),
-                self.subject_prefix.clone(),
-                self.cyclone_pool.clone(),
-                self.shutdown_broadcast_tx.subscribe(),
-            ),
-            process_validation_requests_task(
-                self.metadata.clone(),
-                self.nats.clone(),
-                self.subject_prefix.clone(),
-                self.cyclone_pool.clone(),
-                self.shutdown_broadcast_tx.subscribe(),
-            ),
-            process_action_run_requests_task(
-                self.metadata.clone(),
-                self.nats.clone(),
-                self.subject_prefix.clone(),
-                self.cyclone_pool.clone(),
-                self.shutdown_broadcast_tx.subscribe(),
-            ),
-            process_reconciliation_requests_task(
+        let cancellations = self.cancellations.clone();
+        let cancellations_shutdown_rx = self.shutdown_broadcast_tx.subscribe();
+        let mut tasks: Vec<BoxFuture<'static, ()>> = vec![
+            Box::pin(async move {
+                cancellations
+                    .process(self.cancel_subscriber, cancellations_shutdown_rx)
+                    .await
+            }),
+            Box::pin(process_execution_requests_task(
                 self.metadata.clone(),
                 self.nats.clone(),
                 self.subject_prefix.clone(),
                 self.cyclone_pool.clone(),
+                self.kinds.clone(),
+                self.cancellations.clone(),
                 self.shutdown_broadcast_tx.subscribe(),
-            ),
-            process_schema_variant_definition_requests_task(
+            )),
+        ];
+        // Kinds which predate the execution subject are still requested on subjects of their own
+        for (kind, subject) in self.kinds.subjects() {
+            tasks.push(Box::pin(process_kind_requests_task(
                 self.metadata.clone(),
                 self.nats.clone(),
                 self.subject_prefix.clone(),
                 self.cyclone_pool.clone(),
+                self.kinds.clone(),
+                self.cancellations.clone(),
+                kind,
+                subject,
                 self.shutdown_broadcast_tx.subscribe(),
-            ),
-        );
+            )));
+        }
+        join_all(tasks).await;
 
         let _ = self.shutdown_rx.await;
         info!("received graceful shutdown, terminating server instance");
@@ -216,46 +224,227 @@ impl VeritechShutdownHandle {
     }
 }
 
-// NOTE(fnichol): resolver function, action are parallel and extremely similar, so there
-// is a lurking "unifying" refactor here. It felt like waiting until the third time adding one of
-// these would do the trick, and as a result the first 2 impls are here and not split apart into
-// their own modules.
+type ExecutionKindHandler = fn(
+    Arc<ServerMetadata>,
+    NatsClient,
+    Pool<LocalUdsInstanceSpec>,
+    Cancellations,
+    Request<ExecutionEnvelope>,
+) -> BoxFuture<'static, ()>;
+
+/// The subject, and its queue name, on which requests of a kind of function that predates the
+/// execution subject are received without an [`ExecutionEnvelope`].
+type KindSubject = (fn(Option<&str>) -> String, &'static str);
+
+struct ExecutionKind {
+    handler: ExecutionKindHandler,
+    subject: Option<KindSubject>,
+}
+
+/// The kinds of functions veritech executes, keyed by the kind their requests are tagged with in
+/// an [`ExecutionEnvelope`].
+#[derive(Default)]
+struct ExecutionKinds(HashMap<&'static str, ExecutionKind>);
+
+impl ExecutionKinds {
+    /// Returns the kinds of functions executed by the Cyclone instances of the given spec.
+    fn for_spec(spec: &LocalUdsInstanceSpec) -> Self {
+        let mut kinds = Self::default();
+        kinds
+            .register::<ResolverFunctionRequest>(Some((nats_resolver_function_subject, "resolver")))
+            .register::<ValidationRequest>(Some((nats_validation_subject, "validation")))
+            .register::<ActionRunRequest>(Some((nats_action_run_subject, "action")))
+            .register::<ReconciliationRequest>(Some((
+                nats_reconciliation_subject,
+                "reconciliation",
+            )))
+            .register::<SchemaVariantDefinitionRequest>(Some((
+                nats_schema_variant_definition_subject,
+                "schema_variant_definition",
+            )));
+        kinds.0.retain(|kind, _| spec.executes(kind));
+        kinds
+    }
+
+    /// Registers a kind of function, whose requests are received on the execution subject and,
+    /// if it has one, on a subject of its own.
+    fn register<R>(&mut self, subject: Option<KindSubject>) -> &mut Self
+    where
+        R: FunctionRequest + Send + 'static,
+        R::Success: Unpin + fmt::Debug + Send + Sync + 'static,
+    {
+        self.0.insert(
+            R::KIND,
+            ExecutionKind {
+                handler: |metadata, nats, cyclone_pool, cancellations, request| {
+                    let execute = execution_request_task::<R>;
+                    Box::pin(execute(
+                        metadata,
+                        nats,
+                        cyclone_pool,
+                        cancellations,
+                        request,
+                    ))
+                },
+                subject,
+            },
+        );
+        self
+    }
+
+    /// Returns each kind which has a subject of its own, along with that subject.
+    fn subjects(&self) -> impl Iterator<Item = (&'static str, KindSubject)> + '_ {
+        self.0
+            .iter()
+            .filter_map(|(kind, execution_kind)| Some((*kind, execution_kind.subject?)))
+    }
+
+    /// Spawns a task to process the request with the handler registered for its kind, or to fail
+    /// it if there is none or the envelope is of an unsupported version.
+    fn spawn(
+        &self,
+        metadata: Arc<ServerMetadata>,
+        nats: NatsClient,
+        cyclone_pool: Pool<LocalUdsInstanceSpec>,
+        cancellations: Cancellations,
+        request: Request<ExecutionEnvelope>,
+    ) {
+        let envelope = &request.payload;
+        if envelope.version != EXECUTION_ENVELOPE_VERSION {
+            let message = format!(
+                "unsupported execution envelope version: {}",
+                envelope.version
+            );
+            tokio::spawn(fail_execution_request(nats, request, message));
+            return;
+        }
+
+        match self.0.get(envelope.kind.as_str()) {
+            Some(execution_kind) => {
+                tokio::spawn((execution_kind.handler)(
+                    metadata,
+                    nats,
+                    cyclone_pool,
+                    cancellations,
+                    request,
+                ));
+            }
+            None => {
+                let message = format!("unsupported function kind: {}", envelope.kind);
+                tokio::spawn(fail_execution_request(nats, request, message));
+            }
+        }
+    }
+}
 
-async fn process_resolver_function_requests_task(
+async fn process_execution_requests_task(
     metadata: Arc<ServerMetadata>,
     nats: NatsClient,
     subject_prefix: Option<String>,
     cyclone_pool: Pool<LocalUdsInstanceSpec>,
+    kinds: Arc<ExecutionKinds>,
+    cancellations: Cancellations,
     shutdown_broadcast_rx: broadcast::Receiver<()>,
 ) {
-    if let Err(err) = process_resolver_function_requests(
+    let requests = match FunctionSubscriber::execution(&nats, subject_prefix.as_deref()).await {
+        Ok(requests) => requests,
+        Err(err) => {
+            warn!(error = ?err, "subscribing for execution requests failed");
+            return;
+        }
+    };
+
+    if let Err(err) = process_requests(
         metadata,
         nats,
-        subject_prefix,
         cyclone_pool,
+        kinds,
+        cancellations,
+        requests,
+        |request| request,
         shutdown_broadcast_rx,
     )
     .await
     {
-        warn!(error = ?err, "processing resolver function requests failed");
+        warn!(error = ?err, "processing execution requests failed");
     }
 }
 
-async fn process_resolver_function_requests(
+/// Processes the requests of one kind of function that are received on its own subject, by
+/// wrapping each in an [`ExecutionEnvelope`] of that kind.
+#[allow(clippy::too_many_arguments)]
+async fn process_kind_requests_task(
     metadata: Arc<ServerMetadata>,
     nats: NatsClient,
     subject_prefix: Option<String>,
     cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    mut shutdown_broadcast_rx: broadcast::Receiver<()>,
-) -> ServerResult<()> {
-    let mut requests =
-        FunctionSubscriber::resolver_function(&nats, subject_prefix.as_deref()).await?;
+    kinds: Arc<ExecutionKinds>,
+    cancellations: Cancellations,
+    kind: &'static str,
+    (subject, queue_name): KindSubject,
+    shutdown_broadcast_rx: broadcast::Receiver<()>,
+) {
+    let subject = subject(subject_prefix.as_deref());
+    let requests = match FunctionSubscriber::kind(&nats, subject, queue_name).await {
+        Ok(requests) => requests,
+        Err(err) => {
+            warn!(error = ?err, kind, "subscribing for function requests failed");
+            return;
+        }
+    };
+
+    if let Err(err) = process_requests(
+        metadata,
+        nats,
+        cyclone_pool,
+        kinds,
+        cancellations,
+        requests,
+        |request| enveloped(kind, request),
+        shutdown_broadcast_rx,
+    )
+    .await
+    {
+        warn!(error = ?err, kind, "processing function requests failed");
+    }
+}
+
+/// Wraps a request received without an envelope in one of the given kind.
+fn enveloped(kind: &str, request: Request<serde_json::Value>) -> Request<ExecutionEnvelope> {
+    Request {
+        subject: request.subject,
+        payload: ExecutionEnvelope {
+            version: EXECUTION_ENVELOPE_VERSION,
+            kind: kind.to_string(),
+            request: request.payload,
+        },
+        reply: request.reply,
+        headers: request.headers,
+        status: request.status,
+        description: request.description,
+        process_span: request.process_span,
+    }
+}
 
+#[allow(clippy::too_many_arguments)]
+async fn process_requests<T>(
+    metadata: Arc<ServerMetadata>,
+    nats: NatsClient,
+    cyclone_pool: Pool<LocalUdsInstanceSpec>,
+    kinds: Arc<ExecutionKinds>,
+    cancellations: Cancellations,
+    mut requests: Subscriber<T>,
+    into_envelope: impl Fn(Request<T>) -> Request<ExecutionEnvelope>,
+    mut shutdown_broadcast_rx: broadcast::Receiver<()>,
+) -> ServerResult<()>
+where
+    T: DeserializeOwned,
+{
     loop {
         tokio::select! {
             // Got a broadcasted shutdown message
             _ = shutdown_broadcast_rx.recv() => {
-                trace!("process resolver function requests task received shutdown");
+                trace!(subject = %requests.subject(), "process requests task received shutdown");
                 break;
             }
             // Got the next message on from the subscriber
@@ -263,18 +452,19 @@ async fn process_resolver_function_requests(
                 match request {
                     Some(Ok(request)) => {
                         // Spawn a task an process the request
-                        tokio::spawn(resolver_function_request_task(
+                        kinds.spawn(
                             metadata.clone(),
                             nats.clone(),
                             cyclone_pool.clone(),
-                            request,
-                        ));
+                            cancellations.clone(),
+                            into_envelope(request),
+                        );
                     }
                     Some(Err(err)) => {
-                        warn!(error = ?err, "next resolver function request had error");
+                        warn!(error = ?err, subject = %requests.subject(), "next request had error");
                     }
                     None => {
-                        trace!("resolver function requests subscriber stream has closed");
+                        trace!(subject = %requests.subject(), "requests subscriber stream has closed");
                         break;
                     }
                 }
@@ -293,13 +483,65 @@ async fn process_resolver_function_requests(
     Ok(())
 }
 
-async fn resolver_function_request_task(
+/// Publishes a failed result for an execution request which can't be executed.
+async fn fail_execution_request(
+    nats: NatsClient,
+    request: Request<ExecutionEnvelope>,
+    message: String,
+) {
+    warn!(message, "failing execution request");
+
+    let reply_mailbox = match request.reply {
+        Some(reply) => reply,
+        None => {
+            error!("no reply mailbox found");
+            return;
+        }
+    };
+    // The request couldn't be deserialized, so make do with whatever execution id it has
+    let execution_id = request
+        .payload
+        .request
+        .get("executionId")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let publisher = Publisher::new(&nats, &reply_mailbox);
+
+    let result = FunctionResult::Failure::<()>(FunctionResultFailure {
+        execution_id,
+        error: FunctionResultFailureError {
+            kind: "veritechServer".to_string(),
+            message,
+        },
+        timestamp: timestamp(),
+    });
+    if let Err(err) = publisher.finalize_output().await {
+        error!(error = ?err, "failed to finalize output by sending final message");
+    }
+    if let Err(err) = publisher.publish_result(&result).await {
+        error!(error = ?err, "failed to publish errored result");
+    }
+}
+
+async fn execution_request_task<R>(
     metadata: Arc<ServerMetadata>,
     nats: NatsClient,
     cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ResolverFunctionRequest>,
-) {
-    let cyclone_request = request.payload;
+    cancellations: Cancellations,
+    request: Request<ExecutionEnvelope>,
+) where
+    R: FunctionRequest + Send + 'static,
+    R::Success: Unpin + fmt::Debug + Send + Sync + 'static,
+{
+    let cyclone_request = match request.payload.clone().into_request::<R>() {
+        Ok(cyclone_request) => cyclone_request,
+        Err(err) => {
+            let message = format!("failed to deserialize {} request: {err}", R::KIND);
+            fail_execution_request(nats, request, message).await;
+            return;
+        }
+    };
 
     let reply_mailbox = match request.reply {
         Some(reply) => reply,
@@ -308,30 +550,32 @@ async fn resolver_function_request_task(
             return;
         }
     };
-    let execution_id = cyclone_request.execution_id.clone();
+    let execution_id = cyclone_request.execution_id().to_string();
     let publisher = Publisher::new(&nats, &reply_mailbox);
 
-    let function_result = resolver_function_request(
-        metadata,
-        &publisher,
-        cyclone_pool,
-        cyclone_request,
-        &request.process_span,
+    let function_result = execute_cancellable(
+        &cancellations,
+        &execution_id,
+        execution_request(
+            metadata,
+            &publisher,
+            cyclone_pool,
+            cyclone_request,
+            &request.process_span,
+        ),
     )
     .await;
 
     if let Err(err) = publisher.finalize_output().await {
         error!(error = ?err, "failed to finalize output by sending final message");
-        let result = deadpool_cyclone::FunctionResult::Failure::<ResolverFunctionResultSuccess>(
-            FunctionResultFailure {
-                execution_id,
-                error: FunctionResultFailureError {
-                    kind: "veritechServer".to_string(),
-                    message: "failed to finalize output by sending final message".to_string(),
-                },
-                timestamp: timestamp(),
+        let result = FunctionResult::Failure::<R::Success>(FunctionResultFailure {
+            execution_id,
+            error: FunctionResultFailureError {
+                kind: "veritechServer".to_string(),
+                message: "failed to finalize output by sending final message".to_string(),
             },
-        );
+            timestamp: timestamp(),
+        });
         if let Err(err) = publisher.publish_result(&result).await {
             error!(error = ?err, "failed to publish errored result");
         }
@@ -342,16 +586,14 @@ async fn resolver_function_request_task(
         Ok(fr) => fr,
         Err(err) => {
             error!(error = ?err, "failure trying to run function to completion");
-            deadpool_cyclone::FunctionResult::Failure::<ResolverFunctionResultSuccess>(
-                FunctionResultFailure {
-                    execution_id,
-                    error: FunctionResultFailureError {
-                        kind: "veritechServer".to_string(),
-                        message: err.to_string(),
-                    },
-                    timestamp: timestamp(),
+            FunctionResult::Failure::<R::Success>(FunctionResultFailure {
+                execution_id,
+                error: FunctionResultFailureError {
+                    kind: "veritechServer".to_string(),
+                    message: err.to_string(),
                 },
-            )
+                timestamp: timestamp(),
+            })
         }
     };
 
@@ -361,27 +603,32 @@ async fn resolver_function_request_task(
 }
 
 #[instrument(
-    name = "veritech.resolver_function_request",
+    name = "veritech.execution_request",
     parent = process_span,
     level = "info",
     skip_all,
     fields(
-        job.id = &cyclone_request.execution_id,
+        job.id = cyclone_request.execution_id(),
         job.instance = metadata.job_instance,
-        job.invoked_name = &cyclone_request.handler,
+        job.invoked_name = cyclone_request.handler(),
         job.invoked_provider = metadata.job_invoked_provider,
+        job.kind = R::KIND,
         otel.kind = SpanKind::Server.as_str(),
         otel.status_code = Empty,
         otel.status_message = Empty,
     )
 )]
-async fn resolver_function_request(
+async fn execution_request<R>(
     metadata: Arc<ServerMetadata>,
     publisher: &Publisher<'_>,
     cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    cyclone_request: ResolverFunctionRequest,
+    cyclone_request: R,
     process_span: &Span,
-) -> ServerResult<FunctionResult<ResolverFunctionResultSuccess>> {
+) -> ServerResult<FunctionResult<R::Success>>
+where
+    R: FunctionRequest + Send + 'static,
+    R::Success: Unpin + fmt::Debug + Send + Sync + 'static,
+{
     let span = Span::current();
 
     let mut client = cyclone_pool
@@ -389,12 +636,12 @@ async fn resolver_function_request(
         .await
         .map_err(|err| span.record_err(ServerError::CyclonePool(Box::new(err))))?;
     let mut progress = client
-        .execute_resolver(cyclone_request)
+        .execute_function(cyclone_request)
         .await
         .map_err(|err| span.record_err(err))?
         .start()
         .await
-        .map_err(|err| span.record_err(err))?;
+        .map_err(|err| span.record_err(ServerError::CycloneProgress(Box::new(err))))?;
 
     while let Some(msg) = progress.next().await {
         match msg {
@@ -417,666 +664,38 @@ async fn resolver_function_request(
     let function_result = progress
         .finish()
         .await
-        .map_err(|err| span.record_err(err))?;
+        .map_err(|err| span.record_err(ServerError::CycloneProgress(Box::new(err))))?;
 
     span.record_ok();
     Ok(function_result)
 }
 
-async fn process_validation_requests_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    shutdown_broadcast_rx: broadcast::Receiver<()>,
-) {
-    if let Err(err) = process_validation_requests(
-        metadata,
-        nats,
-        subject_prefix,
-        cyclone_pool,
-        shutdown_broadcast_rx,
-    )
-    .await
-    {
-        warn!(error = ?err, "processing validation requests failed");
-    }
-}
-
-async fn process_validation_requests(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    mut shutdown_broadcast_rx: broadcast::Receiver<()>,
-) -> ServerResult<()> {
-    let mut requests = FunctionSubscriber::validation(&nats, subject_prefix.as_deref()).await?;
-
-    loop {
-        tokio::select! {
-            // Got a broadcasted shutdown message
-            _ = shutdown_broadcast_rx.recv() => {
-                trace!("process validation requests task received shutdown");
-                break;
-            }
-            // Got the next message on from the subscriber
-            request = requests.next() => {
-                match request {
-                    Some(Ok(request)) => {
-                        // Spawn a task an process the request
-                        tokio::spawn(validation_request_task(
-                            metadata.clone(),
-                            nats.clone(),
-                            cyclone_pool.clone(),
-                            request,
-                        ));
-                    }
-                    Some(Err(err)) => {
-                        warn!(error = ?err, "next validation request had error");
-                    }
-                    None => {
-                        trace!("validation requests subscriber stream has closed");
-                        break;
-                    }
-                }
-            }
-            // All other arms are closed, nothing left to do but return
-            else => {
-                trace!("returning with all select arms closed");
-                break
-            }
-        }
-    }
-
-    // Unsubscribe from subscriber without draining the channel
-    requests.unsubscribe_after(0).await?;
-
-    Ok(())
-}
-
-async fn validation_request_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ValidationRequest>,
-) {
-    let process_span = request.process_span.clone();
-    if let Err(err) = validation_request(metadata, nats, cyclone_pool, request, &process_span).await
-    {
-        warn!(error = ?err, "validation execution failed");
-    }
-}
-
-#[instrument(
-    name = "veritech.validation_request",
-    parent = process_span,
-    level = "info",
-    skip_all,
-    fields(
-        job.id = &request.payload.execution_id,
-        job.instance = metadata.job_instance,
-        job.invoked_name = &request.payload.handler,
-        job.invoked_provider = metadata.job_invoked_provider,
-        otel.kind = SpanKind::Server.as_str(),
-        otel.status_code = Empty,
-        otel.status_message = Empty,
-    )
-)]
-async fn validation_request(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ValidationRequest>,
-    process_span: &Span,
-) -> ServerResult<()> {
-    let span = Span::current();
-
-    let cyclone_request = request.payload;
-
-    let reply_mailbox = request
-        .reply
-        .ok_or(ServerError::NoReplyMailboxFound)
-        .map_err(|err| span.record_err(err))?;
-
-    let publisher = Publisher::new(&nats, &reply_mailbox);
-    let mut client = cyclone_pool
-        .get()
-        .await
-        .map_err(|err| span.record_err(ServerError::CyclonePool(Box::new(err))))?;
-    let mut progress = client
-        .execute_validation(cyclone_request)
-        .await
-        .map_err(|err| span.record_err(err))?
-        .start()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    while let Some(msg) = progress.next().await {
-        match msg {
-            Ok(ProgressMessage::OutputStream(output)) => {
-                publisher
-                    .publish_output(&output)
-                    .await
-                    .map_err(|err| span.record_err(err))?;
-            }
-            Ok(ProgressMessage::Heartbeat) => {
-                trace!("received heartbeat message");
-            }
-            Err(err) => {
-                warn!(error = ?err, "next progress message was an error, bailing out");
-                break;
-            }
+/// Runs a function execution until it finishes, or until a message is published on the cancel
+/// subject for its execution id, including one received before the execution started.
+///
+/// A cancelled execution is dropped, closing its connection to cyclone which then kills the
+/// function, and its result is a failure of the
+/// [`KIND_CANCELLED`](FunctionResultFailureError::KIND_CANCELLED) kind.
+async fn execute_cancellable<S>(
+    cancellations: &Cancellations,
+    execution_id: &str,
+    execution: impl Future<Output = ServerResult<FunctionResult<S>>>,
+) -> ServerResult<FunctionResult<S>> {
+    let mut cancellation = cancellations.register(execution_id);
+
+    tokio::select! {
+        // Checked first, so that an execution cancelled before it started never starts
+        biased;
+        _ = cancellation.cancelled() => {
+            info!(execution_id, "cancelling function execution");
+            Ok(FunctionResult::Failure(FunctionResultFailure {
+                execution_id: execution_id.to_string(),
+                error: FunctionResultFailureError::cancelled(),
+                timestamp: timestamp(),
+            }))
         }
+        function_result = execution => function_result,
     }
-    publisher
-        .finalize_output()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    let function_result = progress
-        .finish()
-        .await
-        .map_err(|err| span.record_err(err))?;
-    publisher
-        .publish_result(&function_result)
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    span.record_ok();
-    Ok(())
-}
-
-async fn process_schema_variant_definition_requests_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    shutdown_broadcast_rx: broadcast::Receiver<()>,
-) {
-    if let Err(err) = process_schema_variant_definition_requests(
-        metadata,
-        nats,
-        subject_prefix,
-        cyclone_pool,
-        shutdown_broadcast_rx,
-    )
-    .await
-    {
-        warn!(error = ?err, "processing schema variant definition requests failed");
-    }
-}
-
-async fn process_schema_variant_definition_requests(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    mut shutdown_broadcast_rx: broadcast::Receiver<()>,
-) -> ServerResult<()> {
-    let mut requests =
-        FunctionSubscriber::schema_variant_definition(&nats, subject_prefix.as_deref()).await?;
-
-    loop {
-        tokio::select! {
-            // Got a broadcasted shutdown message
-            _ = shutdown_broadcast_rx.recv() => {
-                trace!("process schema_variant_definition requests task received shutdown");
-                break;
-            }
-            // Got the next message on from the subscriber
-            request = requests.next() => {
-                match request {
-                    Some(Ok(request)) => {
-                        // Spawn a task an process the request
-                        tokio::spawn(schema_variant_definition_request_task(
-                            metadata.clone(),
-                            nats.clone(),
-                            cyclone_pool.clone(),
-                            request,
-                        ));
-                    }
-                    Some(Err(err)) => {
-                        warn!(error = ?err, "next schema variant definition request had error");
-                    }
-                    None => {
-                        trace!("schema variant definition requests subscriber stream has closed");
-                        break;
-                    }
-                }
-            }
-            // All other arms are closed, nothing left to do but return
-            else => {
-                trace!("returning with all select arms closed");
-                break
-            }
-        }
-    }
-
-    // Unsubscribe from subscriber without draining the channel
-    requests.unsubscribe_after(0).await?;
-
-    Ok(())
-}
-
-async fn schema_variant_definition_request_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<SchemaVariantDefinitionRequest>,
-) {
-    let process_span = request.process_span.clone();
-    if let Err(err) =
-        schema_variant_definition_request(metadata, nats, cyclone_pool, request, &process_span)
-            .await
-    {
-        warn!(error = ?err, "schema variant definition execution failed");
-    }
-}
-
-#[instrument(
-    name = "veritech.schema_variant_definition_request",
-    parent = process_span,
-    level = "info",
-    skip_all,
-    fields(
-        job.id = &request.payload.execution_id,
-        job.instance = metadata.job_instance,
-        job.invoked_name = &request.payload.handler,
-        job.invoked_provider = metadata.job_invoked_provider,
-        otel.kind = SpanKind::Server.as_str(),
-        otel.status_code = Empty,
-        otel.status_message = Empty,
-    )
-)]
-async fn schema_variant_definition_request(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<SchemaVariantDefinitionRequest>,
-    process_span: &Span,
-) -> ServerResult<()> {
-    let span = Span::current();
-
-    let cyclone_request = request.payload;
-    let reply_mailbox = request
-        .reply
-        .ok_or(ServerError::NoReplyMailboxFound)
-        .map_err(|err| span.record_err(err))?;
-
-    let publisher = Publisher::new(&nats, &reply_mailbox);
-    let mut client = cyclone_pool
-        .get()
-        .await
-        .map_err(|err| span.record_err(ServerError::CyclonePool(Box::new(err))))?;
-
-    let mut progress = client
-        .execute_schema_variant_definition(cyclone_request)
-        .await
-        .map_err(|err| span.record_err(err))?
-        .start()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    while let Some(msg) = progress.next().await {
-        match msg {
-            Ok(ProgressMessage::OutputStream(output)) => {
-                publisher
-                    .publish_output(&output)
-                    .await
-                    .map_err(|err| span.record_err(err))?;
-            }
-            Ok(ProgressMessage::Heartbeat) => {
-                trace!("received heartbeat message");
-            }
-            Err(err) => {
-                warn!(error = ?err, "next progress message was an error, bailing out");
-                break;
-            }
-        }
-    }
-    publisher
-        .finalize_output()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    let function_result = progress
-        .finish()
-        .await
-        .map_err(|err| span.record_err(err))?;
-    publisher
-        .publish_result(&function_result)
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    span.record_ok();
-    Ok(())
-}
-
-async fn process_action_run_requests_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    shutdown_broadcast_rx: broadcast::Receiver<()>,
-) {
-    if let Err(err) = process_action_run_requests(
-        metadata,
-        nats,
-        subject_prefix,
-        cyclone_pool,
-        shutdown_broadcast_rx,
-    )
-    .await
-    {
-        warn!(error = ?err, "processing action run requests failed");
-    }
-}
-
-async fn process_action_run_requests(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    mut shutdown_broadcast_rx: broadcast::Receiver<()>,
-) -> ServerResult<()> {
-    let mut requests = FunctionSubscriber::action_run(&nats, subject_prefix.as_deref()).await?;
-
-    loop {
-        tokio::select! {
-            // Got a broadcasted shutdown message
-            _ = shutdown_broadcast_rx.recv() => {
-                trace!("process action_run requests task received shutdown");
-                break;
-            }
-            // Got the next message on from the subscriber
-            request = requests.next() => {
-                match request {
-                    Some(Ok(request)) => {
-                        // Spawn a task an process the request
-                        tokio::spawn(action_run_request_task(
-                            metadata.clone(),
-                            nats.clone(),
-                            cyclone_pool.clone(),
-                            request,
-                        ));
-                    }
-                    Some(Err(err)) => {
-                        warn!(error = ?err, "next action run request had error");
-                    }
-                    None => {
-                        trace!("action run requests subscriber stream has closed");
-                        break;
-                    }
-                }
-            }
-            // All other arms are closed, nothing left to do but return
-            else => {
-                trace!("returning with all select arms closed");
-                break
-            }
-        }
-    }
-
-    // Unsubscribe from subscriber without draining the channel
-    requests.unsubscribe_after(0).await?;
-
-    Ok(())
-}
-
-async fn action_run_request_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ActionRunRequest>,
-) {
-    let process_span = request.process_span.clone();
-    if let Err(err) = action_run_request(metadata, nats, cyclone_pool, request, &process_span).await
-    {
-        warn!(error = ?err, "action run execution failed");
-    }
-}
-
-#[instrument(
-    name = "veritech.action_run_request",
-    parent = process_span,
-    level = "info",
-    skip_all,
-    fields(
-        job.id = &request.payload.execution_id,
-        job.instance = metadata.job_instance,
-        job.invoked_name = &request.payload.handler,
-        job.invoked_provider = metadata.job_invoked_provider,
-        otel.kind = SpanKind::Server.as_str(),
-        otel.status_code = Empty,
-        otel.status_message = Empty,
-    )
-)]
-async fn action_run_request(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ActionRunRequest>,
-    process_span: &Span,
-) -> ServerResult<()> {
-    let span = Span::current();
-
-    let cyclone_request = request.payload;
-    let reply_mailbox = request
-        .reply
-        .ok_or(ServerError::NoReplyMailboxFound)
-        .map_err(|err| span.record_err(err))?;
-
-    let publisher = Publisher::new(&nats, &reply_mailbox);
-    let mut client = cyclone_pool
-        .get()
-        .await
-        .map_err(|err| span.record_err(ServerError::CyclonePool(Box::new(err))))?;
-
-    let mut progress = client
-        .execute_action_run(cyclone_request)
-        .await
-        .map_err(|err| span.record_err(err))?
-        .start()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    while let Some(msg) = progress.next().await {
-        match msg {
-            Ok(ProgressMessage::OutputStream(output)) => {
-                publisher
-                    .publish_output(&output)
-                    .await
-                    .map_err(|err| span.record_err(err))?;
-            }
-            Ok(ProgressMessage::Heartbeat) => {
-                trace!("received heartbeat message");
-            }
-            Err(err) => {
-                warn!(error = ?err, "next progress message was an error, bailing out");
-                break;
-            }
-        }
-    }
-    publisher
-        .finalize_output()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    let function_result = progress
-        .finish()
-        .await
-        .map_err(|err| span.record_err(err))?;
-    publisher
-        .publish_result(&function_result)
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    span.record_ok();
-    Ok(())
-}
-
-async fn process_reconciliation_requests_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    shutdown_broadcast_rx: broadcast::Receiver<()>,
-) {
-    if let Err(err) = process_reconciliation_requests(
-        metadata,
-        nats,
-        subject_prefix,
-        cyclone_pool,
-        shutdown_broadcast_rx,
-    )
-    .await
-    {
-        warn!(error = ?err, "processing reconciliation requests failed");
-    }
-}
-
-async fn process_reconciliation_requests(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    subject_prefix: Option<String>,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    mut shutdown_broadcast_rx: broadcast::Receiver<()>,
-) -> ServerResult<()> {
-    let mut requests = FunctionSubscriber::reconciliation(&nats, subject_prefix.as_deref()).await?;
-
-    loop {
-        tokio::select! {
-            // Got a broadcasted shutdown message
-            _ = shutdown_broadcast_rx.recv() => {
-                trace!("process reconciliation requests task received shutdown");
-                break;
-            }
-            // Got the next message on from the subscriber
-            request = requests.next() => {
-                match request {
-                    Some(Ok(request)) => {
-                        // Spawn a task an process the request
-                        tokio::spawn(reconciliation_request_task(
-                            metadata.clone(),
-                            nats.clone(),
-                            cyclone_pool.clone(),
-                            request,
-                        ));
-                    }
-                    Some(Err(err)) => {
-                        warn!(error = ?err, "next reconciliation request had error");
-                    }
-                    None => {
-                        trace!("reconciliation requests subscriber stream has closed");
-                        break;
-                    }
-                }
-            }
-            // All other arms are closed, nothing left to do but return
-            else => {
-                trace!("returning with all select arms closed");
-                break
-            }
-        }
-    }
-
-    // Unsubscribe from subscriber without draining the channel
-    requests.unsubscribe_after(0).await?;
-
-    Ok(())
-}
-
-async fn reconciliation_request_task(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ReconciliationRequest>,
-) {
-    let process_span = request.process_span.clone();
-    if let Err(err) =
-        reconciliation_request(metadata, nats, cyclone_pool, request, &process_span).await
-    {
-        warn!(error = ?err, "reconciliation execution failed");
-    }
-}
-
-#[instrument(
-    name = "veritech.reconciliation_request",
-    parent = process_span,
-    level = "info",
-    skip_all,
-    fields(
-        job.id = &request.payload.execution_id,
-        job.instance = metadata.job_instance,
-        job.invoked_name = &request.payload.handler,
-        job.invoked_provider = metadata.job_invoked_provider,
-        otel.kind = SpanKind::Server.as_str(),
-        otel.status_code = Empty,
-        otel.status_message = Empty,
-    )
-)]
-async fn reconciliation_request(
-    metadata: Arc<ServerMetadata>,
-    nats: NatsClient,
-    cyclone_pool: Pool<LocalUdsInstanceSpec>,
-    request: Request<ReconciliationRequest>,
-    process_span: &Span,
-) -> ServerResult<()> {
-    let span = Span::current();
-
-    let cyclone_request = request.payload;
-    let reply_mailbox = request
-        .reply
-        .ok_or(ServerError::NoReplyMailboxFound)
-        .map_err(|err| span.record_err(err))?;
-
-    let publisher = Publisher::new(&nats, &reply_mailbox);
-    let mut client = cyclone_pool
-        .get()
-        .await
-        .map_err(|err| span.record_err(ServerError::CyclonePool(Box::new(err))))?;
-
-    let mut progress = client
-        .execute_reconciliation(cyclone_request)
-        .await
-        .map_err(|err| span.record_err(err))?
-        .start()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    while let Some(msg) = progress.next().await {
-        match msg {
-            Ok(ProgressMessage::OutputStream(output)) => {
-                publisher
-                    .publish_output(&output)
-                    .await
-                    .map_err(|err| span.record_err(err))?;
-            }
-            Ok(ProgressMessage::Heartbeat) => {
-                trace!("received heartbeat message");
-            }
-            Err(err) => {
-                warn!(error = ?err, "next progress message was an error, bailing out");
-                break;
-            }
-        }
-    }
-    publisher
-        .finalize_output()
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    let function_result = progress
-        .finish()
-        .await
-        .map_err(|err| span.record_err(err))?;
-    publisher
-        .publish_result(&function_result)
-        .await
-        .map_err(|err| span.record_err(err))?;
-
-    span.record_ok();
-    Ok(())
 }
 
 async fn connect_to_nats(config: &Config) -> ServerResult<NatsClient> {